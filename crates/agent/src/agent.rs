@@ -1,14 +1,18 @@
 pub mod agent_profile;
 pub mod context;
+pub mod context_server_invocations;
 pub mod context_server_tool;
 pub mod context_store;
 pub mod history_store;
+pub mod pinned_context;
 pub mod thread;
 pub mod thread_store;
 pub mod tool_use;
 
 pub use context::{AgentContext, ContextId, ContextLoadResult};
+pub use context_server_invocations::{ContextServerInvocation, ContextServerInvocationLog};
 pub use context_store::ContextStore;
+pub use pinned_context::{ActiveThreadDelegate, PinnedSnippet};
 pub use thread::{
     LastRestoreCheckpoint, Message, MessageCrease, MessageId, MessageSegment, Thread, ThreadError,
     ThreadEvent, ThreadFeedback, ThreadId, ThreadSummary, TokenUsageRatio,
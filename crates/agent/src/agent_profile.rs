@@ -231,7 +231,8 @@ mod tests {
             AgentSettings::register(cx);
             language_model::init_settings(cx);
             ToolRegistry::default_global(cx);
-            assistant_tools::init(FakeHttpClient::with_404_response(), cx);
+            let http_client = FakeHttpClient::with_404_response();
+            assistant_tools::init(http_client.clone(), http_client, cx);
         });
 
         cx.update(|cx| {
@@ -0,0 +1,146 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use collections::HashMap;
+use context_server::ContextServerId;
+use gpui::{App, Global, SharedString};
+
+/// How many invocations are kept for each server.
+const MAX_INVOCATIONS_PER_SERVER: usize = 20;
+const MAX_INPUT_SUMMARY_LEN: usize = 200;
+
+#[derive(Debug, Clone)]
+pub struct ContextServerInvocation {
+    pub tool_name: SharedString,
+    pub input_summary: String,
+    pub started_at: DateTime<Local>,
+    pub duration: Duration,
+    /// The error the tool call failed with, if any.
+    pub error: Option<String>,
+}
+
+/// The most recent MCP tool calls made by the agent, per server. Only recorded
+/// when `global_context_server_settings.log_tool_invocations` is enabled.
+#[derive(Default)]
+pub struct ContextServerInvocationLog {
+    invocations: HashMap<ContextServerId, VecDeque<ContextServerInvocation>>,
+}
+
+impl Global for ContextServerInvocationLog {}
+
+impl ContextServerInvocationLog {
+    /// The server's recent invocations, newest first.
+    pub fn recent<'a>(
+        server_id: &ContextServerId,
+        cx: &'a App,
+    ) -> impl Iterator<Item = &'a ContextServerInvocation> {
+        cx.try_global::<Self>()
+            .and_then(|log| log.invocations.get(server_id))
+            .into_iter()
+            .flat_map(|invocations| invocations.iter().rev())
+    }
+
+    pub fn record(server_id: ContextServerId, invocation: ContextServerInvocation, cx: &mut App) {
+        match &invocation.error {
+            Some(error) => log::warn!(
+                "MCP tool {}/{} failed after {:?}: {error} (input: {})",
+                server_id.0,
+                invocation.tool_name,
+                invocation.duration,
+                invocation.input_summary
+            ),
+            None => log::info!(
+                "MCP tool {}/{} succeeded in {:?} (input: {})",
+                server_id.0,
+                invocation.tool_name,
+                invocation.duration,
+                invocation.input_summary
+            ),
+        }
+
+        let invocations = cx
+            .default_global::<Self>()
+            .invocations
+            .entry(server_id)
+            .or_default();
+        if invocations.len() == MAX_INVOCATIONS_PER_SERVER {
+            invocations.pop_front();
+        }
+        invocations.push_back(invocation);
+    }
+
+    pub fn clear(server_id: &ContextServerId, cx: &mut App) {
+        if cx.has_global::<Self>() {
+            cx.update_global::<Self, _>(|log, _| {
+                log.invocations.remove(server_id);
+            });
+        }
+    }
+}
+
+/// A single-line, truncated rendering of a tool's input.
+pub fn summarize_tool_input(input: &serde_json::Value) -> String {
+    let summary = input.to_string();
+    if summary.len() > MAX_INPUT_SUMMARY_LEN {
+        format!(
+            "{}…",
+            util::truncate_to_byte_limit(&summary, MAX_INPUT_SUMMARY_LEN)
+        )
+    } else {
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+
+    #[gpui::test]
+    fn test_record_invocations(cx: &mut TestAppContext) {
+        let server_id = ContextServerId("github".into());
+        cx.update(|cx| {
+            for ix in 0..MAX_INVOCATIONS_PER_SERVER + 1 {
+                ContextServerInvocationLog::record(
+                    server_id.clone(),
+                    ContextServerInvocation {
+                        tool_name: format!("tool-{ix}").into(),
+                        input_summary: String::new(),
+                        started_at: Local::now(),
+                        duration: Duration::from_millis(10),
+                        error: None,
+                    },
+                    cx,
+                );
+            }
+
+            let recent = ContextServerInvocationLog::recent(&server_id, cx).collect::<Vec<_>>();
+            assert_eq!(recent.len(), MAX_INVOCATIONS_PER_SERVER);
+            assert_eq!(
+                recent[0].tool_name.as_ref(),
+                format!("tool-{MAX_INVOCATIONS_PER_SERVER}")
+            );
+            assert_eq!(recent.last().unwrap().tool_name.as_ref(), "tool-1");
+
+            ContextServerInvocationLog::clear(&server_id, cx);
+            assert_eq!(
+                ContextServerInvocationLog::recent(&server_id, cx).count(),
+                0
+            );
+        });
+    }
+
+    #[test]
+    fn test_summarize_tool_input() {
+        let input = serde_json::json!({ "query": "é".repeat(MAX_INPUT_SUMMARY_LEN) });
+        let summary = summarize_tool_input(&input);
+        assert!(summary.ends_with('…'));
+        assert!(summary.len() <= MAX_INPUT_SUMMARY_LEN + '…'.len_utf8());
+
+        assert_eq!(
+            summarize_tool_input(&serde_json::json!({ "a": 1 })),
+            r#"{"a":1}"#
+        );
+    }
+}
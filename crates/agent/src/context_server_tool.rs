@@ -1,12 +1,21 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use anyhow::{Result, anyhow, bail};
 use assistant_tool::{ActionLog, Tool, ToolResult, ToolSource};
+use chrono::Local;
 use context_server::{ContextServerId, types};
 use gpui::{AnyWindowHandle, App, Entity, Task};
 use icons::IconName;
 use language_model::{LanguageModel, LanguageModelRequest, LanguageModelToolSchemaFormat};
-use project::{Project, context_server_store::ContextServerStore};
+use project::{
+    Project, context_server_store::ContextServerStore, project_settings::ProjectSettings,
+};
+use settings::Settings as _;
+use util::ResultExt as _;
+
+use crate::context_server_invocations::{
+    ContextServerInvocation, ContextServerInvocationLog, summarize_tool_input,
+};
 
 pub struct ContextServerTool {
     store: Entity<ContextServerStore>,
@@ -87,51 +96,78 @@ impl Tool for ContextServerTool {
             let tool_name = self.tool.name.clone();
             let server_clone = server.clone();
             let input_clone = input.clone();
-
-            cx.spawn(async move |_cx| {
-                let Some(protocol) = server_clone.client() else {
-                    bail!("Context server not initialized");
-                };
-
-                let arguments = if let serde_json::Value::Object(map) = input_clone {
-                    Some(map.into_iter().collect())
-                } else {
-                    None
-                };
-
-                log::trace!(
-                    "Running tool: {} with arguments: {:?}",
-                    tool_name,
-                    arguments
-                );
-                let response = protocol
-                    .request::<context_server::types::requests::CallTool>(
-                        context_server::types::CallToolParams {
-                            name: tool_name,
-                            arguments,
-                            meta: None,
+            let invocation = ProjectSettings::get_global(cx)
+                .global_context_server_settings
+                .log_tool_invocations
+                .then(|| {
+                    (
+                        self.server_id.clone(),
+                        ContextServerInvocation {
+                            tool_name: tool_name.clone().into(),
+                            input_summary: summarize_tool_input(&input),
+                            started_at: Local::now(),
+                            duration: Default::default(),
+                            error: None,
                         },
                     )
-                    .await?;
-
-                let mut result = String::new();
-                for content in response.content {
-                    match content {
-                        types::ToolResponseContent::Text { text } => {
-                            result.push_str(&text);
-                        }
-                        types::ToolResponseContent::Image { .. } => {
-                            log::warn!("Ignoring image content from tool response");
-                        }
-                        types::ToolResponseContent::Audio { .. } => {
-                            log::warn!("Ignoring audio content from tool response");
-                        }
-                        types::ToolResponseContent::Resource { .. } => {
-                            log::warn!("Ignoring resource content from tool response");
+                });
+
+            let started_at = Instant::now();
+            cx.spawn(async move |cx| {
+                let result = async {
+                    let Some(protocol) = server_clone.client() else {
+                        bail!("Context server not initialized");
+                    };
+
+                    let arguments = if let serde_json::Value::Object(map) = input_clone {
+                        Some(map.into_iter().collect())
+                    } else {
+                        None
+                    };
+
+                    log::trace!(
+                        "Running tool: {} with arguments: {:?}",
+                        tool_name,
+                        arguments
+                    );
+                    let response = protocol
+                        .request::<context_server::types::requests::CallTool>(
+                            context_server::types::CallToolParams {
+                                name: tool_name,
+                                arguments,
+                                meta: None,
+                            },
+                        )
+                        .await?;
+
+                    let mut result = String::new();
+                    for content in response.content {
+                        match content {
+                            types::ToolResponseContent::Text { text } => {
+                                result.push_str(&text);
+                            }
+                            types::ToolResponseContent::Image { .. } => {
+                                log::warn!("Ignoring image content from tool response");
+                            }
+                            types::ToolResponseContent::Audio { .. } => {
+                                log::warn!("Ignoring audio content from tool response");
+                            }
+                            types::ToolResponseContent::Resource { .. } => {
+                                log::warn!("Ignoring resource content from tool response");
+                            }
                         }
                     }
+                    anyhow::Ok(result)
+                }
+                .await;
+
+                if let Some((server_id, mut invocation)) = invocation {
+                    invocation.duration = started_at.elapsed();
+                    invocation.error = result.as_ref().err().map(|error| format!("{error:#}"));
+                    cx.update(|cx| ContextServerInvocationLog::record(server_id, invocation, cx))
+                        .log_err();
                 }
-                Ok(result.into())
+                Ok(result?.into())
             })
             .into()
         } else {
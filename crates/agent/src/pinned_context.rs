@@ -0,0 +1,99 @@
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use gpui::{App, Entity, Global, SharedString};
+use language_model::{LanguageModelRequestMessage, MessageContent};
+use serde::{Deserialize, Serialize};
+use workspace::Workspace;
+
+use crate::Thread;
+
+/// A snippet the user pinned to a thread (e.g. from a file search result). It
+/// is sent along with every subsequent request in that thread until it is
+/// unpinned, and is saved with the thread.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinnedSnippet {
+    pub id: SharedString,
+    pub label: SharedString,
+    pub content: String,
+}
+
+/// Adds the pinned snippets to the start of a user message, the same way
+/// loaded context is added.
+pub fn add_pinned_snippets_to_request_message(
+    snippets: &[PinnedSnippet],
+    request_message: &mut LanguageModelRequestMessage,
+) {
+    if snippets.is_empty() {
+        return;
+    }
+
+    let mut text = String::from(
+        "The user pinned the following snippets. Treat them as context for the whole conversation.\n",
+    );
+    for snippet in snippets {
+        writeln!(
+            text,
+            "\n<pinned_snippet id=\"{}\" label=\"{}\">\n{}\n</pinned_snippet>",
+            snippet.id, snippet.label, snippet.content
+        )
+        .ok();
+    }
+    request_message
+        .content
+        .insert(0, MessageContent::Text(text));
+}
+
+/// Finds the thread shown in a workspace's agent panel, so that tool cards,
+/// which only know their workspace, can pin snippets to the thread they're in.
+pub trait ActiveThreadDelegate {
+    fn active_thread(&self, workspace: &Workspace, cx: &App) -> Option<Entity<Thread>>;
+}
+
+impl dyn ActiveThreadDelegate {
+    /// Returns the global [`ActiveThreadDelegate`], if it exists.
+    pub fn try_global(cx: &App) -> Option<Arc<Self>> {
+        cx.try_global::<GlobalActiveThreadDelegate>()
+            .map(|global| global.0.clone())
+    }
+
+    /// Sets the global [`ActiveThreadDelegate`].
+    pub fn set_global(delegate: Arc<Self>, cx: &mut App) {
+        cx.set_global(GlobalActiveThreadDelegate(delegate));
+    }
+}
+
+struct GlobalActiveThreadDelegate(Arc<dyn ActiveThreadDelegate>);
+
+impl Global for GlobalActiveThreadDelegate {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use language_model::Role;
+
+    #[test]
+    fn test_add_pinned_snippets_to_request_message() {
+        let mut message = LanguageModelRequestMessage {
+            role: Role::User,
+            content: vec![MessageContent::Text("How do I log in?".into())],
+            cache: false,
+        };
+        add_pinned_snippets_to_request_message(&[], &mut message);
+        assert_eq!(message.content.len(), 1);
+
+        let snippet = PinnedSnippet {
+            id: "result-1".into(),
+            label: "Task".into(),
+            content: "Fix the login flow".into(),
+        };
+        add_pinned_snippets_to_request_message(&[snippet], &mut message);
+        assert_eq!(message.content.len(), 2);
+        let MessageContent::Text(pinned) = &message.content[0] else {
+            panic!("expected the pinned snippets first");
+        };
+        assert!(pinned.contains("<pinned_snippet id=\"result-1\" label=\"Task\">"));
+        assert!(pinned.contains("Fix the login flow"));
+        assert!(message.string_contents().ends_with("How do I log in?"));
+    }
+}
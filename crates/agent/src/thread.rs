@@ -1,6 +1,7 @@
 use crate::{
     agent_profile::AgentProfile,
     context::{AgentContext, AgentContextHandle, ContextLoadResult, LoadedContext},
+    pinned_context::{PinnedSnippet, add_pinned_snippets_to_request_message},
     thread_store::{
         SerializedCrease, SerializedLanguageModel, SerializedMessage, SerializedMessageSegment,
         SerializedThread, SerializedToolResult, SerializedToolUse, SharedProjectContext,
@@ -10,7 +11,7 @@ use crate::{
 };
 use agent_settings::{AgentProfileId, AgentSettings, CompletionMode};
 use anyhow::{Result, anyhow};
-use assistant_tool::{ActionLog, AnyToolCard, Tool, ToolWorkingSet};
+use assistant_tool::{ActionLog, AnyToolCard, Tool, ToolOutputLog, ToolWorkingSet};
 use chrono::{DateTime, Utc};
 use client::{ModelRequestUsage, RequestUsage};
 use collections::HashMap;
@@ -385,6 +386,7 @@ pub struct Thread {
     cumulative_token_usage: TokenUsage,
     exceeded_window_error: Option<ExceededWindowError>,
     tool_use_limit_reached: bool,
+    pinned_snippets: Vec<PinnedSnippet>,
     feedback: Option<ThreadFeedback>,
     retry_state: Option<RetryState>,
     message_feedback: HashMap<MessageId, ThreadFeedback>,
@@ -394,6 +396,9 @@ pub struct Thread {
         Box<dyn FnMut(&LanguageModelRequest, &[Result<LanguageModelCompletionEvent, String>])>,
     >,
     remaining_turns: u32,
+    /// Overrides the `always_allow_tool_actions` setting for this thread only.
+    /// Not saved with the thread, so reopening it asks for confirmation again.
+    always_allow_tool_actions: Option<bool>,
     configured_model: Option<ConfiguredModel>,
     profile: AgentProfile,
     last_error_context: Option<(Arc<dyn LanguageModel>, CompletionIntent)>,
@@ -486,6 +491,7 @@ impl Thread {
             cumulative_token_usage: TokenUsage::default(),
             exceeded_window_error: None,
             tool_use_limit_reached: false,
+            pinned_snippets: Vec::new(),
             feedback: None,
             retry_state: None,
             message_feedback: HashMap::default(),
@@ -494,6 +500,7 @@ impl Thread {
             last_received_chunk_at: None,
             request_callback: None,
             remaining_turns: u32::MAX,
+            always_allow_tool_actions: None,
             configured_model: configured_model.clone(),
             profile: AgentProfile::new(profile_id, tools),
         }
@@ -612,6 +619,7 @@ impl Thread {
             cumulative_token_usage: serialized.cumulative_token_usage,
             exceeded_window_error: None,
             tool_use_limit_reached: serialized.tool_use_limit_reached,
+            pinned_snippets: serialized.pinned_snippets,
             feedback: None,
             message_feedback: HashMap::default(),
             last_auto_capture_at: None,
@@ -619,6 +627,7 @@ impl Thread {
             last_received_chunk_at: None,
             request_callback: None,
             remaining_turns: u32::MAX,
+            always_allow_tool_actions: None,
             configured_model,
             profile: AgentProfile::new(profile_id, tools),
         }
@@ -922,6 +931,32 @@ impl Thread {
         self.tool_use_limit_reached
     }
 
+    pub fn pinned_snippets(&self) -> &[PinnedSnippet] {
+        &self.pinned_snippets
+    }
+
+    pub fn is_pinned(&self, id: &str) -> bool {
+        self.pinned_snippets
+            .iter()
+            .any(|snippet| snippet.id.as_ref() == id)
+    }
+
+    pub fn pin_snippet(&mut self, snippet: PinnedSnippet, cx: &mut Context<Self>) {
+        if !self.is_pinned(&snippet.id) {
+            self.pinned_snippets.push(snippet);
+            cx.emit(ThreadEvent::PinnedSnippetsChanged);
+        }
+    }
+
+    pub fn unpin_snippet(&mut self, id: &str, cx: &mut Context<Self>) {
+        let len = self.pinned_snippets.len();
+        self.pinned_snippets
+            .retain(|snippet| snippet.id.as_ref() != id);
+        if self.pinned_snippets.len() != len {
+            cx.emit(ThreadEvent::PinnedSnippetsChanged);
+        }
+    }
+
     /// Returns whether all of the tool uses have finished running.
     pub fn all_tools_finished(&self) -> bool {
         // If the only pending tool uses left are the ones with errors, then
@@ -1240,6 +1275,7 @@ impl Thread {
                 completion_mode: Some(this.completion_mode),
                 tool_use_limit_reached: this.tool_use_limit_reached,
                 profile: Some(this.profile.id().clone()),
+                pinned_snippets: this.pinned_snippets.clone(),
             })
         })
     }
@@ -1252,6 +1288,28 @@ impl Thread {
         self.remaining_turns = remaining_turns;
     }
 
+    /// Whether tools run in this thread without asking for confirmation,
+    /// following the global setting unless the thread overrides it.
+    pub fn always_allow_tool_actions(&self, cx: &App) -> bool {
+        self.always_allow_tool_actions
+            .unwrap_or_else(|| AgentSettings::get_global(cx).always_allow_tool_actions)
+    }
+
+    pub fn always_allow_tool_actions_override(&self) -> Option<bool> {
+        self.always_allow_tool_actions
+    }
+
+    /// Overrides the global setting for this thread, or follows it again when
+    /// `None`. The setting itself is left untouched.
+    pub fn set_always_allow_tool_actions_override(
+        &mut self,
+        always_allow: Option<bool>,
+        cx: &mut Context<Self>,
+    ) {
+        self.always_allow_tool_actions = always_allow;
+        cx.notify();
+    }
+
     pub fn send_to_model(
         &mut self,
         model: Arc<dyn LanguageModel>,
@@ -1393,6 +1451,7 @@ impl Thread {
         }
 
         let mut message_ix_to_cache = None;
+        let mut last_user_message_ix = None;
         for message in &self.messages {
             // ui_only messages are for the UI only, not for the model
             if message.ui_only {
@@ -1473,6 +1532,9 @@ impl Thread {
             if cache_message {
                 message_ix_to_cache = Some(request.messages.len());
             }
+            if message.role == Role::User {
+                last_user_message_ix = Some(request.messages.len());
+            }
             request.messages.push(request_message);
 
             if !tool_results_message.content.is_empty() {
@@ -1483,6 +1545,15 @@ impl Thread {
             }
         }
 
+        // Pinned snippets go with the latest user message, so that changing
+        // them doesn't invalidate the cached prefix of the conversation.
+        if let Some(last_user_message_ix) = last_user_message_ix {
+            add_pinned_snippets_to_request_message(
+                &self.pinned_snippets,
+                &mut request.messages[last_user_message_ix],
+            );
+        }
+
         // https://docs.anthropic.com/en/docs/build-with-claude/prompt-caching
         if let Some(message_ix_to_cache) = message_ix_to_cache {
             request.messages[message_ix_to_cache].cache = true;
@@ -2557,9 +2628,7 @@ impl Thread {
             return self.handle_hallucinated_tool_use(tool_use.id, tool_use.name, window, cx);
         }
 
-        if tool.needs_confirmation(&tool_use.input, cx)
-            && !AgentSettings::get_global(cx).always_allow_tool_actions
-        {
+        if self.tool_use_needs_confirmation(&tool, &tool_use.input, cx) {
             self.tool_use.confirm_tool_use(
                 tool_use.id,
                 tool_use.ui_text,
@@ -2582,6 +2651,25 @@ impl Thread {
         }
     }
 
+    /// The command permissions decide for the commands they match, and the
+    /// thread's `always_allow_tool_actions` for everything else.
+    fn tool_use_needs_confirmation(
+        &self,
+        tool: &Arc<dyn Tool>,
+        input: &serde_json::Value,
+        cx: &App,
+    ) -> bool {
+        if !tool.needs_confirmation(input, cx) {
+            return false;
+        }
+        let allowed = tool.command(input).and_then(|command| {
+            AgentSettings::get_global(cx)
+                .command_permissions
+                .check(&command)
+        });
+        !allowed.unwrap_or_else(|| self.always_allow_tool_actions(cx))
+    }
+
     pub fn handle_hallucinated_tool_use(
         &mut self,
         tool_use_id: LanguageModelToolUseId,
@@ -2682,6 +2770,7 @@ impl Thread {
         cx: &mut Context<Thread>,
     ) -> Task<()> {
         let tool_name: Arc<str> = tool.name().into();
+        let started_at = Instant::now();
 
         let tool_result = tool.run(
             input,
@@ -2705,6 +2794,7 @@ impl Thread {
 
                 thread
                     .update(cx, |thread, cx| {
+                        ToolOutputLog::record(tool_name.clone(), started_at, &output, cx);
                         let pending_tool_use = thread.tool_use.insert_tool_output(
                             tool_use_id.clone(),
                             tool_name,
@@ -3351,6 +3441,7 @@ pub enum ThreadEvent {
     CancelEditing,
     CompletionCanceled,
     ProfileChanged,
+    PinnedSnippetsChanged,
 }
 
 impl EventEmitter<ThreadEvent> for Thread {}
@@ -3856,6 +3947,45 @@ fn main() {{
         );
     }
 
+    #[gpui::test]
+    async fn test_always_allow_tool_actions_override(cx: &mut TestAppContext) {
+        init_test_settings(cx);
+
+        let project = create_test_project(cx, json!({})).await;
+        let (_workspace, thread_store, thread, _context_store, _model) =
+            setup_test_environment(cx, project.clone()).await;
+        let other_thread = thread_store.update(cx, |store, cx| store.create_thread(cx));
+
+        cx.read(|cx| assert!(!thread.read(cx).always_allow_tool_actions(cx)));
+
+        thread.update(cx, |thread, cx| {
+            thread.set_always_allow_tool_actions_override(Some(true), cx);
+        });
+        cx.read(|cx| {
+            assert!(thread.read(cx).always_allow_tool_actions(cx));
+            assert!(!other_thread.read(cx).always_allow_tool_actions(cx));
+            assert!(!AgentSettings::get_global(cx).always_allow_tool_actions);
+        });
+
+        cx.update(|cx| {
+            let mut settings = AgentSettings::get_global(cx).clone();
+            settings.always_allow_tool_actions = true;
+            AgentSettings::override_global(settings, cx);
+        });
+        thread.update(cx, |thread, cx| {
+            thread.set_always_allow_tool_actions_override(Some(false), cx);
+        });
+        cx.read(|cx| {
+            assert!(!thread.read(cx).always_allow_tool_actions(cx));
+            assert!(other_thread.read(cx).always_allow_tool_actions(cx));
+        });
+
+        thread.update(cx, |thread, cx| {
+            thread.set_always_allow_tool_actions_override(None, cx);
+        });
+        cx.read(|cx| assert!(thread.read(cx).always_allow_tool_actions(cx)));
+    }
+
     #[gpui::test]
     async fn test_serializing_thread_profile(cx: &mut TestAppContext) {
         init_test_settings(cx);
@@ -3899,6 +4029,98 @@ fn main() {{
         );
     }
 
+    #[gpui::test]
+    async fn test_pinned_snippets(cx: &mut TestAppContext) {
+        init_test_settings(cx);
+
+        let project = create_test_project(
+            cx,
+            json!({"code.rs": "fn main() {\n    println!(\"Hello, world!\");\n}"}),
+        )
+        .await;
+
+        let (_workspace, _thread_store, thread, _context_store, model) =
+            setup_test_environment(cx, project.clone()).await;
+
+        thread.update(cx, |thread, cx| {
+            thread.insert_user_message(
+                "How does login work?",
+                ContextLoadResult::default(),
+                None,
+                Vec::new(),
+                cx,
+            );
+            let snippet = PinnedSnippet {
+                id: "result-1".into(),
+                label: "Task".into(),
+                content: "Fix the login flow".into(),
+            };
+            thread.pin_snippet(snippet.clone(), cx);
+            thread.pin_snippet(snippet, cx);
+            thread.insert_user_message(
+                "And logout?",
+                ContextLoadResult::default(),
+                None,
+                Vec::new(),
+                cx,
+            );
+        });
+        thread.read_with(cx, |thread, _| {
+            assert_eq!(thread.pinned_snippets().len(), 1);
+            assert!(thread.is_pinned("result-1"));
+        });
+
+        // Pinned snippets only go with the latest user message, not in a
+        // system message of their own.
+        let request = thread.update(cx, |thread, cx| {
+            thread.to_completion_request(model.clone(), CompletionIntent::UserPrompt, cx)
+        });
+        assert_eq!(request.messages.len(), 3);
+        assert_eq!(
+            request
+                .messages
+                .iter()
+                .filter(|message| message.role == Role::System)
+                .count(),
+            1
+        );
+        assert_eq!(
+            request.messages[1].string_contents(),
+            "How does login work?"
+        );
+        let latest = request.messages[2].string_contents();
+        assert!(latest.contains("Fix the login flow"));
+        assert!(latest.ends_with("And logout?"));
+
+        // Pins are saved with the thread.
+        let serialized = thread
+            .update(cx, |thread, cx| thread.serialize(cx))
+            .await
+            .unwrap();
+        assert_eq!(serialized.pinned_snippets.len(), 1);
+        let deserialized = cx.update(|cx| {
+            thread.update(cx, |thread, cx| {
+                Thread::deserialize(
+                    thread.id.clone(),
+                    serialized,
+                    thread.project.clone(),
+                    thread.tools.clone(),
+                    thread.prompt_builder.clone(),
+                    thread.project_context.clone(),
+                    None,
+                    cx,
+                )
+            })
+        });
+        assert!(deserialized.is_pinned("result-1"));
+
+        thread.update(cx, |thread, cx| thread.unpin_snippet("result-1", cx));
+        let request = thread.update(cx, |thread, cx| {
+            thread.to_completion_request(model.clone(), CompletionIntent::UserPrompt, cx)
+        });
+        assert_eq!(request.messages[2].string_contents(), "And logout?");
+    }
+
     #[gpui::test]
     async fn test_temperature_setting(cx: &mut TestAppContext) {
         init_test_settings(cx);
@@ -5452,7 +5674,7 @@ fn main() {{
                 "http://localhost".to_string(),
                 None,
             ));
-            assistant_tools::init(http_client, cx);
+            assistant_tools::init(http_client.clone(), http_client, cx);
         });
     }
 
@@ -1,5 +1,6 @@
 use crate::{
     context_server_tool::ContextServerTool,
+    pinned_context::PinnedSnippet,
     thread::{
         DetailedSummaryState, ExceededWindowError, MessageId, ProjectSnapshot, Thread, ThreadId,
     },
@@ -630,6 +631,8 @@ pub struct SerializedThread {
     pub tool_use_limit_reached: bool,
     #[serde(default)]
     pub profile: Option<AgentProfileId>,
+    #[serde(default)]
+    pub pinned_snippets: Vec<PinnedSnippet>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -777,6 +780,7 @@ impl LegacySerializedThread {
             completion_mode: None,
             tool_use_limit_reached: false,
             profile: None,
+            pinned_snippets: Vec::new(),
         }
     }
 }
@@ -1121,7 +1125,8 @@ mod tests {
                 model: None,
                 completion_mode: None,
                 tool_use_limit_reached: false,
-                profile: None
+                profile: None,
+                pinned_snippets: Vec::new(),
             }
         )
     }
@@ -1189,6 +1194,7 @@ mod tests {
             completion_mode: None,
             tool_use_limit_reached: false,
             profile: None,
+            pinned_snippets: Vec::new(),
         });
         let upgraded = thread_v0_1_0.upgrade();
 
@@ -1241,7 +1247,8 @@ mod tests {
                 model: None,
                 completion_mode: None,
                 tool_use_limit_reached: false,
-                profile: None
+                profile: None,
+                pinned_snippets: Vec::new(),
             }
         )
     }
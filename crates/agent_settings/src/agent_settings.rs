@@ -1,4 +1,5 @@
 mod agent_profile;
+mod command_permissions;
 
 use std::sync::Arc;
 
@@ -7,14 +8,21 @@ use collections::IndexMap;
 use gpui::{App, Pixels, SharedString};
 use language_model::LanguageModel;
 use schemars::{JsonSchema, json_schema};
-use serde::{Deserialize, Serialize};
-use settings::{Settings, SettingsSources};
+use serde::{Deserialize, Deserializer, Serialize};
+use settings::{Settings, SettingsSources, SettingsStore};
 use std::borrow::Cow;
 
 pub use crate::agent_profile::*;
+pub use crate::command_permissions::*;
 
 pub fn init(cx: &mut App) {
     AgentSettings::register(cx);
+
+    language_model::set_llm_request_logging(AgentSettings::get_global(cx).log_llm_requests);
+    cx.observe_global::<SettingsStore>(|cx| {
+        language_model::set_llm_request_logging(AgentSettings::get_global(cx).log_llm_requests);
+    })
+    .detach();
 }
 
 #[derive(Copy, Clone, Default, Debug, Serialize, Deserialize, JsonSchema)]
@@ -43,6 +51,66 @@ pub enum NotifyWhenAgentWaiting {
     Never,
 }
 
+/// The sound played when the agent is done, if `play_sound_when_agent_done`
+/// is enabled.
+#[derive(Copy, Clone, Default, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentDoneSound {
+    #[default]
+    Chime,
+    Rise,
+    Fall,
+    Click,
+    /// The operating system's notification sound.
+    SystemDefault,
+}
+
+impl AgentDoneSound {
+    pub const ALL: [Self; 5] = [
+        Self::Chime,
+        Self::Rise,
+        Self::Fall,
+        Self::Click,
+        Self::SystemDefault,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Chime => "Chime",
+            Self::Rise => "Rise",
+            Self::Fall => "Fall",
+            Self::Click => "Click",
+            Self::SystemDefault => "System Default",
+        }
+    }
+}
+
+/// Whether the task sync section of the agent configuration is expanded.
+#[derive(Copy, Clone, Default, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskSyncSectionExpansion {
+    /// Expanded while no task is synced, and collapsed once one is.
+    #[default]
+    Auto,
+    /// Expanded until it's collapsed by hand, including after syncing.
+    Expanded,
+    /// Collapsed until it's expanded by hand, including when no task is
+    /// synced.
+    Collapsed,
+}
+
+impl TaskSyncSectionExpansion {
+    /// Whether the section starts out expanded, given whether a task is
+    /// synced.
+    pub fn is_initially_expanded(&self, synced: bool) -> bool {
+        match self {
+            Self::Auto => !synced,
+            Self::Expanded => true,
+            Self::Collapsed => false,
+        }
+    }
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct AgentSettings {
     pub enabled: bool,
@@ -55,13 +123,16 @@ pub struct AgentSettings {
     pub commit_message_model: Option<LanguageModelSelection>,
     pub thread_summary_model: Option<LanguageModelSelection>,
     pub inline_alternatives: Vec<LanguageModelSelection>,
+    pub provider_default_models: IndexMap<Arc<str>, String>,
     pub using_outdated_settings_version: bool,
     pub default_profile: AgentProfileId,
     pub default_view: DefaultView,
     pub profiles: IndexMap<AgentProfileId, AgentProfileSettings>,
     pub always_allow_tool_actions: bool,
+    pub command_permissions: CommandPermissionsSettings,
     pub notify_when_agent_waiting: NotifyWhenAgentWaiting,
     pub play_sound_when_agent_done: bool,
+    pub agent_done_sound: AgentDoneSound,
     pub stream_edits: bool,
     pub single_file_review: bool,
     pub model_parameters: Vec<LanguageModelParameters>,
@@ -69,7 +140,13 @@ pub struct AgentSettings {
     pub enable_feedback: bool,
     pub expand_edit_card: bool,
     pub expand_terminal_card: bool,
+    pub task_sync_section_expansion: TaskSyncSectionExpansion,
     pub use_modifier_to_send: bool,
+    pub log_llm_requests: bool,
+    pub warm_up_embeddings: bool,
+    pub reindex_debounce_ms: u64,
+    pub secret_redaction: SecretRedactionSettings,
+    pub file_search: FileSearchSettings,
 }
 
 impl AgentSettings {
@@ -102,6 +179,36 @@ impl AgentSettings {
             model,
         });
     }
+
+    /// Returns the model id that new threads started from the given provider should use.
+    pub fn default_model_for_provider(&self, provider_id: &str) -> Option<&str> {
+        self.provider_default_models
+            .get(provider_id)
+            .map(|model| model.as_str())
+    }
+
+    /// The settings as they are in the default settings, before any user or
+    /// project customizations.
+    pub fn defaults(cx: &mut App) -> Result<Self> {
+        let default_settings =
+            settings::parse_json_with_comments::<serde_json::Value>(&settings::default_settings())?;
+        let content = match Self::KEY.and_then(|key| default_settings.get(key)) {
+            Some(content) => serde_json::from_value(content.clone())?,
+            None => AgentSettingsContent::default(),
+        };
+        Self::load(
+            SettingsSources {
+                default: &content,
+                global: None,
+                extensions: None,
+                user: None,
+                release_channel: None,
+                server: None,
+                project: &[],
+            },
+            cx,
+        )
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
@@ -163,14 +270,46 @@ impl AgentSettingsContent {
         });
     }
 
+    pub fn set_provider_default_model(&mut self, provider: &str, model: String) {
+        self.provider_default_models
+            .get_or_insert_default()
+            .insert(provider.into(), model);
+    }
+
     pub fn set_always_allow_tool_actions(&mut self, allow: bool) {
         self.always_allow_tool_actions = Some(allow);
     }
 
+    pub fn add_command_pattern(&mut self, pattern: String, always_allow: bool) {
+        let command_permissions = self.command_permissions.get_or_insert_default();
+        let patterns = if always_allow {
+            command_permissions.always_allow.get_or_insert_default()
+        } else {
+            command_permissions.always_confirm.get_or_insert_default()
+        };
+        if !patterns.contains(&pattern) {
+            patterns.push(pattern);
+        }
+    }
+
+    pub fn remove_command_pattern(&mut self, pattern: &str, always_allow: bool) {
+        let command_permissions = self.command_permissions.get_or_insert_default();
+        let patterns = if always_allow {
+            command_permissions.always_allow.get_or_insert_default()
+        } else {
+            command_permissions.always_confirm.get_or_insert_default()
+        };
+        patterns.retain(|existing| existing != pattern);
+    }
+
     pub fn set_play_sound_when_agent_done(&mut self, allow: bool) {
         self.play_sound_when_agent_done = Some(allow);
     }
 
+    pub fn set_agent_done_sound(&mut self, sound: AgentDoneSound) {
+        self.agent_done_sound = Some(sound);
+    }
+
     pub fn set_single_file_review(&mut self, allow: bool) {
         self.single_file_review = Some(allow);
     }
@@ -179,6 +318,20 @@ impl AgentSettingsContent {
         self.use_modifier_to_send = Some(always_use);
     }
 
+    /// Resets the settings shown under General Settings in the agent
+    /// configuration to their values in `defaults`.
+    pub fn reset_general_settings(&mut self, defaults: &AgentSettings) {
+        self.set_always_allow_tool_actions(defaults.always_allow_tool_actions);
+        self.set_single_file_review(defaults.single_file_review);
+        self.set_play_sound_when_agent_done(defaults.play_sound_when_agent_done);
+        self.set_agent_done_sound(defaults.agent_done_sound);
+        self.set_use_modifier_to_send(defaults.use_modifier_to_send);
+        self.command_permissions = Some(CommandPermissionsSettingsContent {
+            always_allow: Some(defaults.command_permissions.always_allow.clone()),
+            always_confirm: Some(defaults.command_permissions.always_confirm.clone()),
+        });
+    }
+
     pub fn set_profile(&mut self, profile_id: AgentProfileId) {
         self.default_profile = Some(profile_id);
     }
@@ -250,6 +403,10 @@ pub struct AgentSettingsContent {
     thread_summary_model: Option<LanguageModelSelection>,
     /// Additional models with which to generate alternatives when performing inline assists.
     inline_alternatives: Option<Vec<LanguageModelSelection>>,
+    /// The model to use for new threads started from a specific provider, keyed by provider id.
+    ///
+    /// Default: {}
+    provider_default_models: Option<IndexMap<Arc<str>, String>>,
     /// The default profile to use in the Agent.
     ///
     /// Default: write
@@ -265,6 +422,9 @@ pub struct AgentSettingsContent {
     ///
     /// Default: false
     always_allow_tool_actions: Option<bool>,
+    /// Commands the agent may always run, or must always confirm, regardless
+    /// of `always_allow_tool_actions`.
+    command_permissions: Option<CommandPermissionsSettingsContent>,
     /// Where to show a popup notification when the agent is waiting for user input.
     ///
     /// Default: "primary_screen"
@@ -273,6 +433,12 @@ pub struct AgentSettingsContent {
     ///
     /// Default: false
     play_sound_when_agent_done: Option<bool>,
+    /// Which sound to play when the agent is done: "chime", "rise", "fall",
+    /// "click", or "system_default" for the operating system's notification
+    /// sound.
+    ///
+    /// Default: "chime"
+    agent_done_sound: Option<AgentDoneSound>,
     /// Whether to stream edits from the agent as they are received.
     ///
     /// Default: false
@@ -306,10 +472,177 @@ pub struct AgentSettingsContent {
     ///
     /// Default: true
     expand_terminal_card: Option<bool>,
+    /// Whether the task sync section of the agent configuration is expanded:
+    /// "auto" to expand it while no task is synced and collapse it once one
+    /// is, or "expanded" or "collapsed" to keep it that way until it's
+    /// toggled by hand.
+    ///
+    /// Default: "auto"
+    task_sync_section_expansion: Option<TaskSyncSectionExpansion>,
     /// Whether to always use cmd-enter (or ctrl-enter on Linux) to send messages in the agent panel.
     ///
     /// Default: false
     use_modifier_to_send: Option<bool>,
+    /// Whether to log the raw requests and responses of the search and
+    /// embedding endpoints. The bearer token is redacted. Also enables
+    /// `dev: copy last llm request as curl`.
+    ///
+    /// Default: false
+    log_llm_requests: Option<bool>,
+    /// Whether to send a tiny embedding request at startup, so that a
+    /// cold-starting embedding backend is ready before the first index.
+    ///
+    /// Default: false
+    warm_up_embeddings: Option<bool>,
+    /// How long a changed file has to go without further changes before it's
+    /// embedded again, in milliseconds. Set to 0 to embed changes right away.
+    ///
+    /// Default: 0
+    reindex_debounce_ms: Option<u64>,
+    /// Settings for redacting secrets from code before it's sent to the
+    /// embedding endpoint.
+    secret_redaction: Option<SecretRedactionSettingsContent>,
+    /// Settings for the file search tool.
+    file_search: Option<FileSearchSettingsContent>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SecretRedactionSettings {
+    pub enabled: bool,
+    pub patterns: Vec<String>,
+}
+
+impl Default for SecretRedactionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct SecretRedactionSettingsContent {
+    /// Whether to replace likely secrets, such as API keys and private keys,
+    /// before indexed code is sent to the embedding endpoint.
+    ///
+    /// Default: true
+    pub enabled: Option<bool>,
+    /// Extra regular expressions whose matches are redacted as well.
+    ///
+    /// Default: []
+    pub patterns: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FileSearchSettings {
+    pub max_concurrent_requests: usize,
+    pub api_path: String,
+    pub explain_results: bool,
+    pub auto_scope: bool,
+    pub compact_cards: bool,
+    pub digest_threshold: usize,
+    pub display_similarity_threshold: f32,
+    pub similarity_metric: SimilarityMetric,
+    pub exclude_compressed: bool,
+    pub query_embedding_model: Option<String>,
+}
+
+impl Default for FileSearchSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: 4,
+            api_path: "/api/v1/search".to_string(),
+            explain_results: false,
+            auto_scope: true,
+            compact_cards: false,
+            digest_threshold: 50,
+            display_similarity_threshold: 0.0,
+            similarity_metric: SimilarityMetric::default(),
+            exclude_compressed: false,
+            query_embedding_model: None,
+        }
+    }
+}
+
+/// How the search backend scores results, so that search cards can show
+/// every score as a relevance between 0 and 1.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SimilarityMetric {
+    /// Scores are already between 0 and 1.
+    #[default]
+    Normalized,
+    /// Cosine similarity, between -1 and 1.
+    Cosine,
+    /// Unbounded dot products, mapped onto 0 to 1 with a logistic curve.
+    DotProduct,
+}
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct FileSearchSettingsContent {
+    /// The maximum number of file searches that run at the same time. Further
+    /// searches wait until one of them finishes.
+    ///
+    /// Default: 4
+    pub max_concurrent_requests: Option<usize>,
+    /// The path of the search endpoint, relative to the LLM API URL. Can also
+    /// be set with the `OPPLA_SEARCH_API_PATH` environment variable.
+    ///
+    /// Default: "/api/v1/search"
+    pub api_path: Option<String>,
+    /// Whether to ask the backend for a short explanation of why each result
+    /// matched. This makes searches slower and more expensive.
+    ///
+    /// Default: false
+    pub explain_results: Option<bool>,
+    /// Whether to scope searches to the synced task when the model
+    /// doesn't set the account, product, board or task filters itself.
+    ///
+    /// Default: true
+    pub auto_scope: Option<bool>,
+    /// Whether search cards in the agent panel show a one-line summary until
+    /// they're expanded, instead of the full result list.
+    ///
+    /// Default: false
+    pub compact_cards: Option<bool>,
+    /// Search cards with at least this many results open on a digest that
+    /// lists the best few results of each type, with the full list behind a
+    /// button. Set to 0 to always show the full list.
+    ///
+    /// Default: 50
+    pub digest_threshold: Option<usize>,
+    /// Results with a lower relevance than this, between 0 and 1, are
+    /// collapsed and greyed out in search cards. The model still receives
+    /// every result.
+    ///
+    /// Default: 0.0
+    pub display_similarity_threshold: Option<f32>,
+    /// How the search backend scores results: "normalized" for scores
+    /// between 0 and 1, "cosine" for cosine similarity between -1 and 1, or
+    /// "dot_product" for unbounded dot products.
+    ///
+    /// Default: "normalized"
+    pub similarity_metric: Option<SimilarityMetric>,
+    /// Whether searches across every type leave out compressed results,
+    /// which are lower-fidelity summaries. The model can still ask for them
+    /// with `include_compressed`.
+    ///
+    /// Default: false
+    pub exclude_compressed: Option<bool>,
+    /// The model the search backend embeds content with. When set, queries
+    /// are embedded once with it and the embedding is sent along with every
+    /// search for the same query, instead of the backend embedding the query
+    /// each time. Only set this when the backend accepts query embeddings.
+    /// Set to null to let the backend embed queries again.
+    ///
+    /// Default: null
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub query_embedding_model: Option<Option<String>>,
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Default)]
@@ -443,6 +776,16 @@ impl Settings for AgentSettings {
                 &mut settings.always_allow_tool_actions,
                 value.always_allow_tool_actions,
             );
+            if let Some(command_permissions) = value.command_permissions.as_ref() {
+                merge(
+                    &mut settings.command_permissions.always_allow,
+                    command_permissions.always_allow.clone(),
+                );
+                merge(
+                    &mut settings.command_permissions.always_confirm,
+                    command_permissions.always_confirm.clone(),
+                );
+            }
             merge(
                 &mut settings.notify_when_agent_waiting,
                 value.notify_when_agent_waiting,
@@ -451,6 +794,7 @@ impl Settings for AgentSettings {
                 &mut settings.play_sound_when_agent_done,
                 value.play_sound_when_agent_done,
             );
+            merge(&mut settings.agent_done_sound, value.agent_done_sound);
             merge(&mut settings.stream_edits, value.stream_edits);
             merge(&mut settings.single_file_review, value.single_file_review);
             merge(&mut settings.default_profile, value.default_profile.clone());
@@ -465,15 +809,79 @@ impl Settings for AgentSettings {
                 &mut settings.expand_terminal_card,
                 value.expand_terminal_card,
             );
+            merge(
+                &mut settings.task_sync_section_expansion,
+                value.task_sync_section_expansion,
+            );
             merge(
                 &mut settings.use_modifier_to_send,
                 value.use_modifier_to_send,
             );
+            merge(&mut settings.log_llm_requests, value.log_llm_requests);
+            merge(&mut settings.warm_up_embeddings, value.warm_up_embeddings);
+            merge(&mut settings.reindex_debounce_ms, value.reindex_debounce_ms);
+            if let Some(secret_redaction) = value.secret_redaction.as_ref() {
+                merge(
+                    &mut settings.secret_redaction.enabled,
+                    secret_redaction.enabled,
+                );
+                merge(
+                    &mut settings.secret_redaction.patterns,
+                    secret_redaction.patterns.clone(),
+                );
+            }
+            if let Some(file_search) = value.file_search.as_ref() {
+                merge(
+                    &mut settings.file_search.max_concurrent_requests,
+                    file_search.max_concurrent_requests,
+                );
+                merge(
+                    &mut settings.file_search.api_path,
+                    file_search.api_path.clone(),
+                );
+                merge(
+                    &mut settings.file_search.explain_results,
+                    file_search.explain_results,
+                );
+                merge(&mut settings.file_search.auto_scope, file_search.auto_scope);
+                merge(
+                    &mut settings.file_search.compact_cards,
+                    file_search.compact_cards,
+                );
+                merge(
+                    &mut settings.file_search.digest_threshold,
+                    file_search.digest_threshold,
+                );
+                merge(
+                    &mut settings.file_search.display_similarity_threshold,
+                    file_search.display_similarity_threshold,
+                );
+                merge(
+                    &mut settings.file_search.similarity_metric,
+                    file_search.similarity_metric,
+                );
+                merge(
+                    &mut settings.file_search.exclude_compressed,
+                    file_search.exclude_compressed,
+                );
+                merge(
+                    &mut settings.file_search.query_embedding_model,
+                    file_search.query_embedding_model.clone(),
+                );
+            }
 
             settings
                 .model_parameters
                 .extend_from_slice(&value.model_parameters);
 
+            if let Some(provider_default_models) = value.provider_default_models.as_ref() {
+                settings.provider_default_models.extend(
+                    provider_default_models
+                        .iter()
+                        .map(|(provider, model)| (provider.clone(), model.clone())),
+                );
+            }
+
             if let Some(profiles) = value.profiles.as_ref() {
                 settings
                     .profiles
@@ -523,3 +931,13 @@ fn merge<T>(target: &mut T, value: Option<T>) {
         *target = value;
     }
 }
+
+/// Tells an explicit `null` (`Some(None)`) apart from a missing key (`None`),
+/// so that a `null` can reset a setting from an earlier source.
+fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
@@ -0,0 +1,311 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug)]
+pub struct CommandPermissionsSettings {
+    pub always_allow: Vec<String>,
+    pub always_confirm: Vec<String>,
+}
+
+impl CommandPermissionsSettings {
+    /// Whether `command` may run without confirmation according to the
+    /// patterns alone, or `None` when they don't decide it and
+    /// `always_allow_tool_actions` applies.
+    ///
+    /// A pattern matches a command when the command starts with the
+    /// pattern's words, so `git push` matches `git push --force origin` but
+    /// not `git pushx`. Commands chained with `&&`, `;`, `|` and the like are
+    /// only allowed when every one of them is, and confirmed when any one of
+    /// them is.
+    pub fn check(&self, command: &str) -> Option<bool> {
+        let segments = command_segments(command);
+        if segments
+            .iter()
+            .any(|segment| matches_any(&self.always_confirm, segment, true))
+        {
+            return Some(false);
+        }
+        // Substituted commands, subshells, groups and redirections aren't
+        // understood well enough to be sure what runs or what gets written.
+        if !segments.is_empty()
+            && !has_unsupported_syntax(command)
+            && segments
+                .iter()
+                .all(|segment| matches_any(&self.always_allow, segment, false))
+        {
+            return Some(true);
+        }
+        None
+    }
+}
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct CommandPermissionsSettingsContent {
+    /// Commands the agent may run without asking for confirmation, such as
+    /// "cargo test". Each pattern matches commands that start with its words.
+    ///
+    /// Default: []
+    pub always_allow: Option<Vec<String>>,
+    /// Commands the agent always asks for confirmation before running, even
+    /// when `always_allow_tool_actions` is enabled, such as "rm" or
+    /// "git push". These take precedence over `always_allow`.
+    ///
+    /// Default: []
+    pub always_confirm: Option<Vec<String>>,
+}
+
+/// Commands that run the command following their options, with the options
+/// that take a separate value.
+const WRAPPERS: &[(&str, &[&str])] = &[
+    ("command", &[]),
+    (
+        "env",
+        &["-u", "--unset", "-C", "--chdir", "-S", "--split-string"],
+    ),
+    (
+        "sudo",
+        &["-u", "-g", "-h", "-p", "-C", "-D", "-r", "-t", "-U"],
+    ),
+    (
+        "xargs",
+        &[
+            "-a",
+            "-d",
+            "-E",
+            "-I",
+            "-L",
+            "-n",
+            "-P",
+            "-s",
+            "--arg-file",
+            "--delimiter",
+        ],
+    ),
+];
+
+/// Git options given before the subcommand that take a separate value.
+const GIT_OPTIONS_WITH_VALUES: &[&str] = &[
+    "-C",
+    "-c",
+    "--git-dir",
+    "--work-tree",
+    "--namespace",
+    "--config-env",
+];
+
+/// The words of each command in a chain or substitution, without the
+/// wrappers (`sudo`, `env`, `xargs`, ...) and environment variable
+/// assignments preceding them, and without git's global options.
+fn command_segments(command: &str) -> Vec<Vec<&str>> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+    let mut chars = command.char_indices().peekable();
+    while let Some((ix, c)) = chars.next() {
+        let next = chars.peek().map(|(_, c)| *c);
+        // `&` is also part of redirections like `2>&1` and `&>file`.
+        let is_separator = match c {
+            '\n' | ';' | '|' | '(' | ')' | '{' | '}' | '`' => true,
+            '&' => !matches!(previous, Some('>' | '<')) && next != Some('>'),
+            _ => false,
+        };
+        if is_separator {
+            segments.push(&command[start..ix]);
+            start = ix + c.len_utf8();
+        }
+        previous = Some(c);
+    }
+    segments.push(&command[start..]);
+
+    segments
+        .into_iter()
+        .map(command_words)
+        .filter(|words| !words.is_empty())
+        .collect()
+}
+
+/// The words of a single command, see [`command_segments`].
+fn command_words(segment: &str) -> Vec<&str> {
+    let words = segment.split_whitespace().collect::<Vec<_>>();
+    let mut words = words.as_slice();
+    loop {
+        while let [word, rest @ ..] = words
+            && is_assignment(word)
+        {
+            words = rest;
+        }
+        let [program, args @ ..] = words else {
+            return Vec::new();
+        };
+        let name = program_name(program);
+        if let Some((_, options_with_values)) =
+            WRAPPERS.iter().find(|(wrapper, _)| *wrapper == name)
+        {
+            words = skip_options(args, options_with_values);
+            continue;
+        }
+
+        let args = if name == "git" {
+            skip_options(args, GIT_OPTIONS_WITH_VALUES)
+        } else {
+            args
+        };
+        let mut segment_words = vec![unescape(program)];
+        segment_words.extend_from_slice(args);
+        return segment_words;
+    }
+}
+
+fn skip_options<'a, 'b>(mut words: &'b [&'a str], options_with_values: &[&str]) -> &'b [&'a str] {
+    while let [word, rest @ ..] = words
+        && word.starts_with('-')
+    {
+        words = rest;
+        if *word == "--" {
+            break;
+        }
+        if options_with_values.contains(word)
+            && let [_, rest @ ..] = words
+        {
+            words = rest;
+        }
+    }
+    words
+}
+
+/// `\rm` and `'rm'` run `rm` too.
+fn unescape(word: &str) -> &str {
+    word.trim_start_matches('\\').trim_matches(['\'', '"'])
+}
+
+/// The name of the program a word runs, so that `/bin/rm` matches `rm`.
+fn program_name(word: &str) -> &str {
+    let word = unescape(word);
+    word.rsplit('/').next().unwrap_or(word)
+}
+
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Whether the command uses substitutions (`$(...)`, `<(...)`, backticks),
+/// subshells, groups or redirects output to a file.
+fn has_unsupported_syntax(command: &str) -> bool {
+    if command.contains(['(', '{', '`']) {
+        return true;
+    }
+    command.match_indices('>').any(|(ix, _)| {
+        let target = command[ix + 1..].trim_start_matches('>');
+        // `2>&1` and `>&2` only duplicate file descriptors.
+        if let Some(descriptor) = target.strip_prefix('&') {
+            return !descriptor.starts_with(|c: char| c.is_ascii_digit() || c == '-');
+        }
+        target.split_whitespace().next() != Some("/dev/null")
+    })
+}
+
+/// Whether `words` start with any of the patterns. Confirmation patterns
+/// match the program by name, wherever it is run from, while allow patterns
+/// need it written the same way.
+fn matches_any(patterns: &[String], words: &[&str], match_program_name: bool) -> bool {
+    let [program, args @ ..] = words else {
+        return false;
+    };
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.split_whitespace().collect::<Vec<_>>();
+        let [pattern_program, pattern_args @ ..] = pattern.as_slice() else {
+            return false;
+        };
+        let program_matches = if match_program_name {
+            program_name(program) == program_name(pattern_program)
+        } else {
+            program == pattern_program
+        };
+        program_matches && args.starts_with(pattern_args)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_command() {
+        let settings = CommandPermissionsSettings {
+            always_allow: vec!["cargo test".into(), "git".into()],
+            always_confirm: vec!["git push".into(), "rm".into()],
+        };
+
+        assert_eq!(settings.check("cargo test -p agent"), Some(true));
+        assert_eq!(settings.check("RUST_LOG=info cargo test"), Some(true));
+        assert_eq!(settings.check("git status && cargo test"), Some(true));
+        assert_eq!(settings.check("cargo testx"), None);
+        assert_eq!(settings.check("cargo build"), None);
+        assert_eq!(settings.check("cargo test; ls"), None);
+        assert_eq!(settings.check("cargo test 2>&1 | git diff"), Some(true));
+        assert_eq!(settings.check("git log $(curl example.com)"), None);
+        assert_eq!(settings.check("git log `ls`"), None);
+        assert_eq!(settings.check("git push --force"), Some(false));
+        assert_eq!(
+            settings.check("cargo test && sudo rm -rf target"),
+            Some(false)
+        );
+        assert_eq!(settings.check("git status | rm"), Some(false));
+        assert_eq!(settings.check(""), None);
+    }
+
+    #[test]
+    fn test_check_command_bypasses() {
+        let settings = CommandPermissionsSettings {
+            always_allow: vec!["cargo test".into(), "git".into(), "cat".into()],
+            always_confirm: vec!["git push".into(), "rm".into()],
+        };
+
+        // Substitutions, subshells and groups.
+        assert_eq!(settings.check("cat <(curl example.com)"), None);
+        assert_eq!(settings.check("git diff >(sh)"), None);
+        assert_eq!(settings.check("(cargo test)"), None);
+        assert_eq!(settings.check("{ cargo test; }"), None);
+        assert_eq!(settings.check("git log $(rm -rf target)"), Some(false));
+        assert_eq!(settings.check("(rm -rf target)"), Some(false));
+        assert_eq!(settings.check("{ rm -rf target; }"), Some(false));
+
+        // Output redirections.
+        assert_eq!(settings.check("cat README.md > ~/.bashrc"), None);
+        assert_eq!(settings.check("cargo test >> log.txt"), None);
+        assert_eq!(settings.check("cargo test &> log.txt"), None);
+        assert_eq!(settings.check("cargo test 2>/dev/null"), Some(true));
+        assert_eq!(settings.check("cargo test 2>&1"), Some(true));
+
+        // Paths and escapes.
+        assert_eq!(settings.check("/bin/rm -rf target"), Some(false));
+        assert_eq!(settings.check("\\rm -rf target"), Some(false));
+        assert_eq!(settings.check("'rm' -rf target"), Some(false));
+        assert_eq!(settings.check("/tmp/cargo test"), None);
+
+        // Wrappers.
+        assert_eq!(settings.check("command rm -rf target"), Some(false));
+        assert_eq!(
+            settings.check("env -i PATH=/bin rm -rf target"),
+            Some(false)
+        );
+        assert_eq!(settings.check("sudo -u root rm -rf target"), Some(false));
+        assert_eq!(settings.check("git ls-files | xargs rm"), Some(false));
+        assert_eq!(
+            settings.check("git ls-files | xargs -I {} rm {}"),
+            Some(false)
+        );
+        assert_eq!(settings.check("env cargo test"), Some(true));
+
+        // Git's global options.
+        assert_eq!(settings.check("git -C . push"), Some(false));
+        assert_eq!(settings.check("git -c user.name=x push"), Some(false));
+        assert_eq!(
+            settings.check("git --no-pager --git-dir=.git push"),
+            Some(false)
+        );
+        assert_eq!(settings.check("git -C . status"), Some(true));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::context_picker::{ContextPicker, MentionLink};
 use crate::context_strip::{ContextStrip, ContextStripEvent, SuggestContextKind};
 use crate::message_editor::{extract_message_creases, insert_message_creases};
+use crate::provider_usage::ProviderUsage;
 use crate::ui::{AddedContext, AgentNotification, AgentNotificationEvent, ContextPill};
 use crate::{AgentPanel, ModelUsageContext};
 use agent::{
@@ -10,7 +11,7 @@ use agent::{
     thread_store::RulesLoadingError,
     tool_use::{PendingToolUseStatus, ToolUse},
 };
-use agent_settings::{AgentSettings, NotifyWhenAgentWaiting};
+use agent_settings::{AgentDoneSound, AgentSettings, NotifyWhenAgentWaiting};
 use anyhow::Context as _;
 use assistant_tool::ToolUseStatus;
 use audio::{Audio, Sound};
@@ -974,6 +975,9 @@ impl ActiveThread {
                 self.last_error = Some(error.clone());
             }
             ThreadEvent::NewRequest => {
+                if let Some(configured_model) = self.thread.read(cx).configured_model() {
+                    ProviderUsage::record_use(&configured_model.provider.id(), cx);
+                }
                 cx.notify();
             }
             ThreadEvent::CompletionCanceled => {
@@ -1185,7 +1189,7 @@ impl ActiveThread {
                     cx,
                 );
             }
-            ThreadEvent::ProfileChanged => {
+            ThreadEvent::ProfileChanged | ThreadEvent::PinnedSnippetsChanged => {
                 self.save_thread(cx);
                 cx.notify();
             }
@@ -1208,7 +1212,7 @@ impl ActiveThread {
     fn play_notification_sound(&self, window: &Window, cx: &mut App) {
         let settings = AgentSettings::get_global(cx);
         if settings.play_sound_when_agent_done && !window.is_window_active() {
-            Audio::play_sound(Sound::AgentDone, cx);
+            play_agent_done_sound(cx);
         }
     }
 
@@ -3815,6 +3819,20 @@ fn open_editor_at_position(
     })
 }
 
+/// Plays the sound chosen in `agent_done_sound`.
+pub(crate) fn play_agent_done_sound(cx: &mut App) {
+    let sound = match AgentSettings::get_global(cx).agent_done_sound {
+        AgentDoneSound::Chime => Sound::AgentDone,
+        AgentDoneSound::Rise => Sound::Joined,
+        AgentDoneSound::Fall => Sound::Leave,
+        AgentDoneSound::Click => Sound::Unmute,
+        AgentDoneSound::SystemDefault => {
+            return Audio::play_system_sound(Sound::AgentDone, cx);
+        }
+    };
+    Audio::play_sound(sound, cx);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
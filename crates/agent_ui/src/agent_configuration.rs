@@ -1,39 +1,57 @@
 mod add_llm_provider_modal;
+mod command_patterns;
 mod configure_context_server_modal;
+mod context_servers_json;
 mod manage_profiles_modal;
+mod sync_callback_page;
+mod sync_from_url_modal;
+mod test_search;
 mod tool_picker;
+mod try_tool_modal;
 
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
-use agent_settings::AgentSettings;
+use agent::ContextServerInvocationLog;
+use agent_settings::{AgentDoneSound, AgentSettings, TaskSyncSectionExpansion};
 use anyhow::Context as _;
 use assistant_tool::{ToolSource, ToolWorkingSet};
-use client::Client;
-use collections::HashMap;
+use chrono::Local;
+use client::{CLOUD_FEATURES_DISABLED_MESSAGE, Client, DisableCloudFeaturesSettings};
+use collections::{HashMap, HashSet};
 use context_server::ContextServerId;
-use extension::ExtensionManifest;
+use db::kvp::KEY_VALUE_STORE;
+use extension::{ExtensionManifest, ExtensionTaskContextProxy, SyncedTaskContext};
 use extension_host::ExtensionStore;
 use fs::Fs;
+use futures::FutureExt as _;
 use gpui::{
-    Action, Animation, AnimationExt as _, AnyView, App, Corner, Entity, EventEmitter, FocusHandle,
-    Focusable, ScrollHandle, Subscription, Task, Transformation, WeakEntity, percentage,
+    Action, Animation, AnimationExt as _, AnyView, App, AsyncApp, ClipboardItem, Corner, Entity,
+    EntityId, EventEmitter, FocusHandle, Focusable, PathPromptOptions, PromptLevel, ScrollHandle,
+    Subscription, Task, Transformation, WeakEntity, percentage,
 };
 use language::LanguageRegistry;
 use language_model::{
-    LanguageModelProvider, LanguageModelProviderId, LanguageModelRegistry, ZED_CLOUD_PROVIDER_ID,
+    LanguageModelProvider, LanguageModelProviderId, LanguageModelRegistry, RefreshLlmTokenEvent,
+    RefreshLlmTokenListener, ZED_CLOUD_PROVIDER_ID, llm_token_expires_at,
 };
 use notifications::status_toast::{StatusToast, ToastIcon};
 use oppla_actions::ExtensionCategoryFilter;
 use project::{
+    Project, WorktreeId,
     context_server_store::{ContextServerConfiguration, ContextServerStatus, ContextServerStore},
     project_settings::{ContextServerSettings, ProjectSettings},
 };
 use proto::{self, Plan};
 use settings::{Settings, update_settings_file};
 use ui::{
-    Chip, ContextMenu, Disclosure, Divider, DividerColor, ElevationIndex, Indicator, PopoverMenu,
-    Scrollbar, ScrollbarState, Switch, SwitchColor, SwitchField, Tooltip, prelude::*,
+    Banner, Chip, ContextMenu, DateTimeType, Disclosure, Divider, DividerColor, DropdownMenu,
+    ElevationIndex, Indicator, PopoverMenu, Scrollbar, ScrollbarState, Switch, SwitchColor,
+    SwitchField, Tooltip, format_distance_from_now, prelude::*,
 };
+use ui_input::SingleLineInput;
 use url::Url;
 use util::ResultExt as _;
 use workspace::Workspace;
@@ -41,43 +59,200 @@ use workspace::Workspace;
 pub(crate) use configure_context_server_modal::ConfigureContextServerModal;
 pub(crate) use manage_profiles_modal::ManageProfilesModal;
 
+const CONTEXT_SERVER_TOGGLE_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// How often the session expiry shown in the Oppla provider block is updated.
+const TOKEN_EXPIRY_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+/// Sessions closer than this to expiring are shown as a warning.
+const TOKEN_EXPIRY_WARNING_THRESHOLD: Duration = Duration::from_secs(10 * 60);
+
+const TASK_SYNC_URL: &str = "https://app.oppla.ai/home/ide";
+const SIGN_IN_URL: &str = "https://app.oppla.ai/auth/sign-in";
+/// How long acquiring a token before a task sync may take before the sync is
+/// given up, instead of opening a browser tab that can't complete.
+const TASK_SYNC_TOKEN_TIMEOUT: Duration = Duration::from_secs(10);
+/// Shown when no token could be acquired for a task sync.
+const TASK_SYNC_SIGN_IN_MESSAGE: &str =
+    "Unable to sync task. Please ensure you're signed in to Oppla and try again.";
+
 // Global IDE context for storing synced task information
 use gpui::Global;
 use serde_json;
 use std::sync::RwLock;
 
 pub struct IdeContext {
+    /// The task synced most recently in any workspace. Projects that haven't
+    /// been seen yet start from it.
     pub sync_data: RwLock<Option<TaskSyncData>>,
+    /// The task each project has synced, or `None` when it was cleared, keyed
+    /// by the project's entity id.
+    pub project_sync_data: RwLock<HashMap<EntityId, Option<TaskSyncData>>>,
+    /// How syncing a task in each project last went, keyed by the project's
+    /// entity id.
+    pub project_sync_status: RwLock<HashMap<EntityId, SyncStatus>>,
 }
 
 impl Global for IdeContext {}
 
+/// Where syncing a task with the web app stands for a project.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SyncStatus {
+    #[default]
+    NotSynced,
+    /// Waiting on the web app to send the task back.
+    Syncing,
+    Synced {
+        at: SystemTime,
+    },
+    Failed {
+        reason: SharedString,
+    },
+}
+
 impl IdeContext {
     pub fn init(cx: &mut App) {
         cx.set_global(IdeContext {
             sync_data: RwLock::new(None),
+            project_sync_data: RwLock::new(HashMap::default()),
+            project_sync_status: RwLock::new(HashMap::default()),
         });
     }
 
+    /// Runs `f` on the global context, creating it if needed, and notifies
+    /// the views observing it that the synced task may have changed.
+    pub fn update(cx: &mut App, f: impl FnOnce(&IdeContext)) {
+        if !cx.has_global::<IdeContext>() {
+            IdeContext::init(cx);
+        }
+        cx.update_global::<IdeContext, _>(|ide_context, _| f(ide_context));
+    }
+
+    /// The task synced most recently in any workspace.
     pub fn get_sync_data(&self) -> Option<TaskSyncData> {
         self.sync_data.read().ok()?.clone()
     }
 
-    pub fn set_sync_data(&self, data: TaskSyncData) {
+    /// Has the project adopt the most recently synced task, so that a later
+    /// sync in another workspace doesn't change it, and forgets the project's
+    /// task once the project is released.
+    pub fn track_project(project: &Entity<Project>, cx: &mut App) {
+        let project_id = project.entity_id();
+        IdeContext::update(cx, |ide_context| {
+            if let Ok(mut project_sync_data) = ide_context.project_sync_data.write() {
+                project_sync_data
+                    .entry(project_id)
+                    .or_insert_with(|| ide_context.get_sync_data());
+            }
+        });
+        cx.observe_release(project, move |_, cx| {
+            if let Some(ide_context) = cx.try_global::<IdeContext>() {
+                ide_context.forget_project(project_id);
+            }
+        })
+        .detach();
+    }
+
+    fn forget_project(&self, project_id: EntityId) {
+        if let Ok(mut project_sync_data) = self.project_sync_data.write() {
+            project_sync_data.remove(&project_id);
+        }
+        if let Ok(mut project_sync_status) = self.project_sync_status.write() {
+            project_sync_status.remove(&project_id);
+        }
+    }
+
+    /// The task synced in the given project. A project that isn't tracked
+    /// yet sees the most recently synced one.
+    pub fn get_project_sync_data(&self, project_id: EntityId) -> Option<TaskSyncData> {
+        self.snapshot(project_id, |_| ())
+            .map(|(sync_data, ())| sync_data)
+    }
+
+    /// Returns the project's sync data along with the filter `build_filter`
+    /// derives from it, both read under the same lock so that a concurrent
+    /// [`Self::set_sync_data`] can't land between them.
+    pub fn snapshot<F>(
+        &self,
+        project_id: EntityId,
+        build_filter: impl FnOnce(&TaskSyncData) -> F,
+    ) -> Option<(TaskSyncData, F)> {
+        let project_sync_data = self.project_sync_data.read().ok()?;
+        let sync_data = match project_sync_data.get(&project_id) {
+            Some(sync_data) => sync_data.clone()?,
+            None => self.get_sync_data()?,
+        };
+        let filter = build_filter(&sync_data);
+        Some((sync_data, filter))
+    }
+
+    /// How syncing a task in the given project last went. A project that
+    /// hasn't synced itself is synced when it adopted another project's task.
+    pub fn sync_status(&self, project_id: EntityId) -> SyncStatus {
+        let status = self
+            .project_sync_status
+            .read()
+            .ok()
+            .and_then(|project_sync_status| project_sync_status.get(&project_id).cloned());
+        if let Some(status) = status {
+            return status;
+        }
+        match self.get_project_sync_data(project_id) {
+            Some(sync_data) => SyncStatus::Synced {
+                at: sync_data.synced_at,
+            },
+            None => SyncStatus::NotSynced,
+        }
+    }
+
+    pub fn set_sync_status(&self, project_id: EntityId, status: SyncStatus) {
+        if let Ok(mut project_sync_status) = self.project_sync_status.write() {
+            project_sync_status.insert(project_id, status);
+        }
+    }
+
+    /// Stores the task synced in the given project, which also becomes the
+    /// task projects that haven't been seen yet start from.
+    pub fn set_sync_data(&self, project_id: Option<EntityId>, data: TaskSyncData) {
+        if let Some(project_id) = project_id {
+            if let Ok(mut project_sync_data) = self.project_sync_data.write() {
+                project_sync_data.insert(project_id, Some(data.clone()));
+            }
+            self.set_sync_status(project_id, SyncStatus::Synced { at: data.synced_at });
+        }
         if let Ok(mut sync_data) = self.sync_data.write() {
             *sync_data = Some(data);
         }
     }
 
-    pub fn clear_sync_data(&self) {
-        if let Ok(mut sync_data) = self.sync_data.write() {
+    /// Clears the task synced in the given project, or the most recent task
+    /// when there's no project. Clearing a project's task also clears it as
+    /// the most recent one, so that projects seen later don't adopt it.
+    pub fn clear_sync_data(&self, project_id: Option<EntityId>) {
+        let cleared = match project_id {
+            Some(project_id) => {
+                let cleared =
+                    self.project_sync_data
+                        .write()
+                        .ok()
+                        .and_then(|mut project_sync_data| {
+                            project_sync_data.insert(project_id, None).flatten()
+                        });
+                self.set_sync_status(project_id, SyncStatus::NotSynced);
+                cleared
+            }
+            None => None,
+        };
+        if let Ok(mut sync_data) = self.sync_data.write()
+            && (project_id.is_none() || *sync_data == cleared)
+        {
             *sync_data = None;
         }
     }
 
-    // Helper method to get context filter for API searches
-    pub fn get_context_filter(&self) -> Option<serde_json::Value> {
-        let data = self.get_sync_data()?;
+    /// The filter for API searches scoped to the task synced in the given
+    /// project.
+    pub fn get_context_filter(&self, project_id: EntityId) -> Option<serde_json::Value> {
+        let data = self.get_project_sync_data(project_id)?;
 
         let mut filter = serde_json::json!({
             "type": "tasks"
@@ -109,12 +284,123 @@ impl IdeContext {
     }
 }
 
+/// Exposes the synced task to extensions that have the `task-context:read`
+/// capability, after asking the user to allow each extension once.
+#[derive(Default)]
+pub(crate) struct IdeContextTaskContextProxy {
+    /// Extensions the user refused access to in this session, so that they
+    /// aren't asked again on every read.
+    denied: Arc<parking_lot::Mutex<HashSet<Arc<str>>>>,
+}
+
+impl IdeContextTaskContextProxy {
+    fn grant_key(extension_id: &str) -> String {
+        format!("task_context_read_granted:{extension_id}")
+    }
+
+    /// The project containing the given worktree, or the active workspace's
+    /// project when there's no worktree.
+    fn project_id(worktree_id: Option<u64>, cx: &App) -> Option<EntityId> {
+        let workspace = match worktree_id {
+            Some(worktree_id) => {
+                let worktree_id = WorktreeId::from_proto(worktree_id);
+                cx.windows().into_iter().find_map(|window| {
+                    let workspace = window.downcast::<Workspace>()?.read(cx).ok()?;
+                    workspace
+                        .project()
+                        .read(cx)
+                        .worktree_for_id(worktree_id, cx)
+                        .map(|_| workspace)
+                })?
+            }
+            None => cx.active_window()?.downcast::<Workspace>()?.read(cx).ok()?,
+        };
+        Some(workspace.project().entity_id())
+    }
+
+    fn task_context(worktree_id: Option<u64>, cx: &App) -> Option<SyncedTaskContext> {
+        let project_id = Self::project_id(worktree_id, cx)?;
+        let data = cx
+            .try_global::<IdeContext>()?
+            .get_project_sync_data(project_id)?;
+        Some(SyncedTaskContext {
+            board_id: data.board_id.to_string(),
+            board_name: data.big_bet.map(|name| name.to_string()),
+            task_id: data.task_id.map(|id| id.to_string()),
+            task_name: data.work_item.map(|name| name.to_string()),
+        })
+    }
+}
+
+impl ExtensionTaskContextProxy for IdeContextTaskContextProxy {
+    fn synced_task_context(
+        &self,
+        extension_id: Arc<str>,
+        extension_name: SharedString,
+        worktree_id: Option<u64>,
+        cx: &mut App,
+    ) -> Task<anyhow::Result<Option<SyncedTaskContext>>> {
+        if self.denied.lock().contains(&extension_id) {
+            return Task::ready(Err(anyhow::anyhow!(
+                "access to the task context was denied"
+            )));
+        }
+
+        let denied = self.denied.clone();
+        cx.spawn(async move |cx| {
+            let key = Self::grant_key(&extension_id);
+            let granted = cx
+                .background_spawn({
+                    let key = key.clone();
+                    async move { KEY_VALUE_STORE.read_kvp(&key) }
+                })
+                .await?
+                .is_some();
+
+            if !granted {
+                let window = cx
+                    .update(|cx| cx.active_window().or_else(|| cx.windows().first().copied()))?
+                    .context("no window to ask for access to the task context")?;
+                let answer = window.update(cx, |_, window, cx| {
+                    window.prompt(
+                        PromptLevel::Info,
+                        &format!("Allow {extension_name} to read your synced task?"),
+                        Some(
+                            "The extension will be able to see the board and task you synced from Oppla.",
+                        ),
+                        &["Allow", "Deny"],
+                        cx,
+                    )
+                })?;
+                if answer.await != Ok(0) {
+                    denied.lock().insert(extension_id);
+                    anyhow::bail!("access to the task context was denied");
+                }
+                KEY_VALUE_STORE.write_kvp(key, "true".to_string()).await?;
+            }
+
+            cx.update(|cx| Self::task_context(worktree_id, cx))
+        })
+    }
+}
+
 use crate::{
     AddContextServer,
-    agent_configuration::add_llm_provider_modal::{AddLlmProviderModal, LlmCompatibleProvider},
+    active_thread::play_agent_done_sound,
+    agent_configuration::{
+        add_llm_provider_modal::{AddLlmProviderModal, LlmCompatibleProvider},
+        command_patterns::CommandPatterns,
+        context_servers_json::{export_context_servers, parse_context_servers},
+        sync_callback_page::SyncCallbackPage,
+        sync_from_url_modal::SyncFromUrlModal,
+        test_search::TestSearch,
+        try_tool_modal::TryToolModal,
+    },
+    provider_credentials::ProviderCredentials,
+    provider_usage::ProviderUsage,
 };
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TaskSyncData {
     // Account information
     pub account_id: SharedString,
@@ -135,17 +421,101 @@ pub struct TaskSyncData {
     pub work_item_description: Option<SharedString>, // Task description
 
     // Metadata
-    pub synced_at: Option<std::time::SystemTime>,
+    /// When the task was received from the web app.
+    pub synced_at: std::time::SystemTime,
+}
+
+impl TaskSyncData {
+    /// Reads the sync data from the query parameters the web app sends to the
+    /// sync callback, which are the same ones in the URL it shows for pasting.
+    pub(crate) fn from_url(url: &Url) -> Self {
+        let mut sync_data = TaskSyncData {
+            account_id: SharedString::default(),
+            account_name: SharedString::default(),
+            product_id: SharedString::default(),
+            product_name: SharedString::default(),
+            board_id: SharedString::default(),
+            big_bet: None,
+            big_bet_description: None,
+            task_id: None,
+            work_item: None,
+            work_item_description: None,
+            synced_at: std::time::SystemTime::now(),
+        };
+
+        for (key, value) in url.query_pairs() {
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            let value = SharedString::from(value.to_string());
+            match key.as_ref() {
+                "account_id" => sync_data.account_id = value,
+                "account_name" => sync_data.account_name = value,
+                "product_id" => sync_data.product_id = value,
+                "product_name" => sync_data.product_name = value,
+                "board_id" => sync_data.board_id = value,
+                "board_name" => sync_data.big_bet = Some(value),
+                "board_description" => sync_data.big_bet_description = Some(value),
+                "task_id" => sync_data.task_id = Some(value),
+                "task_name" => sync_data.work_item = Some(value),
+                "task_description" => sync_data.work_item_description = Some(value),
+                _ => {}
+            }
+        }
+        sync_data
+    }
+
+    /// Fails when an id that every synced task needs is missing, or when any
+    /// id can't be one the web app issued.
+    pub(crate) fn ensure_required_ids(&self) -> anyhow::Result<()> {
+        for (name, value) in [
+            ("account_id", &self.account_id),
+            ("product_id", &self.product_id),
+            ("board_id", &self.board_id),
+        ] {
+            if value.is_empty() {
+                anyhow::bail!("The sync URL is missing `{name}`.");
+            }
+            ensure_valid_id(name, value)?;
+        }
+        if let Some(task_id) = &self.task_id {
+            ensure_valid_id("task_id", task_id)?;
+        }
+        Ok(())
+    }
+}
+
+const MAX_SYNC_ID_LEN: usize = 128;
+
+/// Rejects values a broken web app page could send in place of an id, such as
+/// a stringified `undefined`, which would otherwise become a search filter
+/// that matches nothing.
+fn ensure_valid_id(name: &str, value: &str) -> anyhow::Result<()> {
+    let is_placeholder = ["undefined", "null", "nan"]
+        .iter()
+        .any(|placeholder| value.eq_ignore_ascii_case(placeholder));
+    let is_malformed = value.len() > MAX_SYNC_ID_LEN
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || matches!(c, '"' | '\\' | '/'));
+    if is_placeholder || is_malformed {
+        anyhow::bail!("The sync URL has an invalid `{name}`.");
+    }
+    Ok(())
 }
 
 pub struct AgentConfiguration {
     fs: Arc<dyn Fs>,
     language_registry: Arc<LanguageRegistry>,
     workspace: WeakEntity<Workspace>,
+    /// The project whose synced task this configuration shows.
+    project_id: EntityId,
     focus_handle: FocusHandle,
     configuration_views_by_provider: HashMap<LanguageModelProviderId, AnyView>,
     context_server_store: Entity<ContextServerStore>,
     expanded_context_server_tools: HashMap<ContextServerId, bool>,
+    context_server_tool_filters: HashMap<ContextServerId, Entity<SingleLineInput>>,
     expanded_provider_configurations: HashMap<LanguageModelProviderId, bool>,
     tools: Entity<ToolWorkingSet>,
     _registry_subscription: Subscription,
@@ -153,6 +523,13 @@ pub struct AgentConfiguration {
     scrollbar_state: ScrollbarState,
     task_sync_expanded: bool,
     task_sync_data: Option<TaskSyncData>,
+    task_sync_status: SyncStatus,
+    pending_context_server_toggles: HashMap<Arc<str>, bool>,
+    _save_context_server_toggles: Task<()>,
+    authenticated_providers: HashSet<LanguageModelProviderId>,
+    test_search: Entity<TestSearch>,
+    command_patterns: Entity<CommandPatterns>,
+    _update_token_expiry: Task<()>,
 }
 
 impl AgentConfiguration {
@@ -162,10 +539,18 @@ impl AgentConfiguration {
         tools: Entity<ToolWorkingSet>,
         language_registry: Arc<LanguageRegistry>,
         workspace: WeakEntity<Workspace>,
+        project_id: EntityId,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Self {
         let focus_handle = cx.focus_handle();
+        let task_sync_data = cx
+            .try_global::<IdeContext>()
+            .and_then(|ide_context| ide_context.get_project_sync_data(project_id));
+        let task_sync_status = cx
+            .try_global::<IdeContext>()
+            .map(|ide_context| ide_context.sync_status(project_id))
+            .unwrap_or_default();
 
         let registry_subscription = cx.subscribe_in(
             &LanguageModelRegistry::global(cx),
@@ -180,44 +565,142 @@ impl AgentConfiguration {
                 language_model::Event::RemovedProvider(provider_id) => {
                     this.remove_provider_configuration_view(provider_id);
                 }
+                // Some providers load their models asynchronously.
+                language_model::Event::ProviderStateChanged => {
+                    for provider in LanguageModelRegistry::read_global(cx).providers() {
+                        this.expand_if_terms_unaccepted(&provider, cx);
+                    }
+                    this.handle_provider_auth_changes(cx);
+                    cx.notify();
+                }
                 _ => {}
             },
         );
 
-        cx.subscribe(&context_server_store, |_, _, _, cx| cx.notify())
+        cx.subscribe(&context_server_store, |_, _, event, cx| {
+            let project::context_server_store::Event::ServerStatusChanged { server_id, status } =
+                event;
+            if let Some(change) = server_status_announcement(status) {
+                cx.announce(&format!("MCP server {} {change}", server_id.0));
+            }
+            cx.notify();
+        })
+        .detach();
+        cx.observe_global::<ContextServerInvocationLog>(|_, cx| cx.notify())
+            .detach();
+        // Credentials can be revoked server-side after they were entered.
+        ProviderCredentials::revalidate(cx);
+        cx.observe_global::<ProviderCredentials>(|_, cx| cx.notify())
+            .detach();
+        // The task can also be synced or cleared from the status bar.
+        cx.observe_global::<IdeContext>(|this, cx| {
+            let was_synced = this.task_sync_data.is_some();
+            this.task_sync_data = cx
+                .try_global::<IdeContext>()
+                .and_then(|ide_context| ide_context.get_project_sync_data(this.project_id));
+            let sync_status = cx
+                .try_global::<IdeContext>()
+                .map(|ide_context| ide_context.sync_status(this.project_id))
+                .unwrap_or_default();
+            if let Some(announcement) =
+                sync_status_announcement(&this.task_sync_status, &sync_status)
+            {
+                cx.announce(&announcement);
+            }
+            this.task_sync_status = sync_status;
+            if was_synced
+                && this.task_sync_data.is_none()
+                && AgentSettings::get_global(cx).task_sync_section_expansion
+                    == TaskSyncSectionExpansion::Auto
+            {
+                this.task_sync_expanded = true;
+            }
+            cx.notify();
+        })
+        .detach();
+        cx.on_release(|this, cx| this.save_context_server_toggles(cx))
             .detach();
 
         let scroll_handle = ScrollHandle::new();
         let scrollbar_state = ScrollbarState::new(scroll_handle.clone());
 
-        let mut expanded_provider_configurations = HashMap::default();
-        if LanguageModelRegistry::read_global(cx)
-            .provider(&ZED_CLOUD_PROVIDER_ID)
-            .map_or(false, |cloud_provider| cloud_provider.must_accept_terms(cx))
-        {
-            expanded_provider_configurations.insert(ZED_CLOUD_PROVIDER_ID, true);
-        }
+        let test_search =
+            cx.new(|cx| TestSearch::new(tools.clone(), workspace.clone(), window, cx));
+        let command_patterns = cx.new(|cx| CommandPatterns::new(fs.clone(), window, cx));
 
         let mut this = Self {
             fs,
             language_registry,
             workspace,
+            project_id,
             focus_handle,
             configuration_views_by_provider: HashMap::default(),
             context_server_store,
             expanded_context_server_tools: HashMap::default(),
-            expanded_provider_configurations,
+            context_server_tool_filters: HashMap::default(),
+            expanded_provider_configurations: HashMap::default(),
             tools,
             _registry_subscription: registry_subscription,
             scroll_handle,
             scrollbar_state,
-            task_sync_expanded: true, // Start expanded if no task is synced
-            task_sync_data: None,     // Initially no task is synced
+            task_sync_expanded: AgentSettings::get_global(cx)
+                .task_sync_section_expansion
+                .is_initially_expanded(task_sync_data.is_some()),
+            task_sync_data,
+            task_sync_status,
+            pending_context_server_toggles: HashMap::default(),
+            _save_context_server_toggles: Task::ready(()),
+            authenticated_providers: authenticated_providers(cx),
+            test_search,
+            command_patterns,
+            _update_token_expiry: cx.spawn(async move |this, cx| {
+                loop {
+                    cx.background_executor()
+                        .timer(TOKEN_EXPIRY_REFRESH_INTERVAL)
+                        .await;
+                    if llm_token_expires_at().is_none() {
+                        continue;
+                    }
+                    if this.update(cx, |_, cx| cx.notify()).is_err() {
+                        break;
+                    }
+                }
+            }),
         };
         this.build_provider_configuration_views(window, cx);
         this
     }
 
+    /// Announces the providers that were authenticated or signed out since this
+    /// was last checked, and forgets their credential rejections.
+    fn handle_provider_auth_changes(&mut self, cx: &mut Context<Self>) {
+        let authenticated = authenticated_providers(cx);
+        let changed = authenticated
+            .symmetric_difference(&self.authenticated_providers)
+            .cloned()
+            .collect::<Vec<_>>();
+        let registry = LanguageModelRegistry::read_global(cx);
+        let announcements = changed
+            .iter()
+            .filter_map(|provider_id| {
+                let provider = registry.provider(provider_id)?;
+                let change = if authenticated.contains(provider_id) {
+                    "authenticated"
+                } else {
+                    "signed out"
+                };
+                Some(format!("Provider {} {change}", provider.name().0))
+            })
+            .collect::<Vec<_>>();
+        if !announcements.is_empty() {
+            cx.announce(&announcements.join(". "));
+        }
+        for provider_id in changed {
+            ProviderCredentials::clear(&provider_id, cx);
+        }
+        self.authenticated_providers = authenticated;
+    }
+
     fn build_provider_configuration_views(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let providers = LanguageModelRegistry::read_global(cx).providers();
         for provider in providers {
@@ -239,6 +722,40 @@ impl AgentConfiguration {
         let configuration_view = provider.configuration_view(window, cx);
         self.configuration_views_by_provider
             .insert(provider.id(), configuration_view);
+        self.expand_if_terms_unaccepted(provider, cx);
+    }
+
+    /// Expands the provider when it has terms that must be accepted, so the
+    /// prompt its configuration view shows is seen before starting a thread
+    /// fails. A provider the user has collapsed stays collapsed.
+    fn expand_if_terms_unaccepted(&mut self, provider: &Arc<dyn LanguageModelProvider>, cx: &App) {
+        if provider.must_accept_terms(cx) {
+            self.expanded_provider_configurations
+                .entry(provider.id())
+                .or_insert(true);
+        }
+    }
+
+    fn ensure_tool_filter_input(
+        &mut self,
+        context_server_id: &ContextServerId,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self
+            .context_server_tool_filters
+            .contains_key(context_server_id)
+        {
+            return;
+        }
+
+        let input = cx.new(|cx| {
+            SingleLineInput::new(window, cx, "Filter tools…").start_icon(IconName::MagnifyingGlass)
+        });
+        cx.observe(input.read(cx).editor(), |_, _, cx| cx.notify())
+            .detach();
+        self.context_server_tool_filters
+            .insert(context_server_id.clone(), input);
     }
 }
 
@@ -276,6 +793,11 @@ impl AgentConfiguration {
             .unwrap_or(false);
 
         let is_zed_provider = provider.id() == ZED_CLOUD_PROVIDER_ID;
+        let must_accept_terms = provider.must_accept_terms(cx);
+        let credentials_rejection = provider
+            .is_authenticated(cx)
+            .then(|| ProviderCredentials::rejection(&provider.id(), cx))
+            .flatten();
         let current_plan = if is_zed_provider {
             self.workspace
                 .upgrade()
@@ -291,6 +813,19 @@ impl AgentConfiguration {
             })
             .unwrap_or(false);
 
+        let last_used_label = match ProviderUsage::last_used(&provider.id(), cx) {
+            Some(last_used) => format!(
+                "Last used {}",
+                format_distance_from_now(
+                    DateTimeType::Local(last_used.with_timezone(&Local)),
+                    false,
+                    true,
+                    false,
+                )
+            ),
+            None => "Never used".to_string(),
+        };
+
         v_flex()
             .w_full()
             .when(is_expanded, |this| this.mb_2())
@@ -343,9 +878,29 @@ impl AgentConfiguration {
                                                     this.child(
                                                         self.render_zed_plan_info(current_plan, cx),
                                                     )
+                                                    .children(self.render_token_expiry())
                                                 } else {
-                                                    this.when(
+                                                    this.when_some(
+                                                        credentials_rejection.clone(),
+                                                        |parent, rejection| {
+                                                            parent.child(
+                                                                div()
+                                                                    .id("credentials-rejected")
+                                                                    .tooltip(Tooltip::text(
+                                                                        rejection,
+                                                                    ))
+                                                                    .child(
+                                                                        Icon::new(
+                                                                            IconName::Warning,
+                                                                        )
+                                                                        .color(Color::Warning),
+                                                                    ),
+                                                            )
+                                                        },
+                                                    )
+                                                    .when(
                                                         provider.is_authenticated(cx)
+                                                            && credentials_rejection.is_none()
                                                             && !is_expanded,
                                                         |parent| {
                                                             parent.child(
@@ -355,6 +910,31 @@ impl AgentConfiguration {
                                                         },
                                                     )
                                                 }
+                                            })
+                                            .when(must_accept_terms, |this| {
+                                                this.child(
+                                                    div()
+                                                        .id("terms-not-accepted")
+                                                        .tooltip(Tooltip::text(
+                                                            "Accept this provider's terms of service to start threads with it.",
+                                                        ))
+                                                        .child(
+                                                            Chip::new("Terms not accepted")
+                                                                .label_color(Color::Warning)
+                                                                .bg_color(
+                                                                    cx.theme()
+                                                                        .status()
+                                                                        .warning_background,
+                                                                ),
+                                                        ),
+                                                )
+                                            })
+                                            .when(provider.is_authenticated(cx), |this| {
+                                                this.child(
+                                                    Label::new(last_used_label)
+                                                        .size(LabelSize::Small)
+                                                        .color(Color::Muted),
+                                                )
                                             }),
                                     ),
                             )
@@ -376,25 +956,50 @@ impl AgentConfiguration {
                             })),
                     )
                     .when(provider.is_authenticated(cx), |parent| {
-                        parent.child(
-                            Button::new(
-                                SharedString::from(format!("new-thread-{provider_id}")),
-                                "Start New Thread",
+                        parent
+                            .child(self.render_provider_default_model_picker(provider, cx))
+                            .child(
+                                Button::new(
+                                    SharedString::from(format!("new-thread-{provider_id}")),
+                                    "Start New Thread",
+                                )
+                                .icon_position(IconPosition::Start)
+                                .icon(IconName::Plus)
+                                .icon_size(IconSize::Small)
+                                .icon_color(Color::Muted)
+                                .label_size(LabelSize::Small)
+                                .on_click(cx.listener({
+                                    let provider = provider.clone();
+                                    move |_this, _event, _window, cx| {
+                                        cx.emit(AssistantConfigurationEvent::NewThread(
+                                            provider.clone(),
+                                        ))
+                                    }
+                                })),
                             )
-                            .icon_position(IconPosition::Start)
-                            .icon(IconName::Plus)
-                            .icon_size(IconSize::Small)
-                            .icon_color(Color::Muted)
-                            .label_size(LabelSize::Small)
-                            .on_click(cx.listener({
-                                let provider = provider.clone();
-                                move |_this, _event, _window, cx| {
-                                    cx.emit(AssistantConfigurationEvent::NewThread(
-                                        provider.clone(),
-                                    ))
-                                }
-                            })),
-                        )
+                            .when(!is_zed_provider, |parent| {
+                                parent.child(
+                                    IconButton::new(
+                                        SharedString::from(format!(
+                                            "clear-credentials-{provider_id}"
+                                        )),
+                                        IconName::Trash,
+                                    )
+                                    .icon_size(IconSize::Small)
+                                    .icon_color(Color::Muted)
+                                    .tooltip(Tooltip::text("Clear Credentials"))
+                                    .on_click(cx.listener({
+                                        let provider = provider.clone();
+                                        move |this, _event, window, cx| {
+                                            this.reset_provider_credentials(
+                                                provider.clone(),
+                                                window,
+                                                cx,
+                                            );
+                                        }
+                                    })),
+                                )
+                            })
                     }),
             )
             .child(
@@ -409,6 +1014,93 @@ impl AgentConfiguration {
             )
     }
 
+    fn render_provider_default_model_picker(
+        &self,
+        provider: &Arc<dyn LanguageModelProvider>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement + use<> {
+        let provider_id = provider.id().0.clone();
+        let models = provider.provided_models(cx);
+        let selected_model_id = AgentSettings::get_global(cx)
+            .default_model_for_provider(&provider_id)
+            .map(SharedString::new)
+            .or_else(|| provider.default_model(cx).map(|model| model.id().0));
+        let selected_model_name = selected_model_id
+            .as_ref()
+            .and_then(|model_id| models.iter().find(|model| model.id().0 == *model_id))
+            .map(|model| model.name().0);
+
+        let trigger_label = if !models.is_empty() {
+            selected_model_name
+                .or(selected_model_id.clone())
+                .unwrap_or_else(|| SharedString::from("Select a model"))
+        } else if provider.is_loading_models(cx) {
+            SharedString::from("Loading models…")
+        } else {
+            SharedString::from("No models available")
+        };
+
+        PopoverMenu::new(SharedString::from(format!(
+            "provider-default-model-{provider_id}"
+        )))
+        .trigger_with_tooltip(
+            Button::new(
+                SharedString::from(format!("provider-default-model-trigger-{provider_id}")),
+                trigger_label,
+            )
+            .icon(IconName::ChevronDown)
+            .icon_size(IconSize::XSmall)
+            .icon_color(Color::Muted)
+            .label_size(LabelSize::Small)
+            .disabled(models.is_empty()),
+            Tooltip::text("Default model for new threads started from this provider"),
+        )
+        .anchor(Corner::TopRight)
+        .menu({
+            let fs = self.fs.clone();
+            move |window, cx| {
+                let models = models.clone();
+                let fs = fs.clone();
+                let provider_id = provider_id.clone();
+                let selected_model_id = selected_model_id.clone();
+                Some(ContextMenu::build(
+                    window,
+                    cx,
+                    move |mut menu, _window, _cx| {
+                        for model in models {
+                            let model_id = model.id().0;
+                            menu = menu.toggleable_entry(
+                                model.name().0,
+                                selected_model_id.as_ref() == Some(&model_id),
+                                IconPosition::End,
+                                None,
+                                {
+                                    let fs = fs.clone();
+                                    let provider_id = provider_id.clone();
+                                    move |_window, cx| {
+                                        let provider_id = provider_id.clone();
+                                        let model_id = model_id.to_string();
+                                        update_settings_file::<AgentSettings>(
+                                            fs.clone(),
+                                            cx,
+                                            move |settings, _| {
+                                                settings.set_provider_default_model(
+                                                    &provider_id,
+                                                    model_id,
+                                                )
+                                            },
+                                        );
+                                    }
+                                },
+                            );
+                        }
+                        menu
+                    },
+                ))
+            }
+        })
+    }
+
     fn render_provider_configuration_section(
         &mut self,
         cx: &mut Context<Self>,
@@ -534,22 +1226,76 @@ impl AgentConfiguration {
         )
     }
 
-    fn render_sound_notification(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
-        let play_sound_when_agent_done = AgentSettings::get_global(cx).play_sound_when_agent_done;
+    fn render_sound_notification(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let settings = AgentSettings::get_global(cx);
+        let play_sound_when_agent_done = settings.play_sound_when_agent_done;
+        let agent_done_sound = settings.agent_done_sound;
         let fs = self.fs.clone();
 
-        SwitchField::new(
-            "sound-notification",
-            "Play sound when finished generating",
-            "Hear a notification sound when the agent is done generating changes or needs your input.",
-            play_sound_when_agent_done,
-            move |state, _window, cx| {
-                let allow = state == &ToggleState::Selected;
-                update_settings_file::<AgentSettings>(fs.clone(), cx, move |settings, _| {
-                    settings.set_play_sound_when_agent_done(allow);
-                });
-            },
-        )
+        v_flex()
+            .gap_1()
+            .child(SwitchField::new(
+                "sound-notification",
+                "Play sound when finished generating",
+                "Hear a notification sound when the agent is done generating changes or needs your input.",
+                play_sound_when_agent_done,
+                {
+                    let fs = fs.clone();
+                    move |state, _window, cx| {
+                        let allow = state == &ToggleState::Selected;
+                        update_settings_file::<AgentSettings>(fs.clone(), cx, move |settings, _| {
+                            settings.set_play_sound_when_agent_done(allow);
+                        });
+                    }
+                },
+            ))
+            .when(play_sound_when_agent_done, |this| {
+                this.child(
+                    h_flex()
+                        .gap_1()
+                        .child(
+                            Label::new("Sound")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                        .child(DropdownMenu::new(
+                            "agent-done-sound",
+                            agent_done_sound.label(),
+                            ContextMenu::build(window, cx, move |mut menu, _, _| {
+                                for sound in AgentDoneSound::ALL {
+                                    let fs = fs.clone();
+                                    menu = menu.toggleable_entry(
+                                        sound.label(),
+                                        sound == agent_done_sound,
+                                        IconPosition::End,
+                                        None,
+                                        move |_, cx| {
+                                            update_settings_file::<AgentSettings>(
+                                                fs.clone(),
+                                                cx,
+                                                move |settings, _| {
+                                                    settings.set_agent_done_sound(sound);
+                                                },
+                                            );
+                                        },
+                                    );
+                                }
+                                menu
+                            }),
+                        ))
+                        .child(
+                            IconButton::new("preview-agent-done-sound", IconName::PlayFilled)
+                                .icon_size(IconSize::Small)
+                                .icon_color(Color::Muted)
+                                .tooltip(Tooltip::text("Preview Sound"))
+                                .on_click(|_, _, cx| play_agent_done_sound(cx)),
+                        ),
+                )
+            })
     }
 
     fn render_modifier_to_send(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
@@ -570,172 +1316,454 @@ impl AgentConfiguration {
         )
     }
 
-    fn render_general_settings_section(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render_disable_cloud_features(&mut self, cx: &mut Context<Self>) -> impl IntoElement {
+        let disable_cloud_features =
+            DisableCloudFeaturesSettings::get_global(cx).disable_cloud_features;
+        let fs = self.fs.clone();
+
+        SwitchField::new(
+            "disable-cloud-features",
+            "Disable cloud features",
+            "Search, embeddings and task sync won't make any network requests.",
+            disable_cloud_features,
+            move |state, _window, cx| {
+                let disable = state == &ToggleState::Selected;
+                update_settings_file::<DisableCloudFeaturesSettings>(
+                    fs.clone(),
+                    cx,
+                    move |setting, _| *setting = Some(disable),
+                );
+            },
+        )
+    }
+
+    fn render_cloud_features_disabled_banner(&self, cx: &App) -> Option<impl IntoElement> {
+        if !DisableCloudFeaturesSettings::get_global(cx).disable_cloud_features {
+            return None;
+        }
+
+        Some(
+            div()
+                .p(DynamicSpacing::Base16.rems(cx))
+                .pb_0()
+                .child(
+                    Banner::new().severity(ui::Severity::Warning).child(
+                        Label::new(
+                            "Cloud features are disabled. Nothing is sent to Oppla for search, embeddings or task sync.",
+                        )
+                        .size(LabelSize::Small),
+                    ),
+                ),
+        )
+    }
+
+    fn render_general_settings_section(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
         v_flex()
             .p(DynamicSpacing::Base16.rems(cx))
             .pr(DynamicSpacing::Base20.rems(cx))
             .gap_2p5()
             .border_b_1()
             .border_color(cx.theme().colors().border)
-            .child(Headline::new("General Settings"))
+            .child(
+                h_flex()
+                    .w_full()
+                    .gap_2()
+                    .justify_between()
+                    .child(Headline::new("General Settings"))
+                    .child(
+                        Button::new("reset-general-settings", "Reset to Defaults")
+                            .icon_position(IconPosition::Start)
+                            .icon(IconName::RotateCcw)
+                            .icon_size(IconSize::Small)
+                            .icon_color(Color::Muted)
+                            .label_size(LabelSize::Small)
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.reset_general_settings(window, cx)
+                            })),
+                    ),
+            )
             .child(self.render_command_permission(cx))
+            .child(self.command_patterns.clone())
             .child(self.render_single_file_review(cx))
-            .child(self.render_sound_notification(cx))
+            .child(self.render_sound_notification(window, cx))
             .child(self.render_modifier_to_send(cx))
+            .child(self.render_disable_cloud_features(cx))
     }
 
-    fn sync_task(&mut self, cx: &mut Context<Self>) {
-        // Get the client to acquire JWT token
-        let client = Client::global(cx).clone();
-        let workspace = self.workspace.clone();
-
-        // Spawn an async task to get the token and handle the sync flow
-        cx.spawn(async move |this, cx| {
-            let background = cx.background_executor().clone();
-
-            // Try to acquire the LLM token
-            let token_result = client.request(proto::GetLlmToken {}).await;
-
-            match token_result {
-                Ok(response) => {
-                    let token = response.token;
-
-                    // Start a local HTTP server to receive the callback
-                    let server = tiny_http::Server::http("127.0.0.1:0")
-                        .expect("failed to find open port for sync callback");
-                    let port = server.server_addr().port();
+    /// Clears the provider's stored credentials, once the user confirms.
+    fn reset_provider_credentials(
+        &mut self,
+        provider: Arc<dyn LanguageModelProvider>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let answer = window.prompt(
+            PromptLevel::Warning,
+            &format!("Clear the credentials for {}?", provider.name().0),
+            Some("You'll need to enter them again to use this provider's models."),
+            &["Clear", "Cancel"],
+            cx,
+        );
+        cx.spawn(async move |_, cx| {
+            if answer.await != Ok(0) {
+                return anyhow::Ok(());
+            }
+            cx.update(|cx| provider.reset_credentials(cx))?.await
+        })
+        .detach_and_log_err(cx);
+    }
 
-                    // Build the URL with token and callback port
-                    let url = format!(
-                        "https://app.oppla.ai/home/ide?token={}&callback_port={}",
-                        token, port
+    /// Writes the default values of the general settings to the settings file,
+    /// once the user confirms overwriting their current choices.
+    fn reset_general_settings(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let answer = window.prompt(
+            PromptLevel::Warning,
+            "Reset General Settings to their defaults?",
+            Some("This overwrites your current choices for these settings."),
+            &["Reset", "Cancel"],
+            cx,
+        );
+        let fs = self.fs.clone();
+        cx.spawn(async move |_, cx| {
+            if answer.await != Ok(0) {
+                return anyhow::Ok(());
+            }
+            cx.update(|cx| {
+                let defaults = AgentSettings::defaults(cx)?;
+                update_settings_file::<AgentSettings>(fs, cx, move |settings, _| {
+                    settings.reset_general_settings(&defaults);
+                });
+                anyhow::Ok(())
+            })?
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Opens the web app to pick a task to sync. When `refresh` is set, the web
+    /// app refreshes that board and task in place instead of showing the picker.
+    fn sync_task(&mut self, refresh: Option<TaskSyncData>, cx: &mut Context<Self>) {
+        if DisableCloudFeaturesSettings::get_global(cx).disable_cloud_features {
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    workspace.toggle_status_toast(
+                        StatusToast::new(CLOUD_FEATURES_DISABLED_MESSAGE, cx, |toast, _cx| {
+                            toast
+                                .icon(ToastIcon::new(IconName::Warning).color(Color::Warning))
+                                .dismiss_button(true)
+                        }),
+                        cx,
                     );
+                })
+                .log_err();
+            return;
+        }
 
-                    // Open the URL in the default browser
-                    cx.update(|cx| {
-                        cx.open_url(&url);
-                    }).log_err();
+        // Get the client to acquire JWT token
+        let client = Client::global(cx).clone();
+        let workspace = self.workspace.clone();
+        let callback_page = SyncCallbackPage::from_theme(cx);
+        let project_id = self.project_id;
+
+        // The web app can only sync with a token, so there's no point opening
+        // it while disconnected.
+        let status = *client.status().borrow();
+        if !status.is_connected() {
+            let message = if status.is_signed_out() {
+                "Sign in to Oppla to sync a task."
+            } else {
+                "Oppla isn't connected right now. Check your connection and try syncing again."
+            };
+            set_task_sync_status(
+                project_id,
+                SyncStatus::Failed {
+                    reason: message.into(),
+                },
+                cx,
+            );
+            show_task_sync_sign_in_toast(&workspace, message, cx);
+            return;
+        }
+        set_task_sync_status(project_id, SyncStatus::Syncing, cx);
 
-                    // Listen for the callback with sync data
-                    let sync_result = background.spawn(async move {
-                        for _ in 0..300 { // Wait up to 5 minutes (300 seconds)
-                            if let Some(req) = server.recv_timeout(std::time::Duration::from_secs(1)).ok().flatten() {
-                                let path = req.url();
-                                let url = Url::parse(&format!("http://example.com{}", path))
-                                    .context("failed to parse sync callback url")?;
-
-                                // Parse the sync data from query parameters
-                                let mut sync_data = TaskSyncData {
-                                    account_id: SharedString::default(),
-                                    account_name: SharedString::default(),
-                                    product_id: SharedString::default(),
-                                    product_name: SharedString::default(),
-                                    board_id: SharedString::default(),
-                                    big_bet: None,
-                                    big_bet_description: None,
-                                    task_id: None,
-                                    work_item: None,
-                                    work_item_description: None,
-                                    synced_at: Some(std::time::SystemTime::now()),
-                                };
+        // Spawn an async task to get the token and handle the sync flow
+        cx.spawn(async move |this, cx| {
+            let result = Self::run_task_sync(
+                this,
+                workspace,
+                client,
+                callback_page,
+                project_id,
+                refresh,
+                cx,
+            )
+            .await;
+            // However the sync ended, the project mustn't be left syncing.
+            cx.update(|cx| {
+                let still_syncing = cx.try_global::<IdeContext>().is_some_and(|ide_context| {
+                    ide_context.sync_status(project_id) == SyncStatus::Syncing
+                });
+                if still_syncing {
+                    let reason = match &result {
+                        Ok(()) => "The task sync was interrupted.".into(),
+                        Err(error) => format!("Couldn't sync the task. {error}").into(),
+                    };
+                    set_task_sync_status(project_id, SyncStatus::Failed { reason }, cx);
+                }
+            })?;
+            result
+        })
+        .detach_and_log_err(cx);
+    }
 
-                                for (key, value) in url.query_pairs() {
-                                    match key.as_ref() {
-                                        "account_id" => sync_data.account_id = SharedString::from(value.to_string()),
-                                        "account_name" => sync_data.account_name = SharedString::from(value.to_string()),
-                                        "product_id" => sync_data.product_id = SharedString::from(value.to_string()),
-                                        "product_name" => sync_data.product_name = SharedString::from(value.to_string()),
-                                        "board_id" => sync_data.board_id = SharedString::from(value.to_string()),
-                                        "board_name" => sync_data.big_bet = Some(SharedString::from(value.to_string())),
-                                        "board_description" => sync_data.big_bet_description = Some(SharedString::from(value.to_string())),
-                                        "task_id" => sync_data.task_id = Some(SharedString::from(value.to_string())),
-                                        "task_name" => sync_data.work_item = Some(SharedString::from(value.to_string())),
-                                        "task_description" => sync_data.work_item_description = Some(SharedString::from(value.to_string())),
-                                        _ => {}
-                                    }
-                                }
+    async fn run_task_sync(
+        this: WeakEntity<Self>,
+        workspace: WeakEntity<Workspace>,
+        client: Arc<Client>,
+        callback_page: SyncCallbackPage,
+        project_id: EntityId,
+        refresh: Option<TaskSyncData>,
+        cx: &mut AsyncApp,
+    ) -> anyhow::Result<()> {
+        let background = cx.background_executor().clone();
+
+        // Try to acquire the LLM token
+        let token_result = futures::select_biased! {
+            response = client.request(proto::GetLlmToken {}).fuse() => response,
+            _ = background.timer(TASK_SYNC_TOKEN_TIMEOUT).fuse() => {
+                Err(anyhow::anyhow!("timed out acquiring an LLM token"))
+            }
+        };
 
-                                // Send success response and redirect to close the tab
-                                let response_html = r#"<!DOCTYPE html>
-                                <html>
-                                <head>
-                                    <title>Sync Complete</title>
-                                    <script>window.close();</script>
-                                </head>
-                                <body>
-                                    <h1>Sync Complete!</h1>
-                                    <p>You can close this tab and return to Oppla IDE.</p>
-                                </body>
-                                </html>"#;
+        match token_result {
+            Ok(response) => {
+                let token = response.token;
+
+                // Start a local HTTP server to receive the callback
+                let server = tiny_http::Server::http("127.0.0.1:0").map_err(|error| {
+                    anyhow::anyhow!("failed to find open port for sync callback: {error}")
+                })?;
+                let port = server.server_addr().port();
+
+                // Build the URL with token and callback port
+                let mut url = Url::parse(TASK_SYNC_URL)?;
+                url.query_pairs_mut()
+                    .append_pair("token", &token)
+                    .append_pair("callback_port", &port.to_string());
+                if let Some(refresh) = refresh {
+                    let mut query = url.query_pairs_mut();
+                    query
+                        .append_pair("account_id", &refresh.account_id)
+                        .append_pair("product_id", &refresh.product_id)
+                        .append_pair("board_id", &refresh.board_id);
+                    if let Some(task_id) = &refresh.task_id {
+                        query.append_pair("task_id", task_id);
+                    }
+                }
 
+                // Open the URL in the default browser
+                cx.update(|cx| {
+                    cx.open_url(url.as_str());
+                })
+                .log_err();
+
+                // Listen for the callback with sync data
+                let sync_result = background
+                    .spawn(async move {
+                        for _ in 0..300 {
+                            // Wait up to 5 minutes (300 seconds)
+                            if let Some(req) = server
+                                .recv_timeout(std::time::Duration::from_secs(1))
+                                .ok()
+                                .flatten()
+                            {
+                                let path = req.url();
+                                let sync_data = Url::parse(&format!("http://example.com{}", path))
+                                    .context("failed to parse sync callback url")
+                                    .and_then(|url| {
+                                        // Parse the sync data from query parameters
+                                        let sync_data = TaskSyncData::from_url(&url);
+                                        sync_data.ensure_required_ids()?;
+                                        Ok(sync_data)
+                                    });
+
+                                // Always answer, so the browser isn't left waiting on a failed sync
+                                let response_html = match &sync_data {
+                                    Ok(_) => callback_page.success(),
+                                    Err(error) => callback_page.failure(&format!("{error:#}")),
+                                };
                                 req.respond(
-                                    tiny_http::Response::from_string(response_html)
-                                        .with_header(tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"text/html"[..]).unwrap())
-                                ).context("failed to respond to sync callback")?;
+                                    tiny_http::Response::from_string(response_html).with_header(
+                                        tiny_http::Header::from_bytes(
+                                            &b"Content-Type"[..],
+                                            &b"text/html; charset=utf-8"[..],
+                                        )
+                                        .unwrap(),
+                                    ),
+                                )
+                                .context("failed to respond to sync callback")?;
 
                                 return Ok(sync_data);
                             }
                         }
                         anyhow::bail!("Sync timeout - no callback received")
-                    }).await;
+                    })
+                    .await;
 
-                    // Update the sync data if successful
-                    if let Ok(sync_data) = sync_result {
+                // Update the sync data if successful
+                match sync_result {
+                    Ok(Ok(sync_data)) => {
                         cx.update(|cx| {
                             if let Some(this) = this.upgrade() {
                                 this.update(cx, |this, cx| {
                                     this.update_sync_data(sync_data, cx);
                                 });
+                            } else {
+                                // The configuration view was closed while
+                                // waiting, but the project still synced.
+                                IdeContext::update(cx, |ide_context| {
+                                    ide_context.set_sync_data(Some(project_id), sync_data)
+                                });
                             }
-                        }).log_err();
+                        })
+                        .log_err();
                     }
-                },
-                Err(err) => {
-                    log::error!("Failed to acquire JWT token for task sync: {}", err);
-
-                    // Show user-friendly error message
-                    cx.update(|cx| {
-                        workspace
-                            .update(cx, |workspace, cx| {
-                                workspace.toggle_status_toast(
-                                    StatusToast::new(
-                                        "Unable to sync task. Please ensure you're signed in to Oppla and try again.",
+                    Ok(Err(error)) => {
+                        log::error!("Task sync callback had invalid data: {error:#}");
+
+                        cx.update(|cx| {
+                            set_task_sync_status(
+                                project_id,
+                                SyncStatus::Failed {
+                                    reason: error.to_string().into(),
+                                },
+                                cx,
+                            );
+                            workspace
+                                .update(cx, |workspace, cx| {
+                                    workspace.toggle_status_toast(
+                                        StatusToast::new(
+                                            format!("Couldn't sync the task. {error}"),
+                                            cx,
+                                            |toast, _cx| {
+                                                toast
+                                                    .icon(
+                                                        ToastIcon::new(IconName::XCircle)
+                                                            .color(Color::Error),
+                                                    )
+                                                    .dismiss_button(true)
+                                            },
+                                        ),
                                         cx,
-                                        |this, _cx| {
-                                            this.icon(ToastIcon::new(IconName::XCircle).color(Color::Error))
-                                                .dismiss_button(true)
-                                                .action("Sign In", move |_, cx| {
-                                                    cx.open_url("https://app.oppla.ai/auth/sign-in");
-                                                })
-                                        },
-                                    ),
-                                    cx,
-                                );
-                            })
-                            .log_err();
-                    }).log_err();
+                                    );
+                                })
+                                .log_err();
+                        })
+                        .log_err();
+                    }
+                    Err(error) => {
+                        log::error!("Task sync callback failed: {error:#}");
+
+                        // In remote and SSH setups the browser can't reach the
+                        // callback server, so offer to paste the URL instead.
+                        cx.update(|cx| {
+                            set_task_sync_status(
+                                project_id,
+                                SyncStatus::Failed {
+                                    reason: "Oppla didn't hear back from the browser.".into(),
+                                },
+                                cx,
+                            );
+                            workspace
+                                .update(cx, |workspace, cx| {
+                                    let configuration = this.clone();
+                                    let workspace_handle = cx.weak_entity();
+                                    workspace.toggle_status_toast(
+                                        StatusToast::new(
+                                            "Oppla didn't hear back from the browser.",
+                                            cx,
+                                            move |toast, _cx| {
+                                                toast
+                                                    .icon(
+                                                        ToastIcon::new(IconName::Warning)
+                                                            .color(Color::Warning),
+                                                    )
+                                                    .dismiss_button(true)
+                                                    .action("Paste Sync URL", move |window, cx| {
+                                                        let configuration = configuration.clone();
+                                                        workspace_handle
+                                                            .update(cx, |workspace, cx| {
+                                                                SyncFromUrlModal::toggle(
+                                                                    configuration,
+                                                                    workspace,
+                                                                    window,
+                                                                    cx,
+                                                                )
+                                                            })
+                                                            .log_err();
+                                                    })
+                                            },
+                                        ),
+                                        cx,
+                                    );
+                                })
+                                .log_err();
+                        })
+                        .log_err();
+                    }
                 }
             }
+            Err(err) => {
+                log::error!("Failed to acquire JWT token for task sync: {}", err);
+
+                cx.update(|cx| {
+                    set_task_sync_status(
+                        project_id,
+                        SyncStatus::Failed {
+                            reason: "Couldn't get a token to sync with.".into(),
+                        },
+                        cx,
+                    );
+                    show_task_sync_sign_in_toast(&workspace, TASK_SYNC_SIGN_IN_MESSAGE, cx);
+                })
+                .log_err();
+            }
+        }
 
-            anyhow::Ok(())
-        })
-        .detach_and_log_err(cx);
+        anyhow::Ok(())
     }
 
-    fn sync_latest_task(&mut self, cx: &mut Context<Self>) {
-        // Use the same sync flow as sync_task to open the sync page
-        self.sync_task(cx);
+    fn open_sync_from_url_modal(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let configuration = cx.weak_entity();
+        self.workspace
+            .update(cx, |workspace, cx| {
+                SyncFromUrlModal::toggle(configuration, workspace, window, cx)
+            })
+            .log_err();
+    }
+
+    pub(crate) fn sync_latest_task(&mut self, cx: &mut Context<Self>) {
+        let refresh = self.task_sync_data.clone();
+        self.sync_task(refresh, cx);
     }
 
     fn clear_task_sync(&mut self, cx: &mut Context<Self>) {
         // Clear the synced task data
         self.task_sync_data = None;
         // Clear global context as well
-        if let Some(ide_context) = cx.try_global::<IdeContext>() {
-            ide_context.clear_sync_data();
-        }
+        let project_id = self.project_id;
+        IdeContext::update(cx, |ide_context| {
+            ide_context.clear_sync_data(Some(project_id))
+        });
         // Expand the section when cleared so user can sync again
-        self.task_sync_expanded = true;
+        if AgentSettings::get_global(cx).task_sync_section_expansion
+            == TaskSyncSectionExpansion::Auto
+        {
+            self.task_sync_expanded = true;
+        }
         cx.notify();
     }
 
@@ -744,18 +1772,17 @@ impl AgentConfiguration {
         self.task_sync_data = Some(data.clone());
 
         // Store in global context for access across the IDE
-        if let Some(ide_context) = cx.try_global::<IdeContext>() {
-            ide_context.set_sync_data(data);
-        } else {
-            // Initialize global context if not already done
-            IdeContext::init(cx);
-            if let Some(ide_context) = cx.try_global::<IdeContext>() {
-                ide_context.set_sync_data(data);
-            }
-        }
+        let project_id = self.project_id;
+        IdeContext::update(cx, |ide_context| {
+            ide_context.set_sync_data(Some(project_id), data)
+        });
 
         // Collapse the section after syncing
-        self.task_sync_expanded = false;
+        if AgentSettings::get_global(cx).task_sync_section_expansion
+            == TaskSyncSectionExpansion::Auto
+        {
+            self.task_sync_expanded = false;
+        }
         cx.notify();
     }
 
@@ -782,6 +1809,7 @@ impl AgentConfiguration {
                                 h_flex()
                                     .gap_2()
                                     .child(Headline::new("Task Context Sync"))
+                                    .child(self.render_task_sync_status())
                             )
                             .child(
                                 Label::new("Sync your current task to help the AI understand what you're working on")
@@ -803,6 +1831,19 @@ impl AgentConfiguration {
                     v_flex()
                         .gap_2()
                         .mt_2()
+                        .when_some(
+                            match &self.task_sync_status {
+                                SyncStatus::Failed { reason } => Some(reason.clone()),
+                                _ => None,
+                            },
+                            |this, reason| {
+                                this.child(
+                                    Banner::new()
+                                        .severity(ui::Severity::Error)
+                                        .child(Label::new(format!("Couldn't sync the task. {reason}")).size(LabelSize::Small)),
+                                )
+                            },
+                        )
                         .when_some(self.task_sync_data.clone(), |this, task_data| {
                             this.child(
                                 v_flex()
@@ -842,7 +1883,6 @@ impl AgentConfiguration {
                                             .icon(IconName::ArrowCircle)
                                             .icon_position(IconPosition::Start)
                                             .on_click(cx.listener(|this, _event, _window, cx| {
-                                                // Placeholder: This will sync the latest task information
                                                 this.sync_latest_task(cx);
                                             }))
                                     )
@@ -867,12 +1907,69 @@ impl AgentConfiguration {
                                     .icon_size(IconSize::Small)
                                     .icon_position(IconPosition::Start)
                                     .on_click(cx.listener(|this, _event, _window, cx| {
-                                        this.sync_task(cx);
+                                        this.sync_task(None, cx);
+                                    }))
+                            )
+                            .child(
+                                Button::new("sync-task-from-url", "Paste Sync URL")
+                                    .style(ButtonStyle::Subtle)
+                                    .full_width()
+                                    .label_size(LabelSize::Small)
+                                    .tooltip(Tooltip::text(
+                                        "Use the URL shown by the web app when it can't reach Oppla, such as over SSH",
+                                    ))
+                                    .on_click(cx.listener(|this, _event, window, cx| {
+                                        this.open_sync_from_url_modal(window, cx);
                                     }))
                             )
                         })
                 )
             })
+            .child(self.test_search.clone())
+    }
+
+    fn render_task_sync_status(&self) -> AnyElement {
+        match &self.task_sync_status {
+            SyncStatus::NotSynced => div().into_any_element(),
+            SyncStatus::Syncing => h_flex()
+                .gap_1()
+                .child(
+                    Icon::new(IconName::LoadCircle)
+                        .size(IconSize::XSmall)
+                        .color(Color::Accent)
+                        .with_animation(
+                            "task-sync-spinner",
+                            Animation::new(Duration::from_secs(3)).repeat(),
+                            |icon, delta| icon.transform(Transformation::rotate(percentage(delta))),
+                        ),
+                )
+                .child(
+                    Label::new("Waiting for the browser…")
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .into_any_element(),
+            SyncStatus::Synced { at } => Label::new(format!(
+                "Synced {}",
+                format_distance_from_now(DateTimeType::Local((*at).into()), false, true, false)
+            ))
+            .size(LabelSize::Small)
+            .color(Color::Muted)
+            .into_any_element(),
+            SyncStatus::Failed { .. } => h_flex()
+                .gap_1()
+                .child(
+                    Icon::new(IconName::XCircle)
+                        .size(IconSize::XSmall)
+                        .color(Color::Error),
+                )
+                .child(
+                    Label::new("Sync failed")
+                        .size(LabelSize::Small)
+                        .color(Color::Error),
+                )
+                .into_any_element(),
+        }
     }
 
     fn render_zed_plan_info(&self, plan: Option<Plan>, cx: &mut Context<Self>) -> impl IntoElement {
@@ -906,6 +2003,45 @@ impl AgentConfiguration {
         }
     }
 
+    fn render_token_expiry(&self) -> Option<impl IntoElement> {
+        let expires_at = llm_token_expires_at()?;
+        let remaining = expires_at
+            .duration_since(SystemTime::now())
+            .unwrap_or_default();
+        let minutes = remaining.as_secs().div_ceil(60);
+        let (label, color) = if remaining.is_zero() {
+            ("Session expired".to_string(), Color::Error)
+        } else if remaining < TOKEN_EXPIRY_WARNING_THRESHOLD {
+            (format!("Session expires in {minutes} min"), Color::Warning)
+        } else if minutes < 120 {
+            (format!("Session valid for {minutes} min"), Color::Muted)
+        } else {
+            (
+                format!("Session valid for {} h", minutes / 60),
+                Color::Muted,
+            )
+        };
+        let needs_refresh = remaining < TOKEN_EXPIRY_WARNING_THRESHOLD;
+
+        Some(
+            h_flex()
+                .gap_1()
+                .child(Label::new(label).size(LabelSize::Small).color(color))
+                .when(needs_refresh, |this| {
+                    this.child(
+                        IconButton::new("refresh-llm-token", IconName::RotateCw)
+                            .icon_size(IconSize::XSmall)
+                            .icon_color(Color::Muted)
+                            .tooltip(Tooltip::text("Refresh Session"))
+                            .on_click(|_, _, cx| {
+                                RefreshLlmTokenListener::global(cx)
+                                    .update(cx, |_, cx| cx.emit(RefreshLlmTokenEvent));
+                            }),
+                    )
+                }),
+        )
+    }
+
     fn render_context_servers_section(
         &mut self,
         window: &mut Window,
@@ -975,6 +2111,199 @@ impl AgentConfiguration {
                         ),
                     ),
             )
+            .child(
+                h_flex()
+                    .gap_1()
+                    .justify_end()
+                    .child(
+                        Button::new("import-context-servers", "Import Servers from JSON")
+                            .style(ButtonStyle::Subtle)
+                            .icon(IconName::Download)
+                            .icon_size(IconSize::Small)
+                            .icon_color(Color::Muted)
+                            .icon_position(IconPosition::Start)
+                            .label_size(LabelSize::Small)
+                            .on_click(cx.listener(|this, _event, window, cx| {
+                                this.import_context_servers(window, cx)
+                            })),
+                    )
+                    .child(
+                        Button::new("export-context-servers", "Export Servers")
+                            .style(ButtonStyle::Subtle)
+                            .icon(IconName::ArrowUpFromLine)
+                            .icon_size(IconSize::Small)
+                            .icon_color(Color::Muted)
+                            .icon_position(IconPosition::Start)
+                            .label_size(LabelSize::Small)
+                            .on_click(cx.listener(|this, _event, window, cx| {
+                                this.export_context_servers(window, cx)
+                            })),
+                    ),
+            )
+    }
+
+    fn import_context_servers(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+        });
+        let fs = self.fs.clone();
+        let workspace = self.workspace.clone();
+        cx.spawn_in(window, async move |_, cx| {
+            let Some(path) = paths
+                .await
+                .anyhow()
+                .and_then(|paths| paths)?
+                .and_then(|paths| paths.into_iter().next())
+            else {
+                return Ok(());
+            };
+
+            let imported = fs
+                .load(&path)
+                .await
+                .and_then(|contents| parse_context_servers(&contents));
+            workspace.update(cx, |workspace, cx| {
+                let (message, icon) = match imported {
+                    Ok(mut imported) => {
+                        for error in &imported.errors {
+                            log::warn!("Skipped MCP server while importing: {error}");
+                        }
+                        let existing = imported
+                            .skip_existing(&ProjectSettings::get_global(cx).context_servers);
+                        let skipped_count = imported.errors.len() + existing.len();
+                        if !existing.is_empty() {
+                            // Importing never replaces a configured server.
+                            imported.errors.push(format!(
+                                "Already configured: {}.",
+                                existing
+                                    .iter()
+                                    .map(|id| id.as_ref())
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            ));
+                        }
+                        let imported_count = imported.servers.len();
+                        if imported_count > 0 {
+                            update_settings_file::<ProjectSettings>(fs, cx, move |settings, _| {
+                                for (id, server) in imported.servers {
+                                    settings.context_servers.entry(id).or_insert(server);
+                                }
+                            });
+                        }
+                        if imported.errors.is_empty() {
+                            (
+                                format!("Imported {imported_count} MCP servers."),
+                                ToastIcon::new(IconName::Check).color(Color::Success),
+                            )
+                        } else {
+                            (
+                                format!(
+                                    "Imported {imported_count} MCP servers, skipped {skipped_count}. {}",
+                                    imported.errors.join(" ")
+                                ),
+                                ToastIcon::new(IconName::Warning).color(Color::Warning),
+                            )
+                        }
+                    }
+                    Err(error) => (
+                        format!("Failed to import MCP servers: {error:#}"),
+                        ToastIcon::new(IconName::XCircle).color(Color::Error),
+                    ),
+                };
+                let status_toast = StatusToast::new(message, cx, |this, _cx| {
+                    this.icon(icon).dismiss_button(true)
+                });
+                workspace.toggle_status_toast(status_toast, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn export_context_servers(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let servers = &ProjectSettings::get_global(cx).context_servers;
+        let server_count = servers.len();
+        let Some(json) = export_context_servers(servers).log_err() else {
+            return;
+        };
+        let path = cx.prompt_for_new_path(paths::home_dir());
+        let fs = self.fs.clone();
+        let workspace = self.workspace.clone();
+        cx.spawn_in(window, async move |_, cx| {
+            let Some(path) = path.await.anyhow().and_then(|path| path)? else {
+                return Ok(());
+            };
+            let result = fs.atomic_write(path.clone(), json).await;
+            workspace.update(cx, |workspace, cx| {
+                let (message, icon) = match result {
+                    Ok(()) => (
+                        format!(
+                            "Exported {server_count} MCP servers to {}. Secrets were redacted.",
+                            path.display()
+                        ),
+                        ToastIcon::new(IconName::Check).color(Color::Success),
+                    ),
+                    Err(error) => (
+                        format!("Failed to export MCP servers: {error:#}"),
+                        ToastIcon::new(IconName::XCircle).color(Color::Error),
+                    ),
+                };
+                let status_toast = StatusToast::new(message, cx, |this, _cx| {
+                    this.icon(icon).dismiss_button(true)
+                });
+                workspace.toggle_status_toast(status_toast, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn toggle_context_server(
+        &mut self,
+        context_server_id: &ContextServerId,
+        is_enabled: bool,
+        cx: &mut Context<Self>,
+    ) {
+        self.context_server_store.update(cx, |store, cx| {
+            if is_enabled {
+                if let Some(server) = store.get_server(context_server_id) {
+                    store.start_server(server, cx);
+                }
+            } else {
+                store.stop_server(context_server_id, cx).log_err();
+            }
+        });
+
+        // Toggling several servers in a row should only write the settings file once.
+        self.pending_context_server_toggles
+            .insert(context_server_id.0.clone(), is_enabled);
+        self._save_context_server_toggles = cx.spawn(async move |this, cx| {
+            cx.background_executor()
+                .timer(CONTEXT_SERVER_TOGGLE_DEBOUNCE)
+                .await;
+            this.update(cx, |this, cx| this.save_context_server_toggles(cx))
+                .log_err();
+        });
+    }
+
+    fn save_context_server_toggles(&mut self, cx: &mut App) {
+        if self.pending_context_server_toggles.is_empty() {
+            return;
+        }
+
+        let toggles = std::mem::take(&mut self.pending_context_server_toggles);
+        update_settings_file::<ProjectSettings>(self.fs.clone(), cx, move |settings, _| {
+            for (id, is_enabled) in toggles {
+                settings
+                    .context_servers
+                    .entry(id)
+                    .or_insert_with(|| ContextServerSettings::Extension {
+                        enabled: is_enabled,
+                        settings: serde_json::json!({}),
+                    })
+                    .set_enabled(is_enabled);
+            }
+        });
     }
 
     fn render_context_server(
@@ -993,6 +2322,14 @@ impl AgentConfiguration {
             .context_server_store
             .read(cx)
             .configuration_for_server(&context_server_id);
+        let restart_attempt = self
+            .context_server_store
+            .read(cx)
+            .restart_attempt_for_server(&context_server_id);
+        let is_restart_pending = self
+            .context_server_store
+            .read(cx)
+            .is_restart_pending(&context_server_id);
 
         let is_running = matches!(server_status, ContextServerStatus::Running);
         let item_id = SharedString::from(context_server_id.0.clone());
@@ -1006,6 +2343,22 @@ impl AgentConfiguration {
             })
             .unwrap_or(false);
 
+        let providing_extension = is_from_extension
+            .then(|| resolve_extension_for_context_server(&context_server_id, cx))
+            .flatten();
+        let transport = server_configuration
+            .as_ref()
+            .map(|config| match config.as_ref() {
+                ContextServerConfiguration::Http { url, .. } => {
+                    ("HTTP", SharedString::from(format!("Connects to {url}")))
+                }
+                ContextServerConfiguration::Custom { .. }
+                | ContextServerConfiguration::Extension { .. } => (
+                    "stdio",
+                    SharedString::from("Runs a local command and talks to it over stdio"),
+                ),
+            });
+
         let error = if let ContextServerStatus::Error(error) = server_status.clone() {
             Some(error)
         } else {
@@ -1049,19 +2402,35 @@ impl AgentConfiguration {
                         |icon, delta| icon.transform(Transformation::rotate(percentage(delta))),
                     )
                     .into_any_element(),
-                "Server is starting.",
+                match restart_attempt {
+                    Some(restart) => SharedString::from(format!(
+                        "Reconnecting (attempt {}/{}).",
+                        restart.attempt, restart.max_attempts
+                    )),
+                    None => SharedString::from("Server is starting."),
+                },
             ),
             ContextServerStatus::Running => (
                 Indicator::dot().color(Color::Success).into_any_element(),
-                "Server is active.",
+                SharedString::from("Server is active."),
             ),
             ContextServerStatus::Error(_) => (
                 Indicator::dot().color(Color::Error).into_any_element(),
-                "Server has an error.",
+                match restart_attempt {
+                    Some(restart) if is_restart_pending => SharedString::from(format!(
+                        "Server has an error. Reconnecting (attempt {}/{}).",
+                        restart.attempt, restart.max_attempts
+                    )),
+                    Some(restart) if restart.is_exhausted() => SharedString::from(format!(
+                        "Server has an error. Gave up after {} restart attempts.",
+                        restart.attempt
+                    )),
+                    _ => SharedString::from("Server has an error."),
+                },
             ),
             ContextServerStatus::Stopped => (
                 Indicator::dot().color(Color::Muted).into_any_element(),
-                "Server is stopped.",
+                SharedString::from("Server is stopped."),
             ),
         };
 
@@ -1096,6 +2465,15 @@ impl AgentConfiguration {
                                 .detach_and_log_err(cx);
                             }
                         })
+                        .entry("Restart Server", None, {
+                            let context_server_id = context_server_id.clone();
+                            let context_server_store = context_server_store.clone();
+                            move |_, cx| {
+                                context_server_store.update(cx, |store, cx| {
+                                    store.restart_server(&context_server_id, cx).log_err();
+                                });
+                            }
+                        })
                         .separator()
                         .entry("Uninstall", None, {
                             let fs = fs.clone();
@@ -1120,14 +2498,24 @@ impl AgentConfiguration {
                                     resolve_extension_for_context_server(&context_server_id, cx),
                                 ) {
                                     (true, Some((id, manifest))) => {
-                                        if extension_only_provides_context_server(manifest.as_ref())
+                                        let dependent_server_ids =
+                                            context_servers_depending_on_extension(
+                                                manifest.as_ref(),
+                                                &context_server_id,
+                                                &context_server_store,
+                                                cx,
+                                            );
+                                        if dependent_server_ids.is_empty()
+                                            && extension_only_provides_context_server(
+                                                manifest.as_ref(),
+                                            )
                                         {
                                             ExtensionStore::global(cx).update(cx, |store, cx| {
                                                 store.uninstall_extension(id, cx)
                                             })
                                         } else {
                                             workspace.update(cx, |workspace, cx| {
-                                                show_unable_to_uninstall_extension_with_context_server(workspace, context_server_id.clone(), cx);
+                                                show_unable_to_uninstall_extension_with_context_server(workspace, context_server_id.clone(), dependent_server_ids, cx);
                                             }).log_err();
                                             Task::ready(Ok(()))
                                         }
@@ -1189,13 +2577,20 @@ impl AgentConfiguration {
                                 .disabled(tool_count == 0)
                                 .on_click(cx.listener({
                                     let context_server_id = context_server_id.clone();
-                                    move |this, _event, _window, _cx| {
+                                    move |this, _event, window, cx| {
                                         let is_open = this
                                             .expanded_context_server_tools
                                             .entry(context_server_id.clone())
                                             .or_insert(false);
 
                                         *is_open = !*is_open;
+                                        if *is_open {
+                                            this.ensure_tool_filter_input(
+                                                &context_server_id,
+                                                window,
+                                                cx,
+                                            );
+                                        }
                                     }
                                 })),
                             )
@@ -1210,6 +2605,23 @@ impl AgentConfiguration {
                                     .child(status_indicator),
                             )
                             .child(Label::new(item_id).ml_0p5())
+                            .child(
+                                IconButton::new(
+                                    SharedString::from(format!("{}-copy-id", context_server_id.0)),
+                                    IconName::Copy,
+                                )
+                                .icon_size(IconSize::XSmall)
+                                .icon_color(Color::Muted)
+                                .tooltip(Tooltip::text("Copy Server ID"))
+                                .on_click({
+                                    let context_server_id = context_server_id.clone();
+                                    move |_, _, cx| {
+                                        cx.write_to_clipboard(ClipboardItem::new_string(
+                                            context_server_id.0.to_string(),
+                                        ));
+                                    }
+                                }),
+                            )
                             .child(
                                 div()
                                     .id("extension-source")
@@ -1222,6 +2634,47 @@ impl AgentConfiguration {
                                             .color(Color::Muted),
                                     ),
                             )
+                            .when_some(transport, |this, (transport, transport_tooltip)| {
+                                this.child(
+                                    div()
+                                        .id("context-server-transport")
+                                        .tooltip(Tooltip::text(transport_tooltip))
+                                        .child(
+                                            Label::new(transport)
+                                                .size(LabelSize::Small)
+                                                .color(Color::Muted),
+                                        ),
+                                )
+                            })
+                            .when_some(providing_extension, |this, (extension_id, manifest)| {
+                                this.child(
+                                    Button::new(
+                                        SharedString::from(format!(
+                                            "{}-providing-extension",
+                                            context_server_id.0
+                                        )),
+                                        manifest.name.clone(),
+                                    )
+                                    .style(ButtonStyle::Subtle)
+                                    .label_size(LabelSize::Small)
+                                    .color(Color::Muted)
+                                    .tooltip(Tooltip::text(
+                                        "Show the extension providing this MCP server",
+                                    ))
+                                    .on_click(
+                                        move |_, window, cx| {
+                                            window.dispatch_action(
+                                                oppla_actions::Extensions {
+                                                    category_filter: None,
+                                                    id: Some(extension_id.to_string()),
+                                                }
+                                                .boxed_clone(),
+                                                cx,
+                                            )
+                                        },
+                                    ),
+                                )
+                            })
                             .when(is_running, |this| {
                                 this.child(
                                     Label::new(if tool_count == 1 {
@@ -1241,65 +2694,21 @@ impl AgentConfiguration {
                             .child(
                                 Switch::new("context-server-switch", is_running.into())
                                     .color(SwitchColor::Accent)
-                                    .on_click({
-                                        let context_server_manager =
-                                            self.context_server_store.clone();
+                                    .on_click(cx.listener({
                                         let context_server_id = context_server_id.clone();
-                                        let fs = self.fs.clone();
-
-                                        move |state, _window, cx| {
+                                        move |this, state: &ToggleState, _window, cx| {
                                             let is_enabled = match state {
                                                 ToggleState::Unselected
-                                                | ToggleState::Indeterminate => {
-                                                    context_server_manager.update(
-                                                        cx,
-                                                        |this, cx| {
-                                                            this.stop_server(
-                                                                &context_server_id,
-                                                                cx,
-                                                            )
-                                                            .log_err();
-                                                        },
-                                                    );
-                                                    false
-                                                }
-                                                ToggleState::Selected => {
-                                                    context_server_manager.update(
-                                                        cx,
-                                                        |this, cx| {
-                                                            if let Some(server) =
-                                                                this.get_server(&context_server_id)
-                                                            {
-                                                                this.start_server(server, cx);
-                                                            }
-                                                        },
-                                                    );
-                                                    true
-                                                }
+                                                | ToggleState::Indeterminate => false,
+                                                ToggleState::Selected => true,
                                             };
-                                            update_settings_file::<ProjectSettings>(
-                                                fs.clone(),
+                                            this.toggle_context_server(
+                                                &context_server_id,
+                                                is_enabled,
                                                 cx,
-                                                {
-                                                    let context_server_id =
-                                                        context_server_id.clone();
-
-                                                    move |settings, _| {
-                                                        settings
-                                                            .context_servers
-                                                            .entry(context_server_id.0)
-                                                            .or_insert_with(|| {
-                                                                ContextServerSettings::Extension {
-                                                                    enabled: is_enabled,
-                                                                    settings: serde_json::json!({}),
-                                                                }
-                                                            })
-                                                            .set_enabled(is_enabled);
-                                                    }
-                                                },
                                             );
                                         }
-                                    }),
+                                    })),
                             ),
                     ),
             )
@@ -1336,30 +2745,196 @@ impl AgentConfiguration {
                     return parent;
                 }
 
-                parent.child(v_flex().py_1p5().px_1().gap_1().children(
-                    tools.into_iter().enumerate().map(|(ix, tool)| {
-                        h_flex()
-                            .id(("tool-item", ix))
-                            .px_1()
-                            .gap_2()
-                            .justify_between()
-                            .hover(|style| style.bg(cx.theme().colors().element_hover))
-                            .rounded_sm()
-                            .child(
-                                Label::new(tool.name())
-                                    .buffer_font(cx)
+                let tool_filter = self.context_server_tool_filters.get(&context_server_id);
+                let query = tool_filter
+                    .map(|input| input.read(cx).editor().read(cx).text(cx).to_lowercase())
+                    .unwrap_or_default();
+                let matching_tools = tools
+                    .iter()
+                    .filter(|tool| tool.name().to_lowercase().contains(query.trim()))
+                    .collect::<Vec<_>>();
+
+                parent
+                    .when_some(tool_filter, |parent, input| {
+                        parent.child(div().pt_1p5().px_1().child(input.clone()))
+                    })
+                    .when(matching_tools.is_empty(), |parent| {
+                        parent.child(
+                            div().py_1p5().px_2().child(
+                                Label::new("No tools match this filter.")
+                                    .color(Color::Muted)
                                     .size(LabelSize::Small),
-                            )
-                            .child(
-                                Icon::new(IconName::Info)
-                                    .size(IconSize::Small)
-                                    .color(Color::Ignored),
-                            )
-                            .tooltip(Tooltip::text(tool.description()))
-                    }),
-                ))
+                            ),
+                        )
+                    })
+                    .child(v_flex().py_1p5().px_1().gap_1().children(
+                        matching_tools.into_iter().enumerate().map(|(ix, tool)| {
+                            h_flex()
+                                .id(("tool-item", ix))
+                                .px_1()
+                                .gap_2()
+                                .justify_between()
+                                .hover(|style| style.bg(cx.theme().colors().element_hover))
+                                .rounded_sm()
+                                .child(
+                                    Label::new(tool.name())
+                                        .buffer_font(cx)
+                                        .size(LabelSize::Small),
+                                )
+                                .child(
+                                    h_flex()
+                                        .gap_1()
+                                        .child(
+                                            Button::new(("try-tool", ix), "Try Tool")
+                                                .style(ButtonStyle::Subtle)
+                                                .label_size(LabelSize::Small)
+                                                .on_click({
+                                                    let tool = (*tool).clone();
+                                                    let workspace = self.workspace.clone();
+                                                    move |_, window, cx| {
+                                                        TryToolModal::toggle(
+                                                            tool.clone(),
+                                                            workspace.clone(),
+                                                            window,
+                                                            cx,
+                                                        )
+                                                    }
+                                                }),
+                                        )
+                                        .child(
+                                            Icon::new(IconName::Info)
+                                                .size(IconSize::Small)
+                                                .color(Color::Ignored),
+                                        ),
+                                )
+                                .tooltip(Tooltip::text(tool.description()))
+                        }),
+                    ))
+                    .children(self.render_recent_invocations(&context_server_id, cx))
             })
     }
+
+    fn render_recent_invocations(
+        &self,
+        context_server_id: &ContextServerId,
+        cx: &App,
+    ) -> Option<impl IntoElement> {
+        let invocations = ContextServerInvocationLog::recent(context_server_id, cx)
+            .cloned()
+            .collect::<Vec<_>>();
+        if invocations.is_empty() {
+            return None;
+        }
+
+        Some(
+            v_flex()
+                .py_1p5()
+                .px_1()
+                .gap_1()
+                .border_t_1()
+                .border_color(cx.theme().colors().border)
+                .child(
+                    h_flex()
+                        .justify_between()
+                        .child(
+                            Label::new("Recent Invocations")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                        .child(
+                            Button::new(
+                                SharedString::from(format!(
+                                    "clear-invocations-{}",
+                                    context_server_id.0
+                                )),
+                                "Clear",
+                            )
+                            .style(ButtonStyle::Subtle)
+                            .label_size(LabelSize::Small)
+                            .on_click({
+                                let context_server_id = context_server_id.clone();
+                                move |_, _, cx| {
+                                    ContextServerInvocationLog::clear(&context_server_id, cx)
+                                }
+                            }),
+                        ),
+                )
+                .children(invocations.into_iter().enumerate().map(|(ix, invocation)| {
+                    let (icon, color) = match invocation.error {
+                        Some(_) => (IconName::XCircle, Color::Error),
+                        None => (IconName::Check, Color::Success),
+                    };
+                    let tooltip = invocation
+                        .error
+                        .clone()
+                        .unwrap_or_else(|| invocation.input_summary.clone());
+                    h_flex()
+                        .id(("tool-invocation", ix))
+                        .px_1()
+                        .gap_2()
+                        .rounded_sm()
+                        .hover(|style| style.bg(cx.theme().colors().element_hover))
+                        .child(Icon::new(icon).size(IconSize::XSmall).color(color))
+                        .child(
+                            Label::new(invocation.tool_name)
+                                .buffer_font(cx)
+                                .size(LabelSize::Small),
+                        )
+                        .child(
+                            div().flex_1().min_w_0().child(
+                                Label::new(invocation.input_summary)
+                                    .buffer_font(cx)
+                                    .size(LabelSize::XSmall)
+                                    .color(Color::Muted)
+                                    .truncate(),
+                            ),
+                        )
+                        .child(
+                            Label::new(format!(
+                                "{} · {}ms",
+                                invocation.started_at.format("%H:%M:%S"),
+                                invocation.duration.as_millis()
+                            ))
+                            .size(LabelSize::XSmall)
+                            .color(Color::Muted),
+                        )
+                        .tooltip(Tooltip::text(tooltip))
+                }))
+                .into_any_element(),
+        )
+    }
+}
+
+/// What to tell screen reader users about an MCP server whose status changed.
+fn server_status_announcement(status: &ContextServerStatus) -> Option<&'static str> {
+    match status {
+        ContextServerStatus::Starting => None,
+        ContextServerStatus::Running => Some("is running"),
+        ContextServerStatus::Stopped => Some("stopped"),
+        ContextServerStatus::Error(_) => Some("errored"),
+    }
+}
+
+/// What to tell screen reader users when a project's task sync status changes.
+fn sync_status_announcement(previous: &SyncStatus, current: &SyncStatus) -> Option<String> {
+    if previous == current {
+        return None;
+    }
+    match (previous, current) {
+        (_, SyncStatus::Synced { .. }) => Some("Task synced".to_string()),
+        (_, SyncStatus::Failed { reason }) => Some(format!("Task sync failed: {reason}")),
+        (SyncStatus::Synced { .. }, SyncStatus::NotSynced) => Some("Task sync cleared".to_string()),
+        _ => None,
+    }
+}
+
+fn authenticated_providers(cx: &App) -> HashSet<LanguageModelProviderId> {
+    LanguageModelRegistry::read_global(cx)
+        .providers()
+        .into_iter()
+        .filter(|provider| provider.is_authenticated(cx))
+        .map(|provider| provider.id())
+        .collect()
 }
 
 impl Render for AgentConfiguration {
@@ -1378,7 +2953,8 @@ impl Render for AgentConfiguration {
                     .track_scroll(&self.scroll_handle)
                     .size_full()
                     .overflow_y_scroll()
-                    .child(self.render_general_settings_section(cx))
+                    .children(self.render_cloud_features_disabled_banner(cx))
+                    .child(self.render_general_settings_section(window, cx))
                     .child(self.render_task_sync_section(window, cx))
                     .child(self.render_context_servers_section(window, cx))
                     .child(self.render_provider_configuration_section(cx)),
@@ -1437,66 +3013,160 @@ pub(crate) fn resolve_extension_for_context_server(
         .map(|(id, entry)| (id.clone(), entry.manifest.clone()))
 }
 
+/// Returns the other configured context servers provided by the same
+/// extension, which would be orphaned if the extension was uninstalled.
+fn context_servers_depending_on_extension(
+    manifest: &ExtensionManifest,
+    uninstalled_server_id: &ContextServerId,
+    context_server_store: &Entity<ContextServerStore>,
+    cx: &App,
+) -> Vec<ContextServerId> {
+    context_server_store
+        .read(cx)
+        .configured_server_ids()
+        .into_iter()
+        .filter(|id| id != uninstalled_server_id && manifest.context_servers.contains_key(&id.0))
+        .collect()
+}
+
+fn set_task_sync_status(project_id: EntityId, status: SyncStatus, cx: &mut App) {
+    IdeContext::update(cx, |ide_context| {
+        ide_context.set_sync_status(project_id, status)
+    });
+}
+
+fn show_task_sync_sign_in_toast(
+    workspace: &WeakEntity<Workspace>,
+    message: &'static str,
+    cx: &mut App,
+) {
+    workspace
+        .update(cx, |workspace, cx| {
+            workspace.toggle_status_toast(
+                StatusToast::new(message, cx, |this, _cx| {
+                    this.icon(ToastIcon::new(IconName::XCircle).color(Color::Error))
+                        .dismiss_button(true)
+                        .action("Sign In", move |_, cx| cx.open_url(SIGN_IN_URL))
+                }),
+                cx,
+            );
+        })
+        .log_err();
+}
+
 // This notification appears when trying to delete
 // an MCP server extension that not only provides
-// the server, but other things, too, like language servers and more.
+// the server, but other things, too, like language servers,
+// or other configured MCP servers that would stop working.
 fn show_unable_to_uninstall_extension_with_context_server(
     workspace: &mut Workspace,
     id: ContextServerId,
+    dependent_server_ids: Vec<ContextServerId>,
     cx: &mut App,
 ) {
     let workspace_handle = workspace.weak_handle();
     let context_server_id = id.clone();
 
-    let status_toast = StatusToast::new(
+    let message = if dependent_server_ids.is_empty() {
         format!(
             "The {} extension provides more than just the MCP server. Proceed to uninstall anyway?",
             id.0
-        ),
-        cx,
-        move |this, _cx| {
-            let workspace_handle = workspace_handle.clone();
-            let context_server_id = context_server_id.clone();
-
-            this.icon(ToastIcon::new(IconName::Warning).color(Color::Warning))
-                .dismiss_button(true)
-                .action("Uninstall", move |_, _cx| {
-                    if let Some((extension_id, _)) =
-                        resolve_extension_for_context_server(&context_server_id, _cx)
-                    {
-                        ExtensionStore::global(_cx).update(_cx, |store, cx| {
-                            store
-                                .uninstall_extension(extension_id, cx)
-                                .detach_and_log_err(cx);
-                        });
-
-                        workspace_handle
-                            .update(_cx, |workspace, cx| {
-                                let fs = workspace.app_state().fs.clone();
-                                cx.spawn({
-                                    let context_server_id = context_server_id.clone();
-                                    async move |_workspace_handle, cx| {
-                                        cx.update(|cx| {
-                                            update_settings_file::<ProjectSettings>(
-                                                fs,
-                                                cx,
-                                                move |settings, _| {
-                                                    settings
-                                                        .context_servers
-                                                        .remove(&context_server_id.0);
-                                                },
-                                            );
-                                        })?;
-                                        anyhow::Ok(())
-                                    }
-                                })
-                                .detach_and_log_err(cx);
+        )
+    } else {
+        let dependent_server_names = dependent_server_ids
+            .iter()
+            .map(|id| id.0.as_ref())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "The extension providing {} also provides these configured MCP servers, which will stop working: {}. Proceed to uninstall anyway?",
+            id.0, dependent_server_names
+        )
+    };
+
+    let status_toast = StatusToast::new(message, cx, move |this, _cx| {
+        let workspace_handle = workspace_handle.clone();
+        let context_server_id = context_server_id.clone();
+        let dependent_server_ids = dependent_server_ids.clone();
+
+        this.icon(ToastIcon::new(IconName::Warning).color(Color::Warning))
+            .dismiss_button(true)
+            .action("Uninstall", move |_, _cx| {
+                if let Some((extension_id, _)) =
+                    resolve_extension_for_context_server(&context_server_id, _cx)
+                {
+                    ExtensionStore::global(_cx).update(_cx, |store, cx| {
+                        store
+                            .uninstall_extension(extension_id, cx)
+                            .detach_and_log_err(cx);
+                    });
+
+                    workspace_handle
+                        .update(_cx, |workspace, cx| {
+                            let fs = workspace.app_state().fs.clone();
+                            cx.spawn({
+                                let context_server_id = context_server_id.clone();
+                                let dependent_server_ids = dependent_server_ids.clone();
+                                async move |_workspace_handle, cx| {
+                                    cx.update(|cx| {
+                                        update_settings_file::<ProjectSettings>(
+                                            fs,
+                                            cx,
+                                            move |settings, _| {
+                                                for id in std::iter::once(&context_server_id)
+                                                    .chain(&dependent_server_ids)
+                                                {
+                                                    settings.context_servers.remove(&id.0);
+                                                }
+                                            },
+                                        );
+                                    })?;
+                                    anyhow::Ok(())
+                                }
                             })
-                            .log_err();
-                    }
-                })
-        },
-    );
+                            .detach_and_log_err(cx);
+                        })
+                        .log_err();
+                }
+            })
+    });
 
     workspace.toggle_status_toast(status_toast, cx);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_status_announcement() {
+        let synced = SyncStatus::Synced {
+            at: SystemTime::UNIX_EPOCH,
+        };
+        let failed = SyncStatus::Failed {
+            reason: "the web app closed".into(),
+        };
+
+        assert_eq!(
+            sync_status_announcement(&SyncStatus::Syncing, &synced).as_deref(),
+            Some("Task synced")
+        );
+        assert_eq!(
+            sync_status_announcement(&SyncStatus::Syncing, &failed).as_deref(),
+            Some("Task sync failed: the web app closed")
+        );
+        assert_eq!(
+            sync_status_announcement(&synced, &SyncStatus::NotSynced).as_deref(),
+            Some("Task sync cleared")
+        );
+        assert_eq!(
+            sync_status_announcement(&SyncStatus::NotSynced, &SyncStatus::Syncing),
+            None
+        );
+        assert_eq!(sync_status_announcement(&synced, &synced), None);
+        assert_eq!(
+            sync_status_announcement(&failed, &SyncStatus::NotSynced),
+            None
+        );
+    }
+}
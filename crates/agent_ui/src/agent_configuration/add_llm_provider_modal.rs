@@ -1,9 +1,9 @@
 use std::sync::Arc;
 
 use anyhow::Result;
-use collections::HashSet;
+use collections::{HashMap, HashSet};
 use fs::Fs;
-use gpui::{DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Render, Task};
+use gpui::{DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Global, Render, Task};
 use language_model::LanguageModelRegistry;
 use language_models::{
     AllLanguageModelSettings, OpenAiCompatibleSettingsContent,
@@ -14,7 +14,7 @@ use ui::{Banner, KeyBinding, Modal, ModalFooter, ModalHeader, Section, prelude::
 use ui_input::SingleLineInput;
 use workspace::{ModalView, Workspace};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LlmCompatibleProvider {
     OpenAi,
 }
@@ -67,6 +67,73 @@ impl AddLlmProviderInput {
     fn remove_model(&mut self, index: usize) {
         self.models.remove(index);
     }
+
+    fn draft(&self, cx: &App) -> AddLlmProviderDraft {
+        AddLlmProviderDraft {
+            provider_name: self.provider_name.read(cx).text(cx),
+            api_url: self.api_url.read(cx).text(cx),
+            api_key: self.api_key.read(cx).text(cx),
+            models: self.models.iter().map(|model| model.draft(cx)).collect(),
+        }
+    }
+
+    fn restore(&mut self, draft: &AddLlmProviderDraft, window: &mut Window, cx: &mut App) {
+        set_text(&self.provider_name, &draft.provider_name, window, cx);
+        set_text(&self.api_url, &draft.api_url, window, cx);
+        set_text(&self.api_key, &draft.api_key, window, cx);
+        self.models = draft
+            .models
+            .iter()
+            .map(|model_draft| {
+                let model = ModelInput::new(window, cx);
+                model.restore(model_draft, window, cx);
+                model
+            })
+            .collect();
+        if self.models.is_empty() {
+            self.models.push(ModelInput::new(window, cx));
+        }
+    }
+}
+
+/// The fields of an add that failed, so that reopening the modal doesn't
+/// require typing everything again. Only kept in memory, since it includes
+/// the API key.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct AddLlmProviderDraft {
+    provider_name: String,
+    api_url: String,
+    api_key: String,
+    models: Vec<ModelDraft>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct ModelDraft {
+    name: String,
+    max_completion_tokens: String,
+    max_output_tokens: String,
+    max_tokens: String,
+}
+
+#[derive(Default)]
+struct AddLlmProviderDrafts(HashMap<LlmCompatibleProvider, AddLlmProviderDraft>);
+
+impl Global for AddLlmProviderDrafts {}
+
+impl AddLlmProviderDrafts {
+    fn get(provider: LlmCompatibleProvider, cx: &App) -> Option<AddLlmProviderDraft> {
+        cx.try_global::<Self>()?.0.get(&provider).cloned()
+    }
+
+    fn set(provider: LlmCompatibleProvider, draft: AddLlmProviderDraft, cx: &mut App) {
+        cx.default_global::<Self>().0.insert(provider, draft);
+    }
+
+    fn clear(provider: LlmCompatibleProvider, cx: &mut App) {
+        if cx.has_global::<Self>() {
+            cx.update_global::<Self, _>(|drafts, _| drafts.0.remove(&provider));
+        }
+    }
 }
 
 struct ModelInput {
@@ -108,6 +175,32 @@ impl ModelInput {
         }
     }
 
+    fn draft(&self, cx: &App) -> ModelDraft {
+        ModelDraft {
+            name: self.name.read(cx).text(cx),
+            max_completion_tokens: self.max_completion_tokens.read(cx).text(cx),
+            max_output_tokens: self.max_output_tokens.read(cx).text(cx),
+            max_tokens: self.max_tokens.read(cx).text(cx),
+        }
+    }
+
+    fn restore(&self, draft: &ModelDraft, window: &mut Window, cx: &mut App) {
+        set_text(&self.name, &draft.name, window, cx);
+        set_text(
+            &self.max_completion_tokens,
+            &draft.max_completion_tokens,
+            window,
+            cx,
+        );
+        set_text(
+            &self.max_output_tokens,
+            &draft.max_output_tokens,
+            window,
+            cx,
+        );
+        set_text(&self.max_tokens, &draft.max_tokens, window, cx);
+    }
+
     fn parse(&self, cx: &App) -> Result<AvailableModel, SharedString> {
         let name = self.name.read(cx).text(cx);
         if name.is_empty() {
@@ -158,6 +251,14 @@ fn single_line_input(
     })
 }
 
+fn set_text(input: &Entity<SingleLineInput>, text: &str, window: &mut Window, cx: &mut App) {
+    input.update(cx, |input, cx| {
+        input.editor().update(cx, |editor, cx| {
+            editor.set_text(text, window, cx);
+        });
+    });
+}
+
 fn save_provider_to_settings(
     input: &AddLlmProviderInput,
     cx: &mut App,
@@ -243,8 +344,13 @@ impl AddLlmProviderModal {
     }
 
     fn new(provider: LlmCompatibleProvider, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let mut input = AddLlmProviderInput::new(provider, window, cx);
+        if let Some(draft) = AddLlmProviderDrafts::get(provider, cx) {
+            input.restore(&draft, window, cx);
+        }
+
         Self {
-            input: AddLlmProviderInput::new(provider, window, cx),
+            input,
             provider,
             last_error: None,
             focus_handle: cx.focus_handle(),
@@ -257,9 +363,11 @@ impl AddLlmProviderModal {
             let result = task.await;
             this.update(cx, |this, cx| match result {
                 Ok(_) => {
+                    AddLlmProviderDrafts::clear(this.provider, cx);
                     cx.emit(DismissEvent);
                 }
                 Err(error) => {
+                    AddLlmProviderDrafts::set(this.provider, this.input.draft(cx), cx);
                     this.last_error = Some(error);
                     cx.notify();
                 }
@@ -269,6 +377,7 @@ impl AddLlmProviderModal {
     }
 
     fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        AddLlmProviderDrafts::clear(self.provider, cx);
         cx.emit(DismissEvent);
     }
 
@@ -566,6 +675,39 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_draft_restored_after_failed_add(cx: &mut TestAppContext) {
+        let cx = setup_test(cx).await;
+        let provider = LlmCompatibleProvider::OpenAi;
+
+        let modal =
+            cx.new_window_entity(|window, cx| AddLlmProviderModal::new(provider, window, cx));
+        modal.update_in(cx, |modal, window, cx| {
+            set_text(&modal.input.api_url, "https://example.com/v1", window, cx);
+            set_text(&modal.input.api_key, "somekey", window, cx);
+            set_text(&modal.input.provider_name, "", window, cx);
+            modal.confirm(&menu::Confirm, window, cx);
+        });
+        cx.run_until_parked();
+
+        let draft = cx
+            .update(|_, cx| AddLlmProviderDrafts::get(provider, cx))
+            .unwrap();
+        assert_eq!(draft.api_url, "https://example.com/v1");
+        assert_eq!(draft.api_key, "somekey");
+
+        let reopened =
+            cx.new_window_entity(|window, cx| AddLlmProviderModal::new(provider, window, cx));
+        reopened.update_in(cx, |modal, window, cx| {
+            assert_eq!(modal.input.draft(cx), draft);
+            modal.cancel(&menu::Cancel, window, cx);
+        });
+        assert_eq!(
+            cx.update(|_, cx| AddLlmProviderDrafts::get(provider, cx)),
+            None
+        );
+    }
+
     async fn setup_test(cx: &mut TestAppContext) -> &mut VisualTestContext {
         cx.update(|cx| {
             let store = SettingsStore::test(cx);
@@ -595,19 +737,6 @@ mod tests {
         models: Vec<(&str, &str, &str, &str)>,
         cx: &mut VisualTestContext,
     ) -> Option<SharedString> {
-        fn set_text(
-            input: &Entity<SingleLineInput>,
-            text: &str,
-            window: &mut Window,
-            cx: &mut App,
-        ) {
-            input.update(cx, |input, cx| {
-                input.editor().update(cx, |editor, cx| {
-                    editor.set_text(text, window, cx);
-                });
-            });
-        }
-
         let task = cx.update(|window, cx| {
             let mut input = AddLlmProviderInput::new(LlmCompatibleProvider::OpenAi, window, cx);
             set_text(&input.provider_name, provider_name, window, cx);
@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use agent_settings::AgentSettings;
+use fs::Fs;
+use gpui::Entity;
+use settings::{Settings as _, SettingsStore, update_settings_file};
+use ui::{Tooltip, prelude::*};
+use ui_input::SingleLineInput;
+
+/// Edits the command patterns the agent may always run, or must always
+/// confirm, whatever `always_allow_tool_actions` is set to.
+pub struct CommandPatterns {
+    fs: Arc<dyn Fs>,
+    allow_input: Entity<SingleLineInput>,
+    confirm_input: Entity<SingleLineInput>,
+}
+
+impl CommandPatterns {
+    pub fn new(fs: Arc<dyn Fs>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let allow_input = cx.new(|cx| SingleLineInput::new(window, cx, "cargo test"));
+        let confirm_input = cx.new(|cx| SingleLineInput::new(window, cx, "git push"));
+        cx.observe_global::<SettingsStore>(|_, cx| cx.notify())
+            .detach();
+        Self {
+            fs,
+            allow_input,
+            confirm_input,
+        }
+    }
+
+    fn input(&self, always_allow: bool) -> &Entity<SingleLineInput> {
+        if always_allow {
+            &self.allow_input
+        } else {
+            &self.confirm_input
+        }
+    }
+
+    fn add_pattern(&mut self, always_allow: bool, window: &mut Window, cx: &mut Context<Self>) {
+        let input = self.input(always_allow).clone();
+        let pattern = input.read(cx).text(cx).trim().to_string();
+        if pattern.is_empty() {
+            return;
+        }
+
+        update_settings_file::<AgentSettings>(self.fs.clone(), cx, move |settings, _| {
+            settings.add_command_pattern(pattern, always_allow);
+        });
+        input.read(cx).editor().clone().update(cx, |editor, cx| {
+            editor.set_text("", window, cx);
+        });
+    }
+
+    fn remove_pattern(&mut self, pattern: String, always_allow: bool, cx: &mut Context<Self>) {
+        update_settings_file::<AgentSettings>(self.fs.clone(), cx, move |settings, _| {
+            settings.remove_command_pattern(&pattern, always_allow);
+        });
+    }
+
+    fn render_list(
+        &self,
+        label: &'static str,
+        description: &'static str,
+        patterns: &[String],
+        always_allow: bool,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let id_prefix = if always_allow {
+            "always-allow"
+        } else {
+            "always-confirm"
+        };
+
+        v_flex()
+            .gap_1()
+            .child(Label::new(label))
+            .child(
+                Label::new(description)
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+            .children(patterns.iter().enumerate().map(|(ix, pattern)| {
+                let removed_pattern = pattern.clone();
+                h_flex()
+                    .pl_1()
+                    .gap_1()
+                    .justify_between()
+                    .child(
+                        Label::new(pattern.clone())
+                            .size(LabelSize::Small)
+                            .buffer_font(cx),
+                    )
+                    .child(
+                        IconButton::new(
+                            SharedString::from(format!("{id_prefix}-remove-{ix}")),
+                            IconName::Close,
+                        )
+                        .icon_size(IconSize::XSmall)
+                        .icon_color(Color::Muted)
+                        .tooltip(Tooltip::text("Remove Pattern"))
+                        .on_click(cx.listener(move |this, _, _, cx| {
+                            this.remove_pattern(removed_pattern.clone(), always_allow, cx)
+                        })),
+                    )
+            }))
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(div().flex_1().child(self.input(always_allow).clone()))
+                    .child(
+                        Button::new(SharedString::from(format!("{id_prefix}-add")), "Add")
+                            .style(ButtonStyle::Outlined)
+                            .label_size(LabelSize::Small)
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                this.add_pattern(always_allow, window, cx)
+                            })),
+                    ),
+            )
+    }
+}
+
+impl Render for CommandPatterns {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let command_permissions = AgentSettings::get_global(cx).command_permissions.clone();
+
+        v_flex()
+            .gap_2()
+            .child(self.render_list(
+                "Always allow",
+                "Commands starting with these run without confirmation.",
+                &command_permissions.always_allow,
+                true,
+                cx,
+            ))
+            .child(self.render_list(
+                "Always confirm",
+                "Commands starting with these always ask first, even when all actions are allowed.",
+                &command_permissions.always_confirm,
+                false,
+                cx,
+            ))
+    }
+}
@@ -5,6 +5,7 @@ use std::{
 };
 
 use anyhow::{Context as _, Result};
+use collections::HashMap;
 use context_server::{ContextServerCommand, ContextServerId};
 use editor::{Editor, EditorElement, EditorStyle};
 use gpui::{
@@ -24,18 +25,29 @@ use project::{
 };
 use settings::{Settings as _, update_settings_file};
 use theme::ThemeSettings;
-use ui::{KeyBinding, Modal, ModalFooter, ModalHeader, Section, Tooltip, prelude::*};
+use ui::{KeyBinding, Modal, ModalFooter, ModalHeader, Section, ToggleButton, Tooltip, prelude::*};
 use util::ResultExt as _;
 use workspace::{ModalView, Workspace};
 
 use crate::AddContextServer;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Transport {
+    Stdio,
+    Http,
+}
+
 enum ConfigurationTarget {
     New,
     Existing {
         id: ContextServerId,
         command: ContextServerCommand,
     },
+    ExistingHttp {
+        id: ContextServerId,
+        url: String,
+        headers: HashMap<String, String>,
+    },
     Extension {
         id: ContextServerId,
         repository_url: Option<SharedString>,
@@ -46,6 +58,8 @@ enum ConfigurationTarget {
 enum ConfigurationSource {
     New {
         editor: Entity<Editor>,
+        name: String,
+        transport: Transport,
     },
     Existing {
         editor: Entity<Editor>,
@@ -94,17 +108,40 @@ impl ConfigurationSource {
         }
 
         match target {
-            ConfigurationTarget::New => ConfigurationSource::New {
-                editor: create_editor(context_server_input(None), jsonc_language, window, cx),
-            },
+            ConfigurationTarget::New => {
+                let context_servers = &ProjectSettings::get_global(cx).context_servers;
+                let name = unique_context_server_id(NEW_CONTEXT_SERVER_ID, |id| {
+                    context_servers.contains_key(id)
+                });
+                ConfigurationSource::New {
+                    editor: create_editor(
+                        context_server_input(None, &name),
+                        jsonc_language,
+                        window,
+                        cx,
+                    ),
+                    name,
+                    transport: Transport::Stdio,
+                }
+            }
             ConfigurationTarget::Existing { id, command } => ConfigurationSource::Existing {
                 editor: create_editor(
-                    context_server_input(Some((id, command))),
+                    context_server_input(Some(command), &id.0),
                     jsonc_language,
                     window,
                     cx,
                 ),
             },
+            ConfigurationTarget::ExistingHttp { id, url, headers } => {
+                ConfigurationSource::Existing {
+                    editor: create_editor(
+                        context_server_http_input(Some((url, headers)), &id.0),
+                        jsonc_language,
+                        window,
+                        cx,
+                    ),
+                }
+            }
             ConfigurationTarget::Extension {
                 id,
                 repository_url,
@@ -140,16 +177,8 @@ impl ConfigurationSource {
 
     fn output(&self, cx: &mut App) -> Result<(ContextServerId, ContextServerSettings)> {
         match self {
-            ConfigurationSource::New { editor } | ConfigurationSource::Existing { editor } => {
-                parse_input(&editor.read(cx).text(cx)).map(|(id, command)| {
-                    (
-                        id,
-                        ContextServerSettings::Custom {
-                            enabled: true,
-                            command,
-                        },
-                    )
-                })
+            ConfigurationSource::New { editor, .. } | ConfigurationSource::Existing { editor } => {
+                parse_input(&editor.read(cx).text(cx))
             }
             ConfigurationSource::Extension {
                 id,
@@ -180,19 +209,28 @@ impl ConfigurationSource {
     }
 }
 
-fn context_server_input(existing: Option<(ContextServerId, ContextServerCommand)>) -> String {
-    let (name, command, args, env) = match existing {
-        Some((id, cmd)) => {
+const NEW_CONTEXT_SERVER_ID: &str = "some-mcp-server";
+
+/// Returns `base`, or `base` with the lowest numeric suffix that isn't taken,
+/// so a new server doesn't overwrite an existing one in the settings.
+pub(crate) fn unique_context_server_id(base: &str, is_taken: impl Fn(&str) -> bool) -> String {
+    if !is_taken(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|suffix| format!("{base}-{suffix}"))
+        .find(|id| !is_taken(id))
+        .unwrap_or_else(|| base.to_string())
+}
+
+fn context_server_input(existing: Option<ContextServerCommand>, name: &str) -> String {
+    let (command, args, env) = match existing {
+        Some(cmd) => {
             let args = serde_json::to_string(&cmd.args).unwrap();
             let env = serde_json::to_string(&cmd.env.unwrap_or_default()).unwrap();
-            (id.0.to_string(), cmd.path, args, env)
+            (cmd.path, args, env)
         }
-        None => (
-            "some-mcp-server".to_string(),
-            PathBuf::new(),
-            "[]".to_string(),
-            "{}".to_string(),
-        ),
+        None => (PathBuf::new(), "[]".to_string(), "{}".to_string()),
     };
 
     format!(
@@ -211,6 +249,27 @@ fn context_server_input(existing: Option<(ContextServerId, ContextServerCommand)
     )
 }
 
+fn context_server_http_input(
+    existing: Option<(String, HashMap<String, String>)>,
+    name: &str,
+) -> String {
+    let (url, headers) = existing.unwrap_or_default();
+    let url = serde_json::to_string(&url).unwrap_or_default();
+    let headers = serde_json::to_string(&headers).unwrap_or_else(|_| "{}".to_string());
+
+    format!(
+        r#"{{
+  /// The name of your MCP server
+  "{name}": {{
+    /// The URL of the server's MCP endpoint
+    "url": {url},
+    /// Headers to send with every request, e.g. for authorization
+    "headers": {headers}
+  }}
+}}"#
+    )
+}
+
 fn resolve_context_server_extension(
     id: ContextServerId,
     worktree_store: Entity<WorktreeStore>,
@@ -310,6 +369,15 @@ impl ConfigureContextServerModal {
                     id: server_id,
                     command,
                 }),
+                ContextServerSettings::Http {
+                    enabled: _,
+                    url,
+                    headers,
+                } => Some(ConfigurationTarget::ExistingHttp {
+                    id: server_id,
+                    url,
+                    headers,
+                }),
                 ContextServerSettings::Extension { .. } => {
                     match workspace
                         .update(cx, |workspace, cx| {
@@ -380,6 +448,19 @@ impl ConfigureContextServerModal {
             }
         };
 
+        let context_servers = &ProjectSettings::get_global(cx).context_servers;
+        if self.source.is_new() && context_servers.contains_key(&id.0) {
+            let suggestion = unique_context_server_id(&id.0, |id| context_servers.contains_key(id));
+            self.set_error(
+                format!(
+                    "An MCP server named `{}` already exists. Rename this one, for example to `{suggestion}`, or configure the existing server instead.",
+                    id.0
+                ),
+                cx,
+            );
+            return;
+        }
+
         self.state = State::Waiting;
 
         let existing_server = self.context_server_store.read(cx).get_running_server(&id);
@@ -430,6 +511,33 @@ impl ConfigureContextServerModal {
         cx.emit(DismissEvent);
     }
 
+    fn set_transport(
+        &mut self,
+        new_transport: Transport,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let ConfigurationSource::New {
+            editor,
+            name,
+            transport,
+        } = &mut self.source
+        else {
+            return;
+        };
+        if *transport == new_transport {
+            return;
+        }
+        *transport = new_transport;
+        let input = match new_transport {
+            Transport::Stdio => context_server_input(None, name),
+            Transport::Http => context_server_http_input(None, name),
+        };
+        editor.update(cx, |editor, cx| editor.set_text(input, window, cx));
+        self.state = State::Idle;
+        cx.notify();
+    }
+
     fn show_configured_context_server_toast(&self, id: ContextServerId, cx: &mut App) {
         self.workspace
             .update(cx, {
@@ -450,13 +558,42 @@ impl ConfigureContextServerModal {
     }
 }
 
-fn parse_input(text: &str) -> Result<(ContextServerId, ContextServerCommand)> {
+#[derive(serde::Deserialize)]
+struct HttpInput {
+    url: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+}
+
+/// Parses the server entered in the editor, which is a remote server when it
+/// has a `url` and a command otherwise.
+fn parse_input(text: &str) -> Result<(ContextServerId, ContextServerSettings)> {
     let value: serde_json::Value = serde_json_lenient::from_str(text)?;
     let object = value.as_object().context("Expected object")?;
     anyhow::ensure!(object.len() == 1, "Expected exactly one key-value pair");
     let (context_server_name, value) = object.into_iter().next().unwrap();
-    let command: ContextServerCommand = serde_json::from_value(value.clone())?;
-    Ok((ContextServerId(context_server_name.clone().into()), command))
+    let settings = if value.get("url").is_some() {
+        let HttpInput { url, headers } = serde_json::from_value(value.clone())?;
+        let url = url.trim().to_string();
+        anyhow::ensure!(
+            url.starts_with("http://") || url.starts_with("https://"),
+            "The URL must start with http:// or https://"
+        );
+        ContextServerSettings::Http {
+            enabled: true,
+            url,
+            headers,
+        }
+    } else {
+        ContextServerSettings::Custom {
+            enabled: true,
+            command: serde_json::from_value(value.clone())?,
+        }
+    };
+    Ok((
+        ContextServerId(context_server_name.clone().into()),
+        settings,
+    ))
 }
 
 impl ModalView for ConfigureContextServerModal {}
@@ -464,7 +601,7 @@ impl ModalView for ConfigureContextServerModal {}
 impl Focusable for ConfigureContextServerModal {
     fn focus_handle(&self, cx: &App) -> FocusHandle {
         match &self.source {
-            ConfigurationSource::New { editor } => editor.focus_handle(cx),
+            ConfigurationSource::New { editor, .. } => editor.focus_handle(cx),
             ConfigurationSource::Existing { editor, .. } => editor.focus_handle(cx),
             ConfigurationSource::Extension { editor, .. } => editor
                 .as_ref()
@@ -509,9 +646,40 @@ impl ConfigureContextServerModal {
         }
     }
 
+    fn render_transport_toggle(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let ConfigurationSource::New { transport, .. } = &self.source else {
+            return None;
+        };
+        let transport = *transport;
+
+        Some(
+            h_flex()
+                .child(
+                    ToggleButton::new("transport-stdio", "Local (stdio)")
+                        .style(ButtonStyle::Filled)
+                        .size(ButtonSize::Compact)
+                        .first()
+                        .toggle_state(transport == Transport::Stdio)
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            this.set_transport(Transport::Stdio, window, cx)
+                        })),
+                )
+                .child(
+                    ToggleButton::new("transport-http", "Remote (HTTP)")
+                        .style(ButtonStyle::Filled)
+                        .size(ButtonSize::Compact)
+                        .last()
+                        .toggle_state(transport == Transport::Http)
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            this.set_transport(Transport::Http, window, cx)
+                        })),
+                ),
+        )
+    }
+
     fn render_modal_content(&self, cx: &App) -> AnyElement {
         let editor = match &self.source {
-            ConfigurationSource::New { editor } => editor,
+            ConfigurationSource::New { editor, .. } => editor,
             ConfigurationSource::Existing { editor } => editor,
             ConfigurationSource::Extension { editor, .. } => {
                 let Some(editor) = editor else {
@@ -692,6 +860,7 @@ impl Render for ConfigureContextServerModal {
                     .section(
                         Section::new()
                             .child(self.render_modal_description(window, cx))
+                            .children(self.render_transport_toggle(cx))
                             .child(self.render_modal_content(cx))
                             .child(match &self.state {
                                 State::Idle => div(),
@@ -778,3 +947,49 @@ pub(crate) fn default_markdown_style(window: &Window, cx: &App) -> MarkdownStyle
         ..Default::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unique_context_server_id() {
+        let taken = ["github", "github-2", "linear"];
+        let is_taken = |id: &str| taken.contains(&id);
+        assert_eq!(unique_context_server_id("sentry", is_taken), "sentry");
+        assert_eq!(unique_context_server_id("github", is_taken), "github-3");
+        assert_eq!(unique_context_server_id("linear", is_taken), "linear-2");
+    }
+
+    #[test]
+    fn test_parse_input() {
+        let (id, settings) = parse_input(&context_server_input(
+            Some(ContextServerCommand {
+                path: "npx".into(),
+                args: vec!["server".to_string()],
+                env: None,
+            }),
+            "local",
+        ))
+        .unwrap();
+        assert_eq!(id.0.as_ref(), "local");
+        assert!(matches!(settings, ContextServerSettings::Custom { .. }));
+
+        let (id, settings) = parse_input(&context_server_http_input(
+            Some((
+                " https://mcp.example.com/mcp ".to_string(),
+                HashMap::from_iter([("Authorization".to_string(), "Bearer x".to_string())]),
+            )),
+            "remote",
+        ))
+        .unwrap();
+        assert_eq!(id.0.as_ref(), "remote");
+        let ContextServerSettings::Http { url, headers, .. } = settings else {
+            panic!("expected an HTTP server, got {settings:?}");
+        };
+        assert_eq!(url, "https://mcp.example.com/mcp");
+        assert_eq!(headers["Authorization"], "Bearer x");
+
+        assert!(parse_input(&context_server_http_input(None, "remote")).is_err());
+    }
+}
@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result, bail};
+use collections::{HashMap, HashSet};
+use project::project_settings::ContextServerSettings;
+use serde::{Deserialize, Serialize};
+use util::redact::should_redact;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// A context server as it appears in an imported or exported list.
+#[derive(Debug, Serialize, Deserialize)]
+struct ContextServerEntry {
+    id: Arc<str>,
+    #[serde(flatten)]
+    settings: ContextServerSettings,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ImportedContextServers {
+    pub servers: Vec<(Arc<str>, ContextServerSettings)>,
+    /// One message per entry that was skipped.
+    pub errors: Vec<String>,
+}
+
+impl ImportedContextServers {
+    /// Drops the servers whose id is already configured, so that importing
+    /// doesn't overwrite them, and returns their ids.
+    pub fn skip_existing(
+        &mut self,
+        existing: &HashMap<Arc<str>, ContextServerSettings>,
+    ) -> Vec<Arc<str>> {
+        let mut skipped = Vec::new();
+        self.servers.retain(|(id, _)| {
+            let exists = existing.contains_key(id);
+            if exists {
+                skipped.push(id.clone());
+            }
+            !exists
+        });
+        skipped
+    }
+}
+
+/// Parses a JSON array of context servers. Invalid entries are reported in
+/// [`ImportedContextServers::errors`] rather than failing the whole import.
+pub(crate) fn parse_context_servers(json: &str) -> Result<ImportedContextServers> {
+    let entries: Vec<serde_json::Value> =
+        serde_json::from_str(json).context("Expected a JSON array of MCP servers")?;
+
+    let mut imported = ImportedContextServers::default();
+    let mut seen_ids = HashSet::default();
+    for (ix, entry) in entries.into_iter().enumerate() {
+        match parse_entry(entry, &mut seen_ids) {
+            Ok((id, settings)) => imported.servers.push((id, settings)),
+            Err(error) => imported.errors.push(format!("Entry {}: {error}", ix + 1)),
+        }
+    }
+    Ok(imported)
+}
+
+fn parse_entry(
+    entry: serde_json::Value,
+    seen_ids: &mut HashSet<Arc<str>>,
+) -> Result<(Arc<str>, ContextServerSettings)> {
+    if contains_redacted_value(&entry) {
+        bail!("contains redacted values; fill them in before importing");
+    }
+
+    let entry: ContextServerEntry = serde_json::from_value(entry)?;
+    if entry.id.trim().is_empty() {
+        bail!("id cannot be empty");
+    }
+    if let ContextServerSettings::Custom { command, .. } = &entry.settings {
+        if command.path.as_os_str().is_empty() {
+            bail!("{}: command cannot be empty", entry.id);
+        }
+    }
+    if let ContextServerSettings::Http { url, .. } = &entry.settings {
+        if url.trim().is_empty() {
+            bail!("{}: url cannot be empty", entry.id);
+        }
+    }
+    if !seen_ids.insert(entry.id.clone()) {
+        bail!("{}: duplicate id", entry.id);
+    }
+    Ok((entry.id, entry.settings))
+}
+
+fn contains_redacted_value(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::String(value) => value == REDACTED,
+        serde_json::Value::Array(values) => values.iter().any(contains_redacted_value),
+        serde_json::Value::Object(fields) => fields.values().any(contains_redacted_value),
+        _ => false,
+    }
+}
+
+/// Serializes the given context servers as a JSON array, sorted by id, with
+/// secrets in environment variables, extension settings and HTTP headers
+/// redacted.
+pub(crate) fn export_context_servers(
+    servers: &HashMap<Arc<str>, ContextServerSettings>,
+) -> Result<String> {
+    let mut entries = servers
+        .iter()
+        .map(|(id, settings)| {
+            let mut settings = settings.clone();
+            match &mut settings {
+                ContextServerSettings::Custom { command, .. } => {
+                    for (name, value) in command.env.iter_mut().flatten() {
+                        if should_redact(&name.to_uppercase()) {
+                            *value = REDACTED.to_string();
+                        }
+                    }
+                }
+                ContextServerSettings::Extension { settings, .. } => redact_secrets(settings),
+                // Headers are how remote servers are usually authenticated,
+                // so none of their values are exported.
+                ContextServerSettings::Http { headers, .. } => {
+                    for value in headers.values_mut() {
+                        *value = REDACTED.to_string();
+                    }
+                }
+            }
+            ContextServerEntry {
+                id: id.clone(),
+                settings,
+            }
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(serde_json::to_string_pretty(&entries)?)
+}
+
+fn redact_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact_secrets),
+        serde_json::Value::Object(fields) => {
+            for (name, value) in fields.iter_mut() {
+                if value.is_string() && should_redact(&name.to_uppercase()) {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(value);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use context_server::ContextServerCommand;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_context_servers() {
+        let json = json!([
+            { "id": "github", "source": "custom", "command": "gh-mcp", "args": ["--stdio"], "env": null },
+            { "id": "postgres", "source": "extension", "settings": { "database_url": "postgres://" } },
+            { "id": "", "source": "custom", "command": "empty-id", "args": [], "env": null },
+            { "id": "github", "source": "custom", "command": "gh-mcp", "args": [], "env": null },
+            { "id": "broken", "source": "custom" },
+            { "id": "secret", "source": "custom", "command": "s", "args": [], "env": { "API_KEY": "[REDACTED]" } },
+        ])
+        .to_string();
+
+        let imported = parse_context_servers(&json).unwrap();
+        let ids = imported
+            .servers
+            .iter()
+            .map(|(id, _)| id.as_ref())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["github", "postgres"]);
+        assert_eq!(imported.errors.len(), 4);
+        assert!(imported.errors[0].starts_with("Entry 3:"));
+        assert!(imported.errors[1].contains("duplicate id"));
+
+        assert!(parse_context_servers("{}").is_err());
+    }
+
+    #[test]
+    fn test_skip_existing_context_servers() {
+        let json = json!([
+            { "id": "github", "source": "custom", "command": "gh-mcp", "args": [], "env": null },
+            { "id": "postgres", "source": "extension", "settings": {} },
+        ])
+        .to_string();
+        let mut imported = parse_context_servers(&json).unwrap();
+
+        let mut existing = HashMap::default();
+        existing.insert(
+            Arc::from("github"),
+            ContextServerSettings::Extension {
+                enabled: true,
+                settings: json!({}),
+            },
+        );
+        let skipped = imported.skip_existing(&existing);
+
+        assert_eq!(skipped, [Arc::<str>::from("github")]);
+        let ids = imported
+            .servers
+            .iter()
+            .map(|(id, _)| id.as_ref())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["postgres"]);
+    }
+
+    #[test]
+    fn test_export_context_servers_redacts_secrets() {
+        let mut servers = HashMap::default();
+        servers.insert(
+            Arc::from("custom"),
+            ContextServerSettings::Custom {
+                enabled: true,
+                command: ContextServerCommand {
+                    path: "server".into(),
+                    args: vec![],
+                    env: Some(HashMap::from_iter([
+                        ("GITHUB_TOKEN".to_string(), "ghp_secret".to_string()),
+                        ("LOG_LEVEL".to_string(), "debug".to_string()),
+                    ])),
+                },
+            },
+        );
+        servers.insert(
+            Arc::from("extension"),
+            ContextServerSettings::Extension {
+                enabled: false,
+                settings: json!({ "api_key": "sk-secret", "region": "eu" }),
+            },
+        );
+
+        let exported = export_context_servers(&servers).unwrap();
+        assert!(!exported.contains("ghp_secret"));
+        assert!(!exported.contains("sk-secret"));
+        assert!(exported.contains("debug"));
+        assert!(exported.contains("eu"));
+
+        let imported = parse_context_servers(&exported).unwrap();
+        assert!(imported.servers.is_empty());
+        assert_eq!(imported.errors.len(), 2);
+    }
+}
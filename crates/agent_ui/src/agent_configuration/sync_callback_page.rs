@@ -0,0 +1,171 @@
+use gpui::{App, Hsla, Rgba};
+use theme::ActiveTheme as _;
+
+/// The page the browser shows after the web app hands a synced task back to
+/// the local callback server, styled after the active theme.
+#[derive(Clone, Debug)]
+pub struct SyncCallbackPage {
+    background: String,
+    text: String,
+    muted_text: String,
+    accent: String,
+    succeeded_text: SyncCallbackText,
+    failed_text: SyncCallbackText,
+}
+
+/// The wording of the page for one outcome of the sync.
+#[derive(Clone, Copy, Debug)]
+pub struct SyncCallbackText {
+    pub title: &'static str,
+    pub heading: &'static str,
+    pub message: &'static str,
+}
+
+/// What the page says once the task was synced.
+pub const SYNC_SUCCEEDED_TEXT: SyncCallbackText = SyncCallbackText {
+    title: "Sync Complete",
+    heading: "Sync Complete!",
+    message: "You can close this tab and return to Oppla.",
+};
+
+/// What the page says when the task couldn't be read, above the error.
+pub const SYNC_FAILED_TEXT: SyncCallbackText = SyncCallbackText {
+    title: "Sync Failed",
+    heading: "Sync Failed",
+    message: "Oppla couldn't read the task from the web app. Return to Oppla and try syncing again.",
+};
+
+impl SyncCallbackPage {
+    pub fn from_theme(cx: &App) -> Self {
+        let colors = cx.theme().colors();
+        Self {
+            background: css_color(colors.editor_background),
+            text: css_color(colors.text),
+            muted_text: css_color(colors.text_muted),
+            accent: css_color(colors.text_accent),
+            succeeded_text: SYNC_SUCCEEDED_TEXT,
+            failed_text: SYNC_FAILED_TEXT,
+        }
+    }
+
+    pub fn success(&self) -> String {
+        self.render(self.succeeded_text, None, true)
+    }
+
+    pub fn failure(&self, error: &str) -> String {
+        self.render(self.failed_text, Some(error), false)
+    }
+
+    fn render(&self, text: SyncCallbackText, detail: Option<&str>, close_tab: bool) -> String {
+        let close_script = if close_tab {
+            "<script>window.close();</script>"
+        } else {
+            ""
+        };
+        let detail = detail
+            .map(|detail| format!("<pre>{}</pre>", escape_html(detail)))
+            .unwrap_or_default();
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <title>{title}</title>
+    <style>
+        body {{
+            margin: 0;
+            min-height: 100vh;
+            display: flex;
+            flex-direction: column;
+            align-items: center;
+            justify-content: center;
+            font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif;
+            background: {background};
+            color: {text};
+        }}
+        h1 {{ color: {accent}; }}
+        p, pre {{ color: {muted_text}; }}
+        pre {{ white-space: pre-wrap; max-width: 40em; }}
+    </style>
+    {close_script}
+</head>
+<body>
+    <h1>{heading}</h1>
+    <p>{message}</p>
+    {detail}
+</body>
+</html>"#,
+            title = escape_html(text.title),
+            heading = escape_html(text.heading),
+            message = escape_html(text.message),
+            background = self.background,
+            text = self.text,
+            muted_text = self.muted_text,
+            accent = self.accent,
+        )
+    }
+}
+
+fn css_color(color: Hsla) -> String {
+    let Rgba { r, g, b, a } = color.into();
+    format!(
+        "rgba({}, {}, {}, {:.2})",
+        (r * 255.).round(),
+        (g * 255.).round(),
+        (b * 255.).round(),
+        a
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page() -> SyncCallbackPage {
+        SyncCallbackPage {
+            background: css_color(gpui::black()),
+            text: css_color(gpui::white()),
+            muted_text: css_color(gpui::white()),
+            accent: css_color(gpui::blue()),
+            succeeded_text: SYNC_SUCCEEDED_TEXT,
+            failed_text: SYNC_FAILED_TEXT,
+        }
+    }
+
+    #[test]
+    fn test_sync_callback_page() {
+        assert_eq!(css_color(gpui::black()), "rgba(0, 0, 0, 1.00)");
+
+        let success = page().success();
+        assert!(success.contains("window.close()"));
+        assert!(success.contains("background: rgba(0, 0, 0, 1.00)"));
+
+        let failure = page().failure("missing <account_id>");
+        assert!(!failure.contains("window.close()"));
+        assert!(failure.contains("missing &lt;account_id&gt;"));
+
+        let renamed = SyncCallbackPage {
+            succeeded_text: SyncCallbackText {
+                title: "Synchronisé",
+                ..SYNC_SUCCEEDED_TEXT
+            },
+            ..page()
+        };
+        assert!(renamed.success().contains("<title>Synchronisé</title>"));
+    }
+}
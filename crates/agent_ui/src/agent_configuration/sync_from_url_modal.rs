@@ -0,0 +1,196 @@
+use anyhow::{Context as _, Result, bail};
+use gpui::{DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, WeakEntity};
+use ui::{Banner, KeyBinding, Modal, ModalFooter, ModalHeader, Section, prelude::*};
+use ui_input::SingleLineInput;
+use url::Url;
+use workspace::{ModalView, Workspace};
+
+use super::{AgentConfiguration, TaskSyncData};
+
+/// Syncs a task from the URL the web app shows when it can't reach the local
+/// callback server, which happens when Oppla runs on a remote machine.
+pub struct SyncFromUrlModal {
+    configuration: WeakEntity<AgentConfiguration>,
+    url_input: Entity<SingleLineInput>,
+    last_error: Option<SharedString>,
+}
+
+impl SyncFromUrlModal {
+    pub fn toggle(
+        configuration: WeakEntity<AgentConfiguration>,
+        workspace: &mut Workspace,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) {
+        workspace.toggle_modal(window, cx, |window, cx| {
+            let url_input = cx.new(|cx| {
+                SingleLineInput::new(window, cx, "https://app.oppla.ai/home/ide?account_id=…")
+                    .label("Sync URL")
+            });
+            Self {
+                configuration,
+                url_input,
+                last_error: None,
+            }
+        });
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, _: &mut Window, cx: &mut Context<Self>) {
+        let text = self.url_input.read(cx).text(cx);
+        match parse_sync_url(&text) {
+            Ok(sync_data) => {
+                self.configuration
+                    .update(cx, |configuration, cx| {
+                        configuration.update_sync_data(sync_data, cx)
+                    })
+                    .ok();
+                cx.emit(DismissEvent);
+            }
+            Err(error) => {
+                self.last_error = Some(error.to_string().into());
+                cx.notify();
+            }
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+}
+
+/// Accepts either the full URL or just its query string.
+fn parse_sync_url(text: &str) -> Result<TaskSyncData> {
+    let text = text.trim();
+    if text.is_empty() {
+        bail!("Paste the URL shown by the web app");
+    }
+
+    let url = match Url::parse(text) {
+        Ok(url) => url,
+        Err(_) => Url::parse(&format!(
+            "http://localhost/?{}",
+            text.trim_start_matches('?')
+        ))
+        .context("Not a valid sync URL")?,
+    };
+
+    let sync_data = TaskSyncData::from_url(&url);
+    sync_data.ensure_required_ids()?;
+    Ok(sync_data)
+}
+
+impl EventEmitter<DismissEvent> for SyncFromUrlModal {}
+
+impl Focusable for SyncFromUrlModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.url_input.focus_handle(cx)
+    }
+}
+
+impl ModalView for SyncFromUrlModal {}
+
+impl Render for SyncFromUrlModal {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let focus_handle = self.focus_handle(cx);
+
+        div()
+            .elevation_3(cx)
+            .w(rems(34.))
+            .key_context("SyncFromUrlModal")
+            .on_action(cx.listener(Self::confirm))
+            .on_action(cx.listener(Self::cancel))
+            .capture_any_mouse_down(cx.listener(|this, _, window, cx| {
+                this.focus_handle(cx).focus(window);
+            }))
+            .child(
+                Modal::new("sync-from-url", None)
+                    .header(
+                        ModalHeader::new()
+                            .headline("Sync Task from URL")
+                            .description("Paste the sync URL shown by the Oppla web app."),
+                    )
+                    .when_some(self.last_error.clone(), |this, error| {
+                        this.section(
+                            Section::new().child(
+                                Banner::new()
+                                    .severity(ui::Severity::Warning)
+                                    .child(div().text_xs().child(error)),
+                            ),
+                        )
+                    })
+                    .section(Section::new().child(self.url_input.clone()))
+                    .footer(
+                        ModalFooter::new().end_slot(
+                            h_flex()
+                                .gap_1()
+                                .child(
+                                    Button::new("cancel", "Cancel")
+                                        .key_binding(
+                                            KeyBinding::for_action_in(
+                                                &menu::Cancel,
+                                                &focus_handle,
+                                                window,
+                                                cx,
+                                            )
+                                            .map(|kb| kb.size(rems_from_px(12.))),
+                                        )
+                                        .on_click(cx.listener(|this, _event, window, cx| {
+                                            this.cancel(&menu::Cancel, window, cx)
+                                        })),
+                                )
+                                .child(
+                                    Button::new("sync", "Sync Task")
+                                        .key_binding(
+                                            KeyBinding::for_action_in(
+                                                &menu::Confirm,
+                                                &focus_handle,
+                                                window,
+                                                cx,
+                                            )
+                                            .map(|kb| kb.size(rems_from_px(12.))),
+                                        )
+                                        .on_click(cx.listener(|this, _event, window, cx| {
+                                            this.confirm(&menu::Confirm, window, cx)
+                                        })),
+                                ),
+                        ),
+                    ),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sync_url() {
+        let sync_data = parse_sync_url(
+            " https://app.oppla.ai/home/ide/sync?account_id=a1&product_id=p1&board_id=b1&board_name=Big%20Bet&task_id=t1 ",
+        )
+        .unwrap();
+        assert_eq!(sync_data.account_id, SharedString::from("a1"));
+        assert_eq!(sync_data.product_id, SharedString::from("p1"));
+        assert_eq!(sync_data.board_id, SharedString::from("b1"));
+        assert_eq!(sync_data.big_bet, Some(SharedString::from("Big Bet")));
+        assert_eq!(sync_data.task_id, Some(SharedString::from("t1")));
+
+        let sync_data = parse_sync_url("?account_id=a1&product_id=p1&board_id=b1").unwrap();
+        assert_eq!(sync_data.task_id, None);
+
+        let sync_data = parse_sync_url(
+            "?account_id=%20a1%20&product_id=p1&board_id=b1&task_id=%20&board_name=%20",
+        )
+        .unwrap();
+        assert_eq!(sync_data.account_id, SharedString::from("a1"));
+        assert_eq!(sync_data.task_id, None);
+        assert_eq!(sync_data.big_bet, None);
+
+        assert!(parse_sync_url("?account_id=a1&product_id=p1&board_id=%20%20").is_err());
+        assert!(parse_sync_url("?account_id=a1&product_id=p1&board_id=undefined").is_err());
+        assert!(parse_sync_url("?account_id=a1&product_id=p1&board_id=b%201").is_err());
+        assert!(parse_sync_url("?account_id=a1&product_id=p1&board_id=b1&task_id=null").is_err());
+        assert!(parse_sync_url("").is_err());
+        assert!(parse_sync_url("https://app.oppla.ai/home/ide?account_id=a1").is_err());
+    }
+}
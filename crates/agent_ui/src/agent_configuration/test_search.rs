@@ -0,0 +1,220 @@
+use std::sync::Arc;
+
+use assistant_tool::{ActionLog, AnyToolCard, ToolUseStatus, ToolWorkingSet};
+use gpui::{Corner, Entity, WeakEntity};
+use language_model::{LanguageModelRegistry, LanguageModelRequest};
+use ui::{ContextMenu, PopoverMenu, Tooltip, prelude::*};
+use ui_input::SingleLineInput;
+use workspace::Workspace;
+
+use crate::RecentSearches;
+
+const FILE_SEARCH_TOOL_NAME: &str = "file_search";
+
+enum State {
+    Idle,
+    Searching(AnyToolCard),
+    Error(SharedString),
+}
+
+/// Runs the agent's file search tool with a hand-written query, so users can
+/// check that their synced context returns sensible results.
+pub struct TestSearch {
+    query_input: Entity<SingleLineInput>,
+    tools: Entity<ToolWorkingSet>,
+    workspace: WeakEntity<Workspace>,
+    state: State,
+}
+
+impl TestSearch {
+    pub fn new(
+        tools: Entity<ToolWorkingSet>,
+        workspace: WeakEntity<Workspace>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let query_input = cx.new(|cx| {
+            SingleLineInput::new(window, cx, "Search synced context…")
+                .start_icon(IconName::MagnifyingGlass)
+        });
+        Self {
+            query_input,
+            tools,
+            workspace,
+            state: State::Idle,
+        }
+    }
+
+    fn search(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let query = self.query_input.read(cx).text(cx);
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+
+        self.run_search(serde_json::json!({ "query": query }), window, cx);
+    }
+
+    /// Runs a recent search again, with its filters, showing its query in the
+    /// input.
+    fn rerun_search(
+        &mut self,
+        input: serde_json::Value,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let query = input
+            .get("query")
+            .and_then(|query| query.as_str())
+            .unwrap_or_default()
+            .to_string();
+        self.query_input
+            .read(cx)
+            .editor()
+            .clone()
+            .update(cx, |editor, cx| {
+                editor.set_text(query, window, cx);
+            });
+        self.run_search(input, window, cx);
+    }
+
+    fn run_search(
+        &mut self,
+        input: serde_json::Value,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.state = match self.start_search(input, window, cx) {
+            Ok(card) => State::Searching(card),
+            Err(error) => State::Error(error),
+        };
+        cx.notify();
+    }
+
+    fn start_search(
+        &self,
+        input: serde_json::Value,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Result<AnyToolCard, SharedString> {
+        let Some(tool) = self.tools.read(cx).tool(FILE_SEARCH_TOOL_NAME, cx) else {
+            return Err("The file search tool isn't available.".into());
+        };
+        let Some(project) = self
+            .workspace
+            .upgrade()
+            .map(|workspace| workspace.read(cx).project().clone())
+        else {
+            return Err("Open a project to try searching.".into());
+        };
+        // Every tool is handed a model, even though file search never uses it.
+        let Some(configured_model) = LanguageModelRegistry::read_global(cx).default_model() else {
+            return Err("Select a model in the agent panel to try searching.".into());
+        };
+
+        let action_log = cx.new(|_| ActionLog::new(project.clone()));
+        // Only the card is kept. The search it shows keeps running after the
+        // output building the model's response is dropped.
+        let result = tool.run(
+            input,
+            Arc::new(LanguageModelRequest::default()),
+            project,
+            action_log,
+            configured_model.model,
+            Some(window.window_handle()),
+            cx,
+        );
+        result
+            .card
+            .ok_or_else(|| "The file search tool didn't return any results.".into())
+    }
+
+    fn build_recent_searches_menu(
+        this: WeakEntity<Self>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Entity<ContextMenu> {
+        let inputs = RecentSearches::inputs(cx);
+        ContextMenu::build(window, cx, move |mut menu, _, _| {
+            if inputs.is_empty() {
+                return menu.label("No recent searches");
+            }
+            menu = menu.header("Recent Searches");
+            for input in inputs {
+                let this = this.clone();
+                menu = menu.entry(RecentSearches::label(&input), None, move |window, cx| {
+                    this.update(cx, |this, cx| this.rerun_search(input.clone(), window, cx))
+                        .ok();
+                });
+            }
+            menu.separator()
+                .entry("Clear History", None, |_, cx| RecentSearches::clear(cx))
+        })
+    }
+}
+
+impl Render for TestSearch {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("TestSearch")
+            .on_action(cx.listener(Self::search))
+            .gap_1p5()
+            .child(
+                Label::new("Test Search")
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(div().flex_1().child(self.query_input.clone()))
+                    .child(
+                        Button::new("run-test-search", "Search")
+                            .style(ButtonStyle::Outlined)
+                            .label_size(LabelSize::Small)
+                            .on_click(cx.listener(|this, _event, window, cx| {
+                                this.search(&menu::Confirm, window, cx)
+                            })),
+                    )
+                    .child({
+                        let this = cx.entity().downgrade();
+                        PopoverMenu::new("recent-test-searches")
+                            .anchor(Corner::TopRight)
+                            .menu(move |window, cx| {
+                                Some(Self::build_recent_searches_menu(this.clone(), window, cx))
+                            })
+                            .trigger_with_tooltip(
+                                IconButton::new(
+                                    "recent-test-searches-trigger",
+                                    IconName::HistoryRerun,
+                                )
+                                .icon_size(IconSize::Small),
+                                Tooltip::text("Recent Searches"),
+                            )
+                    }),
+            )
+            .map(|this| match &self.state {
+                State::Idle => this,
+                State::Searching(card) => this.child(card.render(
+                    &ToolUseStatus::Running,
+                    window,
+                    self.workspace.clone(),
+                    cx,
+                )),
+                State::Error(error) => this.child(
+                    h_flex()
+                        .gap_1p5()
+                        .child(
+                            Icon::new(IconName::Warning)
+                                .size(IconSize::XSmall)
+                                .color(Color::Warning),
+                        )
+                        .child(
+                            Label::new(error.clone())
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        ),
+                ),
+            })
+    }
+}
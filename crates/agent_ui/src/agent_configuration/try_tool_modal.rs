@@ -0,0 +1,481 @@
+use std::{sync::Arc, time::Duration};
+
+use assistant_tool::{ActionLog, Tool, ToolResultContent};
+use editor::{Editor, EditorElement, EditorStyle};
+use gpui::{
+    Animation, AnimationExt as _, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Task,
+    TextStyle, Transformation, WeakEntity, percentage, prelude::*,
+};
+use language::Language;
+use language_model::{LanguageModelRegistry, LanguageModelRequest, LanguageModelToolSchemaFormat};
+use project::Project;
+use settings::Settings as _;
+use theme::ThemeSettings;
+use ui::{KeyBinding, Modal, ModalFooter, ModalHeader, Section, prelude::*};
+use util::ResultExt as _;
+use workspace::{ModalView, Workspace};
+
+enum State {
+    Idle,
+    Running(Task<()>),
+    Output(SharedString),
+    Error(SharedString),
+}
+
+/// Runs a single tool with hand-written input, outside of any thread, so that
+/// a newly added MCP server can be checked before relying on it.
+pub struct TryToolModal {
+    tool: Arc<dyn Tool>,
+    project: Entity<Project>,
+    input_editor: Entity<Editor>,
+    state: State,
+}
+
+impl TryToolModal {
+    pub fn toggle(
+        tool: Arc<dyn Tool>,
+        workspace: WeakEntity<Workspace>,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        window
+            .spawn(cx, async move |cx| {
+                let language = workspace.read_with(cx, |workspace, cx| {
+                    workspace
+                        .project()
+                        .read(cx)
+                        .languages()
+                        .language_for_name("JSON")
+                })?;
+                let json_language = language.await.ok();
+                workspace.update_in(cx, |workspace, window, cx| {
+                    let project = workspace.project().clone();
+                    workspace.toggle_modal(window, cx, |window, cx| {
+                        Self::new(tool, project, json_language, window, cx)
+                    })
+                })
+            })
+            .detach_and_log_err(cx);
+    }
+
+    fn new(
+        tool: Arc<dyn Tool>,
+        project: Entity<Project>,
+        json_language: Option<Arc<Language>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let input = tool
+            .input_schema(LanguageModelToolSchemaFormat::JsonSchema)
+            .map(|schema| sample_input(&schema))
+            .unwrap_or_else(|_| serde_json::json!({}));
+
+        let input_editor = cx.new(|cx| {
+            let mut editor = Editor::auto_height(4, 16, window, cx);
+            editor.set_text(
+                serde_json::to_string_pretty(&input).unwrap_or_default(),
+                window,
+                cx,
+            );
+            editor.set_show_gutter(false, cx);
+            editor.set_soft_wrap_mode(language::language_settings::SoftWrap::None, cx);
+            if let Some(buffer) = editor.buffer().read(cx).as_singleton() {
+                buffer.update(cx, |buffer, cx| buffer.set_language(json_language, cx))
+            }
+            editor
+        });
+
+        Self {
+            tool,
+            project,
+            input_editor,
+            state: State::Idle,
+        }
+    }
+
+    fn set_error(&mut self, error: impl Into<SharedString>, cx: &mut Context<Self>) {
+        self.state = State::Error(error.into());
+        cx.notify();
+    }
+
+    fn run(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        if matches!(self.state, State::Running(_)) {
+            return;
+        }
+
+        let text = self.input_editor.read(cx).text(cx);
+        let input = match serde_json_lenient::from_str::<serde_json::Value>(&text) {
+            Ok(input) => input,
+            Err(error) => {
+                self.set_error(format!("Invalid JSON input: {error}"), cx);
+                return;
+            }
+        };
+
+        // Every tool is handed a model, even though MCP tools never use it.
+        let Some(configured_model) = LanguageModelRegistry::read_global(cx).default_model() else {
+            self.set_error("Select a model in the agent panel to try tools.", cx);
+            return;
+        };
+
+        let action_log = cx.new(|_| ActionLog::new(self.project.clone()));
+        let result = self.tool.clone().run(
+            input,
+            Arc::new(LanguageModelRequest::default()),
+            self.project.clone(),
+            action_log,
+            configured_model.model,
+            Some(window.window_handle()),
+            cx,
+        );
+
+        let task = cx.spawn(async move |this, cx| {
+            let output = result.output.await;
+            this.update(cx, |this, cx| {
+                this.state = match output {
+                    Ok(output) => State::Output(match &output.content {
+                        ToolResultContent::Text(text) => text.clone().into(),
+                        ToolResultContent::Image(_) => "The tool returned an image.".into(),
+                    }),
+                    Err(error) => State::Error(format!("{error:#}").into()),
+                };
+                cx.notify();
+            })
+            .log_err();
+        });
+        self.state = State::Running(task);
+        cx.notify();
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+}
+
+/// Builds an input object with a placeholder for every property in the tool's
+/// input schema, so the user only has to fill in the values.
+fn sample_input(schema: &serde_json::Value) -> serde_json::Value {
+    let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) else {
+        return serde_json::json!({});
+    };
+
+    properties
+        .iter()
+        .map(|(name, property)| {
+            let value = match property.get("type").and_then(|ty| ty.as_str()) {
+                Some("string") => serde_json::json!(""),
+                Some("number" | "integer") => serde_json::json!(0),
+                Some("boolean") => serde_json::json!(false),
+                Some("array") => serde_json::json!([]),
+                Some("object") => sample_input(property),
+                _ => serde_json::Value::Null,
+            };
+            (name.clone(), value)
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+impl ModalView for TryToolModal {}
+
+impl Focusable for TryToolModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.input_editor.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for TryToolModal {}
+
+impl TryToolModal {
+    fn render_input(&self, cx: &App) -> impl IntoElement {
+        let settings = ThemeSettings::get_global(cx);
+        let text_style = TextStyle {
+            color: cx.theme().colors().text,
+            font_family: settings.buffer_font.family.clone(),
+            font_fallbacks: settings.buffer_font.fallbacks.clone(),
+            font_size: settings.buffer_font_size(cx).into(),
+            font_weight: settings.buffer_font.weight,
+            line_height: relative(settings.buffer_line_height.value()),
+            ..Default::default()
+        };
+
+        div()
+            .p_2()
+            .rounded_md()
+            .border_1()
+            .border_color(cx.theme().colors().border_variant)
+            .bg(cx.theme().colors().editor_background)
+            .child(EditorElement::new(
+                &self.input_editor,
+                EditorStyle {
+                    background: cx.theme().colors().editor_background,
+                    local_player: cx.theme().players().local(),
+                    text: text_style,
+                    syntax: cx.theme().syntax().clone(),
+                    ..Default::default()
+                },
+            ))
+    }
+
+    fn render_state(&self, cx: &App) -> AnyElement {
+        match &self.state {
+            State::Idle => div().into_any_element(),
+            State::Running(_) => h_flex()
+                .gap_2()
+                .child(
+                    Icon::new(IconName::ArrowCircle)
+                        .size(IconSize::XSmall)
+                        .color(Color::Info)
+                        .with_animation(
+                            "try-tool-running",
+                            Animation::new(Duration::from_secs(2)).repeat(),
+                            |icon, delta| icon.transform(Transformation::rotate(percentage(delta))),
+                        ),
+                )
+                .child(
+                    Label::new("Running tool…")
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .into_any_element(),
+            State::Output(output) => v_flex()
+                .gap_1()
+                .child(
+                    Label::new("Output")
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .child(
+                    div()
+                        .id("try-tool-output")
+                        .max_h(rems(16.))
+                        .overflow_y_scroll()
+                        .p_2()
+                        .rounded_md()
+                        .border_1()
+                        .border_color(cx.theme().colors().border_variant)
+                        .child(
+                            Label::new(output.clone())
+                                .buffer_font(cx)
+                                .size(LabelSize::Small),
+                        ),
+                )
+                .into_any_element(),
+            State::Error(error) => h_flex()
+                .gap_2()
+                .child(
+                    Icon::new(IconName::Warning)
+                        .size(IconSize::XSmall)
+                        .color(Color::Warning),
+                )
+                .child(
+                    div().w_full().child(
+                        Label::new(error.clone())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+                )
+                .into_any_element(),
+        }
+    }
+
+    fn render_footer(&self, window: &mut Window, cx: &mut Context<Self>) -> ModalFooter {
+        let focus_handle = self.focus_handle(cx);
+        let is_running = matches!(self.state, State::Running(_));
+
+        ModalFooter::new().end_slot(
+            h_flex()
+                .gap_2()
+                .child(
+                    Button::new("cancel", "Close")
+                        .key_binding(
+                            KeyBinding::for_action_in(&menu::Cancel, &focus_handle, window, cx)
+                                .map(|kb| kb.size(rems_from_px(12.))),
+                        )
+                        .on_click(
+                            cx.listener(|this, _event, _window, cx| this.cancel(&menu::Cancel, cx)),
+                        ),
+                )
+                .child(
+                    Button::new("run-tool", "Run Tool")
+                        .disabled(is_running)
+                        .key_binding(
+                            KeyBinding::for_action_in(&menu::Confirm, &focus_handle, window, cx)
+                                .map(|kb| kb.size(rems_from_px(12.))),
+                        )
+                        .on_click(cx.listener(|this, _event, window, cx| {
+                            this.run(&menu::Confirm, window, cx)
+                        })),
+                ),
+        )
+    }
+}
+
+impl Render for TryToolModal {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .elevation_3(cx)
+            .w(rems(34.))
+            .key_context("TryToolModal")
+            .on_action(
+                cx.listener(|this, _: &menu::Cancel, _window, cx| this.cancel(&menu::Cancel, cx)),
+            )
+            .on_action(cx.listener(Self::run))
+            .capture_any_mouse_down(cx.listener(|this, _, window, cx| {
+                this.focus_handle(cx).focus(window);
+            }))
+            .child(
+                Modal::new("try-tool", None)
+                    .header(ModalHeader::new().headline(format!("Try {}", self.tool.name())))
+                    .section(
+                        Section::new()
+                            .child(
+                                Label::new(self.tool.description())
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            )
+                            .child(self.render_input(cx))
+                            .child(self.render_state(cx)),
+                    )
+                    .footer(self.render_footer(window, cx)),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assistant_tool::{ToolResult, ToolResultOutput};
+    use editor::EditorSettings;
+    use fs::FakeFs;
+    use gpui::{AnyWindowHandle, TestAppContext};
+    use language::language_settings;
+    use language_model::LanguageModel;
+    use parking_lot::Mutex;
+    use serde_json::json;
+    use settings::{KeymapFile, Settings as _, SettingsStore};
+    use util::path;
+
+    /// Records the input it's run with and returns it as its output.
+    #[derive(Default)]
+    struct EchoTool {
+        inputs: Mutex<Vec<serde_json::Value>>,
+    }
+
+    impl Tool for EchoTool {
+        fn name(&self) -> String {
+            "echo".into()
+        }
+
+        fn description(&self) -> String {
+            "Echoes its input".into()
+        }
+
+        fn icon(&self) -> IconName {
+            IconName::ToolHammer
+        }
+
+        fn needs_confirmation(&self, _input: &serde_json::Value, _cx: &App) -> bool {
+            false
+        }
+
+        fn may_perform_edits(&self) -> bool {
+            false
+        }
+
+        fn ui_text(&self, _input: &serde_json::Value) -> String {
+            "Echo".into()
+        }
+
+        fn run(
+            self: Arc<Self>,
+            input: serde_json::Value,
+            _request: Arc<LanguageModelRequest>,
+            _project: Entity<Project>,
+            _action_log: Entity<ActionLog>,
+            _model: Arc<dyn LanguageModel>,
+            _window: Option<AnyWindowHandle>,
+            _cx: &mut App,
+        ) -> ToolResult {
+            self.inputs.lock().push(input.clone());
+            Task::ready(Ok(ToolResultOutput::from(input.to_string()))).into()
+        }
+    }
+
+    #[gpui::test]
+    async fn test_run_with_keybinding(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let store = SettingsStore::test(cx);
+            cx.set_global(store);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language_settings::init(cx);
+            EditorSettings::register(cx);
+            language_model::init_settings(cx);
+            LanguageModelRegistry::test(cx);
+            cx.bind_keys(
+                KeymapFile::load_asset_allow_partial_failure("keymaps/default-linux.json", cx)
+                    .unwrap(),
+            );
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+        let tool = Arc::new(EchoTool::default());
+        let (modal, cx) = cx.add_window_view(|window, cx| {
+            TryToolModal::new(tool.clone(), project.clone(), None, window, cx)
+        });
+        modal.update_in(cx, |modal, window, cx| {
+            modal.focus_handle(cx).focus(window);
+            modal.input_editor.update(cx, |editor, cx| {
+                editor.set_text(r#"{"text": "hi"}"#, window, cx)
+            });
+        });
+
+        // Enter edits the input rather than running the tool.
+        cx.simulate_keystrokes("enter");
+        cx.run_until_parked();
+        modal.read_with(cx, |modal, cx| {
+            assert!(modal.input_editor.read(cx).text(cx).contains('\n'));
+            assert!(matches!(modal.state, State::Idle));
+        });
+        assert!(tool.inputs.lock().is_empty());
+
+        cx.simulate_keystrokes("ctrl-enter");
+        cx.run_until_parked();
+        assert_eq!(*tool.inputs.lock(), [json!({ "text": "hi" })]);
+        modal.read_with(cx, |modal, _| match &modal.state {
+            State::Output(output) => assert_eq!(output.as_ref(), r#"{"text":"hi"}"#),
+            _ => panic!("expected the tool's output"),
+        });
+    }
+
+    #[test]
+    fn test_sample_input() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "limit": { "type": "integer" },
+                "filter": {
+                    "type": "object",
+                    "properties": { "archived": { "type": "boolean" } }
+                },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "anything": {}
+            }
+        });
+        assert_eq!(
+            sample_input(&schema),
+            json!({
+                "query": "",
+                "limit": 0,
+                "filter": { "archived": false },
+                "tags": [],
+                "anything": null
+            })
+        );
+        assert_eq!(sample_input(&json!({ "type": "object" })), json!({}));
+    }
+}
@@ -1488,7 +1488,8 @@ impl AgentDiff {
             | ThreadEvent::ToolConfirmationNeeded
             | ThreadEvent::ToolUseLimitReached
             | ThreadEvent::CancelEditing
-            | ThreadEvent::ProfileChanged => {}
+            | ThreadEvent::ProfileChanged
+            | ThreadEvent::PinnedSnippetsChanged => {}
         }
     }
 
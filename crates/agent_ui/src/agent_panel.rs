@@ -14,10 +14,11 @@ use crate::agent_diff::AgentDiffThread;
 use crate::message_editor::{MAX_EDITOR_LINES, MIN_EDITOR_LINES};
 use crate::ui::NewThreadButton;
 use crate::{
-    AddContextServer, AgentDiffPane, ContinueThread, ContinueWithBurnMode,
-    DeleteRecentlyOpenThread, ExpandMessageEditor, Follow, InlineAssistant, NewTextThread,
-    NewThread, OpenActiveThreadAsMarkdown, OpenAgentDiff, OpenHistory, ResetTrialEndUpsell,
-    ResetTrialUpsell, ToggleBurnMode, ToggleContextPicker, ToggleNavigationMenu, ToggleOptionsMenu,
+    AddContextServer, AgentDiffPane, ClearSyncedTask, ContinueThread, ContinueWithBurnMode,
+    DeleteRecentlyOpenThread, ExpandMessageEditor, Follow, IdeContext, InlineAssistant,
+    NewTextThread, NewThread, OpenActiveThreadAsMarkdown, OpenAgentDiff, OpenHistory,
+    ResetTrialEndUpsell, ResetTrialUpsell, SyncLatestTask, ToggleBurnMode, ToggleContextPicker,
+    ToggleNavigationMenu, ToggleOptionsMenu,
     acp::AcpThreadView,
     active_thread::{self, ActiveThread, ActiveThreadEvent},
     agent_configuration::{AgentConfiguration, AssistantConfigurationEvent},
@@ -32,7 +33,8 @@ use crate::{
     ui::{AgentOnboardingModal, EndTrialUpsell},
 };
 use agent::{
-    Thread, ThreadError, ThreadEvent, ThreadId, ThreadSummary, TokenUsageRatio,
+    ActiveThreadDelegate, Thread, ThreadError, ThreadEvent, ThreadId, ThreadSummary,
+    TokenUsageRatio,
     context_store::ContextStore,
     history_store::{HistoryEntryId, HistoryStore},
     thread_store::{TextThreadStore, ThreadStore},
@@ -55,7 +57,8 @@ use gpui::{
 };
 use language::LanguageRegistry;
 use language_model::{
-    ConfigurationError, ConfiguredModel, LanguageModelProviderTosView, LanguageModelRegistry,
+    ConfigurationError, ConfiguredModel, LanguageModel, LanguageModelProvider,
+    LanguageModelProviderTosView, LanguageModelRegistry,
 };
 use oppla_actions::{
     DecreaseBufferFontSize, IncreaseBufferFontSize, ResetBufferFontSize,
@@ -90,8 +93,10 @@ struct SerializedAgentPanel {
 }
 
 pub fn init(cx: &mut App) {
+    <dyn ActiveThreadDelegate>::set_global(Arc::new(ConcreteAssistantPanelDelegate), cx);
     cx.observe_new(
-        |workspace: &mut Workspace, _window, _cx: &mut Context<Workspace>| {
+        |workspace: &mut Workspace, _window, cx: &mut Context<Workspace>| {
+            IdeContext::track_project(workspace.project(), cx);
             workspace
                 .register_action(|workspace, action: &NewThread, window, cx| {
                     if let Some(panel) = workspace.panel::<AgentPanel>(cx) {
@@ -192,6 +197,18 @@ pub fn init(cx: &mut App) {
                 })
                 .register_action(|_workspace, _: &ResetTrialEndUpsell, _window, cx| {
                     TrialEndUpsell::set_dismissed(false, cx);
+                })
+                .register_action(|workspace, _: &SyncLatestTask, window, cx| {
+                    if let Some(panel) = workspace.panel::<AgentPanel>(cx) {
+                        workspace.focus_panel::<AgentPanel>(window, cx);
+                        panel.update(cx, |panel, cx| panel.sync_latest_task(window, cx));
+                    }
+                })
+                .register_action(|workspace, _: &ClearSyncedTask, _window, cx| {
+                    let project_id = workspace.project().entity_id();
+                    IdeContext::update(cx, |ide_context| {
+                        ide_context.clear_sync_data(Some(project_id))
+                    });
                 });
         },
     )
@@ -1297,6 +1314,7 @@ impl AgentPanel {
                 tools,
                 self.language_registry.clone(),
                 self.workspace.clone(),
+                self.project.entity_id(),
                 window,
                 cx,
             )
@@ -1313,6 +1331,18 @@ impl AgentPanel {
         }
     }
 
+    /// Syncs the latest version of the synced task, opening the
+    /// configuration view first if it hasn't been opened yet, since that's
+    /// where the sync flow reports its progress.
+    fn sync_latest_task(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.configuration.is_none() {
+            self.open_configuration(window, cx);
+        }
+        if let Some(configuration) = self.configuration.as_ref() {
+            configuration.update(cx, |configuration, cx| configuration.sync_latest_task(cx));
+        }
+    }
+
     pub(crate) fn open_active_thread_as_markdown(
         &mut self,
         _: &OpenActiveThreadAsMarkdown,
@@ -1353,11 +1383,13 @@ impl AgentPanel {
     ) {
         match event {
             AssistantConfigurationEvent::NewThread(provider) => {
+                let provider_model = model_for_new_thread(provider.as_ref(), cx);
+
                 if LanguageModelRegistry::read_global(cx)
                     .default_model()
                     .map_or(true, |model| model.provider.id() != provider.id())
                 {
-                    if let Some(model) = provider.default_model(cx) {
+                    if let Some(model) = provider_model.clone() {
                         update_settings_file::<AgentSettings>(
                             self.fs.clone(),
                             cx,
@@ -1367,9 +1399,7 @@ impl AgentPanel {
                 }
 
                 self.new_thread(&NewThread::default(), window, cx);
-                if let Some((thread, model)) =
-                    self.active_thread(cx).zip(provider.default_model(cx))
-                {
+                if let Some((thread, model)) = self.active_thread(cx).zip(provider_model) {
                     thread.update(cx, |thread, cx| {
                         thread.set_configured_model(
                             Some(ConfiguredModel {
@@ -1618,6 +1648,24 @@ fn agent_panel_dock_position(cx: &App) -> DockPosition {
     }
 }
 
+/// The model a thread started from a provider in the agent configuration uses:
+/// the default picked for that provider while it still offers it, and
+/// otherwise the provider's own default.
+fn model_for_new_thread(
+    provider: &dyn LanguageModelProvider,
+    cx: &App,
+) -> Option<Arc<dyn LanguageModel>> {
+    AgentSettings::get_global(cx)
+        .default_model_for_provider(&provider.id().0)
+        .and_then(|model_id| {
+            provider
+                .provided_models(cx)
+                .into_iter()
+                .find(|model| model.id().0.as_ref() == model_id)
+        })
+        .or_else(|| provider.default_model(cx))
+}
+
 impl EventEmitter<PanelEvent> for AgentPanel {}
 
 impl Panel for AgentPanel {
@@ -3396,6 +3444,20 @@ impl rules_library::InlineAssistDelegate for PromptLibraryInlineAssist {
 
 pub struct ConcreteAssistantPanelDelegate;
 
+impl ActiveThreadDelegate for ConcreteAssistantPanelDelegate {
+    fn active_thread(&self, workspace: &Workspace, cx: &App) -> Option<Entity<Thread>> {
+        let panel = workspace.panel::<AgentPanel>(cx)?;
+        match &panel.read(cx).active_view {
+            // Tool cards ask while the thread view is rendering them, so this
+            // can't read the thread view itself.
+            ActiveView::Thread { message_editor, .. } => {
+                Some(message_editor.read(cx).thread().clone())
+            }
+            _ => None,
+        }
+    }
+}
+
 impl AgentPanelDelegate for ConcreteAssistantPanelDelegate {
     fn active_context_editor(
         &self,
@@ -3503,3 +3565,57 @@ struct TrialEndUpsell;
 impl Dismissable for TrialEndUpsell {
     const KEY: &'static str = "dismissed-trial-end-upsell";
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use language_model::fake_provider::FakeLanguageModelProvider;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    fn test_model_for_new_thread(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            AgentSettings::register(cx);
+        });
+        let provider = FakeLanguageModelProvider::default().with_model_ids(["small", "large"]);
+        let set_provider_default_models = |json: &str, cx: &mut TestAppContext| {
+            cx.update(|cx| {
+                SettingsStore::update_global(cx, |store, cx| {
+                    store
+                        .set_user_settings(
+                            &format!(r#"{{"agent": {{"provider_default_models": {json}}}}}"#),
+                            cx,
+                        )
+                        .unwrap();
+                })
+            })
+        };
+        let model_id = |cx: &mut TestAppContext| {
+            cx.update(|cx| {
+                model_for_new_thread(&provider, cx).map(|model| model.id().0.to_string())
+            })
+        };
+
+        // Without a default picked for the provider, the provider's own is used.
+        assert_eq!(model_id(cx).as_deref(), Some("fake"));
+
+        set_provider_default_models(r#"{"fake": "large"}"#, cx);
+        cx.update(|cx| {
+            let settings = AgentSettings::get_global(cx);
+            assert_eq!(settings.default_model_for_provider("fake"), Some("large"));
+            assert_eq!(settings.default_model_for_provider("other"), None);
+        });
+        assert_eq!(model_id(cx).as_deref(), Some("large"));
+
+        // A default the provider no longer offers falls back to its own.
+        set_provider_default_models(r#"{"fake": "removed"}"#, cx);
+        assert_eq!(model_id(cx).as_deref(), Some("fake"));
+
+        // Defaults picked for other providers are ignored.
+        set_provider_default_models(r#"{"other": "large"}"#, cx);
+        assert_eq!(model_id(cx).as_deref(), Some("fake"));
+    }
+}
@@ -13,16 +13,23 @@ mod debug;
 mod inline_assistant;
 mod inline_prompt_editor;
 mod language_model_selector;
+mod llm_request_debug;
+mod llm_token_debug;
 mod message_editor;
 mod profile_selector;
+mod provider_credentials;
+mod provider_usage;
+mod recent_searches;
 mod slash_command;
 mod slash_command_picker;
 mod slash_command_settings;
+mod task_sync_status;
 mod terminal_codegen;
 mod terminal_inline_assistant;
 mod text_thread_editor;
 mod thread_history;
 mod tool_compatibility;
+mod tool_output_debug;
 mod ui;
 
 use std::rc::Rc;
@@ -33,6 +40,7 @@ use agent_settings::{AgentProfileId, AgentSettings, LanguageModelSelection};
 use assistant_slash_command::SlashCommandRegistry;
 use client::{Client, DisableAiSettings};
 use command_palette_hooks::CommandPaletteFilter;
+use extension::ExtensionHostProxy;
 use feature_flags::FeatureFlagAppExt as _;
 use fs::Fs;
 use gpui::{Action, App, Entity, actions};
@@ -51,7 +59,10 @@ use crate::agent_configuration::{ConfigureContextServerModal, ManageProfilesModa
 pub use crate::agent_configuration::{IdeContext, TaskSyncData};
 pub use crate::agent_panel::{AgentPanel, ConcreteAssistantPanelDelegate};
 pub use crate::inline_assistant::InlineAssistant;
+use crate::provider_usage::ProviderUsage;
+pub use crate::recent_searches::RecentSearches;
 use crate::slash_command_settings::SlashCommandSettings;
+pub use crate::task_sync_status::TaskSyncStatus;
 pub use agent_diff::{AgentDiffPane, AgentDiffToolbar};
 use oppla_actions;
 pub use text_thread_editor::{AgentPanelDelegate, TextThreadEditor};
@@ -124,6 +135,12 @@ actions!(
         ContinueWithBurnMode,
         /// Toggles burn mode for faster responses.
         ToggleBurnMode,
+        /// Syncs the latest version of the task synced in this workspace.
+        SyncLatestTask,
+        /// Clears the task synced in this workspace.
+        ClearSyncedTask,
+        /// Clears the cached results of recent file searches.
+        ClearSearchCache,
     ]
 );
 
@@ -223,6 +240,13 @@ pub fn init(
     assistant_slash_command::init(cx);
     agent::init(cx);
     agent_panel::init(cx);
+    ProviderUsage::init(cx);
+    RecentSearches::init(cx);
+    llm_token_debug::init(client.clone(), cx);
+    llm_request_debug::init(cx);
+    tool_output_debug::init(cx);
+    ExtensionHostProxy::default_global(cx)
+        .register_task_context_proxy(agent_configuration::IdeContextTaskContextProxy::default());
     context_server_configuration::init(language_registry.clone(), fs.clone(), cx);
     TextThreadEditor::init(cx);
 
@@ -0,0 +1,45 @@
+use std::any::TypeId;
+
+use agent_settings::AgentSettings;
+use command_palette_hooks::CommandPaletteFilter;
+use gpui::{App, ClipboardItem};
+use language_model::last_llm_request_as_curl;
+use oppla_actions::dev::CopyLastLlmRequestAsCurl;
+use settings::{Settings as _, SettingsStore};
+
+pub fn init(cx: &mut App) {
+    cx.on_action(|_: &CopyLastLlmRequestAsCurl, cx| {
+        if !is_enabled(cx) {
+            log::warn!("dev::CopyLastLlmRequestAsCurl is only available with log_llm_requests");
+            return;
+        }
+        let Some(command) = last_llm_request_as_curl() else {
+            log::warn!(
+                "no search or embedding request was sent since log_llm_requests was enabled"
+            );
+            return;
+        };
+        cx.write_to_clipboard(ClipboardItem::new_string(command));
+        log::info!("copied the last LLM request as a curl command to the clipboard");
+    });
+
+    update_command_palette_filter(cx);
+    cx.observe_global::<SettingsStore>(update_command_palette_filter)
+        .detach();
+}
+
+fn is_enabled(cx: &App) -> bool {
+    AgentSettings::get_global(cx).log_llm_requests
+}
+
+fn update_command_palette_filter(cx: &mut App) {
+    let is_enabled = is_enabled(cx);
+    CommandPaletteFilter::update_global(cx, |filter, _| {
+        let action_types = [TypeId::of::<CopyLastLlmRequestAsCurl>()];
+        if is_enabled {
+            filter.show_action_types(action_types.iter());
+        } else {
+            filter.hide_action_types(&action_types);
+        }
+    });
+}
@@ -0,0 +1,50 @@
+use std::{any::TypeId, sync::Arc};
+
+use client::Client;
+use command_palette_hooks::CommandPaletteFilter;
+use feature_flags::{FeatureFlagAppExt as _, LlmTokenDebugFeatureFlag};
+use gpui::{App, AppContext as _, ClipboardItem};
+use language_model::{LlmApiToken, llm_token_metadata};
+use oppla_actions::dev::CopyLlmTokenMetadata;
+
+pub fn init(client: Arc<Client>, cx: &mut App) {
+    cx.on_action(move |_: &CopyLlmTokenMetadata, cx| {
+        if !is_enabled(cx) {
+            log::warn!("dev::CopyLlmTokenMetadata is only available to staff and in debug builds");
+            return;
+        }
+        copy_llm_token_metadata(client.clone(), cx);
+    });
+
+    update_command_palette_filter(is_enabled(cx), cx);
+    cx.observe_flag::<LlmTokenDebugFeatureFlag, _>(|is_enabled, cx| {
+        update_command_palette_filter(is_enabled || cfg!(debug_assertions), cx)
+    })
+    .detach();
+}
+
+fn is_enabled(cx: &App) -> bool {
+    cfg!(debug_assertions) || cx.has_flag::<LlmTokenDebugFeatureFlag>()
+}
+
+fn update_command_palette_filter(is_enabled: bool, cx: &mut App) {
+    CommandPaletteFilter::update_global(cx, |filter, _| {
+        let action_types = [TypeId::of::<CopyLlmTokenMetadata>()];
+        if is_enabled {
+            filter.show_action_types(action_types.iter());
+        } else {
+            filter.hide_action_types(&action_types);
+        }
+    });
+}
+
+fn copy_llm_token_metadata(client: Arc<Client>, cx: &mut App) {
+    cx.spawn(async move |cx| {
+        let token = LlmApiToken::default().acquire(&client).await?;
+        let metadata = serde_json::to_string_pretty(&llm_token_metadata(&token)?)?;
+        cx.update(|cx| cx.write_to_clipboard(ClipboardItem::new_string(metadata)))?;
+        log::info!("copied LLM API token metadata to the clipboard");
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
@@ -64,7 +64,7 @@ use crate::{
     ToggleContextPicker, ToggleProfileSelector, register_agent_preview,
 };
 use agent::{
-    MessageCrease, Thread, TokenUsageRatio,
+    MessageCrease, Thread, ThreadEvent, TokenUsageRatio,
     context_store::ContextStore,
     thread_store::{TextThreadStore, ThreadStore},
 };
@@ -212,6 +212,11 @@ impl MessageEditor {
             cx.observe(&thread.read(cx).action_log().clone(), |_, _, cx| {
                 cx.notify()
             }),
+            cx.subscribe(&thread, |_, _, event, cx| {
+                if let ThreadEvent::PinnedSnippetsChanged = event {
+                    cx.notify()
+                }
+            }),
         ];
 
         let model_selector = cx.new(|cx| {
@@ -617,6 +622,41 @@ impl MessageEditor {
         )
     }
 
+    fn render_tool_confirmation_toggle(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let thread = self.thread.read(cx);
+        let always_allow = thread.always_allow_tool_actions(cx);
+        let is_overridden = thread.always_allow_tool_actions_override().is_some();
+        let global_always_allow = AgentSettings::get_global(cx).always_allow_tool_actions;
+
+        IconButton::new("thread-always-allow-tool-actions", IconName::CheckDouble)
+            .icon_size(IconSize::Small)
+            .icon_color(Color::Muted)
+            .toggle_state(always_allow)
+            .selected_icon_color(Color::Warning)
+            .tooltip(move |window, cx| {
+                let title = if always_allow {
+                    "Tools Run Without Confirmation"
+                } else {
+                    "Tools Ask for Confirmation"
+                };
+                let meta = if is_overridden {
+                    "Overrides your Agent Panel settings for this thread only."
+                } else {
+                    "Follows your Agent Panel settings. Click to change it for this thread only."
+                };
+                Tooltip::with_meta(title, None, meta, window, cx)
+            })
+            .on_click(cx.listener(move |this, _, _, cx| {
+                // Matching the setting again means following it again.
+                let always_allow = !always_allow;
+                let always_allow_override =
+                    (always_allow != global_always_allow).then_some(always_allow);
+                this.thread.update(cx, |thread, cx| {
+                    thread.set_always_allow_tool_actions_override(always_allow_override, cx);
+                });
+            }))
+    }
+
     fn render_follow_toggle(
         &self,
         is_model_selected: bool,
@@ -661,6 +701,51 @@ impl MessageEditor {
             }))
     }
 
+    pub(crate) fn thread(&self) -> &Entity<Thread> {
+        &self.thread
+    }
+
+    fn render_pinned_snippets(&self, cx: &mut Context<Self>) -> Option<AnyElement> {
+        let snippets = self.thread.read(cx).pinned_snippets();
+        if snippets.is_empty() {
+            return None;
+        }
+
+        Some(
+            h_flex()
+                .flex_wrap()
+                .gap_1()
+                .children(snippets.iter().map(|snippet| {
+                    let id = snippet.id.clone();
+                    let preview: SharedString = snippet
+                        .content
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string()
+                        .into();
+                    Button::new(
+                        SharedString::from(format!("pinned-snippet-{id}")),
+                        snippet.label.clone(),
+                    )
+                    .style(ButtonStyle::Filled)
+                    .label_size(LabelSize::Small)
+                    .icon(IconName::Unpin)
+                    .icon_size(IconSize::XSmall)
+                    .icon_color(Color::Muted)
+                    .icon_position(IconPosition::End)
+                    .tooltip(move |window, cx| {
+                        Tooltip::with_meta("Unpin from Context", None, preview.clone(), window, cx)
+                    })
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        this.thread
+                            .update(cx, |thread, cx| thread.unpin_snippet(&id, cx));
+                    }))
+                }))
+                .into_any_element(),
+        )
+    }
+
     fn render_editor(&self, window: &mut Window, cx: &mut Context<Self>) -> Div {
         let thread = self.thread.read(cx);
         let model = thread.configured_model();
@@ -685,6 +770,8 @@ impl MessageEditor {
             })
             .unwrap_or_default();
 
+        let pinned_snippets = self.render_pinned_snippets(cx);
+
         let is_editor_expanded = self.editor_is_expanded;
         let expand_icon = if is_editor_expanded {
             IconName::Minimize
@@ -756,6 +843,7 @@ impl MessageEditor {
                         )
                     }),
             )
+            .children(pinned_snippets)
             .child(
                 v_flex()
                     .size_full()
@@ -800,6 +888,7 @@ impl MessageEditor {
                             .child(
                                 h_flex()
                                     .child(self.render_follow_toggle(is_model_selected, cx))
+                                    .child(self.render_tool_confirmation_toggle(cx))
                                     .children(self.render_burn_mode_toggle(cx)),
                             )
                             .child(
@@ -0,0 +1,75 @@
+use std::time::{Duration, Instant};
+
+use collections::HashMap;
+use futures::future::join_all;
+use gpui::{App, BorrowAppContext as _, Global, SharedString, Task};
+use language_model::{LanguageModelProviderId, LanguageModelRegistry};
+use util::ResultExt as _;
+
+/// Revalidations closer together than this are skipped, so that reopening the
+/// agent configuration doesn't send a request to every provider each time.
+const REVALIDATION_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Tracks the authenticated providers whose credentials were rejected when
+/// last checked, since `is_authenticated` only says that credentials exist.
+#[derive(Default)]
+pub struct ProviderCredentials {
+    rejected: HashMap<LanguageModelProviderId, SharedString>,
+    last_revalidated_at: Option<Instant>,
+    revalidation: Option<Task<()>>,
+}
+
+impl Global for ProviderCredentials {}
+
+impl ProviderCredentials {
+    /// Why the provider's credentials were rejected, if they were.
+    pub fn rejection(provider_id: &LanguageModelProviderId, cx: &App) -> Option<SharedString> {
+        cx.try_global::<Self>()?.rejected.get(provider_id).cloned()
+    }
+
+    /// Forgets a rejection once the provider's credentials changed.
+    pub fn clear(provider_id: &LanguageModelProviderId, cx: &mut App) {
+        if Self::rejection(provider_id, cx).is_some() {
+            cx.update_global::<Self, _>(|this, _| this.rejected.remove(provider_id));
+        }
+    }
+
+    /// Checks the credentials of every authenticated provider in the
+    /// background, unless they were checked recently.
+    pub fn revalidate(cx: &mut App) {
+        let now = Instant::now();
+        let this = cx.default_global::<Self>();
+        if this
+            .last_revalidated_at
+            .is_some_and(|last_revalidated_at| now - last_revalidated_at < REVALIDATION_INTERVAL)
+        {
+            return;
+        }
+        this.last_revalidated_at = Some(now);
+
+        let checks = LanguageModelRegistry::read_global(cx)
+            .providers()
+            .into_iter()
+            .filter(|provider| provider.is_authenticated(cx))
+            .map(|provider| {
+                let provider_id = provider.id();
+                let check = provider.validate_credentials(cx);
+                async move { (provider_id, check.await) }
+            })
+            .collect::<Vec<_>>();
+
+        let revalidation = cx.spawn(async move |cx| {
+            let results = join_all(checks).await;
+            cx.update_global::<Self, _>(|this, _| {
+                for (provider_id, result) in results {
+                    match result {
+                        Ok(()) => this.rejected.remove(&provider_id),
+                        Err(error) => this.rejected.insert(provider_id, error.to_string().into()),
+                    };
+                }
+            })
+            .log_err();
+        });
+        cx.default_global::<Self>().revalidation = Some(revalidation);
+    }
+}
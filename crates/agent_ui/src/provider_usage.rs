@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use collections::HashMap;
+use db::kvp::KEY_VALUE_STORE;
+use gpui::{App, AppContext as _, Global, Task};
+use language_model::LanguageModelProviderId;
+use util::ResultExt as _;
+
+const PROVIDER_LAST_USED_KEY: &str = "agent_provider_last_used";
+
+/// Uses closer together than this are not written to the database again.
+const RECORD_INTERVAL_SECS: i64 = 60;
+
+/// Tracks when each language model provider was last used by a thread, so that
+/// providers that were configured but never used stand out in the settings.
+#[derive(Default)]
+pub struct ProviderUsage {
+    last_used: HashMap<String, DateTime<Utc>>,
+    pending_write: Option<Task<()>>,
+}
+
+impl Global for ProviderUsage {}
+
+impl ProviderUsage {
+    pub fn init(cx: &mut App) {
+        cx.spawn(async move |cx| {
+            let last_used = cx
+                .background_spawn(async move { KEY_VALUE_STORE.read_kvp(PROVIDER_LAST_USED_KEY) })
+                .await
+                .log_err()
+                .flatten()
+                .and_then(|value| {
+                    serde_json::from_str::<HashMap<String, DateTime<Utc>>>(&value).log_err()
+                })
+                .unwrap_or_default();
+
+            cx.update(|cx| {
+                let usage = cx.default_global::<Self>();
+                for (provider_id, timestamp) in last_used {
+                    // Anything recorded while loading is more recent.
+                    usage.last_used.entry(provider_id).or_insert(timestamp);
+                }
+            })
+            .log_err();
+        })
+        .detach();
+    }
+
+    pub fn last_used(provider_id: &LanguageModelProviderId, cx: &App) -> Option<DateTime<Utc>> {
+        cx.try_global::<Self>()?
+            .last_used
+            .get(provider_id.0.as_ref())
+            .copied()
+    }
+
+    pub fn record_use(provider_id: &LanguageModelProviderId, cx: &mut App) {
+        let now = Utc::now();
+        if Self::last_used(provider_id, cx).is_some_and(|last_used| {
+            now.signed_duration_since(last_used).num_seconds() < RECORD_INTERVAL_SECS
+        }) {
+            return;
+        }
+
+        let usage = cx.default_global::<Self>();
+        usage.last_used.insert(provider_id.0.to_string(), now);
+        let Some(serialized) = serde_json::to_string(&usage.last_used).log_err() else {
+            return;
+        };
+
+        let write = cx.background_spawn(async move {
+            KEY_VALUE_STORE
+                .write_kvp(PROVIDER_LAST_USED_KEY.to_string(), serialized)
+                .await
+                .log_err();
+        });
+        cx.default_global::<Self>().pending_write = Some(write);
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::VecDeque;
+
+use db::kvp::KEY_VALUE_STORE;
+use gpui::{App, AppContext as _, Global, Task};
+use util::ResultExt as _;
+
+const RECENT_SEARCHES_KEY: &str = "agent_recent_file_searches";
+
+const MAX_RECENT_SEARCHES: usize = 10;
+
+/// The last distinct inputs the file search tool was run with, by the agent or
+/// from the test search, most recent first. Kept across sessions so that
+/// common searches can be run again without retyping them.
+#[derive(Default)]
+pub struct RecentSearches {
+    inputs: VecDeque<serde_json::Value>,
+    /// The values the search backend reported supporting for the `type` and
+    /// `content_type` filters, if it did. Searches using others are hidden,
+    /// since running them again would only fail.
+    supported_types: Option<Vec<String>>,
+    supported_content_types: Option<Vec<String>>,
+    pending_write: Option<Task<()>>,
+}
+
+impl Global for RecentSearches {}
+
+impl RecentSearches {
+    pub fn init(cx: &mut App) {
+        cx.spawn(async move |cx| {
+            let inputs = cx
+                .background_spawn(async move { KEY_VALUE_STORE.read_kvp(RECENT_SEARCHES_KEY) })
+                .await
+                .log_err()
+                .flatten()
+                .and_then(|value| serde_json::from_str::<Vec<serde_json::Value>>(&value).log_err())
+                .unwrap_or_default();
+
+            cx.update(|cx| {
+                let recent = cx.default_global::<Self>();
+                // Anything recorded while loading is more recent.
+                for input in inputs {
+                    if recent.inputs.len() >= MAX_RECENT_SEARCHES {
+                        break;
+                    }
+                    if !recent.inputs.contains(&input) {
+                        recent.inputs.push_back(input);
+                    }
+                }
+            })
+            .log_err();
+        })
+        .detach();
+    }
+
+    pub fn inputs(cx: &App) -> Vec<serde_json::Value> {
+        cx.try_global::<Self>()
+            .map(|recent| {
+                recent
+                    .inputs
+                    .iter()
+                    .filter(|input| recent.is_supported(input))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn set_supported_filter_values(
+        types: Option<Vec<String>>,
+        content_types: Option<Vec<String>>,
+        cx: &mut App,
+    ) {
+        let recent = cx.default_global::<Self>();
+        recent.supported_types = types;
+        recent.supported_content_types = content_types;
+    }
+
+    fn is_supported(&self, input: &serde_json::Value) -> bool {
+        let supports = |key: &str, supported: &Option<Vec<String>>| {
+            let value = input
+                .get("filter")
+                .and_then(|filter| filter.get(key))
+                .and_then(|value| value.as_str());
+            match (value, supported) {
+                (Some(value), Some(supported)) => supported.iter().any(|s| s == value),
+                _ => true,
+            }
+        };
+        supports("type", &self.supported_types)
+            && supports("content_type", &self.supported_content_types)
+    }
+
+    pub fn record(input: serde_json::Value, cx: &mut App) {
+        let recent = cx.default_global::<Self>();
+        if recent.inputs.front() == Some(&input) {
+            return;
+        }
+        recent.push(input);
+        Self::save(cx);
+    }
+
+    pub fn clear(cx: &mut App) {
+        cx.default_global::<Self>().inputs.clear();
+        Self::save(cx);
+    }
+
+    fn push(&mut self, input: serde_json::Value) {
+        self.inputs.retain(|recent| *recent != input);
+        self.inputs.push_front(input);
+        self.inputs.truncate(MAX_RECENT_SEARCHES);
+    }
+
+    fn save(cx: &mut App) {
+        let recent = cx.default_global::<Self>();
+        let Some(serialized) = serde_json::to_string(&recent.inputs).log_err() else {
+            return;
+        };
+        let write = cx.background_spawn(async move {
+            KEY_VALUE_STORE
+                .write_kvp(RECENT_SEARCHES_KEY.to_string(), serialized)
+                .await
+                .log_err();
+        });
+        cx.default_global::<Self>().pending_write = Some(write);
+    }
+
+    /// A short description of a search, naming its query or thread and the
+    /// filters it was narrowed by.
+    pub fn label(input: &serde_json::Value) -> String {
+        let filter = input.get("filter").and_then(|filter| filter.as_object());
+        let mut label = match input.get("query").and_then(|query| query.as_str()) {
+            Some(query) => format!("\"{query}\""),
+            None => match filter
+                .and_then(|filter| filter.get("thread_id"))
+                .and_then(|thread_id| thread_id.as_str())
+            {
+                Some(thread_id) => format!("Thread {thread_id}"),
+                None => "Search".to_string(),
+            },
+        };
+
+        let filters = filter
+            .into_iter()
+            .flatten()
+            .filter(|(key, _)| *key != "thread_id")
+            .filter_map(|(key, value)| Some(format!("{key}: {}", value.as_str()?)))
+            .collect::<Vec<_>>();
+        if !filters.is_empty() {
+            label.push_str(&format!(" ({})", filters.join(", ")));
+        }
+        label
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_push_keeps_recent_distinct_inputs() {
+        let mut recent = RecentSearches::default();
+        for ix in 0..MAX_RECENT_SEARCHES + 2 {
+            recent.push(json!({ "query": ix.to_string() }));
+        }
+        recent.push(json!({ "query": "5" }));
+
+        assert_eq!(recent.inputs.len(), MAX_RECENT_SEARCHES);
+        assert_eq!(recent.inputs[0], json!({ "query": "5" }));
+        assert_eq!(recent.inputs[1], json!({ "query": "11" }));
+        assert_eq!(
+            recent
+                .inputs
+                .iter()
+                .filter(|input| **input == json!({ "query": "5" }))
+                .count(),
+            1
+        );
+        assert!(!recent.inputs.contains(&json!({ "query": "0" })));
+    }
+
+    #[test]
+    fn test_unsupported_filters_are_hidden() {
+        let mut recent = RecentSearches::default();
+        let tasks = json!({ "query": "vim", "filter": { "type": "tasks" } });
+        let requirements = json!({ "query": "vim", "filter": { "content_type": "requirements" } });
+        assert!(recent.is_supported(&tasks));
+        assert!(recent.is_supported(&requirements));
+
+        recent.supported_types = Some(vec!["all".into(), "tasks".into()]);
+        recent.supported_content_types = Some(vec!["auto".into()]);
+        assert!(recent.is_supported(&tasks));
+        assert!(!recent.is_supported(&requirements));
+        assert!(recent.is_supported(&json!({ "query": "vim" })));
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(
+            RecentSearches::label(&json!({
+                "query": "vim mode",
+                "filter": { "type": "tasks", "content_type": "requirements" }
+            })),
+            "\"vim mode\" (type: tasks, content_type: requirements)"
+        );
+        assert_eq!(
+            RecentSearches::label(&json!({ "filter": { "thread_id": "t1" } })),
+            "Thread t1"
+        );
+    }
+}
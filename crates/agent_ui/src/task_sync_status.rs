@@ -0,0 +1,76 @@
+use gpui::{Action, Corner, Entity, EntityId, Subscription};
+use ui::{ContextMenu, PopoverMenu, Tooltip, prelude::*};
+use workspace::{ItemHandle, StatusItemView, Workspace};
+
+use crate::{ClearSyncedTask, IdeContext, SyncLatestTask};
+
+/// Shows the task synced in the workspace's project in the status bar, with
+/// a menu to sync the latest version of it or clear it.
+pub struct TaskSyncStatus {
+    project_id: EntityId,
+    _observe_ide_context: Subscription,
+}
+
+impl TaskSyncStatus {
+    pub fn new(workspace: &Workspace, cx: &mut Context<Self>) -> Self {
+        Self {
+            project_id: workspace.project().entity_id(),
+            _observe_ide_context: cx.observe_global::<IdeContext>(|_, cx| cx.notify()),
+        }
+    }
+
+    fn build_menu(window: &mut Window, cx: &mut App) -> Entity<ContextMenu> {
+        ContextMenu::build(window, cx, |menu, _, _| {
+            menu.action("Sync Latest", SyncLatestTask.boxed_clone())
+                .action("Clear", ClearSyncedTask.boxed_clone())
+        })
+    }
+}
+
+impl Render for TaskSyncStatus {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let Some(sync_data) = cx
+            .try_global::<IdeContext>()
+            .and_then(|ide_context| ide_context.get_project_sync_data(self.project_id))
+        else {
+            return div().w_0().invisible();
+        };
+
+        let label = sync_data
+            .work_item
+            .clone()
+            .or_else(|| sync_data.big_bet.clone())
+            .unwrap_or_else(|| sync_data.board_id.clone());
+        let tooltip = match &sync_data.big_bet {
+            Some(big_bet) if sync_data.work_item.is_some() => {
+                format!("Synced task in {big_bet}")
+            }
+            _ => "Synced task".to_string(),
+        };
+
+        div().child(
+            PopoverMenu::new("task-sync-status-menu")
+                .anchor(Corner::BottomRight)
+                .menu(|window, cx| Some(Self::build_menu(window, cx)))
+                .trigger_with_tooltip(
+                    Button::new("task-sync-status", label)
+                        .label_size(LabelSize::Small)
+                        .icon(IconName::ListTodo)
+                        .icon_size(IconSize::XSmall)
+                        .icon_color(Color::Muted)
+                        .icon_position(IconPosition::Start),
+                    Tooltip::text(tooltip),
+                ),
+        )
+    }
+}
+
+impl StatusItemView for TaskSyncStatus {
+    fn set_active_pane_item(
+        &mut self,
+        _active_pane_item: Option<&dyn ItemHandle>,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) {
+    }
+}
@@ -0,0 +1,10 @@
+use assistant_tool::ToolOutputLog;
+use gpui::{App, ClipboardItem};
+use oppla_actions::dev::CopyRecentToolOutputs;
+
+pub fn init(cx: &mut App) {
+    cx.on_action(|_: &CopyRecentToolOutputs, cx| {
+        cx.write_to_clipboard(ClipboardItem::new_string(ToolOutputLog::dump(cx)));
+        log::info!("copied the recent tool outputs to the clipboard");
+    });
+}
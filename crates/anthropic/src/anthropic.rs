@@ -471,6 +471,28 @@ fn get_header<'a>(key: &str, headers: &'a HeaderMap) -> anyhow::Result<&'a str>
         .to_str()?)
 }
 
+/// Checks that the API key is accepted by listing the available models.
+/// Failures other than the key being rejected, like the network being down,
+/// aren't reported, since they say nothing about the key.
+pub async fn validate_api_key(client: &dyn HttpClient, api_url: &str, api_key: &str) -> Result<()> {
+    let request = HttpRequest::builder()
+        .method(Method::GET)
+        .uri(format!("{api_url}/v1/models"))
+        .header("Anthropic-Version", "2023-06-01")
+        .header("X-Api-Key", api_key)
+        .body(AsyncBody::default())?;
+    let Ok(response) = client.send(request).await else {
+        return Ok(());
+    };
+    match response.status() {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(anyhow!(
+            "The API key was rejected ({}). It may have been revoked.",
+            response.status()
+        )),
+        _ => Ok(()),
+    }
+}
+
 pub async fn stream_completion_with_rate_limit_info(
     client: &dyn HttpClient,
     api_url: &str,
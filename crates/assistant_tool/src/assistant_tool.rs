@@ -1,5 +1,6 @@
 mod action_log;
 pub mod outline;
+mod tool_output_log;
 mod tool_registry;
 mod tool_schema;
 mod tool_working_set;
@@ -26,6 +27,7 @@ use project::Project;
 use workspace::Workspace;
 
 pub use crate::action_log::*;
+pub use crate::tool_output_log::*;
 pub use crate::tool_registry::*;
 pub use crate::tool_schema::*;
 pub use crate::tool_working_set::*;
@@ -218,6 +220,12 @@ pub trait Tool: 'static + Send + Sync {
     /// before having permission to run.
     fn needs_confirmation(&self, input: &serde_json::Value, cx: &App) -> bool;
 
+    /// Returns the shell command the tool would run with this input, so that
+    /// it can be checked against the user's command permissions.
+    fn command(&self, _input: &serde_json::Value) -> Option<String> {
+        None
+    }
+
     /// Returns true if the tool may perform edits.
     fn may_perform_edits(&self) -> bool;
 
@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use gpui::{App, Global};
+
+use crate::{ToolResultContent, ToolResultOutput};
+
+const MAX_LOGGED_TOOL_OUTPUTS: usize = 50;
+
+/// How much of each output is kept, so that a few large outputs can't hold on
+/// to much memory.
+const MAX_LOGGED_OUTPUT_LEN: usize = 4096;
+
+pub struct LoggedToolOutput {
+    pub tool_name: Arc<str>,
+    pub finished_at: Instant,
+    pub duration: Duration,
+    /// The output's text, or the error the tool failed with.
+    pub result: Result<String, String>,
+}
+
+/// The last outputs produced by tools in this session, kept whether or not
+/// request logging is enabled, so that intermittent agent issues can be
+/// diagnosed after the fact.
+#[derive(Default)]
+pub struct ToolOutputLog {
+    outputs: VecDeque<LoggedToolOutput>,
+}
+
+impl Global for ToolOutputLog {}
+
+impl ToolOutputLog {
+    pub fn record(
+        tool_name: Arc<str>,
+        started_at: Instant,
+        output: &Result<ToolResultOutput>,
+        cx: &mut App,
+    ) {
+        let result = match output {
+            Ok(output) => Ok(match &output.content {
+                ToolResultContent::Text(text) => truncate(text),
+                ToolResultContent::Image(image) => format!("[image, {} bytes]", image.len()),
+            }),
+            Err(error) => Err(truncate(&format!("{error:#}"))),
+        };
+        let finished_at = Instant::now();
+        cx.default_global::<Self>().push(LoggedToolOutput {
+            tool_name,
+            finished_at,
+            duration: finished_at.saturating_duration_since(started_at),
+            result,
+        });
+    }
+
+    fn push(&mut self, output: LoggedToolOutput) {
+        if self.outputs.len() >= MAX_LOGGED_TOOL_OUTPUTS {
+            self.outputs.pop_front();
+        }
+        self.outputs.push_back(output);
+    }
+
+    /// Every logged output as plain text, oldest first.
+    pub fn dump(cx: &App) -> String {
+        let Some(log) = cx
+            .try_global::<Self>()
+            .filter(|log| !log.outputs.is_empty())
+        else {
+            return "No tool outputs were logged in this session.".to_string();
+        };
+        let now = Instant::now();
+        let mut dump = String::new();
+        for output in &log.outputs {
+            let status = if output.result.is_ok() { "ok" } else { "error" };
+            writeln!(
+                dump,
+                "## {} ({status}, took {:?}, finished {}s ago)\n",
+                output.tool_name,
+                output.duration,
+                now.saturating_duration_since(output.finished_at).as_secs()
+            )
+            .ok();
+            let (Ok(text) | Err(text)) = &output.result;
+            writeln!(dump, "{}\n", text.trim_end()).ok();
+        }
+        dump
+    }
+}
+
+fn truncate(text: &str) -> String {
+    if text.len() <= MAX_LOGGED_OUTPUT_LEN {
+        return text.to_string();
+    }
+    let kept = util::truncate_to_byte_limit(text, MAX_LOGGED_OUTPUT_LEN);
+    format!("{kept}… ({} bytes truncated)", text.len() - kept.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_output_log_is_bounded() {
+        let mut log = ToolOutputLog::default();
+        for ix in 0..MAX_LOGGED_TOOL_OUTPUTS + 5 {
+            log.push(LoggedToolOutput {
+                tool_name: "file_search".into(),
+                finished_at: Instant::now(),
+                duration: Duration::ZERO,
+                result: Ok(ix.to_string()),
+            });
+        }
+        assert_eq!(log.outputs.len(), MAX_LOGGED_TOOL_OUTPUTS);
+        assert_eq!(log.outputs.front().unwrap().result, Ok("5".to_string()));
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short"), "short");
+        let long = "é".repeat(MAX_LOGGED_OUTPUT_LEN);
+        let truncated = truncate(&long);
+        assert!(truncated.len() < long.len());
+        let kept = MAX_LOGGED_OUTPUT_LEN - 2;
+        assert!(truncated.starts_with(&long[..kept]));
+        assert!(truncated.ends_with(&format!("({} bytes truncated)", long.len() - kept)));
+    }
+}
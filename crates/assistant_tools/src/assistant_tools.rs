@@ -51,7 +51,13 @@ pub use project_notifications_tool::ProjectNotificationsTool;
 pub use read_file_tool::{ReadFileTool, ReadFileToolInput};
 pub use terminal_tool::TerminalTool;
 
-pub fn init(http_client: Arc<HttpClientWithUrl>, cx: &mut App) {
+/// `search_http_client` is used for requests to the LLM service, which are
+/// frequent enough to benefit from a client that keeps connections pooled.
+pub fn init(
+    http_client: Arc<HttpClientWithUrl>,
+    search_http_client: Arc<HttpClientWithUrl>,
+    cx: &mut App,
+) {
     assistant_tool::init(cx);
 
     let registry = ToolRegistry::global(cx);
@@ -69,9 +75,11 @@ pub fn init(http_client: Arc<HttpClientWithUrl>, cx: &mut App) {
     registry.register_tool(ReadFileTool);
     registry.register_tool(GrepTool);
     registry.register_tool(ThinkingTool);
-    registry.register_tool(FetchTool::new(http_client.clone()));
+    registry.register_tool(FetchTool::new(http_client));
     registry.register_tool(EditFileTool);
-    registry.register_tool(FileSearchTool::new(http_client));
+    let file_search_tool = FileSearchTool::new(search_http_client);
+    file_search_tool::init_search_cache(&file_search_tool, cx);
+    registry.register_tool(file_search_tool);
 
     register_web_search_tool(&LanguageModelRegistry::global(cx), cx);
     cx.subscribe(
@@ -1,25 +1,54 @@
-use std::sync::Arc;
+use std::{
+    collections::VecDeque,
+    fmt,
+    path::Path,
+    pin::pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::schema::json_schema_for;
 use crate::ui::ToolCallCardHeader;
-use agent_ui::IdeContext;
+use agent::{ActiveThreadDelegate, PinnedSnippet, Thread};
+use agent_settings::{AgentSettings, FileSearchSettings, SimilarityMetric};
+use agent_ui::{ClearSearchCache, IdeContext, RecentSearches};
 use anyhow::{Context as _, Result, anyhow};
 use assistant_tool::{
     ActionLog, Tool, ToolCard, ToolResult, ToolResultContent, ToolResultOutput, ToolUseStatus,
 };
-use client::Client;
-use futures::AsyncReadExt as _;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use client::{CLOUD_FEATURES_DISABLED_MESSAGE, Client, DisableCloudFeaturesSettings};
+use collections::HashSet;
+use editor::{Editor, MultiBuffer};
+use futures::{
+    FutureExt as _,
+    channel::oneshot,
+    future::{self, Either, Shared},
+};
 use gpui::{
-    AnyWindowHandle, App, AppContext, Context, Entity, IntoElement, Task, WeakEntity, Window,
+    AnyWindowHandle, App, AppContext, ClipboardItem, Context, Entity, Global, IntoElement, Task,
+    WeakEntity, Window,
 };
-use http_client::{HttpClientWithUrl, Method};
+use http_client::HttpClientWithUrl;
 use language_model::{
-    LanguageModel, LanguageModelRequest, LanguageModelToolSchemaFormat, LlmApiToken,
+    LanguageModel, LanguageModelRequest, LanguageModelToolSchemaFormat, LlmApiError, LlmApiToken,
+    LlmHttp, LlmPostOptions, LlmRetryPolicy,
+};
+use parking_lot::Mutex;
+use project::{
+    Project,
+    context_server_store::{self, ContextServerStatus, ContextServerStore},
+    project_settings::{FileSearchFilterSettings, ProjectSettings},
 };
-use project::Project;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use ui::{Component, ComponentScope, Disclosure, IconName, Label, LabelSize, prelude::*};
+use settings::{Settings as _, SettingsLocation};
+use smol::lock::Semaphore;
+use ui::{
+    Banner, Checkbox, Chip, Component, ComponentScope, Disclosure, IconName, Label, LabelSize,
+    Tooltip, prelude::*,
+};
+use util::ResultExt as _;
 use workspace::Workspace;
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -35,15 +64,71 @@ pub struct FileSearchToolInput {
     /// Filter options for the search
     #[serde(skip_serializing_if = "Option::is_none")]
     filter: Option<SearchFilter>,
+
+    /// Whether to add the path of the file the user is editing, and the code they have selected, to the query. Use this when the user asks about the code they're currently working on.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    scope_to_active_editor: bool,
+
+    /// Optional model the backend should rerank results with, instead of ordering them by embedding similarity alone. Only set this when the user asks for it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rerank_model: Option<String>,
+
+    /// Whether to include "compressed" results, which are lower-fidelity summaries. Leave this unset to follow the user's settings, and set it to true when you need summaries that were left out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    include_compressed: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+impl FileSearchToolInput {
+    /// The query, unless it's blank.
+    fn query(&self) -> Option<&str> {
+        self.query
+            .as_deref()
+            .filter(|query| !query.trim().is_empty())
+    }
+
+    /// Drops a blank query and thread ID, so that they're treated the same as
+    /// missing ones, and checks that there's still something to search for.
+    fn validate(&mut self) -> Result<()> {
+        if self.query().is_none() {
+            self.query = None;
+        }
+        if let Some(filter) = self.filter.as_mut()
+            && filter
+                .thread_id
+                .as_deref()
+                .is_some_and(|thread_id| thread_id.trim().is_empty())
+        {
+            filter.thread_id = None;
+        }
+        anyhow::ensure!(
+            self.query.is_some()
+                || self
+                    .filter
+                    .as_ref()
+                    .is_some_and(|filter| !filter.is_empty()),
+            "Either 'query' or 'filter' must be provided"
+        );
+        Ok(())
+    }
+
+    /// Whether there's only a filter, in which case matching items are listed
+    /// by their metadata instead of being searched for by similarity.
+    fn is_metadata_only(&self) -> bool {
+        self.query.is_none()
+            && self
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.thread_id.is_none())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct SearchFilter {
     /// Type of content to search: "conversations", "tasks", "compressed", or "all"
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     search_type: Option<String>,
 
-    /// Content to extract: "work_item" (work item details only), "big_bet" (big bet details only), or "auto" (automatically decide based on context)
+    /// Content to extract: "work_item" (work item details only), "big_bet" (big bet details only), "requirements" (requirements and acceptance criteria only), or "auto" (automatically decide based on context)
     #[serde(skip_serializing_if = "Option::is_none")]
     content_type: Option<String>,
 
@@ -66,6 +151,67 @@ pub struct SearchFilter {
     /// Optional task ID to filter results by specific task
     #[serde(skip_serializing_if = "Option::is_none")]
     task_id: Option<String>,
+
+    /// Only return content updated at or after this ISO 8601 timestamp (e.g. "2024-05-01T00:00:00Z" or "2024-05-01")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_since: Option<String>,
+
+    /// How to order results: "relevance" (most similar first, the default) or "recency" (most recently updated first). Use "recency" to find the latest updates to a work item.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort: Option<SearchSort>,
+}
+
+impl SearchFilter {
+    /// Fills in the scope and content fields that aren't set from `defaults`.
+    fn fill_defaults(&mut self, defaults: SearchFilter) {
+        self.search_type = self.search_type.take().or(defaults.search_type);
+        self.content_type = self.content_type.take().or(defaults.content_type);
+        self.account_id = self.account_id.take().or(defaults.account_id);
+        self.product_id = self.product_id.take().or(defaults.product_id);
+        self.board_id = self.board_id.take().or(defaults.board_id);
+        self.task_id = self.task_id.take().or(defaults.task_id);
+    }
+
+    /// Whether nothing is filtered on. The sort order alone doesn't count.
+    fn is_empty(&self) -> bool {
+        self.search_type.is_none()
+            && self.content_type.is_none()
+            && self.thread_id.is_none()
+            && self.account_id.is_none()
+            && self.product_id.is_none()
+            && self.board_id.is_none()
+            && self.task_id.is_none()
+            && self.updated_since.is_none()
+    }
+
+    fn searches_all_types(&self) -> bool {
+        self.search_type
+            .as_deref()
+            .is_none_or(|search_type| search_type == "all")
+    }
+}
+
+impl From<FileSearchFilterSettings> for SearchFilter {
+    fn from(settings: FileSearchFilterSettings) -> Self {
+        Self {
+            search_type: settings.search_type,
+            content_type: settings.content_type,
+            account_id: settings.account_id,
+            product_id: settings.product_id,
+            board_id: settings.board_id,
+            task_id: settings.task_id,
+            ..Default::default()
+        }
+    }
+}
+
+/// How search results are ordered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSort {
+    #[default]
+    Relevance,
+    Recency,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -76,339 +222,2282 @@ struct FileSearchRequest {
     limit: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     filter: Option<SearchFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rerank_model: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    explain: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude_types: Vec<SearchResultType>,
+    /// The query embedded with the model the backend embeds content with, so
+    /// that the backend doesn't have to embed it again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    query_embedding: Option<Vec<f32>>,
+    /// Lists the items matching the filter, newest first, without embedding
+    /// a query.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    metadata_only: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct FileSearchResult {
-    pub id: String,
-    pub content: String,
-    #[serde(rename = "type")]
-    pub result_type: String,
-    pub similarity: f32,
-    pub metadata: serde_json::Value,
+const EMBEDDINGS_API_PATH: &str = "/embeddings";
+
+#[derive(Serialize)]
+struct QueryEmbeddingRequest<'a> {
+    model: &'a str,
+    input: [&'a str; 1],
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct FileSearchResponse {
-    pub results: Vec<FileSearchResult>,
-    pub total: usize,
-    pub query: String,
+#[derive(Deserialize)]
+struct QueryEmbeddingResponse {
+    data: Vec<QueryEmbedding>,
 }
 
-pub struct FileSearchTool {
-    http_client: Arc<HttpClientWithUrl>,
+#[derive(Deserialize)]
+struct QueryEmbedding {
+    embedding: Vec<f32>,
 }
 
-impl FileSearchTool {
-    pub fn new(http_client: Arc<HttpClientWithUrl>) -> Self {
-        Self { http_client }
-    }
+/// What the search backend supports, as reported by its capabilities
+/// endpoint. A list the backend doesn't report, such as when it predates the
+/// endpoint, is assumed to include everything.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+struct SearchCapabilities {
+    #[serde(default)]
+    search_types: Option<Vec<String>>,
+    #[serde(default)]
+    content_types: Option<Vec<String>>,
+    /// Optional request features, like "rerank", "explain", "metadata_only",
+    /// "query_embedding", "sort" and "updated_since".
+    #[serde(default)]
+    modes: Option<Vec<String>>,
+}
 
-    async fn perform_search(
-        http_client: Arc<HttpClientWithUrl>,
-        input: FileSearchToolInput,
-        llm_api_token: LlmApiToken,
-        client: Arc<Client>,
-        context_filters: Option<SearchFilter>,
-    ) -> Result<FileSearchResponse> {
-        // Acquire the token
-        let token = llm_api_token
-            .acquire(&client)
-            .await
-            .context("Failed to acquire LLM API token")?;
+impl SearchCapabilities {
+    fn supports_search_type(&self, search_type: &str) -> bool {
+        search_type == "all" || supports(&self.search_types, search_type)
+    }
 
-        // Merge context filters with input filters
-        let filter = if let Some(context_filter) = context_filters {
-            let mut merged_filter = input.filter.unwrap_or_else(|| SearchFilter {
-                search_type: None,
-                content_type: None,
-                thread_id: None,
-                account_id: None,
-                product_id: None,
-                board_id: None,
-                task_id: None,
-            });
+    fn supports_content_type(&self, content_type: &str) -> bool {
+        content_type == "auto" || supports(&self.content_types, content_type)
+    }
 
-            // Only apply context filters if not already specified
-            if merged_filter.account_id.is_none() {
-                merged_filter.account_id = context_filter.account_id;
-            }
-            if merged_filter.product_id.is_none() {
-                merged_filter.product_id = context_filter.product_id;
-            }
-            if merged_filter.board_id.is_none() {
-                merged_filter.board_id = context_filter.board_id;
-            }
-            if merged_filter.task_id.is_none() {
-                merged_filter.task_id = context_filter.task_id;
-            }
+    fn supports_mode(&self, mode: &str) -> bool {
+        supports(&self.modes, mode)
+    }
 
-            Some(merged_filter)
-        } else {
-            input.filter
+    /// Rejects what the model asked for that the backend can't do, listing
+    /// what it can do instead.
+    fn check_input(&self, input: &FileSearchToolInput) -> Result<()> {
+        if input.is_metadata_only() {
+            anyhow::ensure!(
+                self.supports_mode("metadata_only"),
+                "The search backend can't list items without a query. Provide a 'query' to search for."
+            );
+        }
+        let Some(filter) = &input.filter else {
+            return Ok(());
         };
+        if let Some(search_type) = &filter.search_type
+            && !self.supports_search_type(search_type)
+        {
+            anyhow::bail!(
+                "The search backend doesn't support the '{search_type}' type. Supported types: {}",
+                supported_values("all", &self.search_types)
+            );
+        }
+        if let Some(content_type) = &filter.content_type
+            && !self.supports_content_type(content_type)
+        {
+            anyhow::bail!(
+                "The search backend doesn't support the '{content_type}' content type. Supported content types: {}",
+                supported_values("auto", &self.content_types)
+            );
+        }
+        Ok(())
+    }
 
-        // Build the request body
-        let request_body = FileSearchRequest {
-            query: input.query,
-            limit: input.limit,
-            filter,
+    /// Leaves out the parts of the request the backend doesn't support, so
+    /// that an older backend still searches instead of rejecting the request.
+    /// Sorting and `updated_since` are applied to the results afterwards
+    /// either way.
+    fn degrade(&self, request: &mut FileSearchRequest) {
+        if !self.supports_mode("rerank") {
+            request.rerank_model = None;
+        }
+        if !self.supports_mode("explain") {
+            request.explain = false;
+        }
+        if !self.supports_mode("query_embedding") {
+            request.query_embedding = None;
+        }
+        let Some(filter) = request.filter.as_mut() else {
+            return;
         };
+        if !self.supports_mode("sort") {
+            filter.sort = None;
+        }
+        if !self.supports_mode("updated_since") {
+            filter.updated_since = None;
+        }
+        // The model's own choices were already checked, so these came from
+        // the synced task or the project's defaults.
+        if let Some(search_type) = filter
+            .search_type
+            .take_if(|search_type| !self.supports_search_type(search_type))
+        {
+            log::warn!("Search backend doesn't support the '{search_type}' type, ignoring it");
+        }
+        if let Some(content_type) = filter
+            .content_type
+            .take_if(|content_type| !self.supports_content_type(content_type))
+        {
+            log::warn!(
+                "Search backend doesn't support the '{content_type}' content type, ignoring it"
+            );
+        }
+    }
+}
 
-        // Build the URL for the search endpoint
-        let url = http_client
-            .build_zed_llm_url("/api/v1/search", &[])
-            .context("Failed to build search URL")?;
+fn supports(supported: &Option<Vec<String>>, value: &str) -> bool {
+    supported
+        .as_ref()
+        .is_none_or(|supported| supported.iter().any(|supported| supported == value))
+}
 
-        // Create the HTTP request
-        let request = http_client::Request::builder()
-            .method(Method::POST)
-            .uri(url.as_ref())
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", token))
-            .body(serde_json::to_string(&request_body)?.into())?;
+/// The reported values along with the one that's always supported.
+fn supported_list(always_supported: &str, mut supported: Vec<String>) -> Vec<String> {
+    if !supported.iter().any(|value| value == always_supported) {
+        supported.insert(0, always_supported.to_string());
+    }
+    supported
+}
 
-        // Send the request
-        let mut response = http_client
-            .send(request)
-            .await
-            .context("Failed to send search request")?;
+fn supported_values(always_supported: &str, supported: &Option<Vec<String>>) -> String {
+    supported_list(always_supported, supported.clone().unwrap_or_default())
+        .iter()
+        .map(|value| format!("'{value}'"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-        // Check response status
-        if !response.status().is_success() {
-            let mut body = String::new();
-            response.body_mut().read_to_string(&mut body).await?;
-            return Err(anyhow!(
-                "Search request failed with status {}: {}",
-                response.status(),
-                body
-            ));
+/// The kind of content a search result came from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchResultType {
+    Conversation,
+    Task,
+    Compressed,
+    /// A type the backend added that this version doesn't know about yet.
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl SearchResultType {
+    /// The name used for this type by the search API.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Conversation => "conversation",
+            Self::Task => "task",
+            Self::Compressed => "compressed",
+            Self::Unknown(name) => name,
         }
+    }
 
-        // Read and parse the response
-        let mut body = String::new();
-        response.body_mut().read_to_string(&mut body).await?;
-        let search_response: FileSearchResponse =
-            serde_json::from_str(&body).context("Failed to parse search response")?;
+    pub fn label(&self) -> SharedString {
+        match self {
+            Self::Conversation => "Conversation".into(),
+            Self::Task => "Task".into(),
+            Self::Compressed => "Compressed".into(),
+            Self::Unknown(name) => name.clone().into(),
+        }
+    }
 
-        Ok(search_response)
+    fn color(&self) -> Color {
+        match self {
+            Self::Conversation => Color::Accent,
+            Self::Task => Color::Success,
+            Self::Compressed => Color::Info,
+            Self::Unknown(_) => Color::Muted,
+        }
     }
 }
 
-impl Tool for FileSearchTool {
-    fn name(&self) -> String {
-        "file_search".into()
+impl fmt::Display for SearchResultType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
     }
+}
 
-    fn needs_confirmation(&self, _: &serde_json::Value, _: &App) -> bool {
-        false
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileSearchResult {
+    pub id: String,
+    pub content: String,
+    #[serde(rename = "type")]
+    pub result_type: SearchResultType,
+    pub similarity: f32,
+    pub metadata: serde_json::Value,
+    /// Whether `content` was cut down to [`MAX_RESULT_CONTENT_LEN`] on receipt.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub content_truncated: bool,
+    /// Why the result matched, when explanations were requested and the
+    /// backend supports them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+}
+
+/// Metadata keys that are rendered as chips on each result.
+const KNOWN_METADATA_KEYS: [&str; 4] = ["author", "updated_at", "status", "path"];
+
+/// The metadata the backend records on task and big bet results.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+struct TaskMetadata {
+    status: Option<String>,
+    assignee: Option<String>,
+}
+
+impl TaskMetadata {
+    const KEYS: [&'static str; 2] = ["status", "assignee"];
+
+    fn status_color(status: &str) -> Color {
+        match status.to_lowercase().replace(['_', '-'], " ").as_str() {
+            "done" | "completed" | "closed" => Color::Success,
+            "blocked" => Color::Error,
+            "in progress" | "in review" => Color::Accent,
+            _ => Color::Muted,
+        }
     }
+}
 
-    fn may_perform_edits(&self) -> bool {
-        false
+impl FileSearchResult {
+    /// A result without content or metadata, for previews and tests.
+    fn example(id: impl Into<String>, result_type: SearchResultType, similarity: f32) -> Self {
+        Self {
+            id: id.into(),
+            content: String::new(),
+            result_type,
+            similarity,
+            metadata: serde_json::Value::Null,
+            content_truncated: false,
+            explanation: None,
+        }
     }
 
-    fn description(&self) -> String {
-        "Search project planning context including big bet descriptions, work item details, requirements, and specifications. \
-         Use this to understand what needs to be implemented and find acceptance criteria. \
-         Filter by type: 'conversations', 'tasks' (work items), 'compressed', or 'all'. \
-         Use content_type to get specific information: 'work_item' for work item details only, 'big_bet' for big bet overview only, or 'auto' (default) to automatically decide. \
-         Automatically uses your synced big bet and work item context. Results include content, type, and similarity score."
-            .into()
+    fn metadata_chips(&self) -> Vec<(&'static str, String)> {
+        KNOWN_METADATA_KEYS
+            .iter()
+            .filter_map(|key| {
+                let value = match self.metadata.get(key)? {
+                    serde_json::Value::String(value) => value.clone(),
+                    serde_json::Value::Number(value) => value.to_string(),
+                    serde_json::Value::Bool(value) => value.to_string(),
+                    _ => return None,
+                };
+                Some((*key, value))
+            })
+            .collect()
     }
 
-    fn icon(&self) -> IconName {
-        IconName::MagnifyingGlass
+    /// The typed metadata of task and big bet results. `None` for other types,
+    /// or when the metadata has none of the expected fields, in which case
+    /// the generic chips are shown instead.
+    fn task_metadata(&self) -> Option<TaskMetadata> {
+        let is_task = match &self.result_type {
+            SearchResultType::Task => true,
+            SearchResultType::Unknown(name) => name == "big_bet",
+            SearchResultType::Conversation | SearchResultType::Compressed => false,
+        };
+        if !is_task {
+            return None;
+        }
+        serde_json::from_value::<TaskMetadata>(self.metadata.clone())
+            .ok()
+            .filter(|metadata| metadata.status.is_some() || metadata.assignee.is_some())
     }
 
-    fn input_schema(&self, format: LanguageModelToolSchemaFormat) -> Result<serde_json::Value> {
-        json_schema_for::<FileSearchToolInput>(format)
+    /// The project file the result came from, if the backend recorded one.
+    fn path(&self) -> Option<&str> {
+        self.metadata
+            .get("path")?
+            .as_str()
+            .filter(|path| !path.is_empty())
     }
 
-    fn ui_text(&self, input: &serde_json::Value) -> String {
-        match serde_json::from_value::<FileSearchToolInput>(input.clone()) {
-            Ok(input) => {
-                if let Some(query) = &input.query {
-                    format!("Searching for \"{}\"", query)
-                } else if let Some(filter) = &input.filter {
-                    if let Some(thread_id) = &filter.thread_id {
-                        format!("Searching thread {}", thread_id)
-                    } else if let Some(search_type) = &filter.search_type {
-                        format!("Searching {} content", search_type)
-                    } else {
-                        "Searching content".to_string()
-                    }
-                } else {
-                    "Searching content".to_string()
-                }
-            }
-            Err(_) => "Search content".to_string(),
+    /// Whether the metadata has anything besides the keys shown as chips.
+    fn has_unknown_metadata(&self) -> bool {
+        let typed_keys: &[&str] = if self.task_metadata().is_some() {
+            &TaskMetadata::KEYS
+        } else {
+            &[]
+        };
+        match &self.metadata {
+            serde_json::Value::Null => false,
+            serde_json::Value::Object(fields) => fields.keys().any(|key| {
+                !KNOWN_METADATA_KEYS.contains(&key.as_str()) && !typed_keys.contains(&key.as_str())
+            }),
+            _ => true,
         }
     }
+}
 
-    fn run(
-        self: Arc<Self>,
-        input: serde_json::Value,
-        _request: Arc<LanguageModelRequest>,
-        _project: Entity<Project>,
-        _action_log: Entity<ActionLog>,
-        _model: Arc<dyn LanguageModel>,
-        _window: Option<AnyWindowHandle>,
-        cx: &mut App,
-    ) -> ToolResult {
-        let input = match serde_json::from_value::<FileSearchToolInput>(input) {
-            Ok(input) => input,
-            Err(err) => return Task::ready(Err(anyhow!(err))).into(),
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct FileSearchResponse {
+    pub results: Vec<FileSearchResult>,
+    pub total: usize,
+    pub query: String,
+    /// The content type the results were extracted as, filled in from the
+    /// request rather than sent by the backend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// The order that was requested, filled in from the request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SearchSort>,
+    /// The model results were reranked with, as reported by the backend,
+    /// which may not rerank even when asked to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerank_model: Option<String>,
+    /// The thread searched within, filled in from the request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+    /// Whether compressed results were left out, filled in from the request.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compressed_excluded: bool,
+    /// Whether the results were listed by their metadata rather than found by
+    /// similarity, filled in from the request.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub metadata_only: bool,
+}
+
+impl FileSearchResponse {
+    /// A response that returned every result found, for previews and tests.
+    fn example(query: impl Into<String>, results: Vec<FileSearchResult>) -> Self {
+        Self {
+            total: results.len(),
+            query: query.into(),
+            results,
+            ..Default::default()
+        }
+    }
+
+    /// How to see the results the backend found but didn't return, or `None`
+    /// when every result was returned.
+    fn omitted_results_hint(&self) -> Option<String> {
+        let shown = self.results.len();
+        if shown >= self.total {
+            return None;
+        }
+        let action = if shown >= MAX_SEARCH_LIMIT {
+            "narrow the search with a more specific query or filter"
+        } else {
+            "increase the limit (up to 100) or narrow the search"
         };
+        Some(format!(
+            "Showing {shown} of {} results. To see the rest, {action}.",
+            self.total
+        ))
+    }
 
-        // Validate input
-        if input.query.is_none()
-            && input
-                .filter
+    /// What the results were found for: the query, or the thread whose
+    /// context was searched when there was no query.
+    fn source(&self) -> Option<String> {
+        if !self.query.is_empty() {
+            Some(format!("\"{}\"", self.query))
+        } else {
+            self.thread_id
                 .as_ref()
-                .and_then(|f| f.thread_id.as_ref())
-                .is_none()
-        {
-            return Task::ready(Err(anyhow!(
-                "Either 'query' or 'filter.thread_id' must be provided"
-            )))
-            .into();
+                .map(|thread_id| format!("thread {thread_id}'s context"))
         }
+    }
 
-        // Get the LLM API token and client
-        let llm_api_token = LlmApiToken::default();
-        let client = Client::global(cx);
+    /// The full results, formatted for reviewing and editing in a buffer.
+    fn to_markdown(&self) -> String {
+        let mut markdown = match self.source() {
+            Some(source) => format!("# Search Results for {source}\n\n"),
+            None if self.metadata_only => "# Listed Items\n\n".to_string(),
+            None => "# Search Results\n\n".to_string(),
+        };
 
-        // Extract context filters from IdeContext if available
-        let context_filters = cx
-            .try_global::<IdeContext>()
-            .and_then(|ide_context| ide_context.get_sync_data())
-            .map(|sync_data| {
-                // Only include filters that have values
-                let mut filter = SearchFilter {
-                    search_type: None,
-                    content_type: Some("auto".to_string()), // Default to auto
-                    thread_id: None,
-                    account_id: None,
-                    product_id: None,
-                    board_id: None,
-                    task_id: None,
-                };
+        if let Some(content_type) = &self.content_type {
+            markdown.push_str(&format!("- Content type: {content_type}\n"));
+        }
+        if self.sort == Some(SearchSort::Recency) {
+            markdown.push_str("- Sorted by: most recently updated\n");
+        }
+        if let Some(rerank_model) = &self.rerank_model {
+            markdown.push_str(&format!("- Reranked with: {rerank_model}\n"));
+        }
+        if self.compressed_excluded {
+            markdown.push_str("- Compressed summaries excluded\n");
+        }
+        markdown.push_str(&format!(
+            "- Showing {} of {} results\n",
+            self.results.len(),
+            self.total
+        ));
 
-                // Always include account, product, and board if we have sync data
-                filter.account_id = Some(sync_data.account_id.to_string());
-                filter.product_id = Some(sync_data.product_id.to_string());
-                filter.board_id = Some(sync_data.board_id.to_string());
+        for (ix, result) in self.results.iter().enumerate() {
+            if self.metadata_only {
+                markdown.push_str(&format!(
+                    "\n## {}. {}\n\n",
+                    ix + 1,
+                    result.result_type.label()
+                ));
+            } else {
+                markdown.push_str(&format!(
+                    "\n## {}. {} (similarity: {:.2})\n\n",
+                    ix + 1,
+                    result.result_type.label(),
+                    result.similarity
+                ));
+            }
+            for (key, value) in result.metadata_chips() {
+                markdown.push_str(&format!("- {key}: {value}\n"));
+            }
+            if let Some(assignee) = result
+                .task_metadata()
+                .and_then(|metadata| metadata.assignee)
+            {
+                markdown.push_str(&format!("- assignee: {assignee}\n"));
+            }
+            if let Some(explanation) = &result.explanation {
+                markdown.push_str(&format!("- Why it matched: {explanation}\n"));
+            }
+            markdown.push_str(&format!("\n{}\n", result.content.trim_end()));
+            if result.content_truncated {
+                markdown.push_str("\n_Content truncated._\n");
+            }
+        }
+        markdown
+    }
+}
 
-                // Only include task_id if it's actually present
-                filter.task_id = sync_data.task_id.map(|id| id.to_string());
+const REQUIREMENTS_CONTENT_TYPE: &str = "requirements";
 
-                filter
-            });
+/// The most results a single search can return.
+const MAX_SEARCH_LIMIT: usize = 100;
 
-        let http_client = self.http_client.clone();
-        let http_client2 = http_client.clone();
-        let input2 = input.clone();
-        let llm_api_token2 = llm_api_token.clone();
-        let client2 = client.clone();
-        let context_filters2 = context_filters.clone();
+/// Results are stored, rendered and sent to the model, so a runaway backend
+/// payload is cut down before it gets that far.
+const MAX_RESULT_CONTENT_LEN: usize = 10 * 1024;
 
-        let search_task = cx.background_spawn(async move {
-            Self::perform_search(http_client, input, llm_api_token, client, context_filters).await
-        });
+/// How a search request is retried before giving up on a rate-limited or
+/// failing search endpoint.
+const SEARCH_RETRY_POLICY: LlmRetryPolicy = LlmRetryPolicy {
+    max_attempts: 3,
+    base_delay: Duration::from_millis(500),
+    max_delay: Duration::from_secs(10),
+};
+/// How long a single attempt may take, so that a hung connection is retried
+/// instead of leaving the turn waiting.
+const SEARCH_ATTEMPT_TIMEOUT: Duration = Duration::from_secs(30);
+/// The capabilities are only a hint, so a slow endpoint isn't waited on for
+/// long before searching as if everything were supported.
+const CAPABILITIES_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long after failing to fetch the capabilities they're fetched again, so
+/// that an unreachable endpoint doesn't hold up every search.
+const CAPABILITIES_REFETCH_DELAY: Duration = Duration::from_secs(5 * 60);
 
-        let card = cx.new(|cx| FileSearchToolCard::new(search_task, cx));
+/// Searches with larger bodies are refused before they're sent, since
+/// proxies in front of the backend reject them with an unhelpful 413.
+const MAX_SEARCH_REQUEST_LEN: usize = 64 * 1024;
 
-        let output = cx.background_spawn(async move {
-            let response = Self::perform_search(
-                http_client2,
-                input2,
-                llm_api_token2,
-                client2,
-                context_filters2,
-            )
-            .await?;
+/// Longer selections are cut down before they're added to the query, so they
+/// don't drown out what the model asked for.
+const MAX_ACTIVE_SELECTION_LEN: usize = 1024;
 
-            let mut message = format!("Found {} results", response.total);
+pub struct FileSearchTool {
+    http_client: Arc<HttpClientWithUrl>,
+    /// Shared by all invocations so that a turn with many searches doesn't
+    /// send them all at once.
+    search_limiter: Mutex<SearchLimiter>,
+    circuit_breaker: Arc<Mutex<SearchCircuitBreaker>>,
+    cache: Arc<Mutex<SearchCache>>,
+    query_embeddings: Arc<Mutex<QueryEmbeddingCache>>,
+    /// Shared by all searches, so that the LLM API token is only fetched
+    /// again once it expires.
+    llm_api_token: LlmApiToken,
+    /// Fetched once per session for the search endpoint they were fetched
+    /// from.
+    capabilities: Arc<Mutex<Option<CachedCapabilities>>>,
+}
 
-            if !response.query.is_empty() {
-                message.push_str(&format!(" for query \"{}\"", response.query));
-            }
+struct CachedCapabilities {
+    search_api_path: String,
+    capabilities: Shared<Task<SearchCapabilities>>,
+    /// When to ask again, if fetching them failed in a way that may succeed
+    /// later. Until then, searches go ahead as if everything were supported.
+    refetch_at: Option<Instant>,
+}
 
-            if !response.results.is_empty() {
-                message.push_str(":\n\n");
-                for (i, result) in response.results.iter().enumerate() {
-                    message.push_str(&format!(
-                        "{}. [{}] (similarity: {:.2})\n{}\n\n",
-                        i + 1,
-                        result.result_type,
-                        result.similarity,
-                        if result.content.len() > 200 {
-                            format!("{}...", &result.content[..200])
-                        } else {
-                            result.content.clone()
-                        }
-                    ));
-                }
-            }
+/// How long a search's response is reused for the same search.
+const SEARCH_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+/// How many responses are kept, the oldest being dropped first.
+const SEARCH_CACHE_CAPACITY: usize = 32;
 
-            Ok(ToolResultOutput {
-                content: ToolResultContent::Text(message),
-                output: Some(serde_json::to_value(response)?),
-            })
-        });
+/// Recent search responses, keyed by everything that went into the request.
+/// It's cleared whenever what the search can find may have changed, like a
+/// context server starting or a different task being synced.
+#[derive(Default)]
+struct SearchCache {
+    entries: VecDeque<(String, Instant, FileSearchResponse)>,
+}
 
-        ToolResult {
-            output,
-            card: Some(card.into()),
+impl SearchCache {
+    fn get(&mut self, key: &str, now: Instant) -> Option<FileSearchResponse> {
+        self.entries
+            .retain(|(_, cached_at, _)| now.duration_since(*cached_at) < SEARCH_CACHE_TTL);
+        self.entries
+            .iter()
+            .find(|(cached_key, _, _)| cached_key == key)
+            .map(|(_, _, response)| response.clone())
+    }
+
+    fn insert(&mut self, key: String, response: FileSearchResponse, now: Instant) {
+        self.entries.retain(|(cached_key, _, _)| *cached_key != key);
+        if self.entries.len() >= SEARCH_CACHE_CAPACITY {
+            self.entries.pop_front();
         }
+        self.entries.push_back((key, now, response));
     }
 
-    fn deserialize_card(
-        self: Arc<Self>,
-        output: serde_json::Value,
-        _project: Entity<Project>,
-        _window: &mut Window,
-        cx: &mut App,
-    ) -> Option<assistant_tool::AnyToolCard> {
-        let output = serde_json::from_value::<FileSearchResponse>(output).ok()?;
-        let card = cx.new(|_| FileSearchToolCard::from_output(output));
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+struct GlobalSearchCache(Arc<Mutex<SearchCache>>);
+
+impl Global for GlobalSearchCache {}
+
+/// Clears the tool's search cache whenever a context server starts or stops,
+/// the synced task changes, or the user asks to.
+pub(crate) fn init_search_cache(tool: &FileSearchTool, cx: &mut App) {
+    cx.set_global(GlobalSearchCache(tool.cache.clone()));
+    cx.observe_global::<IdeContext>(clear_search_cache).detach();
+    cx.observe_new(|_: &mut ContextServerStore, _, cx| {
+        cx.subscribe_self(|_, event: &context_server_store::Event, cx| {
+            let context_server_store::Event::ServerStatusChanged { status, .. } = event;
+            if matches!(
+                status,
+                ContextServerStatus::Running | ContextServerStatus::Stopped
+            ) {
+                clear_search_cache(cx);
+            }
+        })
+        .detach();
+    })
+    .detach();
+    cx.on_action(|_: &ClearSearchCache, cx| clear_search_cache(cx));
+}
+
+/// The embeddings of recent queries, so that searching for the same query
+/// with different filters only embeds it once.
+#[derive(Default)]
+struct QueryEmbeddingCache {
+    /// The model, query, when it was embedded and its embedding.
+    entries: VecDeque<(String, String, Instant, Arc<[f32]>)>,
+}
+
+impl QueryEmbeddingCache {
+    fn get(&mut self, model: &str, query: &str, now: Instant) -> Option<Arc<[f32]>> {
+        self.entries
+            .retain(|(_, _, embedded_at, _)| now.duration_since(*embedded_at) < SEARCH_CACHE_TTL);
+        self.entries
+            .iter()
+            .find(|(cached_model, cached_query, _, _)| {
+                cached_model == model && cached_query == query
+            })
+            .map(|(_, _, _, embedding)| embedding.clone())
+    }
+
+    fn insert(&mut self, model: String, query: String, embedding: Arc<[f32]>, now: Instant) {
+        self.entries.retain(|(cached_model, cached_query, _, _)| {
+            *cached_model != model || *cached_query != query
+        });
+        if self.entries.len() >= SEARCH_CACHE_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((model, query, now, embedding));
+    }
+}
+
+fn clear_search_cache(cx: &mut App) {
+    if let Some(cache) = cx.try_global::<GlobalSearchCache>() {
+        cache.0.lock().clear();
+    }
+}
+
+/// How many searches in a row have to fail before the circuit opens.
+const CIRCUIT_BREAKER_FAILURE_THRESHOLD: usize = 5;
+/// How long searches fail fast once the circuit is open.
+const CIRCUIT_BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Stops sending searches for a while once the endpoint keeps failing, so an
+/// outage doesn't make every search wait out its retries. After the cooldown
+/// a single search is let through as a probe while the others keep failing
+/// fast, and a failure reopens the circuit right away.
+#[derive(Default)]
+struct SearchCircuitBreaker {
+    consecutive_failures: usize,
+    open_until: Option<Instant>,
+    /// When the probe was let through, until its outcome is recorded. A probe
+    /// that never reports back, like a cancelled one, only holds other
+    /// searches back for another cooldown.
+    probe_sent_at: Option<Instant>,
+}
+
+impl SearchCircuitBreaker {
+    /// Whether a search may be sent, or otherwise how long searches still
+    /// fail fast for.
+    fn admit(&mut self, now: Instant) -> Result<(), Duration> {
+        let Some(open_until) = self.open_until else {
+            return Ok(());
+        };
+        if open_until > now {
+            return Err(open_until - now);
+        }
+        if let Some(probe_sent_at) = self.probe_sent_at {
+            let probe_expires_at = probe_sent_at + CIRCUIT_BREAKER_COOLDOWN;
+            if probe_expires_at > now {
+                return Err(probe_expires_at - now);
+            }
+        }
+        self.probe_sent_at = Some(now);
+        Ok(())
+    }
+
+    fn record<T>(&mut self, result: &Result<T, LlmApiError>, now: Instant) {
+        match result {
+            Err(LlmApiError::Server { .. } | LlmApiError::Network(_)) => {
+                self.consecutive_failures += 1;
+                if self.consecutive_failures >= CIRCUIT_BREAKER_FAILURE_THRESHOLD {
+                    if self.open_until.is_none_or(|open_until| open_until <= now) {
+                        log::warn!(
+                            "Search failed {} times in a row, pausing searches for {:?}",
+                            self.consecutive_failures,
+                            CIRCUIT_BREAKER_COOLDOWN
+                        );
+                    }
+                    self.open_until = Some(now + CIRCUIT_BREAKER_COOLDOWN);
+                }
+                self.probe_sent_at = None;
+            }
+            // Other errors mean the backend is up but rejected the request.
+            _ => {
+                self.consecutive_failures = 0;
+                self.open_until = None;
+                self.probe_sent_at = None;
+            }
+        }
+    }
+}
+
+struct SearchLimiter {
+    limit: usize,
+    semaphore: Arc<Semaphore>,
+}
+
+impl SearchLimiter {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            semaphore: Arc::new(Semaphore::new(limit)),
+        }
+    }
+}
+
+impl FileSearchTool {
+    pub fn new(http_client: Arc<HttpClientWithUrl>) -> Self {
+        Self {
+            http_client,
+            search_limiter: Mutex::new(SearchLimiter::new(
+                FileSearchSettings::default().max_concurrent_requests,
+            )),
+            circuit_breaker: Arc::default(),
+            cache: Arc::default(),
+            query_embeddings: Arc::default(),
+            llm_api_token: LlmApiToken::default(),
+            capabilities: Arc::default(),
+        }
+    }
+
+    /// Fetches what the search backend supports, once per session. Resolves
+    /// to everything being supported when the backend doesn't report it, so
+    /// that searching works the same as before against older backends.
+    fn capabilities(
+        &self,
+        http: &LlmHttp,
+        search_api_path: &str,
+        cx: &mut App,
+    ) -> Shared<Task<SearchCapabilities>> {
+        let mut cached = self.capabilities.lock();
+        if let Some(cached) = cached.as_ref()
+            && cached.search_api_path == search_api_path
+            && cached
+                .refetch_at
+                .is_none_or(|refetch_at| refetch_at > Instant::now())
+        {
+            return cached.capabilities.clone();
+        }
+
+        let http = http.clone();
+        let path = format!("{search_api_path}/capabilities");
+        let cached_capabilities = self.capabilities.clone();
+        let search_api_path = search_api_path.to_string();
+        let search_api_path2 = search_api_path.clone();
+        let capabilities = cx
+            .background_spawn(async move {
+                let options = LlmPostOptions {
+                    timeout: Some(CAPABILITIES_TIMEOUT),
+                    ..Default::default()
+                };
+                match http.post_json(&path, &serde_json::json!({}), options).await {
+                    Ok(capabilities) => capabilities,
+                    Err(error) => {
+                        log::info!("Failed to fetch search capabilities from {path}: {error:#}");
+                        // Only a backend that answered is taken at its word.
+                        if (error.is_retryable()
+                            || matches!(error, LlmApiError::Unauthorized { .. }))
+                            && let Some(cached) = cached_capabilities.lock().as_mut()
+                            && cached.search_api_path == search_api_path2
+                        {
+                            cached.refetch_at = Some(Instant::now() + CAPABILITIES_REFETCH_DELAY);
+                        }
+                        SearchCapabilities::default()
+                    }
+                }
+            })
+            .shared();
+        *cached = Some(CachedCapabilities {
+            search_api_path,
+            capabilities: capabilities.clone(),
+            refetch_at: None,
+        });
+        drop(cached);
+
+        let fetched = capabilities.clone();
+        cx.spawn(async move |cx| {
+            let capabilities = fetched.await;
+            cx.update(|cx| {
+                RecentSearches::set_supported_filter_values(
+                    capabilities
+                        .search_types
+                        .map(|types| supported_list("all", types)),
+                    capabilities
+                        .content_types
+                        .map(|types| supported_list("auto", types)),
+                    cx,
+                )
+            })
+            .ok();
+        })
+        .detach();
+        capabilities
+    }
+
+    /// Embeds the query for a backend that accepts query embeddings, reusing
+    /// the embedding from an earlier search for the same query. Returns
+    /// `None` when the backend should embed the query itself, including when
+    /// embedding it here fails.
+    async fn embed_query(
+        http: &LlmHttp,
+        model: String,
+        query: String,
+        query_embeddings: &Mutex<QueryEmbeddingCache>,
+    ) -> Option<Arc<[f32]>> {
+        let cached = query_embeddings.lock().get(&model, &query, Instant::now());
+        if cached.is_some() {
+            return cached;
+        }
+
+        let request = QueryEmbeddingRequest {
+            model: &model,
+            input: [&query],
+        };
+        let options = LlmPostOptions {
+            timeout: Some(SEARCH_ATTEMPT_TIMEOUT),
+            ..Default::default()
+        };
+        let embedding = match http
+            .post_json::<_, QueryEmbeddingResponse>(EMBEDDINGS_API_PATH, &request, options)
+            .await
+        {
+            Ok(response) => Arc::<[f32]>::from(response.data.into_iter().next()?.embedding),
+            Err(error) => {
+                log::warn!("Failed to embed search query, leaving it to the backend: {error:#}");
+                return None;
+            }
+        };
+        query_embeddings
+            .lock()
+            .insert(model, query, embedding.clone(), Instant::now());
+        Some(embedding)
+    }
+
+    /// Returns the semaphore for the configured limit. Changing the limit
+    /// replaces the semaphore, so searches already in flight don't count
+    /// against the new one.
+    fn search_limiter(&self, cx: &App) -> Arc<Semaphore> {
+        let limit = AgentSettings::get_global(cx)
+            .file_search
+            .max_concurrent_requests
+            .max(1);
+        let mut search_limiter = self.search_limiter.lock();
+        if search_limiter.limit != limit {
+            *search_limiter = SearchLimiter::new(limit);
+        }
+        search_limiter.semaphore.clone()
+    }
+
+    async fn perform_search(
+        http: LlmHttp,
+        input: FileSearchToolInput,
+        context_filters: Option<SearchFilter>,
+        search_api_path: String,
+        explain_results: bool,
+        query_embedding_model: Option<String>,
+        query_embeddings: Arc<Mutex<QueryEmbeddingCache>>,
+        capabilities: Shared<Task<SearchCapabilities>>,
+        search_limiter: Arc<Semaphore>,
+        circuit_breaker: Arc<Mutex<SearchCircuitBreaker>>,
+        cache: Arc<Mutex<SearchCache>>,
+    ) -> Result<FileSearchResponse, LlmApiError> {
+        let cache_key =
+            serde_json::to_string(&(&search_api_path, &input, &context_filters, explain_results))
+                .context("Failed to serialize search request")?;
+        if let Some(response) = cache.lock().get(&cache_key, Instant::now()) {
+            return Ok(response);
+        }
+
+        if let Err(cooldown) = circuit_breaker.lock().admit(Instant::now()) {
+            return Err(anyhow!(
+                "Search is temporarily unavailable after repeated failures. Try again in {}s.",
+                cooldown.as_secs().max(1)
+            )
+            .into());
+        }
+
+        let result = Self::send_search(
+            http,
+            input,
+            context_filters,
+            search_api_path,
+            explain_results,
+            query_embedding_model,
+            query_embeddings,
+            capabilities,
+            search_limiter,
+        )
+        .await;
+        circuit_breaker.lock().record(&result, Instant::now());
+        if let Ok(response) = &result {
+            cache
+                .lock()
+                .insert(cache_key, response.clone(), Instant::now());
+        }
+        result
+    }
+
+    async fn send_search(
+        http: LlmHttp,
+        input: FileSearchToolInput,
+        context_filters: Option<SearchFilter>,
+        search_api_path: String,
+        explain_results: bool,
+        query_embedding_model: Option<String>,
+        query_embeddings: Arc<Mutex<QueryEmbeddingCache>>,
+        capabilities: Shared<Task<SearchCapabilities>>,
+        search_limiter: Arc<Semaphore>,
+    ) -> Result<FileSearchResponse, LlmApiError> {
+        let capabilities = capabilities.await;
+        capabilities.check_input(&input)?;
+
+        let query_embedding = match (query_embedding_model, &input.query) {
+            (Some(model), Some(query)) if capabilities.supports_mode("query_embedding") => {
+                Self::embed_query(&http, model, query.clone(), &query_embeddings).await
+            }
+            _ => None,
+        };
+
+        let updated_since = input
+            .filter
+            .as_ref()
+            .and_then(|filter| filter.updated_since.as_deref())
+            .map(|since| {
+                parse_timestamp(since)
+                    .with_context(|| format!("Invalid 'updated_since' timestamp: {since}"))
+            })
+            .transpose()?;
+
+        let metadata_only = input.is_metadata_only();
+
+        // Merge context filters with input filters
+        let mut filter = if let Some(context_filter) = context_filters {
+            // Only apply context filters if not already specified
+            let mut merged_filter = input.filter.unwrap_or_default();
+            merged_filter.fill_defaults(context_filter);
+            Some(merged_filter)
+        } else {
+            input.filter
+        };
+
+        // Searches narrowed to a single type already include or exclude
+        // compressed results.
+        let compressed_excluded = input.include_compressed == Some(false)
+            && filter.as_ref().is_none_or(SearchFilter::searches_all_types);
+
+        // Listed items have no similarity to rank or explain them by.
+        if metadata_only && let Some(filter) = filter.as_mut() {
+            filter.sort.get_or_insert(SearchSort::Recency);
+        }
+
+        // Build the request body
+        let mut request = FileSearchRequest {
+            query: input.query,
+            limit: input.limit,
+            filter,
+            rerank_model: input.rerank_model.filter(|_| !metadata_only),
+            explain: explain_results && !metadata_only,
+            exclude_types: if compressed_excluded {
+                vec![SearchResultType::Compressed]
+            } else {
+                Vec::new()
+            },
+            query_embedding: query_embedding.map(|embedding| embedding.to_vec()),
+            metadata_only,
+        };
+        let sort = request.filter.as_ref().and_then(|filter| filter.sort);
+        capabilities.degrade(&mut request);
+
+        check_search_request_size(&request)?;
+
+        // Held across retries, so a rate-limited search keeps its slot
+        // instead of letting queued searches pile onto the backend.
+        let _permit = search_limiter.acquire_arc().await;
+
+        let mut search_response: FileSearchResponse = loop {
+            let options = LlmPostOptions {
+                retry: SEARCH_RETRY_POLICY,
+                timeout: Some(SEARCH_ATTEMPT_TIMEOUT),
+                ..Default::default()
+            };
+            let error = match http.post_json(&search_api_path, &request, options).await {
+                Ok(response) => break response,
+                Err(error) => error,
+            };
+
+            // Older backends reject content types they don't know about.
+            let requirements_filter = request
+                .filter
+                .as_mut()
+                .filter(|filter| filter.content_type.as_deref() == Some(REQUIREMENTS_CONTENT_TYPE));
+            match (error, requirements_filter) {
+                (LlmApiError::Rejected { message, .. }, _) if request.query_embedding.is_some() => {
+                    log::warn!(
+                        "Search backend rejected the query embedding, retrying without it: {message}"
+                    );
+                    request.query_embedding = None;
+                }
+                (LlmApiError::Rejected { message, .. }, Some(filter)) => {
+                    log::warn!(
+                        "Search backend rejected the requirements content type, retrying with auto: {message}"
+                    );
+                    filter.content_type = Some("auto".to_string());
+                }
+                (LlmApiError::PayloadTooLarge { status, message }, _) => {
+                    log::warn!("Search backend rejected the request as too large: {message}");
+                    return Err(search_too_large_error(&format!(
+                        "the search backend rejected it with status {status}"
+                    )));
+                }
+                (error, _) => return Err(error),
+            }
+        };
+
+        if let Some(filter) = request.filter {
+            search_response.content_type = filter.content_type;
+            search_response.thread_id = filter.thread_id;
+        }
+        search_response.sort = sort;
+        search_response.metadata_only = metadata_only;
+        truncate_result_contents(&mut search_response.results);
+
+        // The backend should already apply this filter, but not every
+        // deployment supports it yet.
+        if let Some(updated_since) = updated_since {
+            retain_updated_since(&mut search_response, updated_since);
+        }
+
+        if compressed_excluded {
+            exclude_compressed_results(&mut search_response);
+        }
+
+        // Backends that don't support sorting return results by similarity.
+        if search_response.sort == Some(SearchSort::Recency) {
+            sort_by_recency(&mut search_response.results);
+        }
+
+        Ok(search_response)
+    }
+}
+
+impl Tool for FileSearchTool {
+    fn name(&self) -> String {
+        "file_search".into()
+    }
+
+    fn needs_confirmation(&self, _: &serde_json::Value, _: &App) -> bool {
+        false
+    }
+
+    fn may_perform_edits(&self) -> bool {
+        false
+    }
+
+    fn description(&self) -> String {
+        "Search project planning context including big bet descriptions, work item details, requirements, and specifications. \
+         Use this to understand what needs to be implemented and find acceptance criteria. \
+         Filter by type: 'conversations', 'tasks' (work items), 'compressed', or 'all'. \
+         Use content_type to get specific information: 'work_item' for work item details only, 'big_bet' for big bet overview only, 'requirements' for requirements and acceptance criteria only, or 'auto' (default) to automatically decide. \
+         Use filter.updated_since with an ISO 8601 timestamp to only get context that changed recently, \
+         and filter.sort = 'recency' to get the latest updates first instead of the most similar content. \
+         Set scope_to_active_editor to bias results toward the file and selection the user is currently editing. \
+         Omit query and pass only a filter to list the matching items, most recently updated first, without a similarity search. \
+         Automatically uses your synced big bet and work item context. Results include content, type, and similarity score."
+            .into()
+    }
+
+    fn icon(&self) -> IconName {
+        IconName::MagnifyingGlass
+    }
+
+    fn input_schema(&self, format: LanguageModelToolSchemaFormat) -> Result<serde_json::Value> {
+        json_schema_for::<FileSearchToolInput>(format)
+    }
+
+    fn ui_text(&self, input: &serde_json::Value) -> String {
+        match serde_json::from_value::<FileSearchToolInput>(input.clone()) {
+            Ok(input) => {
+                if let Some(query) = input.query() {
+                    format!("Searching for \"{}\"", query)
+                } else if let Some(filter) = &input.filter {
+                    if let Some(thread_id) = &filter.thread_id {
+                        format!("Searching thread {}", thread_id)
+                    } else if let Some(search_type) = &filter.search_type {
+                        format!("Listing {} content", search_type)
+                    } else {
+                        "Listing content".to_string()
+                    }
+                } else {
+                    "Searching content".to_string()
+                }
+            }
+            Err(_) => "Search content".to_string(),
+        }
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: serde_json::Value,
+        _request: Arc<LanguageModelRequest>,
+        project: Entity<Project>,
+        _action_log: Entity<ActionLog>,
+        _model: Arc<dyn LanguageModel>,
+        window: Option<AnyWindowHandle>,
+        cx: &mut App,
+    ) -> ToolResult {
+        if DisableCloudFeaturesSettings::get_global(cx).disable_cloud_features {
+            return Task::ready(Err(anyhow!(CLOUD_FEATURES_DISABLED_MESSAGE))).into();
+        }
+
+        let mut input = match serde_json::from_value::<FileSearchToolInput>(input) {
+            Ok(input) => input,
+            Err(err) => return Task::ready(Err(anyhow!(err))).into(),
+        };
+
+        if let Err(error) = input.validate() {
+            return Task::ready(Err(error)).into();
+        }
+        if let Some(input) = serde_json::to_value(&input).log_err() {
+            RecentSearches::record(input, cx);
+        }
+
+        if input.scope_to_active_editor {
+            let active_editor_context =
+                window.and_then(|window| ActiveEditorContext::read(window, cx));
+            if let Some((query, active_editor_context)) =
+                input.query.as_mut().zip(active_editor_context)
+            {
+                *query = active_editor_context.apply_to_query(query);
+            }
+        }
+
+        let exclude_compressed = AgentSettings::get_global(cx).file_search.exclude_compressed;
+        input.include_compressed.get_or_insert(!exclude_compressed);
+
+        let http = LlmHttp::new(
+            self.http_client.clone(),
+            Client::global(cx),
+            self.llm_api_token.clone(),
+            cx.background_executor().clone(),
+        );
+
+        // Extract context filters from IdeContext if available
+        let ide_filters = cx
+            .try_global::<IdeContext>()
+            .filter(|_| AgentSettings::get_global(cx).file_search.auto_scope)
+            .and_then(|ide_context| {
+                ide_context.snapshot(project.entity_id(), |sync_data| SearchFilter {
+                    // Always include account, product, and board if we have sync data
+                    account_id: Some(sync_data.account_id.to_string()),
+                    product_id: Some(sync_data.product_id.to_string()),
+                    board_id: Some(sync_data.board_id.to_string()),
+                    // Only include task_id if it's actually present
+                    task_id: sync_data.task_id.as_ref().map(|id| id.to_string()),
+                    ..Default::default()
+                })
+            })
+            .map(|(_sync_data, filter)| filter);
+
+        // The project's default filters apply below the synced task.
+        let project_filters = project_search_filter(&project, cx);
+        let context_filters = match (ide_filters, project_filters) {
+            (Some(mut ide_filters), Some(project_filters)) => {
+                ide_filters.fill_defaults(project_filters);
+                Some(ide_filters)
+            }
+            (ide_filters, project_filters) => ide_filters.or(project_filters),
+        };
+
+        let file_search_settings = &AgentSettings::get_global(cx).file_search;
+        let search_api_path = search_api_path(file_search_settings);
+        let explain_results = file_search_settings.explain_results;
+        let query_embedding_model = file_search_settings.query_embedding_model.clone();
+        let query_embeddings = self.query_embeddings.clone();
+        let capabilities = self.capabilities(&http, &search_api_path, cx);
+
+        let search_limiter = self.search_limiter(cx);
+        let circuit_breaker = self.circuit_breaker.clone();
+        let cache = self.cache.clone();
+
+        // The card and the model's output share one search, so that it's
+        // only sent, and its outcome only recorded, once.
+        let search = cx
+            .background_spawn(async move {
+                Self::perform_search(
+                    http,
+                    input,
+                    context_filters,
+                    search_api_path,
+                    explain_results,
+                    query_embedding_model,
+                    query_embeddings,
+                    capabilities,
+                    search_limiter,
+                    circuit_breaker,
+                    cache,
+                )
+                .await
+                .map_err(Arc::new)
+            })
+            .shared();
+        let card_search = search.clone();
+        let search_task = cx.background_spawn(async move { Ok(card_search.await?) });
+
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        let card = cx.new(|cx| FileSearchToolCard::new(search_task, cancel_tx, cx));
+
+        let output = cx.background_spawn(async move {
+            // Only an explicit cancel from the card stops the search, not the
+            // card going away.
+            let cancelled = async move {
+                if cancel_rx.await.is_err() {
+                    future::pending::<()>().await;
+                }
+            };
+            let response = match future::select(pin!(cancelled), pin!(search)).await {
+                Either::Left(((), _)) => anyhow::bail!("The user cancelled the search."),
+                Either::Right((response, _)) => response?,
+            };
+
+            let mut message = if response.metadata_only {
+                format!("Listed {} items matching the filter", response.total)
+            } else {
+                format!("Found {} results", response.total)
+            };
+
+            if !response.query.is_empty() {
+                message.push_str(&format!(" for query \"{}\"", response.query));
+            } else if let Some(thread_id) = &response.thread_id {
+                message.push_str(&format!(" in the context of thread {thread_id}"));
+            }
+            if response.sort == Some(SearchSort::Recency) {
+                message.push_str(", most recently updated first");
+            }
+            if let Some(rerank_model) = &response.rerank_model {
+                message.push_str(&format!(", reranked with {rerank_model}"));
+            }
+            if response.compressed_excluded {
+                message.push_str(
+                    ", excluding compressed summaries (search again with include_compressed set to true to include them)",
+                );
+            }
+            if let Some(hint) = response.omitted_results_hint() {
+                message.push_str(&format!(". {hint}"));
+            }
+
+            if !response.results.is_empty() {
+                message.push_str(":\n\n");
+                for (i, result) in response.results.iter().enumerate() {
+                    let score = if response.metadata_only {
+                        result
+                            .metadata
+                            .get("updated_at")
+                            .and_then(|updated_at| updated_at.as_str())
+                            .map(|updated_at| format!(" (updated: {updated_at})"))
+                            .unwrap_or_default()
+                    } else {
+                        format!(" (similarity: {:.2})", result.similarity)
+                    };
+                    message.push_str(&format!(
+                        "{}. [{}]{}\n{}\n\n",
+                        i + 1,
+                        result.result_type,
+                        score,
+                        util::truncate_and_trailoff(&result.content, 200)
+                    ));
+                }
+            }
+
+            Ok(ToolResultOutput {
+                content: ToolResultContent::Text(message),
+                output: Some(serde_json::to_value(response)?),
+            })
+        });
+
+        ToolResult {
+            output,
+            card: Some(card.into()),
+        }
+    }
+
+    fn deserialize_card(
+        self: Arc<Self>,
+        output: serde_json::Value,
+        _project: Entity<Project>,
+        _window: &mut Window,
+        cx: &mut App,
+    ) -> Option<assistant_tool::AnyToolCard> {
+        let output = serde_json::from_value::<FileSearchResponse>(output).ok()?;
+        let card = cx.new(|_| FileSearchToolCard::from_output(output));
         Some(card.into())
     }
 }
 
-#[derive(RegisterComponent)]
-struct FileSearchToolCard {
-    response: Option<Result<FileSearchResponse>>,
-    expanded: bool,
-    _task: Task<()>,
+/// The `file_search_filter` from the project's settings, including those in its
+/// first worktree's local settings.
+fn project_search_filter(project: &Entity<Project>, cx: &App) -> Option<SearchFilter> {
+    let location = project
+        .read(cx)
+        .visible_worktrees(cx)
+        .next()
+        .map(|worktree| SettingsLocation {
+            worktree_id: worktree.read(cx).id(),
+            path: Path::new(""),
+        });
+    let settings = &ProjectSettings::get(location, cx).file_search_filter;
+    (!settings.is_empty()).then(|| settings.clone().into())
+}
+
+/// The search endpoint path, from `OPPLA_SEARCH_API_PATH` if set and otherwise
+/// from the settings.
+fn search_api_path(settings: &FileSearchSettings) -> String {
+    let path = std::env::var("OPPLA_SEARCH_API_PATH")
+        .ok()
+        .filter(|path| !path.trim().is_empty())
+        .unwrap_or_else(|| settings.api_path.clone());
+    normalize_api_path(&path)
+}
+
+fn normalize_api_path(path: &str) -> String {
+    let path = path.trim().trim_end_matches('/');
+    if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{path}")
+    }
+}
+
+/// Parses an RFC 3339 timestamp, or a plain date interpreted as midnight UTC.
+fn parse_timestamp(timestamp: &str) -> Result<DateTime<Utc>> {
+    let timestamp = timestamp.trim();
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(timestamp) {
+        return Ok(date_time.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(timestamp, "%Y-%m-%d")?;
+    Ok(date.and_time(NaiveTime::MIN).and_utc())
+}
+
+/// Drops results whose `updated_at` metadata is older than `since`. Results
+/// without a readable `updated_at` are kept, since their age is unknown.
+fn retain_updated_since(response: &mut FileSearchResponse, since: DateTime<Utc>) {
+    let original_len = response.results.len();
+    response
+        .results
+        .retain(|result| updated_at(result).map_or(true, |updated_at| updated_at >= since));
+    let removed = original_len - response.results.len();
+    response.total = response.total.saturating_sub(removed);
+}
+
+/// Drops compressed results, for backends that ignore `exclude_types`.
+fn exclude_compressed_results(response: &mut FileSearchResponse) {
+    let original_len = response.results.len();
+    response
+        .results
+        .retain(|result| result.result_type != SearchResultType::Compressed);
+    let removed = original_len - response.results.len();
+    response.total = response.total.saturating_sub(removed);
+    response.compressed_excluded = true;
+}
+
+fn truncate_result_contents(results: &mut [FileSearchResult]) {
+    for result in results {
+        if result.content.len() > MAX_RESULT_CONTENT_LEN {
+            let end = util::truncate_to_byte_limit(&result.content, MAX_RESULT_CONTENT_LEN).len();
+            log::warn!(
+                "Truncating search result {} content from {} bytes",
+                result.id,
+                result.content.len()
+            );
+            result.content.truncate(end);
+            result.content_truncated = true;
+        }
+    }
+}
+
+/// The file and selection in the workspace's active editor, which
+/// `scope_to_active_editor` adds to the query.
+#[derive(Debug, Default, PartialEq)]
+struct ActiveEditorContext {
+    path: Option<String>,
+    selection: Option<String>,
+}
+
+impl ActiveEditorContext {
+    fn read(window: AnyWindowHandle, cx: &App) -> Option<Self> {
+        let workspace = window.downcast::<Workspace>()?.entity(cx).ok()?;
+        let editor = workspace.read(cx).active_item_as::<Editor>(cx)?;
+        let editor = editor.read(cx);
+
+        let path = editor
+            .project_path(cx)
+            .map(|project_path| project_path.path.to_string_lossy().into_owned());
+        let selection = editor.selections.newest_anchor();
+        let selection = editor
+            .buffer()
+            .read(cx)
+            .snapshot(cx)
+            .text_for_range(selection.start..selection.end)
+            .collect::<String>();
+        let selection = Some(selection.trim())
+            .filter(|selection| !selection.is_empty())
+            .map(|selection| {
+                util::truncate_to_byte_limit(selection, MAX_ACTIVE_SELECTION_LEN).to_string()
+            });
+
+        let context = Self { path, selection };
+        (context != Self::default()).then_some(context)
+    }
+
+    fn apply_to_query(&self, query: &str) -> String {
+        let mut query = query.to_string();
+        if let Some(path) = &self.path {
+            query.push_str(&format!("\n\nCurrent file: {path}"));
+        }
+        if let Some(selection) = &self.selection {
+            query.push_str(&format!("\n\nSelected code:\n{selection}"));
+        }
+        query
+    }
+}
+
+/// Opens the project files at `paths` as tabs, focusing the last one. Paths
+/// that aren't in the project are skipped.
+fn open_result_paths(
+    paths: &[String],
+    workspace: Entity<Workspace>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    workspace.update(cx, |workspace, cx| {
+        let project = workspace.project().clone();
+        for (ix, path) in paths.iter().enumerate() {
+            let Some(project_path) = project.read(cx).find_project_path(path, cx) else {
+                log::warn!("Search result path {path} isn't in the project");
+                continue;
+            };
+            let focus_item = ix == paths.len() - 1;
+            workspace
+                .open_path(project_path, None, focus_item, window, cx)
+                .detach_and_log_err(cx);
+        }
+    });
+}
+
+fn open_response_in_buffer(
+    response: &FileSearchResponse,
+    workspace: Entity<Workspace>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Task<Result<()>> {
+    let markdown = response.to_markdown();
+    let title = match response.source() {
+        Some(source) => format!("Search Results: {source}"),
+        None => "Search Results".to_string(),
+    };
+    let markdown_language_task = workspace
+        .read(cx)
+        .app_state()
+        .languages
+        .language_for_name("Markdown");
+
+    window.spawn(cx, async move |cx| {
+        let markdown_language = markdown_language_task.await?;
+        workspace.update_in(cx, |workspace, window, cx| {
+            let project = workspace.project().clone();
+            if !project.read(cx).is_local() {
+                anyhow::bail!("search results can't be opened in a buffer in remote projects");
+            }
+            let buffer = project.update(cx, |project, cx| {
+                project.create_local_buffer(&markdown, Some(markdown_language), cx)
+            });
+            let buffer = cx.new(|cx| MultiBuffer::singleton(buffer, cx).with_title(title));
+            workspace.add_item_to_active_pane(
+                Box::new(
+                    cx.new(|cx| Editor::for_multibuffer(buffer, Some(project.clone()), window, cx)),
+                ),
+                None,
+                true,
+                window,
+                cx,
+            );
+            anyhow::Ok(())
+        })?
+    })
+}
+
+fn updated_at(result: &FileSearchResult) -> Option<DateTime<Utc>> {
+    result
+        .metadata
+        .get("updated_at")
+        .and_then(|updated_at| updated_at.as_str())
+        .and_then(|updated_at| parse_timestamp(updated_at).ok())
+}
+
+/// Orders results by their `updated_at` metadata, newest first. Results
+/// without a readable `updated_at` keep their order at the end.
+fn sort_by_recency(results: &mut [FileSearchResult]) {
+    results.sort_by_key(|result| std::cmp::Reverse(updated_at(result)));
+}
+
+/// Maps a similarity reported by the backend onto a relevance between 0 and 1,
+/// so that scores read the same whichever metric the backend uses.
+fn relevance(similarity: f32, metric: SimilarityMetric) -> f32 {
+    let relevance = match metric {
+        SimilarityMetric::Normalized => similarity,
+        SimilarityMetric::Cosine => (similarity + 1.) / 2.,
+        SimilarityMetric::DotProduct => 1. / (1. + (-similarity).exp()),
+    };
+    relevance.clamp(0., 1.)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RelevanceLevel {
+    High,
+    Medium,
+    Low,
+}
+
+impl RelevanceLevel {
+    fn from_relevance(relevance: f32) -> Self {
+        if relevance >= 0.75 {
+            Self::High
+        } else if relevance >= 0.5 {
+            Self::Medium
+        } else {
+            Self::Low
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::High => "High",
+            Self::Medium => "Medium",
+            Self::Low => "Low",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Self::High => Color::Success,
+            Self::Medium => Color::Default,
+            Self::Low => Color::Muted,
+        }
+    }
+}
+
+/// How many of the best results of each type a digest lists.
+const DIGEST_RESULTS_PER_TYPE: usize = 3;
+
+const DIGEST_LINE_LEN: usize = 100;
+
+#[derive(RegisterComponent)]
+struct FileSearchToolCard {
+    response: Option<Result<FileSearchResponse>>,
+    expanded: bool,
+    expanded_raw_metadata: HashSet<usize>,
+    /// Ids of the results whose full content is shown.
+    expanded_results: HashSet<String>,
+    collapsed_groups: HashSet<SearchResultType>,
+    /// Ids of the results checked for opening together.
+    selected_results: HashSet<String>,
+    /// Whether results below the display similarity threshold are shown.
+    show_low_similarity: bool,
+    /// Whether the response is also shown as the JSON it deserialized to.
+    show_raw_json: bool,
+    /// Whether every result is listed below the digest of a large response.
+    show_all_results: bool,
+    /// Stops the search whose output the model receives. Taken once the
+    /// search is cancelled.
+    cancel: Option<oneshot::Sender<()>>,
+    /// The thread this card is shown in, which results are pinned to.
+    thread: Option<WeakEntity<Thread>>,
+    _task: Task<()>,
+}
+
+impl FileSearchToolCard {
+    fn new(
+        search_task: Task<Result<FileSearchResponse>>,
+        cancel: oneshot::Sender<()>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let _task = cx.spawn(async move |this, cx| {
+            let response = search_task.await;
+            this.update(cx, |this, cx| {
+                this.response = Some(response);
+                cx.notify();
+            })
+            .ok();
+        });
+
+        Self {
+            _task,
+            ..Self::with_response(None, Some(cancel), false)
+        }
+    }
+
+    /// A card showing `response`, with every result in its default state.
+    fn with_response(
+        response: Option<Result<FileSearchResponse>>,
+        cancel: Option<oneshot::Sender<()>>,
+        expanded: bool,
+    ) -> Self {
+        Self {
+            response,
+            expanded,
+            expanded_raw_metadata: HashSet::default(),
+            expanded_results: HashSet::default(),
+            collapsed_groups: HashSet::default(),
+            selected_results: HashSet::default(),
+            show_low_similarity: false,
+            show_raw_json: false,
+            show_all_results: false,
+            cancel,
+            thread: None,
+            _task: Task::ready(()),
+        }
+    }
+
+    fn cancel(&mut self, cx: &mut Context<Self>) {
+        if self.response.is_some() {
+            return;
+        }
+        if let Some(cancel) = self.cancel.take() {
+            cancel.send(()).ok();
+        }
+        self._task = Task::ready(());
+        self.response = Some(Err(anyhow!("Cancelled")));
+        cx.notify();
+    }
+
+    fn from_output(output: FileSearchResponse) -> Self {
+        Self::with_response(Some(Ok(output)), None, false)
+    }
+
+    fn shows_requirements(&self) -> bool {
+        self.response
+            .as_ref()
+            .and_then(|response| response.as_ref().ok())
+            .is_some_and(|response| {
+                response.content_type.as_deref() == Some(REQUIREMENTS_CONTENT_TYPE)
+            })
+    }
+
+    fn render_result_group(
+        &self,
+        result_type: SearchResultType,
+        indices: Vec<usize>,
+        results: &[FileSearchResult],
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let is_collapsed = self.collapsed_groups.contains(&result_type);
+        let group_id = SharedString::from(format!("file-search-group-{}", result_type));
+        let settings = &AgentSettings::get_global(cx).file_search;
+        let threshold = settings.display_similarity_threshold;
+        let metric = settings.similarity_metric;
+        let (indices, low_similarity_indices): (Vec<_>, Vec<_>) = indices
+            .into_iter()
+            .partition(|index| relevance(results[*index].similarity, metric) >= threshold);
+
+        v_flex()
+            .gap_1()
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Disclosure::new(group_id, !is_collapsed).on_click(cx.listener({
+                            let result_type = result_type.clone();
+                            move |this, _, _, cx| {
+                                if !this.collapsed_groups.remove(&result_type) {
+                                    this.collapsed_groups.insert(result_type.clone());
+                                }
+                                cx.notify();
+                            }
+                        })),
+                    )
+                    .child(
+                        Label::new(result_type.label())
+                            .size(LabelSize::Small)
+                            .color(result_type.color()),
+                    )
+                    .child(
+                        Label::new(format!(
+                            "({})",
+                            indices.len() + low_similarity_indices.len()
+                        ))
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                    ),
+            )
+            .when(!is_collapsed, |this| {
+                this.child(
+                    v_flex()
+                        .pl_4()
+                        .gap_2()
+                        .children(
+                            indices
+                                .into_iter()
+                                .map(|index| self.render_result(index, &results[index], cx)),
+                        )
+                        .when(!low_similarity_indices.is_empty(), |this| {
+                            let count = low_similarity_indices.len();
+                            this.child(
+                                Button::new(
+                                    SharedString::from(format!(
+                                        "file-search-low-similarity-{result_type}"
+                                    )),
+                                    if self.show_low_similarity {
+                                        format!("Hide {count} below {threshold:.2} relevance")
+                                    } else {
+                                        format!("Show {count} below {threshold:.2} relevance")
+                                    },
+                                )
+                                .label_size(LabelSize::Small)
+                                .color(Color::Muted)
+                                .on_click(cx.listener(
+                                    |this, _, _, cx| {
+                                        this.show_low_similarity = !this.show_low_similarity;
+                                        cx.notify();
+                                    },
+                                )),
+                            )
+                            .when(self.show_low_similarity, |this| {
+                                this.children(low_similarity_indices.into_iter().map(|index| {
+                                    div().opacity(0.5).child(self.render_result(
+                                        index,
+                                        &results[index],
+                                        cx,
+                                    ))
+                                }))
+                            })
+                        }),
+                )
+            })
+            .into_any_element()
+    }
+
+    /// Lists the best few results of each type, so that responses too large
+    /// to scroll through can be triaged at a glance.
+    fn render_digest(
+        &self,
+        groups: &[(SearchResultType, Vec<usize>)],
+        results: &[FileSearchResult],
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let metric = AgentSettings::get_global(cx).file_search.similarity_metric;
+
+        v_flex()
+            .gap_1p5()
+            .children(groups.iter().map(|(result_type, indices)| {
+                let remaining = indices.len().saturating_sub(DIGEST_RESULTS_PER_TYPE);
+                v_flex()
+                    .gap_0p5()
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(
+                                Label::new(result_type.label())
+                                    .size(LabelSize::Small)
+                                    .color(result_type.color()),
+                            )
+                            .child(
+                                Label::new(format!("({})", indices.len()))
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            ),
+                    )
+                    .children(indices.iter().take(DIGEST_RESULTS_PER_TYPE).map(|index| {
+                        let result = &results[*index];
+                        h_flex()
+                            .pl_4()
+                            .gap_1p5()
+                            .child(
+                                Label::new(format!("{:.2}", relevance(result.similarity, metric)))
+                                    .size(LabelSize::XSmall)
+                                    .color(Color::Muted),
+                            )
+                            .child(
+                                Label::new(digest_line(&result.content))
+                                    .size(LabelSize::Small)
+                                    .truncate(),
+                            )
+                    }))
+                    .when(remaining > 0, |this| {
+                        this.child(
+                            div().pl_4().child(
+                                Label::new(format!("+{remaining} more"))
+                                    .size(LabelSize::XSmall)
+                                    .color(Color::Muted),
+                            ),
+                        )
+                    })
+            }))
+            .child(
+                Button::new(
+                    "file-search-show-all-results",
+                    if self.show_all_results {
+                        "Hide Full List".to_string()
+                    } else {
+                        format!("Show All {} Results", results.len())
+                    },
+                )
+                .label_size(LabelSize::Small)
+                .color(Color::Muted)
+                .on_click(cx.listener(|this, _, _, cx| {
+                    this.show_all_results = !this.show_all_results;
+                    cx.notify();
+                })),
+            )
+    }
+
+    /// Lists items returned without a similarity, one line each, in the order
+    /// the backend returned them.
+    fn render_item_list(
+        &self,
+        results: &[FileSearchResult],
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        v_flex().gap_0p5().children(results.iter().map(|result| {
+            let status = result
+                .task_metadata()
+                .and_then(|task_metadata| task_metadata.status);
+            let updated_at = result
+                .metadata
+                .get("updated_at")
+                .and_then(|updated_at| updated_at.as_str())
+                .map(ToString::to_string);
+            h_flex()
+                .gap_1p5()
+                .child(
+                    Label::new(result.result_type.label())
+                        .size(LabelSize::Small)
+                        .color(result.result_type.color()),
+                )
+                .child(
+                    div().flex_1().min_w_0().child(
+                        Label::new(digest_line(&result.content))
+                            .size(LabelSize::Small)
+                            .truncate(),
+                    ),
+                )
+                .when_some(status, |this, status| {
+                    this.child(
+                        Chip::new(status.clone()).label_color(TaskMetadata::status_color(&status)),
+                    )
+                })
+                .when_some(updated_at, |this, updated_at| {
+                    this.child(
+                        Label::new(updated_at)
+                            .size(LabelSize::XSmall)
+                            .color(Color::Muted)
+                            .buffer_font(cx),
+                    )
+                })
+        }))
+    }
+
+    fn render_raw_json(
+        &self,
+        response: &FileSearchResponse,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        v_flex()
+            .gap_1()
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Disclosure::new("file-search-raw-json", self.show_raw_json).on_click(
+                            cx.listener(|this, _, _, cx| {
+                                this.show_raw_json = !this.show_raw_json;
+                                cx.notify();
+                            }),
+                        ),
+                    )
+                    .child(
+                        Label::new("Show raw JSON")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .when(self.show_raw_json, |this| {
+                this.child(
+                    div()
+                        .px_2()
+                        .py_1()
+                        .rounded_md()
+                        .bg(cx.theme().colors().element_background)
+                        .child(
+                            Label::new(serde_json::to_string_pretty(response).unwrap_or_default())
+                                .buffer_font(cx)
+                                .size(LabelSize::XSmall),
+                        ),
+                )
+            })
+    }
+
+    fn render_result(
+        &self,
+        index: usize,
+        result: &FileSearchResult,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let task_metadata = result.task_metadata();
+        let metadata_chips = result
+            .metadata_chips()
+            .into_iter()
+            .filter(|(key, _)| task_metadata.is_none() || !TaskMetadata::KEYS.contains(key))
+            .collect::<Vec<_>>();
+        let is_raw_metadata_expanded = self.expanded_raw_metadata.contains(&index);
+        let is_expanded = self.expanded_results.contains(&result.id);
+        let is_truncated = !self.shows_requirements() && result.content.len() > 300;
+        let thread = self.thread.as_ref().and_then(|thread| thread.upgrade());
+        let is_pinned = thread
+            .as_ref()
+            .is_some_and(|thread| thread.read(cx).is_pinned(&result.id));
+        let relevance = relevance(
+            result.similarity,
+            AgentSettings::get_global(cx).file_search.similarity_metric,
+        );
+        let relevance_level = RelevanceLevel::from_relevance(relevance);
+        let is_selected = self.selected_results.contains(&result.id);
+
+        v_flex()
+            .gap_1()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .when(result.path().is_some(), |this| {
+                                this.child(
+                                    Checkbox::new(
+                                        ("file-search-select-result", index),
+                                        is_selected.into(),
+                                    )
+                                    .on_click({
+                                        let id = result.id.clone();
+                                        cx.listener(move |this, state: &ToggleState, _, cx| {
+                                            if state.selected() {
+                                                this.selected_results.insert(id.clone());
+                                            } else {
+                                                this.selected_results.remove(&id);
+                                            }
+                                            cx.notify();
+                                        })
+                                    }),
+                                )
+                            })
+                            .child(
+                                div()
+                                    .id(("file-search-relevance", index))
+                                    .child(
+                                        Label::new(format!("Relevance: {relevance:.2}"))
+                                            .size(LabelSize::Small)
+                                            .color(Color::Muted),
+                                    )
+                                    .tooltip(Tooltip::text(format!(
+                                        "Similarity reported by the backend: {:.3}",
+                                        result.similarity
+                                    ))),
+                            )
+                            .child(
+                                Chip::new(relevance_level.label())
+                                    .label_color(relevance_level.color()),
+                            )
+                            .when_some(task_metadata, |this, task_metadata| {
+                                this.when_some(task_metadata.status, |this, status| {
+                                    this.child(
+                                        Chip::new(status.clone())
+                                            .label_color(TaskMetadata::status_color(&status)),
+                                    )
+                                })
+                                .when_some(
+                                    task_metadata.assignee,
+                                    |this, assignee| {
+                                        this.child(
+                                            h_flex()
+                                                .gap_0p5()
+                                                .child(
+                                                    Icon::new(IconName::Person)
+                                                        .size(IconSize::XSmall)
+                                                        .color(Color::Muted),
+                                                )
+                                                .child(Label::new(assignee).size(LabelSize::Small)),
+                                        )
+                                    },
+                                )
+                            }),
+                    )
+                    .child(
+                        h_flex()
+                            .gap_0p5()
+                            .when_some(result.path(), |this, path| {
+                                let path = path.to_string();
+                                this.child(
+                                    IconButton::new(
+                                        ("file-search-copy-path", index),
+                                        IconName::FileText,
+                                    )
+                                    .icon_size(IconSize::XSmall)
+                                    .icon_color(Color::Muted)
+                                    .tooltip(Tooltip::text("Copy Path"))
+                                    .on_click(
+                                        move |_, _, cx| {
+                                            cx.write_to_clipboard(ClipboardItem::new_string(
+                                                path.clone(),
+                                            ))
+                                        },
+                                    ),
+                                )
+                            })
+                            .child(
+                                // The full content, even when the preview is cut short.
+                                IconButton::new(
+                                    ("file-search-copy-content", index),
+                                    IconName::Copy,
+                                )
+                                .icon_size(IconSize::XSmall)
+                                .icon_color(Color::Muted)
+                                .tooltip(Tooltip::text("Copy Content"))
+                                .on_click({
+                                    let content = result.content.clone();
+                                    move |_, _, cx| {
+                                        cx.write_to_clipboard(ClipboardItem::new_string(
+                                            content.clone(),
+                                        ))
+                                    }
+                                }),
+                            )
+                            .child(
+                                IconButton::new(
+                                    ("file-search-pin", index),
+                                    if is_pinned {
+                                        IconName::Unpin
+                                    } else {
+                                        IconName::Pin
+                                    },
+                                )
+                                .icon_size(IconSize::XSmall)
+                                .icon_color(Color::Muted)
+                                .toggle_state(is_pinned)
+                                .disabled(thread.is_none())
+                                .tooltip(Tooltip::text(if is_pinned {
+                                    "Unpin from Context"
+                                } else {
+                                    "Pin to Context"
+                                }))
+                                .on_click({
+                                    let snippet = PinnedSnippet {
+                                        id: result.id.clone().into(),
+                                        label: result.result_type.label(),
+                                        content: result.content.clone(),
+                                    };
+                                    cx.listener(move |this, _, _, cx| {
+                                        let Some(thread) = this
+                                            .thread
+                                            .as_ref()
+                                            .and_then(|thread| thread.upgrade())
+                                        else {
+                                            return;
+                                        };
+                                        thread.update(cx, |thread, cx| {
+                                            if is_pinned {
+                                                thread.unpin_snippet(&snippet.id, cx);
+                                            } else {
+                                                thread.pin_snippet(snippet.clone(), cx);
+                                            }
+                                        });
+                                        cx.notify();
+                                    })
+                                }),
+                            ),
+                    ),
+            )
+            .child(
+                div()
+                    .px_2()
+                    .py_1()
+                    .rounded_md()
+                    .bg(cx.theme().colors().element_background)
+                    .map(|this| {
+                        if self.shows_requirements() {
+                            this.child(v_flex().gap_0p5().children(
+                                requirement_items(&result.content).into_iter().map(|item| {
+                                    h_flex()
+                                        .gap_1p5()
+                                        .items_start()
+                                        .child(
+                                            Icon::new(IconName::Check)
+                                                .size(IconSize::XSmall)
+                                                .color(Color::Muted),
+                                        )
+                                        .child(Label::new(item.to_string()).size(LabelSize::Small))
+                                }),
+                            ))
+                        } else {
+                            this.child(
+                                Label::new(if is_expanded {
+                                    result.content.clone()
+                                } else {
+                                    util::truncate_and_trailoff(&result.content, 300)
+                                })
+                                .size(LabelSize::Small)
+                                .color(Color::Default),
+                            )
+                        }
+                    }),
+            )
+            .when_some(result.explanation.as_ref(), |this, explanation| {
+                this.child(
+                    h_flex()
+                        .gap_1()
+                        .items_start()
+                        .child(
+                            Icon::new(IconName::Info)
+                                .size(IconSize::XSmall)
+                                .color(Color::Muted),
+                        )
+                        .child(
+                            Label::new(explanation.clone())
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted),
+                        ),
+                )
+            })
+            .when(is_truncated, |this| {
+                this.child(
+                    Button::new(
+                        ("file-search-expand-result", index),
+                        if is_expanded {
+                            "Show Less"
+                        } else {
+                            "Show More"
+                        },
+                    )
+                    .label_size(LabelSize::Small)
+                    .color(Color::Muted)
+                    .on_click({
+                        let id = result.id.clone();
+                        cx.listener(move |this, _, _, cx| {
+                            if !this.expanded_results.remove(&id) {
+                                this.expanded_results.insert(id.clone());
+                            }
+                            cx.notify();
+                        })
+                    }),
+                )
+            })
+            .when(result.content_truncated, |this| {
+                this.child(
+                    Label::new("Content truncated")
+                        .size(LabelSize::XSmall)
+                        .color(Color::Warning),
+                )
+            })
+            .when(!metadata_chips.is_empty(), |this| {
+                this.child(
+                    h_flex()
+                        .gap_1()
+                        .flex_wrap()
+                        .children(metadata_chips.into_iter().map(|(key, value)| {
+                            Chip::new(format!("{key}: {value}")).label_color(Color::Muted)
+                        })),
+                )
+            })
+            .when(result.has_unknown_metadata(), |this| {
+                this.child(
+                    h_flex()
+                        .gap_1()
+                        .child(
+                            Disclosure::new(
+                                ("file-search-raw-metadata", index),
+                                is_raw_metadata_expanded,
+                            )
+                            .on_click(cx.listener(
+                                move |this, _, _, cx| {
+                                    if !this.expanded_raw_metadata.remove(&index) {
+                                        this.expanded_raw_metadata.insert(index);
+                                    }
+                                    cx.notify();
+                                },
+                            )),
+                        )
+                        .child(
+                            Label::new("Metadata")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        ),
+                )
+                .when(is_raw_metadata_expanded, |this| {
+                    this.child(
+                        div()
+                            .px_2()
+                            .py_1()
+                            .rounded_md()
+                            .bg(cx.theme().colors().element_background)
+                            .child(
+                                Label::new(
+                                    serde_json::to_string_pretty(&result.metadata)
+                                        .unwrap_or_default(),
+                                )
+                                .buffer_font(cx)
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted),
+                            ),
+                    )
+                })
+            })
+            .into_any_element()
+    }
+}
+
+fn check_search_request_size(request: &FileSearchRequest) -> Result<(), LlmApiError> {
+    let len = serde_json::to_string(request)
+        .context("Failed to serialize search request")?
+        .len();
+    if len > MAX_SEARCH_REQUEST_LEN {
+        return Err(search_too_large_error(&format!(
+            "it is {} KB, and searches are limited to {} KB",
+            len / 1024,
+            MAX_SEARCH_REQUEST_LEN / 1024
+        )));
+    }
+    Ok(())
 }
 
-impl FileSearchToolCard {
-    fn new(search_task: Task<Result<FileSearchResponse>>, cx: &mut Context<Self>) -> Self {
-        let _task = cx.spawn(async move |this, cx| {
-            let response = search_task.await;
-            this.update(cx, |this, cx| {
-                this.response = Some(response);
-                cx.notify();
-            })
-            .ok();
-        });
+/// An error the model can act on, instead of the backend's raw response.
+fn search_too_large_error(detail: &str) -> LlmApiError {
+    anyhow!(
+        "The search query or filters are too large ({detail}). Narrow your search with a shorter query or fewer filters."
+    )
+    .into()
+}
 
-        Self {
-            response: None,
-            expanded: false,
-            _task,
-        }
+/// Splits a requirements chunk into one item per line, dropping list markers
+/// and checkboxes the backend may have left in.
+fn requirement_items(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .map(|line| {
+            let line = line.trim().trim_start_matches(['-', '*', '•']).trim_start();
+            let line = ["[ ]", "[x]", "[X]"]
+                .iter()
+                .find_map(|checkbox| line.strip_prefix(checkbox))
+                .unwrap_or(line);
+            let digits = line
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(line.len());
+            let line = if digits > 0 {
+                line[digits..].strip_prefix(['.', ')']).unwrap_or(line)
+            } else {
+                line
+            };
+            line.trim()
+        })
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// The first non-empty line of a result, shortened to fit on one line of a
+/// digest.
+fn digest_line(content: &str) -> String {
+    let line = content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    util::truncate_and_trailoff(line, DIGEST_LINE_LEN)
+}
+
+/// Groups result indices by type, ordering groups by their best match and
+/// results within each group by similarity. When sorted by recency, results
+/// keep the order they were returned in.
+fn group_results_by_type(
+    results: &[FileSearchResult],
+    sort: SearchSort,
+) -> Vec<(SearchResultType, Vec<usize>)> {
+    let mut indices = (0..results.len()).collect::<Vec<_>>();
+    if sort == SearchSort::Relevance {
+        indices.sort_by(|a, b| {
+            results[*b]
+                .similarity
+                .partial_cmp(&results[*a].similarity)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
     }
 
-    fn from_output(output: FileSearchResponse) -> Self {
-        Self {
-            response: Some(Ok(output)),
-            expanded: false,
-            _task: Task::ready(()),
+    let mut groups: Vec<(SearchResultType, Vec<usize>)> = Vec::new();
+    for index in indices {
+        let result_type = &results[index].result_type;
+        match groups
+            .iter_mut()
+            .find(|(group_type, _)| group_type == result_type)
+        {
+            Some((_, group)) => group.push(index),
+            None => groups.push((result_type.clone(), vec![index])),
         }
     }
+    groups
 }
 
 impl ToolCard for FileSearchToolCard {
@@ -416,21 +2505,61 @@ impl ToolCard for FileSearchToolCard {
         &mut self,
         _status: &ToolUseStatus,
         _window: &mut Window,
-        _workspace: WeakEntity<Workspace>,
+        workspace: WeakEntity<Workspace>,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
+        self.thread = workspace.upgrade().and_then(|workspace| {
+            let delegate = <dyn ActiveThreadDelegate>::try_global(cx)?;
+            let thread = delegate.active_thread(workspace.read(cx), cx)?;
+            Some(thread.downgrade())
+        });
         let icon = IconName::MagnifyingGlass;
+        let is_compact = AgentSettings::get_global(cx).file_search.compact_cards;
 
         let header = match self.response.as_ref() {
             Some(Ok(response)) => {
-                let text: SharedString = if response.results.is_empty() {
-                    "No results found".into()
+                let noun = if response.metadata_only {
+                    "item"
+                } else {
+                    "result"
+                };
+                let mut text: SharedString = if response.results.is_empty() {
+                    format!("No {noun}s found").into()
+                } else if response.results.len() < response.total {
+                    format!("{} of {} {noun}s", response.results.len(), response.total).into()
                 } else if response.results.len() == 1 {
-                    "1 result".into()
+                    format!("1 {noun}").into()
                 } else {
-                    format!("{} results", response.results.len()).into()
+                    format!("{} {noun}s", response.results.len()).into()
                 };
-                ToolCallCardHeader::new(icon, "Searched Content").with_secondary_text(text)
+                if response.sort == Some(SearchSort::Recency) && !response.results.is_empty() {
+                    text = format!("{text}, newest first").into();
+                }
+                let metric = AgentSettings::get_global(cx).file_search.similarity_metric;
+                let top_relevance = response
+                    .results
+                    .iter()
+                    .map(|result| relevance(result.similarity, metric))
+                    .reduce(f32::max);
+                if let Some(top_relevance) =
+                    top_relevance.filter(|_| is_compact && !response.metadata_only)
+                {
+                    text = format!("{text}, top relevance {top_relevance:.2}").into();
+                }
+                match response
+                    .thread_id
+                    .as_ref()
+                    .filter(|_| response.query.is_empty())
+                {
+                    Some(thread_id) => ToolCallCardHeader::new(icon, "Searched Thread Context")
+                        .with_secondary_text(format!("{text} from thread {thread_id}")),
+                    None if response.metadata_only => {
+                        ToolCallCardHeader::new(icon, "Listed Items").with_secondary_text(text)
+                    }
+                    None => {
+                        ToolCallCardHeader::new(icon, "Searched Content").with_secondary_text(text)
+                    }
+                }
             }
             Some(Err(error)) => {
                 ToolCallCardHeader::new(icon, "Content Search").with_error(error.to_string())
@@ -438,9 +2567,10 @@ impl ToolCard for FileSearchToolCard {
             None => ToolCallCardHeader::new(icon, "Searching Content").loading(),
         };
 
+        let digest_threshold = AgentSettings::get_global(cx).file_search.digest_threshold;
         let content = if self.expanded {
             self.response.as_ref().and_then(|response| match response {
-                Ok(response) if !response.results.is_empty() => Some(
+                Ok(response) if response.metadata_only && !response.results.is_empty() => Some(
                     v_flex()
                         .overflow_hidden()
                         .ml_1p5()
@@ -448,59 +2578,156 @@ impl ToolCard for FileSearchToolCard {
                         .border_l_1()
                         .border_color(cx.theme().colors().border_variant)
                         .gap_2()
-                        .children(response.results.iter().enumerate().map(|(_index, result)| {
-                            v_flex()
-                                .gap_1()
-                                .child(
-                                    h_flex()
-                                        .gap_2()
-                                        .child(
-                                            div()
-                                                .px_1()
-                                                .rounded_md()
-                                                .bg(cx.theme().colors().element_background)
-                                                .child(
-                                                    Label::new(format!("[{}]", result.result_type))
-                                                        .size(LabelSize::Small)
-                                                        .color(Color::Muted),
-                                                ),
-                                        )
-                                        .child(
-                                            Label::new(format!(
-                                                "Similarity: {:.2}",
-                                                result.similarity
-                                            ))
-                                            .size(LabelSize::Small)
-                                            .color(Color::Muted),
-                                        ),
-                                )
-                                .child(
-                                    div()
-                                        .px_2()
-                                        .py_1()
-                                        .rounded_md()
-                                        .bg(cx.theme().colors().element_background)
-                                        .child(
-                                            Label::new(if result.content.len() > 300 {
-                                                format!("{}...", &result.content[..300])
-                                            } else {
-                                                result.content.clone()
-                                            })
-                                            .size(LabelSize::Small)
-                                            .color(Color::Default),
-                                        ),
-                                )
-                        }))
+                        .child(self.render_item_list(&response.results, cx))
+                        .child(self.render_raw_json(response, cx))
                         .into_any(),
                 ),
+                Ok(response) if !response.results.is_empty() => {
+                    let groups =
+                        group_results_by_type(&response.results, response.sort.unwrap_or_default());
+                    let show_digest =
+                        digest_threshold > 0 && response.results.len() >= digest_threshold;
+                    Some(
+                        v_flex()
+                            .overflow_hidden()
+                            .ml_1p5()
+                            .pl(px(5.))
+                            .border_l_1()
+                            .border_color(cx.theme().colors().border_variant)
+                            .gap_2()
+                            .when(show_digest, |this| {
+                                this.child(self.render_digest(&groups, &response.results, cx))
+                            })
+                            .when(!show_digest || self.show_all_results, |this| {
+                                this.children(groups.into_iter().map(|(result_type, indices)| {
+                                    self.render_result_group(
+                                        result_type,
+                                        indices,
+                                        &response.results,
+                                        cx,
+                                    )
+                                }))
+                            })
+                            .child(self.render_raw_json(response, cx))
+                            .into_any(),
+                    )
+                }
                 _ => None,
             })
         } else {
             None
         };
 
+        let omitted_results_hint = match self.response.as_ref() {
+            Some(Ok(response)) if self.expanded || !is_compact => response.omitted_results_hint(),
+            _ => None,
+        };
+
+        let footer = match self.response.as_ref() {
+            Some(Ok(response))
+                if !response.results.is_empty() && (self.expanded || !is_compact) =>
+            {
+                Some(
+                    h_flex()
+                        .ml_1p5()
+                        .gap_1()
+                        .justify_between()
+                        .child(
+                            Label::new(
+                                response
+                                    .rerank_model
+                                    .as_ref()
+                                    .map(|rerank_model| format!("Reranked with {rerank_model}"))
+                                    .unwrap_or_default(),
+                            )
+                            .size(LabelSize::XSmall)
+                            .color(Color::Muted),
+                        )
+                        .child(
+                            h_flex()
+                                .gap_1()
+                                .when(!self.selected_results.is_empty(), |this| {
+                                    this.child(
+                                        Button::new(
+                                            "open-selected-search-results",
+                                            format!(
+                                                "Open Selected ({})",
+                                                self.selected_results.len()
+                                            ),
+                                        )
+                                        .icon(IconName::ArrowUpRight)
+                                        .icon_position(IconPosition::Start)
+                                        .icon_size(IconSize::XSmall)
+                                        .icon_color(Color::Muted)
+                                        .label_size(LabelSize::Small)
+                                        .on_click({
+                                            let workspace = workspace.clone();
+                                            cx.listener(move |this, _, window, cx| {
+                                                let Some(Ok(response)) = this.response.as_ref()
+                                                else {
+                                                    return;
+                                                };
+                                                let Some(workspace) = workspace.upgrade() else {
+                                                    return;
+                                                };
+                                                let paths = response
+                                                    .results
+                                                    .iter()
+                                                    .filter(|result| {
+                                                        this.selected_results.contains(&result.id)
+                                                    })
+                                                    .filter_map(|result| result.path())
+                                                    .map(ToString::to_string)
+                                                    .collect::<Vec<_>>();
+                                                open_result_paths(&paths, workspace, window, cx);
+                                            })
+                                        }),
+                                    )
+                                })
+                                .child(
+                                    Button::new(
+                                        "open-search-results-in-buffer",
+                                        "Open in New Buffer",
+                                    )
+                                    .icon(IconName::FileText)
+                                    .icon_position(IconPosition::Start)
+                                    .icon_size(IconSize::XSmall)
+                                    .icon_color(Color::Muted)
+                                    .label_size(LabelSize::Small)
+                                    .on_click(cx.listener(
+                                        move |this, _, window, cx| {
+                                            let Some(Ok(response)) = this.response.as_ref() else {
+                                                return;
+                                            };
+                                            let Some(workspace) = workspace.upgrade() else {
+                                                return;
+                                            };
+                                            open_response_in_buffer(
+                                                response, workspace, window, cx,
+                                            )
+                                            .detach_and_log_err(cx);
+                                        },
+                                    )),
+                                ),
+                        ),
+                )
+            }
+            None if self.cancel.is_some() => Some(
+                h_flex().ml_1p5().child(
+                    Button::new("cancel-file-search", "Cancel")
+                        .icon(IconName::Close)
+                        .icon_position(IconPosition::Start)
+                        .icon_size(IconSize::XSmall)
+                        .icon_color(Color::Muted)
+                        .label_size(LabelSize::Small)
+                        .on_click(cx.listener(|this, _, _, cx| this.cancel(cx))),
+                ),
+            ),
+            _ => None,
+        };
+
         v_flex()
-            .mb_3()
+            .map(|this| if is_compact { this.mb_1() } else { this.mb_3() })
             .gap_1()
             .child(
                 header.disclosure_slot(
@@ -515,7 +2742,17 @@ impl ToolCard for FileSearchToolCard {
                         })),
                 ),
             )
+            .when_some(omitted_results_hint, |this, hint| {
+                this.child(
+                    div().ml_1p5().child(
+                        Banner::new()
+                            .severity(ui::Severity::Info)
+                            .child(Label::new(hint).size(LabelSize::Small)),
+                    ),
+                )
+            })
             .children(content)
+            .children(footer)
     }
 }
 
@@ -525,42 +2762,39 @@ impl Component for FileSearchToolCard {
     }
 
     fn preview(window: &mut Window, cx: &mut App) -> Option<AnyElement> {
-        let successful_card = cx.new(|_| FileSearchToolCard {
-            response: Some(Ok(FileSearchResponse {
-                results: vec![
-                    FileSearchResult {
-                        id: "1".to_string(),
-                        content:
-                            "User mentioned they want to implement vim mode with yank functionality"
-                                .to_string(),
-                        result_type: "conversation".to_string(),
-                        similarity: 0.92,
-                        metadata: serde_json::json!({}),
-                    },
-                    FileSearchResult {
-                        id: "2".to_string(),
-                        content: "Task: Implement yank mode for vim - Status: In Progress"
-                            .to_string(),
-                        result_type: "task".to_string(),
-                        similarity: 0.87,
-                        metadata: serde_json::json!({}),
-                    },
-                ],
-                total: 2,
-                query: "vim yank mode".to_string(),
-            })),
-            expanded: true,
-            _task: Task::ready(()),
+        let results = vec![
+            FileSearchResult {
+                content: "User mentioned they want to implement vim mode with yank functionality"
+                    .to_string(),
+                ..FileSearchResult::example("1", SearchResultType::Conversation, 0.92)
+            },
+            FileSearchResult {
+                content: "Task: Implement yank mode for vim - Status: In Progress".to_string(),
+                metadata: serde_json::json!({
+                    "author": "jane",
+                    "updated_at": "2025-06-30",
+                    "status": "in_progress",
+                }),
+                ..FileSearchResult::example("2", SearchResultType::Task, 0.87)
+            },
+        ];
+        let successful_card = cx.new(|_| {
+            FileSearchToolCard::with_response(
+                Some(Ok(FileSearchResponse::example("vim yank mode", results))),
+                None,
+                true,
+            )
         });
 
-        let empty_card = cx.new(|_| FileSearchToolCard {
-            response: Some(Ok(FileSearchResponse {
-                results: Vec::new(),
-                total: 0,
-                query: "nonexistent query".to_string(),
-            })),
-            expanded: false,
-            _task: Task::ready(()),
+        let empty_card = cx.new(|_| {
+            FileSearchToolCard::with_response(
+                Some(Ok(FileSearchResponse::example(
+                    "nonexistent query",
+                    Vec::new(),
+                ))),
+                None,
+                false,
+            )
         });
 
         Some(
@@ -602,3 +2836,600 @@ impl Component for FileSearchToolCard {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_client::StatusCode;
+    use serde_json::json;
+
+    #[test]
+    fn test_search_circuit_breaker() {
+        let server_error = || {
+            Err::<(), _>(LlmApiError::Server {
+                status: StatusCode::BAD_GATEWAY,
+                message: String::new(),
+            })
+        };
+        let now = Instant::now();
+        let mut circuit_breaker = SearchCircuitBreaker::default();
+
+        for _ in 1..CIRCUIT_BREAKER_FAILURE_THRESHOLD {
+            circuit_breaker.record(&server_error(), now);
+        }
+        assert_eq!(circuit_breaker.admit(now), Ok(()));
+        circuit_breaker.record(&server_error(), now);
+        assert_eq!(circuit_breaker.admit(now), Err(CIRCUIT_BREAKER_COOLDOWN));
+
+        // After the cooldown a single probe is let through, and its failure
+        // reopens the circuit.
+        let later = now + CIRCUIT_BREAKER_COOLDOWN;
+        assert_eq!(circuit_breaker.admit(later), Ok(()));
+        assert!(circuit_breaker.admit(later).is_err());
+        circuit_breaker.record(&server_error(), later);
+        assert_eq!(circuit_breaker.admit(later), Err(CIRCUIT_BREAKER_COOLDOWN));
+
+        // A probe that never reports back is replaced after another cooldown.
+        let reopened_at = later + CIRCUIT_BREAKER_COOLDOWN;
+        assert_eq!(circuit_breaker.admit(reopened_at), Ok(()));
+        assert!(circuit_breaker.admit(reopened_at).is_err());
+        let probe_expired_at = reopened_at + CIRCUIT_BREAKER_COOLDOWN;
+        assert_eq!(circuit_breaker.admit(probe_expired_at), Ok(()));
+
+        // A rejected request still shows the backend is reachable.
+        let rejected = Err::<(), _>(LlmApiError::Unauthorized {
+            status: StatusCode::UNAUTHORIZED,
+            message: String::new(),
+        });
+        circuit_breaker.record(&rejected, probe_expired_at);
+        assert_eq!(circuit_breaker.admit(probe_expired_at), Ok(()));
+        assert_eq!(circuit_breaker.admit(probe_expired_at), Ok(()));
+        assert_eq!(circuit_breaker.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_search_cache() {
+        let response = |query: &str| FileSearchResponse::example(query, Vec::new());
+        let now = Instant::now();
+        let mut cache = SearchCache::default();
+        cache.insert("a".into(), response("a"), now);
+        assert_eq!(cache.get("a", now).unwrap().query, "a");
+        assert!(cache.get("b", now).is_none());
+
+        let expired_at = now + SEARCH_CACHE_TTL;
+        assert!(cache.get("a", expired_at).is_none());
+        assert!(cache.entries.is_empty());
+
+        for ix in 0..=SEARCH_CACHE_CAPACITY {
+            cache.insert(ix.to_string(), response(&ix.to_string()), now);
+        }
+        assert_eq!(cache.entries.len(), SEARCH_CACHE_CAPACITY);
+        assert!(cache.get("0", now).is_none());
+        assert!(cache.get("1", now).is_some());
+
+        cache.clear();
+        assert!(cache.get("1", now).is_none());
+    }
+
+    #[test]
+    fn test_omitted_results_hint() {
+        let response = |shown: usize, total: usize| FileSearchResponse {
+            total,
+            ..FileSearchResponse::example(
+                "vim",
+                (0..shown)
+                    .map(|ix| {
+                        FileSearchResult::example(ix.to_string(), SearchResultType::Task, 1.0)
+                    })
+                    .collect(),
+            )
+        };
+
+        assert_eq!(response(10, 10).omitted_results_hint(), None);
+        assert_eq!(response(0, 0).omitted_results_hint(), None);
+        assert_eq!(
+            response(10, 240).omitted_results_hint().as_deref(),
+            Some(
+                "Showing 10 of 240 results. To see the rest, increase the limit (up to 100) or narrow the search."
+            )
+        );
+        assert!(
+            response(MAX_SEARCH_LIMIT, 240)
+                .omitted_results_hint()
+                .unwrap()
+                .ends_with("narrow the search with a more specific query or filter.")
+        );
+    }
+
+    #[test]
+    fn test_query_embedding_cache() {
+        let now = Instant::now();
+        let mut cache = QueryEmbeddingCache::default();
+        cache.insert("model".into(), "vim".into(), Arc::from([1.0, 0.0]), now);
+        assert_eq!(
+            cache.get("model", "vim", now).as_deref(),
+            Some([1.0, 0.0].as_slice())
+        );
+        assert!(cache.get("other-model", "vim", now).is_none());
+        assert!(cache.get("model", "emacs", now).is_none());
+
+        cache.insert("model".into(), "vim".into(), Arc::from([0.0, 1.0]), now);
+        assert_eq!(cache.entries.len(), 1);
+        assert!(cache.get("model", "vim", now + SEARCH_CACHE_TTL).is_none());
+    }
+
+    #[test]
+    fn test_search_result_type_serialization() {
+        let types: Vec<SearchResultType> =
+            serde_json::from_str(r#"["conversation", "task", "compressed", "design_doc"]"#)
+                .unwrap();
+        assert_eq!(
+            types,
+            vec![
+                SearchResultType::Conversation,
+                SearchResultType::Task,
+                SearchResultType::Compressed,
+                SearchResultType::Unknown("design_doc".into()),
+            ]
+        );
+        assert_eq!(
+            serde_json::to_string(&types).unwrap(),
+            r#"["conversation","task","compressed","design_doc"]"#
+        );
+    }
+
+    #[test]
+    fn test_group_results_by_type() {
+        let result =
+            |result_type, similarity| FileSearchResult::example("", result_type, similarity);
+        let results = vec![
+            result(SearchResultType::Task, 0.5),
+            result(SearchResultType::Conversation, 0.9),
+            result(SearchResultType::Task, 0.8),
+            result(SearchResultType::Compressed, 0.1),
+        ];
+        assert_eq!(
+            group_results_by_type(&results, SearchSort::Relevance),
+            vec![
+                (SearchResultType::Conversation, vec![1]),
+                (SearchResultType::Task, vec![2, 0]),
+                (SearchResultType::Compressed, vec![3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_search_request_size() {
+        let request = |query: String| FileSearchRequest {
+            query: Some(query),
+            limit: None,
+            filter: None,
+            rerank_model: None,
+            explain: false,
+            exclude_types: Vec::new(),
+            query_embedding: None,
+            metadata_only: false,
+        };
+        assert!(check_search_request_size(&request("vim mode".into())).is_ok());
+        let error =
+            check_search_request_size(&request("a".repeat(MAX_SEARCH_REQUEST_LEN))).unwrap_err();
+        assert!(error.to_string().contains("Narrow your search"));
+    }
+
+    #[test]
+    fn test_digest_line() {
+        assert_eq!(digest_line("\n  Task: fix yank  \nmore"), "Task: fix yank");
+        assert_eq!(digest_line(""), "");
+        let long = "é".repeat(DIGEST_LINE_LEN + 1);
+        assert_eq!(
+            digest_line(&long),
+            format!("{}…", "é".repeat(DIGEST_LINE_LEN))
+        );
+    }
+
+    #[test]
+    fn test_normalize_api_path() {
+        assert_eq!(normalize_api_path("/api/v1/search"), "/api/v1/search");
+        assert_eq!(normalize_api_path("custom/search/"), "/custom/search");
+        assert_eq!(normalize_api_path(" /custom/search "), "/custom/search");
+    }
+
+    #[test]
+    fn test_requirement_items() {
+        let content = "Acceptance criteria:\n- [ ] Users can log in\n* [x] Errors are shown\n\n2) Works offline\n2024 roadmap";
+        assert_eq!(
+            requirement_items(content),
+            [
+                "Acceptance criteria:",
+                "Users can log in",
+                "Errors are shown",
+                "Works offline",
+                "2024 roadmap",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retain_updated_since() {
+        let result = |id: &str, metadata| FileSearchResult {
+            metadata,
+            ..FileSearchResult::example(id, SearchResultType::Task, 1.0)
+        };
+        let mut response = FileSearchResponse {
+            total: 10,
+            ..FileSearchResponse::example(
+                "",
+                vec![
+                    result("old", json!({ "updated_at": "2024-04-30T23:59:59Z" })),
+                    result("new", json!({ "updated_at": "2024-05-01T00:00:00+00:00" })),
+                    result("unknown", serde_json::Value::Null),
+                ],
+            )
+        };
+
+        retain_updated_since(&mut response, parse_timestamp("2024-05-01").unwrap());
+        let ids: Vec<_> = response.results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["new", "unknown"]);
+        assert_eq!(response.total, 9);
+
+        assert!(parse_timestamp("last week").is_err());
+    }
+
+    #[test]
+    fn test_exclude_compressed_results() {
+        let result = |id: &str, result_type| FileSearchResult::example(id, result_type, 1.0);
+        let mut response = FileSearchResponse {
+            total: 5,
+            ..FileSearchResponse::example(
+                "vim",
+                vec![
+                    result("task", SearchResultType::Task),
+                    result("summary", SearchResultType::Compressed),
+                    result("conversation", SearchResultType::Conversation),
+                ],
+            )
+        };
+
+        exclude_compressed_results(&mut response);
+        let ids: Vec<_> = response.results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["task", "conversation"]);
+        assert_eq!(response.total, 4);
+        assert!(response.compressed_excluded);
+
+        assert!(SearchFilter::default().searches_all_types());
+        let filter = |search_type: &str| SearchFilter {
+            search_type: Some(search_type.into()),
+            ..Default::default()
+        };
+        assert!(filter("all").searches_all_types());
+        assert!(!filter("compressed").searches_all_types());
+    }
+
+    #[test]
+    fn test_blank_query_is_treated_as_missing() {
+        let input = |value| serde_json::from_value::<FileSearchToolInput>(value).unwrap();
+
+        let mut query_only = input(json!({ "query": "  vim  " }));
+        query_only.validate().unwrap();
+        assert_eq!(query_only.query.as_deref(), Some("  vim  "));
+
+        for query in ["", "   ", "\n\t"] {
+            let mut blank = input(json!({ "query": query }));
+            assert!(blank.query().is_none());
+            assert!(blank.validate().is_err());
+
+            let mut blank_with_thread = input(json!({
+                "query": query,
+                "filter": { "thread_id": "thread-1" }
+            }));
+            blank_with_thread.validate().unwrap();
+            assert_eq!(blank_with_thread.query, None);
+        }
+
+        let mut blank_thread = input(json!({ "filter": { "thread_id": " " } }));
+        assert!(blank_thread.validate().is_err());
+        assert!(input(json!({})).validate().is_err());
+        let mut sort_only = input(json!({ "filter": { "sort": "recency" } }));
+        assert!(sort_only.validate().is_err());
+
+        let mut filter_only = input(json!({ "filter": { "type": "tasks", "thread_id": "" } }));
+        filter_only.validate().unwrap();
+        assert_eq!(filter_only.filter.as_ref().unwrap().thread_id, None);
+        assert!(filter_only.is_metadata_only());
+        assert!(!query_only.is_metadata_only());
+        let mut thread_only = input(json!({ "filter": { "thread_id": "thread-1" } }));
+        thread_only.validate().unwrap();
+        assert!(!thread_only.is_metadata_only());
+    }
+
+    #[test]
+    fn test_search_capabilities() {
+        let input = |value| serde_json::from_value::<FileSearchToolInput>(value).unwrap();
+        let capabilities: SearchCapabilities = serde_json::from_value(json!({
+            "search_types": ["tasks", "conversations"],
+            "content_types": ["work_item"],
+            "modes": ["sort"]
+        }))
+        .unwrap();
+
+        capabilities
+            .check_input(&input(
+                json!({ "query": "vim", "filter": { "type": "all" } }),
+            ))
+            .unwrap();
+        let error = capabilities
+            .check_input(&input(
+                json!({ "query": "vim", "filter": { "type": "compressed" } }),
+            ))
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .ends_with("Supported types: 'all', 'tasks', 'conversations'")
+        );
+        let error = capabilities
+            .check_input(&input(json!({
+                "query": "vim",
+                "filter": { "content_type": "requirements" }
+            })))
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .ends_with("Supported content types: 'auto', 'work_item'")
+        );
+        assert!(
+            capabilities
+                .check_input(&input(json!({ "filter": { "type": "tasks" } })))
+                .is_err()
+        );
+
+        let mut request = FileSearchRequest {
+            query: Some("vim".into()),
+            limit: None,
+            filter: Some(SearchFilter {
+                search_type: Some("compressed".into()),
+                content_type: Some("work_item".into()),
+                updated_since: Some("2024-05-01".into()),
+                sort: Some(SearchSort::Recency),
+                ..Default::default()
+            }),
+            rerank_model: Some("rerank-v1".into()),
+            explain: true,
+            exclude_types: Vec::new(),
+            query_embedding: Some(vec![0.5]),
+            metadata_only: false,
+        };
+        capabilities.degrade(&mut request);
+        let filter = request.filter.as_ref().unwrap();
+        assert_eq!(filter.search_type, None);
+        assert_eq!(filter.content_type.as_deref(), Some("work_item"));
+        assert_eq!(filter.updated_since, None);
+        assert_eq!(filter.sort, Some(SearchSort::Recency));
+        assert_eq!(request.rerank_model, None);
+        assert!(!request.explain);
+        assert_eq!(request.query_embedding, None);
+
+        // Backends that don't report their capabilities support everything.
+        let unknown = SearchCapabilities::default();
+        unknown
+            .check_input(&input(json!({ "filter": { "type": "compressed" } })))
+            .unwrap();
+        request.rerank_model = Some("rerank-v1".into());
+        unknown.degrade(&mut request);
+        assert_eq!(request.rerank_model.as_deref(), Some("rerank-v1"));
+    }
+
+    #[test]
+    fn test_relevance() {
+        assert_eq!(relevance(0.3, SimilarityMetric::Normalized), 0.3);
+        assert_eq!(relevance(1.2, SimilarityMetric::Normalized), 1.);
+        assert_eq!(relevance(-1., SimilarityMetric::Cosine), 0.);
+        assert_eq!(relevance(0.5, SimilarityMetric::Cosine), 0.75);
+        assert_eq!(relevance(0., SimilarityMetric::DotProduct), 0.5);
+        assert!(relevance(8., SimilarityMetric::DotProduct) > 0.99);
+
+        assert_eq!(RelevanceLevel::from_relevance(0.8), RelevanceLevel::High);
+        assert_eq!(RelevanceLevel::from_relevance(0.5), RelevanceLevel::Medium);
+        assert_eq!(RelevanceLevel::from_relevance(0.49), RelevanceLevel::Low);
+    }
+
+    #[test]
+    fn test_search_filter_fill_defaults() {
+        let mut filter = SearchFilter {
+            account_id: Some("input-account".into()),
+            ..Default::default()
+        };
+        filter.fill_defaults(SearchFilter {
+            account_id: Some("synced-account".into()),
+            board_id: Some("synced-board".into()),
+            ..Default::default()
+        });
+        filter.fill_defaults(
+            FileSearchFilterSettings {
+                search_type: Some("tasks".into()),
+                board_id: Some("project-board".into()),
+                product_id: Some("project-product".into()),
+                ..Default::default()
+            }
+            .into(),
+        );
+
+        assert_eq!(filter.account_id.as_deref(), Some("input-account"));
+        assert_eq!(filter.board_id.as_deref(), Some("synced-board"));
+        assert_eq!(filter.product_id.as_deref(), Some("project-product"));
+        assert_eq!(filter.search_type.as_deref(), Some("tasks"));
+        assert_eq!(filter.task_id, None);
+    }
+
+    #[test]
+    fn test_sort_by_recency() {
+        let result = |id: &str, similarity, metadata| FileSearchResult {
+            metadata,
+            ..FileSearchResult::example(id, SearchResultType::Task, similarity)
+        };
+        let mut results = vec![
+            result("similar", 0.9, json!({ "updated_at": "2024-01-01" })),
+            result("unknown", 0.8, serde_json::Value::Null),
+            result(
+                "latest",
+                0.2,
+                json!({ "updated_at": "2024-06-01T12:00:00Z" }),
+            ),
+            result("recent", 0.5, json!({ "updated_at": "2024-05-01" })),
+        ];
+
+        sort_by_recency(&mut results);
+        let ids: Vec<_> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["latest", "recent", "similar", "unknown"]);
+        assert_eq!(
+            group_results_by_type(&results, SearchSort::Recency),
+            vec![(SearchResultType::Task, vec![0, 1, 2, 3])]
+        );
+
+        let filter: SearchFilter = serde_json::from_value(json!({ "sort": "recency" })).unwrap();
+        assert_eq!(filter.sort, Some(SearchSort::Recency));
+    }
+
+    #[test]
+    fn test_active_editor_context_apply_to_query() {
+        let context = ActiveEditorContext {
+            path: Some("src/auth.rs".into()),
+            selection: Some("fn refresh_token()".into()),
+        };
+        assert_eq!(
+            context.apply_to_query("token refresh"),
+            "token refresh\n\nCurrent file: src/auth.rs\n\nSelected code:\nfn refresh_token()"
+        );
+
+        let context = ActiveEditorContext {
+            path: Some("src/auth.rs".into()),
+            selection: None,
+        };
+        assert_eq!(
+            context.apply_to_query("token refresh"),
+            "token refresh\n\nCurrent file: src/auth.rs"
+        );
+    }
+
+    #[test]
+    fn test_response_to_markdown() {
+        let response = FileSearchResponse {
+            total: 3,
+            content_type: Some("work_item".into()),
+            ..FileSearchResponse::example(
+                "vim",
+                vec![FileSearchResult {
+                    content: "Add a yank mode.\n".into(),
+                    metadata: json!({ "status": "open" }),
+                    content_truncated: true,
+                    ..FileSearchResult::example("1", SearchResultType::Task, 0.9)
+                }],
+            )
+        };
+        assert_eq!(
+            response.to_markdown(),
+            "# Search Results for \"vim\"\n\n\
+             - Content type: work_item\n\
+             - Showing 1 of 3 results\n\
+             \n## 1. Task (similarity: 0.90)\n\n\
+             - status: open\n\
+             \nAdd a yank mode.\n\
+             \n_Content truncated._\n"
+        );
+
+        let thread_response = FileSearchResponse {
+            thread_id: Some("thread-1".into()),
+            compressed_excluded: true,
+            ..FileSearchResponse::example("", Vec::new())
+        };
+        assert_eq!(
+            thread_response.to_markdown(),
+            "# Search Results for thread thread-1's context\n\n\
+             - Compressed summaries excluded\n\
+             - Showing 0 of 0 results\n"
+        );
+
+        let listed_response = FileSearchResponse {
+            query: String::new(),
+            content_type: None,
+            sort: Some(SearchSort::Recency),
+            metadata_only: true,
+            ..response
+        };
+        assert_eq!(
+            listed_response.to_markdown(),
+            "# Listed Items\n\n\
+             - Sorted by: most recently updated\n\
+             - Showing 1 of 3 results\n\
+             \n## 1. Task\n\n\
+             - status: open\n\
+             \nAdd a yank mode.\n\
+             \n_Content truncated._\n"
+        );
+    }
+
+    #[test]
+    fn test_task_metadata() {
+        let result = |result_type, metadata| FileSearchResult {
+            metadata,
+            ..FileSearchResult::example("1", result_type, 1.0)
+        };
+
+        let task = result(
+            SearchResultType::Task,
+            json!({ "status": "in_progress", "assignee": "Ana", "sprint": 4 }),
+        );
+        assert_eq!(
+            task.task_metadata(),
+            Some(TaskMetadata {
+                status: Some("in_progress".into()),
+                assignee: Some("Ana".into()),
+            })
+        );
+        assert!(task.has_unknown_metadata());
+        assert_eq!(TaskMetadata::status_color("In-Progress"), Color::Accent);
+
+        let big_bet = result(
+            SearchResultType::Unknown("big_bet".into()),
+            json!({ "assignee": "Ana" }),
+        );
+        assert!(big_bet.task_metadata().is_some());
+        assert!(!big_bet.has_unknown_metadata());
+
+        // Unexpected shapes fall back to the generic chips.
+        let task = result(SearchResultType::Task, json!({ "status": 3 }));
+        assert_eq!(task.task_metadata(), None);
+        let conversation = result(SearchResultType::Conversation, json!({ "status": "open" }));
+        assert_eq!(conversation.task_metadata(), None);
+    }
+
+    #[test]
+    fn test_truncate_result_contents() {
+        let mut results = vec![FileSearchResult {
+            content: "é".repeat(MAX_RESULT_CONTENT_LEN),
+            ..FileSearchResult::example("large", SearchResultType::Task, 1.0)
+        }];
+        truncate_result_contents(&mut results);
+        assert!(results[0].content_truncated);
+        assert!(results[0].content.len() <= MAX_RESULT_CONTENT_LEN);
+
+        let response: FileSearchResponse = serde_json::from_value(json!({
+            "results": [{ "id": "1", "content": "small", "type": "task", "similarity": 0.5, "metadata": {} }],
+            "total": 1,
+            "query": "",
+        }))
+        .unwrap();
+        assert!(!response.results[0].content_truncated);
+        assert_eq!(response.rerank_model, None);
+
+        let response: FileSearchResponse = serde_json::from_value(json!({
+            "results": [],
+            "total": 0,
+            "query": "vim",
+            "rerank_model": "rerank-v1",
+        }))
+        .unwrap();
+        assert_eq!(response.rerank_model.as_deref(), Some("rerank-v1"));
+    }
+}
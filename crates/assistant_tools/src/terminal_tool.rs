@@ -81,6 +81,12 @@ impl Tool for TerminalTool {
         true
     }
 
+    fn command(&self, input: &serde_json::Value) -> Option<String> {
+        serde_json::from_value::<TerminalToolInput>(input.clone())
+            .ok()
+            .map(|input| input.command)
+    }
+
     fn may_perform_edits(&self) -> bool {
         false
     }
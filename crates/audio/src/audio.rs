@@ -2,7 +2,7 @@ use assets::SoundRegistry;
 use derive_more::{Deref, DerefMut};
 use gpui::{App, AssetSource, BorrowAppContext, Global};
 use rodio::{OutputStream, OutputStreamHandle};
-use util::ResultExt;
+use util::{ResultExt, command::new_smol_command};
 
 mod assets;
 
@@ -74,6 +74,28 @@ impl Audio {
         });
     }
 
+    /// Plays the operating system's notification sound, or `fallback` where
+    /// it can't be played.
+    pub fn play_system_sound(fallback: Sound, cx: &mut App) {
+        let Some((program, args)) = system_sound_command() else {
+            return Self::play_sound(fallback, cx);
+        };
+        cx.spawn(async move |cx| {
+            let played = new_smol_command(program)
+                .args(args)
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status()
+                .await
+                .is_ok_and(|status| status.success());
+            if !played {
+                log::debug!("failed to play the system notification sound with {program}");
+                cx.update(|cx| Self::play_sound(fallback, cx)).ok();
+            }
+        })
+        .detach();
+    }
+
     pub fn end_call(cx: &mut App) {
         if !cx.has_global::<GlobalAudio>() {
             return;
@@ -85,3 +107,23 @@ impl Audio {
         });
     }
 }
+
+/// The sound files these play are only decodable by the platform's own player.
+fn system_sound_command() -> Option<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "macos") {
+        Some(("afplay", &["/System/Library/Sounds/Glass.aiff"]))
+    } else if cfg!(any(target_os = "linux", target_os = "freebsd")) {
+        Some(("canberra-gtk-play", &["--id", "complete"]))
+    } else if cfg!(target_os = "windows") {
+        Some((
+            "powershell",
+            &[
+                "-NoProfile",
+                "-Command",
+                "(New-Object Media.SoundPlayer \"$env:WINDIR\\Media\\Windows Notify System Generic.wav\").PlaySync()",
+            ],
+        ))
+    } else {
+        None
+    }
+}
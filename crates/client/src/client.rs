@@ -30,7 +30,7 @@ use release_channel::{AppVersion, ReleaseChannel};
 use rpc::proto::{AnyTypedEnvelope, EnvelopedMessage, PeerId, RequestMessage};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use settings::{Settings, SettingsSources};
+use settings::{Settings, SettingsSources, SettingsStore};
 use std::pin::Pin;
 use std::{
     any::TypeId,
@@ -41,7 +41,7 @@ use std::{
     path::PathBuf,
     sync::{
         Arc, LazyLock, Weak,
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
     },
     time::{Duration, Instant},
 };
@@ -152,8 +152,13 @@ impl Settings for ProxySettings {
 pub fn init_settings(cx: &mut App) {
     TelemetrySettings::register(cx);
     DisableAiSettings::register(cx);
+    DisableCloudFeaturesSettings::register(cx);
     ClientSettings::register(cx);
     ProxySettings::register(cx);
+
+    DisableCloudFeaturesSettings::update_flag(cx);
+    cx.observe_global::<SettingsStore>(DisableCloudFeaturesSettings::update_flag)
+        .detach();
 }
 
 pub fn init(client: &Arc<Client>, cx: &mut App) {
@@ -576,6 +581,57 @@ impl settings::Settings for DisableAiSettings {
     fn import_from_vscode(_vscode: &settings::VsCodeSettings, _current: &mut Self::FileContent) {}
 }
 
+/// Mirrors [`DisableCloudFeaturesSettings`] for code that sends requests
+/// without access to the app, such as embedding providers.
+static CLOUD_FEATURES_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Shown instead of a result when a cloud feature is used while
+/// `disable_cloud_features` is on.
+pub const CLOUD_FEATURES_DISABLED_MESSAGE: &str =
+    "Cloud features are disabled by the `disable_cloud_features` setting.";
+
+/// Whether to keep every LLM-backed feature (search, embeddings and task sync)
+/// from making network requests.
+///
+/// Default: false
+#[derive(Copy, Clone, Debug)]
+pub struct DisableCloudFeaturesSettings {
+    pub disable_cloud_features: bool,
+}
+
+impl DisableCloudFeaturesSettings {
+    /// Whether cloud features are disabled. Can be called from any thread.
+    pub fn is_disabled() -> bool {
+        CLOUD_FEATURES_DISABLED.load(Ordering::Relaxed)
+    }
+
+    fn update_flag(cx: &mut App) {
+        CLOUD_FEATURES_DISABLED.store(
+            Self::get_global(cx).disable_cloud_features,
+            Ordering::Relaxed,
+        );
+    }
+}
+
+impl settings::Settings for DisableCloudFeaturesSettings {
+    const KEY: Option<&'static str> = Some("disable_cloud_features");
+
+    type FileContent = Option<bool>;
+
+    fn load(sources: SettingsSources<Self::FileContent>, _: &mut App) -> Result<Self> {
+        Ok(Self {
+            disable_cloud_features: sources
+                .user
+                .or(sources.server)
+                .copied()
+                .flatten()
+                .unwrap_or(sources.default.ok_or_else(Self::missing_default)?),
+        })
+    }
+
+    fn import_from_vscode(_vscode: &settings::VsCodeSettings, _current: &mut Self::FileContent) {}
+}
+
 impl Client {
     pub fn new(
         clock: Arc<dyn SystemClock>,
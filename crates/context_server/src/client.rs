@@ -3,7 +3,7 @@ use collections::HashMap;
 use futures::{FutureExt, StreamExt, channel::oneshot, future, select};
 use gpui::{AppContext as _, AsyncApp, BackgroundExecutor, Task};
 use parking_lot::Mutex;
-use postage::barrier;
+use postage::{barrier, prelude::Stream as _};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::{Value, value::RawValue};
 use smol::channel;
@@ -57,6 +57,7 @@ pub(crate) struct Client {
     io_tasks: Mutex<Option<(Task<Option<()>>, Task<Option<()>>)>>,
     #[allow(dead_code)]
     output_done_rx: Mutex<Option<barrier::Receiver>>,
+    input_done_rx: barrier::Receiver,
     executor: BackgroundExecutor,
     #[allow(dead_code)]
     transport: Arc<dyn Transport>,
@@ -185,6 +186,7 @@ impl Client {
     ) -> Result<Self> {
         let (outbound_tx, outbound_rx) = channel::unbounded::<String>();
         let (output_done_tx, output_done_rx) = barrier::channel();
+        let (input_done_tx, input_done_rx) = barrier::channel();
 
         let notification_handlers =
             Arc::new(Mutex::new(HashMap::<_, NotificationHandler>::default()));
@@ -215,6 +217,7 @@ impl Client {
         });
         let input_task = cx.spawn(async move |_| {
             let (input, err) = futures::join!(receive_input_task, receive_err_task);
+            drop(input_done_tx);
             input.or(err)
         });
 
@@ -239,10 +242,20 @@ impl Client {
             executor: cx.background_executor().clone(),
             io_tasks: Mutex::new(Some((input_task, output_task))),
             output_done_rx: Mutex::new(Some(output_done_rx)),
+            input_done_rx,
             transport,
         })
     }
 
+    /// Resolves once the server stops sending messages, e.g. because its
+    /// process exited.
+    pub fn closed(&self) -> impl Future<Output = ()> + use<> {
+        let mut input_done_rx = self.input_done_rx.clone();
+        async move {
+            input_done_rx.recv().await;
+        }
+    }
+
     /// Handles input from the server's stdout.
     ///
     /// This function continuously reads lines from the provided stdout stream,
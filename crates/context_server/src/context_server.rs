@@ -128,6 +128,18 @@ impl ContextServer {
         Ok(())
     }
 
+    /// Resolves once the connection to a started server closes, e.g. because
+    /// its process exited. Never resolves if the server isn't running.
+    pub fn closed(&self) -> impl Future<Output = ()> + use<> {
+        let closed = self.client().map(|client| client.closed());
+        async move {
+            match closed {
+                Some(closed) => closed.await,
+                None => futures::future::pending().await,
+            }
+        }
+    }
+
     pub fn stop(&self) -> Result<()> {
         let mut client = self.client.write();
         if let Some(protocol) = client.take() {
@@ -83,6 +83,11 @@ pub enum ServerCapability {
 }
 
 impl InitializedContextServerProtocol {
+    /// Resolves once the server's connection closes.
+    pub fn closed(&self) -> impl Future<Output = ()> + use<> {
+        self.inner.closed()
+    }
+
     /// Check if the server supports a specific capability
     pub fn capable(&self, capability: ServerCapability) -> bool {
         match capability {
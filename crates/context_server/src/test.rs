@@ -50,6 +50,11 @@ impl FakeTransport {
         }
     }
 
+    /// Closes the connection, as if the server's process had exited.
+    pub fn kill(&self) {
+        self.tx.close_channel();
+    }
+
     pub fn on_request<T: crate::types::Request>(
         mut self,
         handler: impl Fn(T::Params) -> T::Response + Send + Sync + 'static,
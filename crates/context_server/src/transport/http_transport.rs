@@ -0,0 +1,277 @@
+use std::pin::Pin;
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result, anyhow};
+use async_trait::async_trait;
+use collections::HashMap;
+use futures::io::BufReader;
+use futures::{AsyncBufReadExt as _, AsyncReadExt as _, Stream};
+use gpui::BackgroundExecutor;
+use http_client::{AsyncBody, HttpClient, Method, Request, Response};
+use parking_lot::Mutex;
+use smol::channel;
+use util::ResultExt as _;
+
+use crate::transport::Transport;
+
+const SESSION_ID_HEADER: &str = "Mcp-Session-Id";
+
+/// Talks to a remote context server over the MCP streamable HTTP transport.
+///
+/// Every message is POSTed to the server's URL, which answers with either a
+/// single JSON message or an SSE stream of them.
+pub struct HttpTransport {
+    http_client: Arc<dyn HttpClient>,
+    url: String,
+    headers: HashMap<String, String>,
+    session_id: Mutex<Option<String>>,
+    executor: BackgroundExecutor,
+    response_sender: channel::Sender<String>,
+    response_receiver: channel::Receiver<String>,
+    error_sender: channel::Sender<String>,
+    error_receiver: channel::Receiver<String>,
+}
+
+impl HttpTransport {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        url: String,
+        headers: HashMap<String, String>,
+        executor: BackgroundExecutor,
+    ) -> Self {
+        let (response_sender, response_receiver) = channel::unbounded();
+        let (error_sender, error_receiver) = channel::unbounded();
+        Self {
+            http_client,
+            url,
+            headers,
+            session_id: Mutex::new(None),
+            executor,
+            response_sender,
+            response_receiver,
+            error_sender,
+            error_receiver,
+        }
+    }
+
+    fn build_request(&self, message: String) -> Result<Request<AsyncBody>> {
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.url)
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json, text/event-stream");
+        for (name, value) in &self.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(session_id) = self.session_id.lock().as_ref() {
+            request = request.header(SESSION_ID_HEADER, session_id.as_str());
+        }
+        Ok(request.body(AsyncBody::from(message))?)
+    }
+
+    async fn forward_response(
+        response: Response<AsyncBody>,
+        response_sender: channel::Sender<String>,
+    ) -> Result<()> {
+        let is_event_stream = response
+            .headers()
+            .get("Content-Type")
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with("text/event-stream"));
+
+        if is_event_stream {
+            let mut reader = BufReader::new(response.into_body());
+            let mut line = String::new();
+            let mut data = String::new();
+            while reader.read_line(&mut line).await? > 0 {
+                if let Some(message) = push_sse_line(&line, &mut data) {
+                    response_sender.send(message).await?;
+                }
+                line.clear();
+            }
+            if !data.is_empty() {
+                response_sender.send(data).await?;
+            }
+        } else {
+            let mut body = String::new();
+            response.into_body().read_to_string(&mut body).await?;
+            if !body.trim().is_empty() {
+                response_sender.send(body).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds a line of an SSE stream to the data of the event being read, returning
+/// that data once the blank line ending the event is reached.
+fn push_sse_line(line: &str, data: &mut String) -> Option<String> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.is_empty() {
+        return (!data.is_empty()).then(|| std::mem::take(data));
+    }
+    if let Some(value) = line.strip_prefix("data:") {
+        if !data.is_empty() {
+            data.push('\n');
+        }
+        data.push_str(value.strip_prefix(' ').unwrap_or(value));
+    }
+    None
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn send(&self, message: String) -> Result<()> {
+        log::trace!("outgoing message: {}", message);
+        let request = self.build_request(message)?;
+        let response = self
+            .http_client
+            .send(request)
+            .await
+            .with_context(|| format!("failed to reach context server at {}", self.url))?;
+
+        if let Some(session_id) = response
+            .headers()
+            .get(SESSION_ID_HEADER)
+            .and_then(|session_id| session_id.to_str().ok())
+        {
+            *self.session_id.lock() = Some(session_id.to_string());
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            let mut body = String::new();
+            response
+                .into_body()
+                .read_to_string(&mut body)
+                .await
+                .log_err();
+            return Err(anyhow!(
+                "context server at {} responded with {status}: {body}",
+                self.url
+            ));
+        }
+
+        // Streamed responses can stay open for as long as the server has
+        // messages to send, so they're read without holding up this send.
+        let response_sender = self.response_sender.clone();
+        let error_sender = self.error_sender.clone();
+        self.executor
+            .spawn(async move {
+                if let Err(error) = Self::forward_response(response, response_sender).await {
+                    error_sender.send(error.to_string()).await.ok();
+                }
+            })
+            .detach();
+        Ok(())
+    }
+
+    fn receive(&self) -> Pin<Box<dyn Stream<Item = String> + Send>> {
+        Box::pin(self.response_receiver.clone())
+    }
+
+    fn receive_err(&self) -> Pin<Box<dyn Stream<Item = String> + Send>> {
+        Box::pin(self.error_receiver.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::{FutureExt as _, StreamExt as _};
+    use gpui::TestAppContext;
+    use http_client::FakeHttpClient;
+
+    use super::*;
+
+    #[gpui::test]
+    async fn test_http_transport(cx: &mut TestAppContext) {
+        let request_count = Arc::new(AtomicUsize::new(0));
+        let session_ids = Arc::new(Mutex::new(Vec::new()));
+        let http_client = FakeHttpClient::create({
+            let request_count = request_count.clone();
+            let session_ids = session_ids.clone();
+            move |request| {
+                session_ids.lock().push(
+                    request
+                        .headers()
+                        .get(SESSION_ID_HEADER)
+                        .and_then(|session_id| session_id.to_str().ok())
+                        .map(str::to_string),
+                );
+                let response = match request_count.fetch_add(1, Ordering::SeqCst) {
+                    0 => Response::builder()
+                        .header("Content-Type", "text/event-stream")
+                        .header(SESSION_ID_HEADER, "session-1")
+                        .body(AsyncBody::from("data: {\"id\":1}\n\ndata: {\"id\":2}\n\n")),
+                    1 => Response::builder()
+                        .header("Content-Type", "application/json")
+                        .body(AsyncBody::from("{\"id\":3}")),
+                    _ => Response::builder()
+                        .status(500)
+                        .body(AsyncBody::from("overloaded")),
+                };
+                async move { Ok(response.unwrap()) }
+            }
+        });
+        let transport = HttpTransport::new(
+            http_client,
+            "http://test.example/mcp".into(),
+            HashMap::default(),
+            cx.executor(),
+        );
+        let mut responses = transport.receive();
+        let mut errors = transport.receive_err();
+
+        transport.send("{\"id\":1}".into()).await.unwrap();
+        cx.run_until_parked();
+        assert_eq!(responses.next().await.unwrap(), "{\"id\":1}");
+        assert_eq!(responses.next().await.unwrap(), "{\"id\":2}");
+
+        transport.send("{\"id\":3}".into()).await.unwrap();
+        cx.run_until_parked();
+        assert_eq!(responses.next().await.unwrap(), "{\"id\":3}");
+
+        let error = transport
+            .send("{\"id\":4}".into())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("500"), "unexpected error: {error}");
+        assert!(error.contains("overloaded"), "unexpected error: {error}");
+        cx.run_until_parked();
+        assert!(responses.next().now_or_never().is_none());
+        assert!(errors.next().now_or_never().is_none());
+
+        assert_eq!(
+            *session_ids.lock(),
+            [
+                None,
+                Some("session-1".to_string()),
+                Some("session-1".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_push_sse_line() {
+        let mut data = String::new();
+        let lines = [
+            "event: message\n",
+            "data: {\"a\":\n",
+            "data: 1}\n",
+            "\n",
+            ": keep-alive\r\n",
+            "\r\n",
+            "data:{\"b\":2}\r\n",
+            "\r\n",
+        ];
+        let messages = lines
+            .into_iter()
+            .filter_map(|line| push_sse_line(line, &mut data))
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["{\"a\":\n1}", "{\"b\":2}"]);
+        assert!(data.is_empty());
+    }
+}
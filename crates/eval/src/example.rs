@@ -295,6 +295,7 @@ impl ExampleContext {
                 | ThreadEvent::SummaryChanged
                 | ThreadEvent::SummaryGenerated
                 | ThreadEvent::ProfileChanged
+                | ThreadEvent::PinnedSnippetsChanged
                 | ThreadEvent::ReceivedTextChunk
                 | ThreadEvent::StreamedToolUse { .. }
                 | ThreadEvent::CheckpointChanged
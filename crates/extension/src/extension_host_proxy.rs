@@ -30,6 +30,7 @@ pub struct ExtensionHostProxy {
     context_server_proxy: RwLock<Option<Arc<dyn ExtensionContextServerProxy>>>,
     indexed_docs_provider_proxy: RwLock<Option<Arc<dyn ExtensionIndexedDocsProviderProxy>>>,
     debug_adapter_provider_proxy: RwLock<Option<Arc<dyn ExtensionDebugAdapterProviderProxy>>>,
+    task_context_proxy: RwLock<Option<Arc<dyn ExtensionTaskContextProxy>>>,
 }
 
 impl ExtensionHostProxy {
@@ -56,6 +57,7 @@ impl ExtensionHostProxy {
             context_server_proxy: RwLock::default(),
             indexed_docs_provider_proxy: RwLock::default(),
             debug_adapter_provider_proxy: RwLock::default(),
+            task_context_proxy: RwLock::default(),
         }
     }
 
@@ -100,6 +102,10 @@ impl ExtensionHostProxy {
             .write()
             .replace(Arc::new(proxy));
     }
+
+    pub fn register_task_context_proxy(&self, proxy: impl ExtensionTaskContextProxy) {
+        self.task_context_proxy.write().replace(Arc::new(proxy));
+    }
 }
 
 pub trait ExtensionThemeProxy: Send + Sync + 'static {
@@ -480,3 +486,41 @@ impl ExtensionDebugAdapterProviderProxy for ExtensionHostProxy {
         proxy.unregister_debug_locator(locator_name)
     }
 }
+
+/// The task the user synced from Oppla, as exposed to extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncedTaskContext {
+    pub board_id: String,
+    pub board_name: Option<String>,
+    pub task_id: Option<String>,
+    pub task_name: Option<String>,
+}
+
+pub trait ExtensionTaskContextProxy: Send + Sync + 'static {
+    /// Returns the task synced in the project containing the given worktree,
+    /// or in the active project, once the user has allowed the extension to
+    /// read it.
+    fn synced_task_context(
+        &self,
+        extension_id: Arc<str>,
+        extension_name: SharedString,
+        worktree_id: Option<u64>,
+        cx: &mut App,
+    ) -> Task<Result<Option<SyncedTaskContext>>>;
+}
+
+impl ExtensionTaskContextProxy for ExtensionHostProxy {
+    fn synced_task_context(
+        &self,
+        extension_id: Arc<str>,
+        extension_name: SharedString,
+        worktree_id: Option<u64>,
+        cx: &mut App,
+    ) -> Task<Result<Option<SyncedTaskContext>>> {
+        let Some(proxy) = self.task_context_proxy.read().clone() else {
+            return Task::ready(Ok(None));
+        };
+
+        proxy.synced_task_context(extension_id, extension_name, worktree_id, cx)
+    }
+}
@@ -131,6 +131,17 @@ impl ExtensionManifest {
         Ok(())
     }
 
+    pub fn allow_task_context_read(&self) -> Result<()> {
+        if !self
+            .capabilities
+            .contains(&ExtensionCapability::TaskContextRead)
+        {
+            bail!("capability for task-context:read was not listed in the extension manifest");
+        }
+
+        Ok(())
+    }
+
     pub fn allow_remote_load(&self) -> bool {
         !self.language_servers.is_empty()
             || !self.debug_adapters.is_empty()
@@ -160,6 +171,9 @@ pub enum ExtensionCapability {
         /// If the last element is `**`, then any trailing arguments are allowed.
         args: Vec<String>,
     },
+    /// Read access to the task the user synced from Oppla.
+    #[serde(rename = "task-context:read")]
+    TaskContextRead,
 }
 
 #[derive(Clone, Default, PartialEq, Eq, Debug, Deserialize, Serialize)]
@@ -374,6 +388,18 @@ mod tests {
         assert!(manifest.allow_exec("pwd", &[] as &[&str]).is_err());
     }
 
+    #[test]
+    fn test_allow_task_context_read() {
+        let manifest = extension_manifest();
+        assert!(manifest.allow_task_context_read().is_err());
+
+        let manifest = ExtensionManifest {
+            capabilities: vec![ExtensionCapability::TaskContextRead],
+            ..extension_manifest()
+        };
+        assert!(manifest.allow_task_context_read().is_ok());
+    }
+
     #[test]
     fn test_allow_wildcard_arg() {
         let manifest = ExtensionManifest {
@@ -1,6 +1,6 @@
 //! Provides access to Zed settings.
 
-#[path = "../wit/since_v0.2.0/settings.rs"]
+#[path = "../wit/since_v0.7.0/settings.rs"]
 mod types;
 
 use crate::{Project, Result, SettingsLocation, Worktree, wit};
@@ -41,6 +41,16 @@ impl ContextServerSettings {
     }
 }
 
+impl TaskContext {
+    /// Returns the task the user synced from Oppla, if any.
+    ///
+    /// Requires the `task-context:read` capability in the extension manifest.
+    /// The user is asked to allow the extension the first time it reads the task.
+    pub fn current() -> Result<Option<Self>> {
+        get_settings("task_context", None, None)
+    }
+}
+
 fn get_settings<T: serde::de::DeserializeOwned>(
     settings_type: &str,
     settings_name: Option<&str>,
@@ -97,6 +97,12 @@ impl FeatureFlag for JjUiFeatureFlag {
     const NAME: &'static str = "jj-ui";
 }
 
+pub struct LlmTokenDebugFeatureFlag;
+
+impl FeatureFlag for LlmTokenDebugFeatureFlag {
+    const NAME: &'static str = "llm-token-debug";
+}
+
 pub struct AcpFeatureFlag;
 
 impl FeatureFlag for AcpFeatureFlag {
@@ -1530,6 +1530,12 @@ impl App {
         self.platform.add_recent_document(path);
     }
 
+    /// Asks the platform's screen reader to read out the given message, for changes
+    /// that happen outside of the focused element. Only supported on macOS for now.
+    pub fn announce(&self, message: &str) {
+        self.platform.announce(message);
+    }
+
     /// Updates the jump list with the updated list of recent paths for the application, only used on Windows for now.
     /// Note that this also sets the dock menu on Windows.
     pub fn update_jump_list(
@@ -153,6 +153,11 @@ impl TestAppContext {
         self.fn_name
     }
 
+    /// Returns the messages announced to the screen reader since the last call.
+    pub fn take_announcements(&self) -> Vec<String> {
+        self.test_platform.announcements.take()
+    }
+
     /// Checks whether there have been any new path prompts received by the platform.
     pub fn did_prompt_for_new_path(&self) -> bool {
         self.test_platform.did_prompt_for_new_path()
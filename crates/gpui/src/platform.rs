@@ -238,6 +238,7 @@ pub(crate) trait Platform: 'static {
     fn set_dock_menu(&self, menu: Vec<MenuItem>, keymap: &Keymap);
     fn perform_dock_menu_action(&self, _action: usize) {}
     fn add_recent_document(&self, _path: &Path) {}
+    fn announce(&self, _message: &str) {}
     fn update_jump_list(
         &self,
         _menus: Vec<MenuItem>,
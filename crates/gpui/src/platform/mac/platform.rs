@@ -901,6 +901,25 @@ impl Platform for MacPlatform {
         }
     }
 
+    fn announce(&self, message: &str) {
+        unsafe {
+            let app: id = msg_send![APP_CLASS, sharedApplication];
+            let message = ns_string(message);
+            let priority: id =
+                msg_send![class!(NSNumber), numberWithInteger: NS_ACCESSIBILITY_PRIORITY_HIGH];
+            let user_info: id = msg_send![class!(NSMutableDictionary), dictionary];
+            let key = NSAccessibilityAnnouncementKey;
+            let _: () = msg_send![user_info, setObject: message forKey: key];
+            let key = NSAccessibilityPriorityKey;
+            let _: () = msg_send![user_info, setObject: priority forKey: key];
+            NSAccessibilityPostNotificationWithUserInfo(
+                app,
+                NSAccessibilityAnnouncementRequestedNotification,
+                user_info,
+            );
+        }
+    }
+
     fn path_for_auxiliary_executable(&self, name: &str) -> Result<PathBuf> {
         unsafe {
             let bundle: id = NSBundle::mainBundle();
@@ -1478,6 +1497,18 @@ unsafe fn ns_url_to_path(url: id) -> Result<PathBuf> {
     })))
 }
 
+/// `NSAccessibilityPriorityHigh`, so that announcements interrupt other speech.
+const NS_ACCESSIBILITY_PRIORITY_HIGH: NSInteger = 90;
+
+#[link(name = "AppKit", kind = "framework")]
+unsafe extern "C" {
+    static NSAccessibilityAnnouncementRequestedNotification: id;
+    static NSAccessibilityAnnouncementKey: id;
+    static NSAccessibilityPriorityKey: id;
+
+    fn NSAccessibilityPostNotificationWithUserInfo(element: id, notification: id, user_info: id);
+}
+
 #[link(name = "Carbon", kind = "framework")]
 unsafe extern "C" {
     pub(super) fn TISCopyCurrentKeyboardLayoutInputSource() -> *mut Object;
@@ -14,6 +14,7 @@ use std::sync::Arc;
 pub struct FakeLanguageModelProvider {
     id: LanguageModelProviderId,
     name: LanguageModelProviderName,
+    model_ids: Vec<LanguageModelId>,
 }
 
 impl Default for FakeLanguageModelProvider {
@@ -21,6 +22,7 @@ impl Default for FakeLanguageModelProvider {
         Self {
             id: LanguageModelProviderId::from("fake".to_string()),
             name: LanguageModelProviderName::from("Fake".to_string()),
+            model_ids: vec![LanguageModelId::from("fake".to_string())],
         }
     }
 }
@@ -51,7 +53,15 @@ impl LanguageModelProvider for FakeLanguageModelProvider {
     }
 
     fn provided_models(&self, _: &App) -> Vec<Arc<dyn LanguageModel>> {
-        vec![Arc::new(FakeLanguageModel::default())]
+        self.model_ids
+            .iter()
+            .map(|id| {
+                Arc::new(FakeLanguageModel {
+                    id: id.clone(),
+                    ..FakeLanguageModel::default()
+                }) as Arc<dyn LanguageModel>
+            })
+            .collect()
     }
 
     fn is_authenticated(&self, _: &App) -> bool {
@@ -73,7 +83,21 @@ impl LanguageModelProvider for FakeLanguageModelProvider {
 
 impl FakeLanguageModelProvider {
     pub fn new(id: LanguageModelProviderId, name: LanguageModelProviderName) -> Self {
-        Self { id, name }
+        Self {
+            id,
+            name,
+            ..Self::default()
+        }
+    }
+
+    /// Makes the provider offer models with these ids, rather than just its
+    /// default model.
+    pub fn with_model_ids<'a>(mut self, model_ids: impl IntoIterator<Item = &'a str>) -> Self {
+        self.model_ids = model_ids
+            .into_iter()
+            .map(|id| LanguageModelId::from(id.to_string()))
+            .collect();
+        self
     }
 
     pub fn test_model(&self) -> FakeLanguageModel {
@@ -90,6 +114,7 @@ pub struct ToolUseRequest {
 }
 
 pub struct FakeLanguageModel {
+    id: LanguageModelId,
     provider_id: LanguageModelProviderId,
     provider_name: LanguageModelProviderName,
     current_completion_txs: Mutex<Vec<(LanguageModelRequest, mpsc::UnboundedSender<String>)>>,
@@ -98,6 +123,7 @@ pub struct FakeLanguageModel {
 impl Default for FakeLanguageModel {
     fn default() -> Self {
         Self {
+            id: LanguageModelId::from("fake".to_string()),
             provider_id: LanguageModelProviderId::from("fake".to_string()),
             provider_name: LanguageModelProviderName::from("Fake".to_string()),
             current_completion_txs: Mutex::new(Vec::new()),
@@ -149,7 +175,7 @@ impl FakeLanguageModel {
 
 impl LanguageModel for FakeLanguageModel {
     fn id(&self) -> LanguageModelId {
-        LanguageModelId::from("fake".to_string())
+        self.id.clone()
     }
 
     fn name(&self) -> LanguageModelName {
@@ -629,6 +629,11 @@ pub trait LanguageModelProvider: 'static {
     fn default_model(&self, cx: &App) -> Option<Arc<dyn LanguageModel>>;
     fn default_fast_model(&self, cx: &App) -> Option<Arc<dyn LanguageModel>>;
     fn provided_models(&self, cx: &App) -> Vec<Arc<dyn LanguageModel>>;
+    /// Whether the provider is still fetching the models it offers, so that
+    /// having none yet doesn't mean it has none.
+    fn is_loading_models(&self, _cx: &App) -> bool {
+        false
+    }
     fn recommended_models(&self, _cx: &App) -> Vec<Arc<dyn LanguageModel>> {
         Vec::new()
     }
@@ -646,6 +651,12 @@ pub trait LanguageModelProvider: 'static {
         None
     }
     fn reset_credentials(&self, cx: &mut App) -> Task<Result<()>>;
+    /// Checks with the provider that the stored credentials still work, e.g.
+    /// that an API key hasn't been revoked since it was entered. Providers
+    /// that can't check this cheaply report success.
+    fn validate_credentials(&self, _cx: &mut App) -> Task<Result<()>> {
+        Task::ready(Ok(()))
+    }
     fn can_select_model(&self, _cx: &App) -> bool {
         true // Default to true for non-Oppla providers
     }
@@ -1,11 +1,17 @@
+use std::borrow::Cow;
 use std::fmt;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anyhow::Result;
+use anyhow::{Context as _, Result};
+use base64::Engine as _;
 use client::Client;
 use gpui::{
     App, AppContext as _, AsyncApp, Context, Entity, EventEmitter, Global, ReadGlobal as _,
 };
+use http_client::{StatusCode, http::HeaderMap};
+use parking_lot::Mutex;
 use proto::{Plan, TypedEnvelope};
 use smol::lock::{RwLock, RwLockUpgradableReadGuard, RwLockWriteGuard};
 use thiserror::Error;
@@ -47,6 +53,13 @@ impl fmt::Display for ModelRequestLimitReachedError {
 pub struct LlmApiToken(Arc<RwLock<Option<String>>>);
 
 impl LlmApiToken {
+    /// A token that's already been fetched, so that requests can be tested
+    /// without a server to fetch it from.
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn fake(token: &str) -> Self {
+        Self(Arc::new(RwLock::new(Some(token.to_string()))))
+    }
+
     pub async fn acquire(&self, client: &Arc<Client>) -> Result<String> {
         let lock = self.0.upgradable_read().await;
         if let Some(token) = lock.as_ref() {
@@ -65,11 +78,32 @@ impl LlmApiToken {
         client: &Arc<Client>,
     ) -> Result<String> {
         let response = client.request(proto::GetLlmToken {}).await?;
+        LLM_TOKEN_EXPIRES_AT.store(
+            token_expires_at(&response.token).unwrap_or(0),
+            Ordering::Relaxed,
+        );
         *lock = Some(response.token.clone());
         Ok(response.token.clone())
     }
 }
 
+// Tokens are fetched from background tasks all over the app, so the expiry of
+// the latest one is kept here for the UI to show. In Unix seconds, or 0 if
+// unknown.
+static LLM_TOKEN_EXPIRES_AT: AtomicU64 = AtomicU64::new(0);
+
+/// When the most recently fetched LLM API token expires, if it has an expiry.
+pub fn llm_token_expires_at() -> Option<SystemTime> {
+    match LLM_TOKEN_EXPIRES_AT.load(Ordering::Relaxed) {
+        0 => None,
+        expires_at => Some(UNIX_EPOCH + Duration::from_secs(expires_at)),
+    }
+}
+
+fn token_expires_at(token: &str) -> Option<u64> {
+    llm_token_metadata(token).ok()?.get("exp")?.as_u64()
+}
+
 struct GlobalRefreshLlmTokenListener(Entity<RefreshLlmTokenListener>);
 
 impl Global for GlobalRefreshLlmTokenListener {}
@@ -107,3 +141,298 @@ impl RefreshLlmTokenListener {
         this.update(&mut cx, |_this, cx| cx.emit(RefreshLlmTokenEvent))
     }
 }
+
+const REDACTED: &str = "[REDACTED]";
+
+// Requests to the LLM-backed endpoints are sent from background tasks that
+// can't read settings, so the setting is mirrored here.
+static LOG_LLM_REQUESTS: AtomicBool = AtomicBool::new(false);
+
+/// The URL and redacted body of the last logged request, kept so that it can
+/// be reproduced outside the app with [`last_llm_request_as_curl`].
+static LAST_LLM_REQUEST: Mutex<Option<(String, String)>> = Mutex::new(None);
+
+/// Enables logging of the raw requests and responses sent to Oppla's
+/// LLM-backed endpoints, such as search and embeddings.
+pub fn set_llm_request_logging(enabled: bool) {
+    LOG_LLM_REQUESTS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn log_llm_request(url: &str, token: &str, body: &str) {
+    if LOG_LLM_REQUESTS.load(Ordering::Relaxed) {
+        let body = redact_token(body, token);
+        log::info!("LLM request: POST {url} (Authorization: Bearer {REDACTED})\n{body}");
+        *LAST_LLM_REQUEST.lock() = Some((url.to_string(), body.into_owned()));
+    }
+}
+
+/// The last logged request as a `curl` command, with the bearer token left
+/// to a `$TOKEN` shell variable. Only requests sent while request logging is
+/// enabled are recorded.
+pub fn last_llm_request_as_curl() -> Option<String> {
+    let (url, body) = LAST_LLM_REQUEST.lock().clone()?;
+    Some(curl_command(&url, &body))
+}
+
+fn curl_command(url: &str, body: &str) -> String {
+    [
+        format!("curl -X POST {}", shell_quote(url)),
+        "-H 'Content-Type: application/json'".to_string(),
+        "-H \"Authorization: Bearer $TOKEN\"".to_string(),
+        format!("--data-raw {}", shell_quote(body)),
+    ]
+    .join(" \\\n  ")
+}
+
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r#"'\''"#))
+}
+
+pub fn log_llm_response(url: &str, token: &str, status: impl fmt::Display, body: &str) {
+    if LOG_LLM_REQUESTS.load(Ordering::Relaxed) {
+        log::info!(
+            "LLM response: {status} from {url}\n{}",
+            redact_token(body, token)
+        );
+    }
+}
+
+fn redact_token<'a>(text: &'a str, token: &str) -> Cow<'a, str> {
+    if token.is_empty() || !text.contains(token) {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(text.replace(token, REDACTED))
+    }
+}
+
+/// An error from one of Oppla's LLM-backed HTTP endpoints, such as search and
+/// embeddings, classified so that callers can decide whether to retry, refresh
+/// the token or surface it.
+#[derive(Error, Debug)]
+pub enum LlmApiError {
+    #[error("request was not authorized ({status}): {message}")]
+    Unauthorized { status: StatusCode, message: String },
+    #[error("rate limit exceeded")]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("request was too large ({status}): {message}")]
+    PayloadTooLarge { status: StatusCode, message: String },
+    #[error("request was rejected ({status}): {message}")]
+    Rejected { status: StatusCode, message: String },
+    #[error("server error ({status}): {message}")]
+    Server { status: StatusCode, message: String },
+    #[error("network error")]
+    Network(#[source] anyhow::Error),
+    #[error("failed to decode response")]
+    Decode(#[source] serde_json::Error),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl LlmApiError {
+    /// Classifies an unsuccessful response from its status code.
+    pub fn from_response(status: StatusCode, headers: &HeaderMap, body: String) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Self::Unauthorized {
+                status,
+                message: body,
+            },
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited {
+                retry_after: headers
+                    .get("retry-after")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.trim().parse::<u64>().ok())
+                    .map(Duration::from_secs),
+            },
+            StatusCode::PAYLOAD_TOO_LARGE
+            | StatusCode::URI_TOO_LONG
+            | StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE => Self::PayloadTooLarge {
+                status,
+                message: body,
+            },
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY
+                if mentions_input_size(&body) =>
+            {
+                Self::PayloadTooLarge {
+                    status,
+                    message: body,
+                }
+            }
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Self::Rejected {
+                status,
+                message: body,
+            },
+            status if status.is_server_error() => Self::Server {
+                status,
+                message: body,
+            },
+            status => Self::Other(anyhow::anyhow!(
+                "request failed with status {status}: {body}"
+            )),
+        }
+    }
+
+    /// Whether sending the same request again may succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited { .. } | Self::Server { .. } | Self::Network(_)
+        )
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+/// Some backends reject oversized inputs with a generic client error, naming
+/// the problem only in the body.
+fn mentions_input_size(body: &str) -> bool {
+    let body = body.to_lowercase();
+    [
+        "too large",
+        "too long",
+        "too many tokens",
+        "maximum context length",
+    ]
+    .iter()
+    .any(|phrase| body.contains(phrase))
+}
+
+/// The only claims [`llm_token_metadata`] copies out of a token.
+const DEBUG_TOKEN_CLAIMS: [&str; 6] = ["iss", "sub", "aud", "iat", "nbf", "exp"];
+
+/// Decodes the registered claims of an LLM API token, such as its issuer,
+/// subject and expiry, to debug authentication failures. The signature and
+/// any other claims are left out.
+pub fn llm_token_metadata(token: &str) -> Result<serde_json::Value> {
+    let payload = token
+        .split('.')
+        .nth(1)
+        .context("LLM API token is not a JWT")?;
+    let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .context("failed to decode LLM API token claims")?;
+    let claims: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(&payload).context("failed to parse LLM API token claims")?;
+
+    let mut metadata = claims
+        .into_iter()
+        .filter(|(claim, _)| DEBUG_TOKEN_CLAIMS.contains(&claim.as_str()))
+        .collect::<serde_json::Map<_, _>>();
+    if let Some(expires_at) = metadata.get("exp").and_then(|exp| exp.as_i64()) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs() as i64);
+        metadata.insert("expires_in_seconds".into(), (expires_at - now).into());
+    }
+    Ok(metadata.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curl_command() {
+        assert_eq!(
+            curl_command("https://llm.oppla.ai/search", r#"{"query":"it's"}"#),
+            "curl -X POST 'https://llm.oppla.ai/search' \\\n  \
+             -H 'Content-Type: application/json' \\\n  \
+             -H \"Authorization: Bearer $TOKEN\" \\\n  \
+             --data-raw '{\"query\":\"it'\\''s\"}'"
+        );
+    }
+
+    #[test]
+    fn test_llm_api_error_from_response() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", "7".parse().unwrap());
+
+        let error = LlmApiError::from_response(StatusCode::TOO_MANY_REQUESTS, &headers, "".into());
+        assert!(error.is_retryable());
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(7)));
+
+        let error =
+            LlmApiError::from_response(StatusCode::UNAUTHORIZED, &HeaderMap::new(), "".into());
+        assert!(matches!(error, LlmApiError::Unauthorized { .. }));
+        assert!(!error.is_retryable());
+
+        let error = LlmApiError::from_response(
+            StatusCode::BAD_GATEWAY,
+            &HeaderMap::new(),
+            "upstream".into(),
+        );
+        assert!(matches!(error, LlmApiError::Server { .. }));
+        assert!(error.is_retryable());
+        assert_eq!(error.retry_after(), None);
+
+        let error =
+            LlmApiError::from_response(StatusCode::NOT_FOUND, &HeaderMap::new(), "missing".into());
+        assert!(matches!(error, LlmApiError::Other(_)));
+        assert!(error.to_string().contains("missing"));
+
+        let error = LlmApiError::from_response(
+            StatusCode::BAD_REQUEST,
+            &HeaderMap::new(),
+            "Input is too long for model".into(),
+        );
+        assert!(matches!(error, LlmApiError::PayloadTooLarge { .. }));
+        assert!(!error.is_retryable());
+
+        let error =
+            LlmApiError::from_response(StatusCode::URI_TOO_LONG, &HeaderMap::new(), String::new());
+        assert!(matches!(error, LlmApiError::PayloadTooLarge { .. }));
+
+        let error = LlmApiError::from_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            &HeaderMap::new(),
+            "unknown content type".into(),
+        );
+        assert!(matches!(error, LlmApiError::Rejected { .. }));
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn test_llm_token_metadata() {
+        let claims = serde_json::json!({
+            "iss": "oppla",
+            "sub": "user-1",
+            "exp": 4102444800i64,
+            "email": "someone@example.com",
+        });
+        let token = format!(
+            "{}.{}.signature",
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256"}"#),
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(claims.to_string()),
+        );
+
+        let metadata = llm_token_metadata(&token).unwrap();
+        assert_eq!(metadata["iss"], "oppla");
+        assert_eq!(metadata["sub"], "user-1");
+        assert_eq!(metadata["exp"], 4102444800i64);
+        assert!(metadata["expires_in_seconds"].as_i64().unwrap() > 0);
+        assert!(metadata.get("email").is_none());
+        assert!(!metadata.to_string().contains("signature"));
+
+        assert!(llm_token_metadata("not-a-token").is_err());
+
+        assert_eq!(token_expires_at(&token), Some(4102444800));
+        assert_eq!(token_expires_at("not-a-token"), None);
+    }
+
+    #[test]
+    fn test_redact_token() {
+        assert_eq!(
+            redact_token("{\"query\":\"auth\"}", "secret"),
+            "{\"query\":\"auth\"}"
+        );
+        assert_eq!(
+            redact_token("invalid token: secret", "secret"),
+            "invalid token: [REDACTED]"
+        );
+        assert_eq!(redact_token("body", ""), "body");
+    }
+}
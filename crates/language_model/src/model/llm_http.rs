@@ -0,0 +1,327 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context as _, anyhow};
+use client::{CLOUD_FEATURES_DISABLED_MESSAGE, Client, DisableCloudFeaturesSettings};
+use futures::{AsyncReadExt as _, FutureExt as _};
+use gpui::BackgroundExecutor;
+use http_client::{AsyncBody, HttpClient, HttpClientWithUrl, Method, Request, http::HeaderMap};
+use oppla_llm_client::EXPIRED_LLM_TOKEN_HEADER_NAME;
+use rand::Rng as _;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{LlmApiError, LlmApiToken, log_llm_request, log_llm_response};
+
+/// How a request that failed in a way that may succeed when sent again is
+/// retried.
+#[derive(Clone, Copy, Debug)]
+pub struct LlmRetryPolicy {
+    /// Including the first attempt.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl LlmRetryPolicy {
+    /// Sends the request once, leaving retries to the caller.
+    pub const NONE: Self = Self {
+        max_attempts: 1,
+        base_delay: Duration::ZERO,
+        max_delay: Duration::ZERO,
+    };
+
+    /// Honors the server's `Retry-After` header when present, otherwise backs
+    /// off exponentially, waiting no longer than `max_delay` either way.
+    /// Jitter keeps concurrent requests from retrying in lockstep.
+    fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let delay = retry_after
+            .unwrap_or_else(|| self.base_delay * 2u32.pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        let jitter = delay.mul_f64(rand::thread_rng().gen_range(0.0..0.5));
+        delay + jitter
+    }
+}
+
+impl Default for LlmRetryPolicy {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+#[derive(Default)]
+pub struct LlmPostOptions<'a> {
+    pub retry: LlmRetryPolicy,
+    /// How long each attempt may take before it fails with a network error.
+    pub timeout: Option<Duration>,
+    /// Called with the headers of every response, successful or not, such as
+    /// to track the rate limit an endpoint reports.
+    pub inspect_headers: Option<&'a (dyn Fn(&HeaderMap) + Send + Sync)>,
+}
+
+/// A successful response from one of Oppla's LLM-backed endpoints.
+pub struct LlmResponse {
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+/// Sends JSON requests to Oppla's LLM-backed endpoints, such as search and
+/// embeddings, taking care of the LLM API token, logging, retries and
+/// classifying failures as [`LlmApiError`]s.
+#[derive(Clone)]
+pub struct LlmHttp {
+    http_client: Arc<HttpClientWithUrl>,
+    client: Arc<Client>,
+    llm_api_token: LlmApiToken,
+    executor: BackgroundExecutor,
+}
+
+impl LlmHttp {
+    pub fn new(
+        http_client: Arc<HttpClientWithUrl>,
+        client: Arc<Client>,
+        llm_api_token: LlmApiToken,
+        executor: BackgroundExecutor,
+    ) -> Self {
+        Self {
+            http_client,
+            client,
+            llm_api_token,
+            executor,
+        }
+    }
+
+    pub fn executor(&self) -> &BackgroundExecutor {
+        &self.executor
+    }
+
+    /// POSTs `body` to `path` and deserializes the response.
+    pub async fn post_json<T: Serialize + ?Sized, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &T,
+        options: LlmPostOptions<'_>,
+    ) -> Result<R, LlmApiError> {
+        let response = self.post(path, body, options).await?;
+        serde_json::from_str(&response.body).map_err(LlmApiError::Decode)
+    }
+
+    /// POSTs `body` to `path`, returning the response if it was successful.
+    pub async fn post<T: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        body: &T,
+        options: LlmPostOptions<'_>,
+    ) -> Result<LlmResponse, LlmApiError> {
+        if DisableCloudFeaturesSettings::is_disabled() {
+            return Err(anyhow!(CLOUD_FEATURES_DISABLED_MESSAGE).into());
+        }
+
+        let url = self
+            .http_client
+            .build_zed_llm_url(path, &[])
+            .with_context(|| format!("Failed to build URL for {path}"))?;
+        let body = serde_json::to_string(body).context("Failed to serialize request")?;
+        let mut token = self
+            .llm_api_token
+            .acquire(&self.client)
+            .await
+            .context("Failed to acquire LLM API token")?;
+        log_llm_request(url.as_str(), &token, &body);
+
+        let mut attempt = 0;
+        let mut refreshed_token = false;
+        loop {
+            attempt += 1;
+
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri(url.as_str())
+                .header("Content-Type", "application/json")
+                .header("Authorization", format!("Bearer {token}"))
+                .body(AsyncBody::from(body.clone()))
+                .context("Failed to build HTTP request")?;
+            let response = match self.send(request, options.timeout).await {
+                Ok(response) => response,
+                Err(error) if attempt < options.retry.max_attempts => {
+                    let delay = options.retry.delay(attempt, None);
+                    log::warn!(
+                        "Request to {path} failed ({error:#}), retrying in {delay:?} (attempt {attempt}/{})",
+                        options.retry.max_attempts
+                    );
+                    self.executor.timer(delay).await;
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            if let Some(inspect_headers) = options.inspect_headers {
+                inspect_headers(&response.headers);
+            }
+            log_llm_response(url.as_str(), &token, response.status, &response.body);
+
+            if response.status.is_success() {
+                return Ok(LlmResponse {
+                    headers: response.headers,
+                    body: response.body,
+                });
+            }
+
+            // An expired token is refreshed once, without using up an attempt.
+            if !refreshed_token && response.headers.contains_key(EXPIRED_LLM_TOKEN_HEADER_NAME) {
+                token = self
+                    .llm_api_token
+                    .refresh(&self.client)
+                    .await
+                    .context("Failed to refresh LLM API token")?;
+                refreshed_token = true;
+                attempt -= 1;
+                continue;
+            }
+
+            let error =
+                LlmApiError::from_response(response.status, &response.headers, response.body);
+            if !error.is_retryable() || attempt >= options.retry.max_attempts {
+                return Err(error);
+            }
+            let delay = options.retry.delay(attempt, error.retry_after());
+            log::warn!(
+                "Request to {path} failed with status {}, retrying in {delay:?} (attempt {attempt}/{})",
+                response.status,
+                options.retry.max_attempts
+            );
+            self.executor.timer(delay).await;
+        }
+    }
+
+    async fn send(
+        &self,
+        request: Request<AsyncBody>,
+        timeout: Option<Duration>,
+    ) -> Result<RawResponse, LlmApiError> {
+        let send = async {
+            let mut response = self
+                .http_client
+                .send(request)
+                .await
+                .map_err(LlmApiError::Network)?;
+            let mut body = String::new();
+            response
+                .body_mut()
+                .read_to_string(&mut body)
+                .await
+                .map_err(|error| LlmApiError::Network(error.into()))?;
+            Ok(RawResponse {
+                status: response.status(),
+                headers: response.headers().clone(),
+                body,
+            })
+        };
+        let Some(timeout) = timeout else {
+            return send.await;
+        };
+        futures::select_biased! {
+            response = send.fuse() => response,
+            _ = self.executor.timer(timeout).fuse() => Err(LlmApiError::Network(anyhow!(
+                "request timed out after {timeout:?}"
+            ))),
+        }
+    }
+}
+
+struct RawResponse {
+    status: http_client::StatusCode,
+    headers: HeaderMap,
+    body: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::FakeSystemClock;
+    use gpui::TestAppContext;
+    use http_client::{FakeHttpClient, Response};
+    use settings::SettingsStore;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = LlmRetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+        };
+        for (attempt, expected) in [(1, 500), (2, 1000), (3, 2000), (10, 10_000)] {
+            let delay = policy.delay(attempt, None);
+            let expected = Duration::from_millis(expected);
+            assert!(delay >= expected && delay < expected.mul_f64(1.5));
+        }
+
+        let delay = policy.delay(1, Some(Duration::from_secs(7)));
+        assert!(delay >= Duration::from_secs(7) && delay < Duration::from_secs_f64(10.5));
+
+        let delay = policy.delay(1, Some(Duration::from_secs(86400)));
+        assert!(delay >= Duration::from_secs(10) && delay < Duration::from_secs(15));
+    }
+
+    #[gpui::test]
+    async fn test_post_retries_and_times_out(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            client::init_settings(cx);
+        });
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let http_client = FakeHttpClient::create({
+            let attempts = attempts.clone();
+            move |_| {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    match attempt {
+                        0 => Ok(Response::builder().status(503).body("".into())?),
+                        1 => Ok(Response::builder().status(200).body("true".into())?),
+                        _ => futures::future::pending().await,
+                    }
+                }
+            }
+        });
+        let client =
+            cx.update(|cx| Client::new(Arc::new(FakeSystemClock::new()), http_client.clone(), cx));
+        let http = LlmHttp::new(
+            http_client,
+            client,
+            LlmApiToken::fake("token"),
+            cx.executor(),
+        );
+
+        let retry = LlmRetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(1),
+        };
+        let response = cx.executor().spawn({
+            let http = http.clone();
+            async move {
+                let options = LlmPostOptions {
+                    retry,
+                    ..Default::default()
+                };
+                http.post_json::<_, bool>("/search", &(), options).await
+            }
+        });
+        cx.run_until_parked();
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        cx.executor().advance_clock(Duration::from_secs(2));
+        assert!(response.await.unwrap());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        let response = cx.executor().spawn(async move {
+            let options = LlmPostOptions {
+                timeout: Some(Duration::from_secs(30)),
+                ..Default::default()
+            };
+            http.post_json::<_, bool>("/search", &(), options).await
+        });
+        cx.run_until_parked();
+        cx.executor().advance_clock(Duration::from_secs(30));
+        assert!(matches!(response.await, Err(LlmApiError::Network(_))));
+    }
+}
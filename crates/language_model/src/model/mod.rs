@@ -1,3 +1,5 @@
 pub mod cloud_model;
+pub mod llm_http;
 
 pub use cloud_model::*;
+pub use llm_http::*;
@@ -128,6 +128,7 @@ pub struct State {
     default_fast_model: Option<Arc<oppla_llm_client::LanguageModel>>,
     recommended_models: Vec<Arc<oppla_llm_client::LanguageModel>>,
     can_select_model: bool,
+    loading_models: bool,
     _fetch_models_task: Task<()>,
     _settings_subscription: Subscription,
     _llm_token_subscription: Subscription,
@@ -153,7 +154,9 @@ impl State {
             default_fast_model: None,
             recommended_models: Vec::new(),
             can_select_model: false,
+            loading_models: true,
             _fetch_models_task: cx.spawn(async move |this, cx| {
+                let (state, mut state_cx) = (this.clone(), cx.clone());
                 maybe!(async move {
                     let (client, llm_api_token) = this
                         .read_with(cx, |this, _cx| (client.clone(), this.llm_api_token.clone()))?;
@@ -177,6 +180,12 @@ impl State {
                 .await
                 .context("failed to fetch Zed models")
                 .log_err();
+                state
+                    .update(&mut state_cx, |this, cx| {
+                        this.loading_models = false;
+                        cx.notify();
+                    })
+                    .ok();
             }),
             _settings_subscription: cx.observe_global::<SettingsStore>(|_, cx| {
                 cx.notify();
@@ -406,6 +415,10 @@ impl LanguageModelProvider for CloudLanguageModelProvider {
             .collect()
     }
 
+    fn is_loading_models(&self, cx: &App) -> bool {
+        self.state.read(cx).loading_models
+    }
+
     fn is_authenticated(&self, cx: &App) -> bool {
         let state = self.state.read(cx);
         !state.is_signed_out() && state.has_accepted_terms_of_service(cx)
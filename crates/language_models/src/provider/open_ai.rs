@@ -239,6 +239,20 @@ impl LanguageModelProvider for OpenAiLanguageModelProvider {
     fn reset_credentials(&self, cx: &mut App) -> Task<Result<()>> {
         self.state.update(cx, |state, cx| state.reset_api_key(cx))
     }
+
+    fn validate_credentials(&self, cx: &mut App) -> Task<Result<()>> {
+        let Some(api_key) = self.state.read(cx).api_key.clone() else {
+            return Task::ready(Ok(()));
+        };
+        let api_url = AllLanguageModelSettings::get_global(cx)
+            .openai
+            .api_url
+            .clone();
+        let http_client = self.http_client.clone();
+        cx.background_executor().spawn(async move {
+            open_ai::validate_api_key(http_client.as_ref(), &api_url, &api_key).await
+        })
+    }
 }
 
 pub struct OpenAiLanguageModel {
@@ -394,6 +394,27 @@ pub struct ResponseStreamEvent {
     pub usage: Option<Usage>,
 }
 
+/// Checks that the API key is accepted by listing the available models.
+/// Failures other than the key being rejected, like the network being down,
+/// aren't reported, since they say nothing about the key.
+pub async fn validate_api_key(client: &dyn HttpClient, api_url: &str, api_key: &str) -> Result<()> {
+    let request = HttpRequest::builder()
+        .method(Method::GET)
+        .uri(format!("{api_url}/models"))
+        .header("Authorization", format!("Bearer {}", api_key))
+        .body(AsyncBody::default())?;
+    let Ok(response) = client.send(request).await else {
+        return Ok(());
+    };
+    match response.status().as_u16() {
+        401 | 403 => Err(anyhow!(
+            "The API key was rejected ({}). It may have been revoked.",
+            response.status()
+        )),
+        _ => Ok(()),
+    }
+}
+
 pub async fn stream_completion(
     client: &dyn HttpClient,
     api_url: &str,
@@ -1,6 +1,7 @@
 mod oppla;
 mod reliability;
 
+use agent_settings::AgentSettings;
 use agent_ui::AgentPanel;
 use anyhow::{Context as _, Result};
 use clap::{Parser, command};
@@ -18,10 +19,10 @@ use git::GitHostingProviderRegistry;
 use gpui::{App, AppContext as _, Application, AsyncApp, Focusable as _, UpdateGlobal as _};
 
 use gpui_tokio::Tokio;
-use http_client::{Url, read_proxy_from_env};
+use http_client::{HttpClientWithUrl, Url, read_proxy_from_env};
 use language::LanguageRegistry;
 use prompt_store::PromptBuilder;
-use reqwest_client::ReqwestClient;
+use reqwest_client::{ConnectionPool, ReqwestClient};
 
 use assets::Assets;
 use language_model::LlmApiToken;
@@ -36,7 +37,7 @@ use parking_lot::Mutex;
 use project::project_settings::ProjectSettings;
 use recent_projects::{SshSettings, open_ssh_project};
 use release_channel::{AppCommitSha, AppVersion, ReleaseChannel};
-use semantic_index::{CloudEmbeddingProvider, SemanticDb};
+use semantic_index::{CLOUD_EMBEDDING_MODEL, CloudEmbeddingProvider, SecretRedactor, SemanticDb};
 use session::{AppSession, Session};
 use settings::{BaseKeymap, Settings, SettingsStore, watch_config_file};
 use std::{
@@ -405,11 +406,20 @@ pub fn main() {
                     .ok()
             })
             .or_else(read_proxy_from_env);
-        let http = {
+        let (http, llm_http) = {
             let _guard = Tokio::handle(cx).enter();
 
-            ReqwestClient::proxy_and_user_agent(proxy_url, &user_agent)
-                .expect("could not start HTTP client")
+            let http = ReqwestClient::proxy_and_user_agent(proxy_url.clone(), &user_agent)
+                .expect("could not start HTTP client");
+            // Embedding batches and file searches send many small requests to
+            // the same host, so they keep their connections pooled.
+            let llm_http = ReqwestClient::proxy_and_user_agent_with_pool(
+                proxy_url.clone(),
+                &user_agent,
+                ConnectionPool::default(),
+            )
+            .expect("could not start HTTP client");
+            (http, llm_http)
         };
         cx.set_http_client(Arc::new(http));
 
@@ -425,6 +435,11 @@ pub fn main() {
 
         let client = Client::production(cx);
         cx.set_http_client(client.http_client());
+        let llm_http_client = Arc::new(HttpClientWithUrl::new_url(
+            Arc::new(llm_http),
+            client.http_client().base_url(),
+            proxy_url,
+        ));
         let mut languages = LanguageRegistry::new(cx.background_executor().clone());
         languages.set_language_server_download_dir(paths::languages_dir().clone());
         let languages = Arc::new(languages);
@@ -571,23 +586,45 @@ pub fn main() {
         {
             let db_path = paths::database_dir().join("semantic_index.db");
             let client = app_state.client.clone();
-            let http_client = client.http_client();
+            let http_client = llm_http_client.clone();
+            let secret_redaction = AgentSettings::get_global(cx).secret_redaction.clone();
+            let reindex_debounce_ms = AgentSettings::get_global(cx).reindex_debounce_ms;
 
             cx.spawn(async move |mut cx| {
                 // Get token for authentication
                 let llm_api_token = LlmApiToken::default();
 
                 // Create embedding provider with Oppla embeddings
-                let embedding_provider = Arc::new(CloudEmbeddingProvider::new(
+                let mut embedding_provider = CloudEmbeddingProvider::new(
                     http_client,
-                    "togethercomputer/m2-bert-80M-32k-retrieval".to_string(),
+                    CLOUD_EMBEDDING_MODEL.to_string(),
                     llm_api_token,
                     client,
-                ));
+                    cx.background_executor().clone(),
+                );
+                if secret_redaction.enabled {
+                    embedding_provider = embedding_provider
+                        .with_secret_redactor(SecretRedactor::new(&secret_redaction.patterns));
+                }
+                let embedding_provider = Arc::new(embedding_provider);
+
+                let warm_up = cx
+                    .update(|cx| AgentSettings::get_global(cx).warm_up_embeddings)
+                    .unwrap_or(false);
+                if warm_up {
+                    let embedding_provider = embedding_provider.clone();
+                    cx.background_spawn(async move { embedding_provider.warm_up().await })
+                        .detach();
+                }
 
                 // Initialize semantic database
                 match SemanticDb::new(db_path, embedding_provider, &mut cx).await {
-                    Ok(semantic_db) => {
+                    Ok(mut semantic_db) => {
+                        if reindex_debounce_ms > 0 {
+                            semantic_db = semantic_db.with_reindex_debounce(
+                                std::time::Duration::from_millis(reindex_debounce_ms),
+                            );
+                        }
                         cx.update(|cx| {
                             cx.set_global(semantic_db);
                             log::info!("Semantic index initialized with Oppla embeddings");
@@ -610,7 +647,7 @@ pub fn main() {
             false,
             cx,
         );
-        assistant_tools::init(app_state.client.http_client(), cx);
+        assistant_tools::init(app_state.client.http_client(), llm_http_client, cx);
         repl::init(app_state.fs.clone(), cx);
         extension_host::init(
             extension_host_proxy,
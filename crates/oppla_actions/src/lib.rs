@@ -142,7 +142,18 @@ pub mod dev {
         dev,
         [
             /// Toggles the developer inspector for debugging UI elements.
-            ToggleInspector
+            ToggleInspector,
+            /// Copies the issuer, subject and expiry of the LLM API token to the
+            /// clipboard, without its signature. Only available to staff and in
+            /// debug builds.
+            CopyLlmTokenMetadata,
+            /// Copies the last search or embedding request as a `curl` command,
+            /// with the bearer token left to `$TOKEN`. Only available while
+            /// `log_llm_requests` is enabled.
+            CopyLastLlmRequestAsCurl,
+            /// Copies the outputs of the last tools the agent ran, with how long
+            /// each took and whether it failed, to the clipboard.
+            CopyRecentToolOutputs
         ]
     );
 }
@@ -1,11 +1,13 @@
 pub mod extension;
 pub mod registry;
 
-use std::{path::Path, sync::Arc};
+use std::{path::Path, sync::Arc, time::Duration};
 
-use anyhow::{Context as _, Result};
+use anyhow::{Context as _, Result, anyhow};
 use collections::{HashMap, HashSet};
-use context_server::{ContextServer, ContextServerCommand, ContextServerId};
+use context_server::{
+    ContextServer, ContextServerCommand, ContextServerId, transport::HttpTransport,
+};
 use futures::{FutureExt as _, future::join_all};
 use gpui::{App, AsyncApp, Context, Entity, EventEmitter, Subscription, Task, WeakEntity, actions};
 use registry::ContextServerDescriptorRegistry;
@@ -57,6 +59,8 @@ enum ContextServerState {
     Running {
         server: Arc<ContextServer>,
         configuration: Arc<ContextServerConfiguration>,
+        /// Marks the server as failed if it exits on its own.
+        _exit_task: Task<()>,
     },
     Stopped {
         server: Arc<ContextServer>,
@@ -98,13 +102,19 @@ pub enum ContextServerConfiguration {
         command: ContextServerCommand,
         settings: serde_json::Value,
     },
+    Http {
+        url: String,
+        headers: HashMap<String, String>,
+    },
 }
 
 impl ContextServerConfiguration {
-    pub fn command(&self) -> &ContextServerCommand {
+    /// The command that starts the server, or `None` for remote servers.
+    pub fn command(&self) -> Option<&ContextServerCommand> {
         match self {
-            ContextServerConfiguration::Custom { command } => command,
-            ContextServerConfiguration::Extension { command, .. } => command,
+            ContextServerConfiguration::Custom { command } => Some(command),
+            ContextServerConfiguration::Extension { command, .. } => Some(command),
+            ContextServerConfiguration::Http { .. } => None,
         }
     }
 
@@ -133,10 +143,37 @@ impl ContextServerConfiguration {
 
                 Some(ContextServerConfiguration::Extension { command, settings })
             }
+            ContextServerSettings::Http {
+                enabled: _,
+                url,
+                headers,
+            } => Some(ContextServerConfiguration::Http { url, headers }),
         }
     }
 }
 
+const RESTART_BASE_DELAY: Duration = Duration::from_secs(1);
+const RESTART_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// How many times a failed context server has been restarted automatically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextServerRestartAttempt {
+    pub attempt: u32,
+    pub max_attempts: u32,
+}
+
+impl ContextServerRestartAttempt {
+    pub fn is_exhausted(&self) -> bool {
+        self.attempt >= self.max_attempts
+    }
+}
+
+fn restart_delay(attempt: u32) -> Duration {
+    RESTART_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(RESTART_MAX_DELAY)
+}
+
 pub type ContextServerFactory =
     Box<dyn Fn(ContextServerId, Arc<ContextServerConfiguration>) -> Arc<ContextServer>>;
 
@@ -148,6 +185,8 @@ pub struct ContextServerStore {
     update_servers_task: Option<Task<Result<()>>>,
     context_server_factory: Option<ContextServerFactory>,
     needs_server_update: bool,
+    restart_attempts: HashMap<ContextServerId, ContextServerRestartAttempt>,
+    pending_restarts: HashMap<ContextServerId, Task<()>>,
     _subscriptions: Vec<Subscription>,
 }
 
@@ -171,13 +210,17 @@ impl ContextServerStore {
         )
     }
 
-    /// Returns all configured context server ids, regardless of enabled state.
+    /// Returns all configured context server ids, regardless of enabled state,
+    /// sorted alphabetically so that lists built from them don't reorder
+    /// between reads.
     pub fn configured_server_ids(&self) -> Vec<ContextServerId> {
-        self.context_server_settings
+        let mut ids = self
+            .context_server_settings
             .keys()
             .cloned()
-            .map(ContextServerId)
-            .collect()
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.into_iter().map(ContextServerId).collect()
     }
 
     #[cfg(any(test, feature = "test-support"))]
@@ -240,6 +283,8 @@ impl ContextServerStore {
             servers: HashMap::default(),
             update_servers_task: None,
             context_server_factory,
+            restart_attempts: HashMap::default(),
+            pending_restarts: HashMap::default(),
         };
         if maintain_server_loop {
             this.available_context_servers_changed(cx);
@@ -263,6 +308,20 @@ impl ContextServerStore {
         self.servers.get(id).map(ContextServerStatus::from_state)
     }
 
+    /// Returns the automatic restart progress for a server that has failed,
+    /// if any restarts have been attempted since it last started successfully.
+    pub fn restart_attempt_for_server(
+        &self,
+        id: &ContextServerId,
+    ) -> Option<ContextServerRestartAttempt> {
+        self.restart_attempts.get(id).copied()
+    }
+
+    /// Whether an automatic restart is currently scheduled for the server.
+    pub fn is_restart_pending(&self, id: &ContextServerId) -> bool {
+        self.pending_restarts.contains_key(id)
+    }
+
     pub fn configuration_for_server(
         &self,
         id: &ContextServerId,
@@ -323,6 +382,7 @@ impl ContextServerStore {
     }
 
     pub fn stop_server(&mut self, id: &ContextServerId, cx: &mut Context<Self>) -> Result<()> {
+        self.cancel_restarts(id);
         if matches!(
             self.servers.get(id),
             Some(ContextServerState::Stopped { .. })
@@ -356,11 +416,12 @@ impl ContextServerStore {
     }
 
     pub fn restart_server(&mut self, id: &ContextServerId, cx: &mut Context<Self>) -> Result<()> {
+        self.cancel_restarts(id);
         if let Some(state) = self.servers.get(&id) {
             let configuration = state.configuration();
 
             self.stop_server(&state.server().id(), cx)?;
-            let new_server = self.create_context_server(id.clone(), configuration.clone())?;
+            let new_server = self.create_context_server(id.clone(), configuration.clone(), cx)?;
             self.run_server(new_server, configuration, cx);
         }
         Ok(())
@@ -380,22 +441,47 @@ impl ContextServerStore {
             self.stop_server(&id, cx).log_err();
         }
 
+        let startup_timeout = ProjectSettings::get_global(cx)
+            .global_context_server_settings
+            .startup_timeout_secs;
         let task = cx.spawn({
             let id = server.id();
             let server = server.clone();
             let configuration = configuration.clone();
             async move |this, cx| {
-                match server.clone().start(&cx).await {
+                let start = server.clone().start(&cx);
+                let result = if startup_timeout == 0 {
+                    start.await
+                } else {
+                    let timeout = cx
+                        .background_executor()
+                        .timer(Duration::from_secs(startup_timeout));
+                    futures::select_biased! {
+                        result = start.fuse() => result,
+                        _ = timeout.fuse() => Err(anyhow!("Timed out during startup")),
+                    }
+                };
+                match result {
                     Ok(_) => {
                         log::info!("Started {} context server", id);
                         debug_assert!(server.client().is_some());
 
                         this.update(cx, |this, cx| {
+                            this.restart_attempts.remove(&id);
+                            let _exit_task = cx.spawn({
+                                let server = server.clone();
+                                async move |this, cx| {
+                                    server.closed().await;
+                                    this.update(cx, |this, cx| this.server_exited(&server, cx))
+                                        .log_err();
+                                }
+                            });
                             this.update_server_state(
                                 id.clone(),
                                 ContextServerState::Running {
                                     server,
                                     configuration,
+                                    _exit_task,
                                 },
                                 cx,
                             )
@@ -413,7 +499,8 @@ impl ContextServerStore {
                                     error: err.to_string().into(),
                                 },
                                 cx,
-                            )
+                            );
+                            this.schedule_restart(id.clone(), cx);
                         })
                         .log_err()
                     }
@@ -432,7 +519,108 @@ impl ContextServerStore {
         );
     }
 
+    /// Handles a running server's connection closing without it being stopped,
+    /// e.g. because its process crashed, by restarting it like a server that
+    /// failed to start.
+    fn server_exited(&mut self, server: &Arc<ContextServer>, cx: &mut Context<Self>) {
+        let id = server.id();
+        let Some(ContextServerState::Running {
+            server: running,
+            configuration,
+            ..
+        }) = self.servers.get(&id)
+        else {
+            return;
+        };
+        if !Arc::ptr_eq(running, server) {
+            return;
+        }
+
+        log::error!("{} context server exited unexpectedly", id);
+        let configuration = configuration.clone();
+        server.stop().log_err();
+        self.update_server_state(
+            id.clone(),
+            ContextServerState::Error {
+                configuration,
+                server: server.clone(),
+                error: "Exited unexpectedly".into(),
+            },
+            cx,
+        );
+        self.schedule_restart(id, cx);
+    }
+
+    fn schedule_restart(&mut self, id: ContextServerId, cx: &mut Context<Self>) {
+        let settings = &ProjectSettings::get_global(cx).global_context_server_settings;
+        if !settings.auto_restart {
+            return;
+        }
+
+        let max_attempts = settings.max_restart_attempts;
+        let attempt = self
+            .restart_attempts
+            .get(&id)
+            .map_or(0, |restart| restart.attempt);
+        if attempt >= max_attempts {
+            log::error!(
+                "{} context server failed after {} restart attempts, giving up",
+                id,
+                attempt
+            );
+            return;
+        }
+
+        let attempt = attempt + 1;
+        self.restart_attempts.insert(
+            id.clone(),
+            ContextServerRestartAttempt {
+                attempt,
+                max_attempts,
+            },
+        );
+
+        let delay = restart_delay(attempt);
+        log::info!(
+            "restarting {} context server in {:?} (attempt {}/{})",
+            id,
+            delay,
+            attempt,
+            max_attempts
+        );
+        let task = cx.spawn({
+            let id = id.clone();
+            async move |this, cx| {
+                cx.background_executor().timer(delay).await;
+                this.update(cx, |this, cx| {
+                    this.pending_restarts.remove(&id);
+                    let Some(ContextServerState::Error { configuration, .. }) =
+                        this.servers.get(&id)
+                    else {
+                        return;
+                    };
+                    let configuration = configuration.clone();
+                    if let Some(server) = this
+                        .create_context_server(id.clone(), configuration.clone(), cx)
+                        .log_err()
+                    {
+                        this.run_server(server, configuration, cx);
+                    }
+                })
+                .log_err();
+            }
+        });
+        self.pending_restarts.insert(id, task);
+        cx.notify();
+    }
+
+    fn cancel_restarts(&mut self, id: &ContextServerId) {
+        self.restart_attempts.remove(id);
+        self.pending_restarts.remove(id);
+    }
+
     fn remove_server(&mut self, id: &ContextServerId, cx: &mut Context<Self>) -> Result<()> {
+        self.cancel_restarts(id);
         let state = self
             .servers
             .remove(id)
@@ -449,15 +637,27 @@ impl ContextServerStore {
         &self,
         id: ContextServerId,
         configuration: Arc<ContextServerConfiguration>,
+        cx: &App,
     ) -> Result<Arc<ContextServer>> {
         if let Some(factory) = self.context_server_factory.as_ref() {
-            Ok(factory(id, configuration))
-        } else {
-            Ok(Arc::new(ContextServer::stdio(
-                id,
-                configuration.command().clone(),
-            )))
+            return Ok(factory(id, configuration));
         }
+        let server = match configuration.as_ref() {
+            ContextServerConfiguration::Custom { command }
+            | ContextServerConfiguration::Extension { command, .. } => {
+                ContextServer::stdio(id, command.clone())
+            }
+            ContextServerConfiguration::Http { url, headers } => ContextServer::new(
+                id,
+                Arc::new(HttpTransport::new(
+                    cx.http_client(),
+                    url.clone(),
+                    headers.clone(),
+                    cx.background_executor().clone(),
+                )),
+            ),
+        };
+        Ok(Arc::new(server))
     }
 
     fn resolve_context_server_settings<'a>(
@@ -553,7 +753,7 @@ impl ContextServerStore {
         let mut servers_to_remove = HashSet::default();
         let mut servers_to_stop = HashSet::default();
 
-        this.update(cx, |this, _cx| {
+        this.update(cx, |this, cx| {
             for server_id in this.servers.keys() {
                 // All servers that are not in desired_servers should be removed from the store.
                 // This can happen if the user removed a server from the context server settings.
@@ -573,7 +773,7 @@ impl ContextServerStore {
                 if existing_config.as_deref() != Some(&config) || is_stopped {
                     let config = Arc::new(config);
                     if let Some(server) = this
-                        .create_context_server(id.clone(), config.clone())
+                        .create_context_server(id.clone(), config.clone(), cx)
                         .log_err()
                     {
                         servers_to_start.push((server, config));
@@ -604,10 +804,11 @@ impl ContextServerStore {
 mod tests {
     use super::*;
     use crate::{
-        FakeFs, Project, context_server_store::registry::ContextServerDescriptor,
-        project_settings::ProjectSettings,
+        FakeFs, Project,
+        context_server_store::registry::ContextServerDescriptor,
+        project_settings::{GlobalContextServerSettings, ProjectSettings},
     };
-    use context_server::test::create_fake_transport;
+    use context_server::test::{FakeTransport, create_fake_transport};
     use gpui::{AppContext, TestAppContext, UpdateGlobal as _};
     use serde_json::json;
     use std::{cell::RefCell, path::PathBuf, rc::Rc};
@@ -1039,6 +1240,47 @@ mod tests {
         }
     }
 
+    #[gpui::test]
+    async fn test_configured_server_ids_are_sorted(cx: &mut TestAppContext) {
+        let server_settings = |id: &str| {
+            (
+                Arc::<str>::from(id),
+                ContextServerSettings::Custom {
+                    enabled: false,
+                    command: ContextServerCommand {
+                        path: "somebinary".into(),
+                        args: Vec::new(),
+                        env: None,
+                    },
+                },
+            )
+        };
+        let (_fs, project) = setup_context_server_test(
+            cx,
+            json!({"code.rs": ""}),
+            vec![
+                server_settings("zeta"),
+                server_settings("alpha"),
+                server_settings("mcp-2"),
+                server_settings("mcp-1"),
+            ],
+        )
+        .await;
+
+        let registry = cx.new(|_| ContextServerDescriptorRegistry::new());
+        let store =
+            cx.new(|cx| ContextServerStore::test(registry, project.read(cx).worktree_store(), cx));
+
+        let ids = store.read_with(cx, |store, _| store.configured_server_ids());
+        assert_eq!(
+            ids,
+            ["alpha", "mcp-1", "mcp-2", "zeta"]
+                .into_iter()
+                .map(|id| ContextServerId(id.into()))
+                .collect::<Vec<_>>()
+        );
+    }
+
     #[gpui::test]
     async fn test_context_server_enabled_disabled(cx: &mut TestAppContext) {
         const SERVER_1_ID: &'static str = "mcp-1";
@@ -1145,6 +1387,266 @@ mod tests {
         }
     }
 
+    #[gpui::test]
+    async fn test_context_server_auto_restart(cx: &mut TestAppContext) {
+        const SERVER_1_ID: &'static str = "mcp-1";
+
+        let server_1_id = ContextServerId(SERVER_1_ID.into());
+
+        let (_fs, project) = setup_context_server_test(
+            cx,
+            json!({"code.rs": ""}),
+            vec![(SERVER_1_ID.into(), dummy_server_settings())],
+        )
+        .await;
+        cx.update(|cx| {
+            let mut settings = ProjectSettings::get_global(cx).clone();
+            settings.global_context_server_settings = GlobalContextServerSettings {
+                auto_restart: true,
+                max_restart_attempts: 2,
+                ..Default::default()
+            };
+            ProjectSettings::override_global(settings, cx);
+        });
+
+        let executor = cx.executor();
+        let registry = cx.new(|_| ContextServerDescriptorRegistry::new());
+        let store = cx.new(|cx| {
+            ContextServerStore::test_maintain_server_loop(
+                Box::new(move |id, _| {
+                    Arc::new(ContextServer::new(
+                        id.clone(),
+                        Arc::new(create_failing_transport(executor.clone())),
+                    ))
+                }),
+                registry.clone(),
+                project.read(cx).worktree_store(),
+                cx,
+            )
+        });
+
+        cx.run_until_parked();
+        cx.update(|cx| {
+            let store = store.read(cx);
+            assert!(matches!(
+                store.status_for_server(&server_1_id),
+                Some(ContextServerStatus::Error(_))
+            ));
+            assert_eq!(
+                store.restart_attempt_for_server(&server_1_id),
+                Some(ContextServerRestartAttempt {
+                    attempt: 1,
+                    max_attempts: 2
+                })
+            );
+            assert!(store.is_restart_pending(&server_1_id));
+        });
+
+        cx.executor().advance_clock(restart_delay(1));
+        cx.run_until_parked();
+        cx.update(|cx| {
+            let store = store.read(cx);
+            assert_eq!(
+                store.restart_attempt_for_server(&server_1_id),
+                Some(ContextServerRestartAttempt {
+                    attempt: 2,
+                    max_attempts: 2
+                })
+            );
+            assert!(store.is_restart_pending(&server_1_id));
+        });
+
+        cx.executor().advance_clock(restart_delay(2));
+        cx.run_until_parked();
+        cx.update(|cx| {
+            let store = store.read(cx);
+            assert!(matches!(
+                store.status_for_server(&server_1_id),
+                Some(ContextServerStatus::Error(_))
+            ));
+            assert!(
+                store
+                    .restart_attempt_for_server(&server_1_id)
+                    .is_some_and(|restart| restart.is_exhausted())
+            );
+            assert!(!store.is_restart_pending(&server_1_id));
+        });
+
+        store
+            .update(cx, |store, cx| store.stop_server(&server_1_id, cx))
+            .unwrap();
+        cx.update(|cx| {
+            assert_eq!(
+                store.read(cx).restart_attempt_for_server(&server_1_id),
+                None
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_context_server_restarts_after_exit(cx: &mut TestAppContext) {
+        const SERVER_1_ID: &'static str = "mcp-1";
+
+        let server_1_id = ContextServerId(SERVER_1_ID.into());
+
+        let (_fs, project) = setup_context_server_test(
+            cx,
+            json!({"code.rs": ""}),
+            vec![(SERVER_1_ID.into(), dummy_server_settings())],
+        )
+        .await;
+        cx.update(|cx| {
+            let mut settings = ProjectSettings::get_global(cx).clone();
+            settings.global_context_server_settings = GlobalContextServerSettings {
+                auto_restart: true,
+                max_restart_attempts: 2,
+                ..Default::default()
+            };
+            ProjectSettings::override_global(settings, cx);
+        });
+
+        let executor = cx.executor();
+        let transports = Rc::new(RefCell::new(Vec::<Arc<FakeTransport>>::new()));
+        let registry = cx.new(|_| ContextServerDescriptorRegistry::new());
+        let store = cx.new(|cx| {
+            ContextServerStore::test_maintain_server_loop(
+                Box::new({
+                    let transports = transports.clone();
+                    move |id, _| {
+                        let transport =
+                            Arc::new(create_fake_transport(id.0.to_string(), executor.clone()));
+                        transports.borrow_mut().push(transport.clone());
+                        Arc::new(ContextServer::new(id.clone(), transport))
+                    }
+                }),
+                registry.clone(),
+                project.read(cx).worktree_store(),
+                cx,
+            )
+        });
+
+        cx.run_until_parked();
+        cx.update(|cx| {
+            assert_eq!(
+                store.read(cx).status_for_server(&server_1_id),
+                Some(ContextServerStatus::Running)
+            );
+        });
+
+        // The server's process exiting on its own is treated like a failed
+        // start, and the server is restarted.
+        transports.borrow().last().unwrap().kill();
+        cx.run_until_parked();
+        cx.update(|cx| {
+            let store = store.read(cx);
+            assert!(matches!(
+                store.status_for_server(&server_1_id),
+                Some(ContextServerStatus::Error(_))
+            ));
+            assert!(store.is_restart_pending(&server_1_id));
+        });
+
+        cx.executor().advance_clock(restart_delay(1));
+        cx.run_until_parked();
+        cx.update(|cx| {
+            let store = store.read(cx);
+            assert_eq!(
+                store.status_for_server(&server_1_id),
+                Some(ContextServerStatus::Running)
+            );
+            assert_eq!(store.restart_attempt_for_server(&server_1_id), None);
+        });
+        assert_eq!(transports.borrow().len(), 2);
+
+        // Stopping a server isn't mistaken for it exiting.
+        store
+            .update(cx, |store, cx| store.stop_server(&server_1_id, cx))
+            .unwrap();
+        cx.run_until_parked();
+        cx.update(|cx| {
+            let store = store.read(cx);
+            assert_eq!(
+                store.status_for_server(&server_1_id),
+                Some(ContextServerStatus::Stopped)
+            );
+            assert!(!store.is_restart_pending(&server_1_id));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_context_server_startup_timeout(cx: &mut TestAppContext) {
+        const SERVER_1_ID: &'static str = "mcp-1";
+
+        let server_1_id = ContextServerId(SERVER_1_ID.into());
+
+        let (_fs, project) = setup_context_server_test(
+            cx,
+            json!({"code.rs": ""}),
+            vec![(SERVER_1_ID.into(), dummy_server_settings())],
+        )
+        .await;
+        cx.update(|cx| {
+            let mut settings = ProjectSettings::get_global(cx).clone();
+            settings.global_context_server_settings = GlobalContextServerSettings {
+                startup_timeout_secs: 5,
+                ..Default::default()
+            };
+            ProjectSettings::override_global(settings, cx);
+        });
+
+        let executor = cx.executor();
+        let registry = cx.new(|_| ContextServerDescriptorRegistry::new());
+        let store = cx.new(|cx| {
+            ContextServerStore::test_maintain_server_loop(
+                Box::new(move |id, _| {
+                    // The server never responds to the initialize request.
+                    Arc::new(ContextServer::new(
+                        id.clone(),
+                        Arc::new(FakeTransport::new(executor.clone())),
+                    ))
+                }),
+                registry.clone(),
+                project.read(cx).worktree_store(),
+                cx,
+            )
+        });
+
+        cx.run_until_parked();
+        cx.update(|cx| {
+            assert_eq!(
+                store.read(cx).status_for_server(&server_1_id),
+                Some(ContextServerStatus::Starting)
+            );
+        });
+
+        cx.executor().advance_clock(Duration::from_secs(5));
+        cx.run_until_parked();
+        cx.update(|cx| {
+            assert_eq!(
+                store.read(cx).status_for_server(&server_1_id),
+                Some(ContextServerStatus::Error(
+                    "Timed out during startup".into()
+                ))
+            );
+        });
+    }
+
+    /// A transport whose server always reports a protocol version we don't
+    /// support, so that starting the server fails.
+    fn create_failing_transport(executor: gpui::BackgroundExecutor) -> FakeTransport {
+        FakeTransport::new(executor).on_request::<context_server::types::requests::Initialize>(
+            |_| context_server::types::InitializeResponse {
+                protocol_version: context_server::types::ProtocolVersion("1970-01-01".into()),
+                server_info: context_server::types::Implementation {
+                    name: "failing".into(),
+                    version: "1.0.0".into(),
+                },
+                capabilities: Default::default(),
+                meta: None,
+            },
+        )
+    }
+
     fn set_context_server_configuration(
         context_servers: Vec<(Arc<str>, ContextServerSettings)>,
         cx: &mut TestAppContext,
@@ -60,6 +60,15 @@ pub struct ProjectSettings {
     #[serde(default)]
     pub context_servers: HashMap<Arc<str>, ContextServerSettings>,
 
+    /// Settings shared by all context servers.
+    #[serde(default)]
+    pub global_context_server_settings: GlobalContextServerSettings,
+
+    /// Filters applied to every agent file search in this project, unless the
+    /// synced task or the search itself sets them.
+    #[serde(default)]
+    pub file_search_filter: FileSearchFilterSettings,
+
     /// Configuration for Diagnostics-related features.
     #[serde(default)]
     pub diagnostics: DiagnosticsSettings,
@@ -110,6 +119,18 @@ pub enum ContextServerSettings {
         /// are supported.
         settings: serde_json::Value,
     },
+    /// A remote context server reached over the streamable HTTP transport,
+    /// which also covers servers that stream their responses as SSE.
+    Http {
+        /// Whether the context server is enabled.
+        #[serde(default = "default_true")]
+        enabled: bool,
+        /// The URL of the server's MCP endpoint.
+        url: String,
+        /// Headers to send with every request, e.g. for authorization.
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
 }
 
 /// Common language server settings.
@@ -122,6 +143,79 @@ pub struct GlobalLspSettings {
     pub button: bool,
 }
 
+/// Default filters for the agent's file search tool.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct FileSearchFilterSettings {
+    /// Type of content to search: "conversations", "tasks", "compressed", or "all".
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub search_type: Option<String>,
+    /// Content to extract: "work_item", "big_bet", "requirements", or "auto".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+}
+
+impl FileSearchFilterSettings {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Settings shared by all context servers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct GlobalContextServerSettings {
+    /// Whether to automatically restart context servers that fail, waiting
+    /// exponentially longer between each attempt.
+    ///
+    /// Default: `false`
+    #[serde(default)]
+    pub auto_restart: bool,
+    /// How many times to try restarting a failed context server before
+    /// leaving it in the error state.
+    ///
+    /// Default: `5`
+    #[serde(default = "default_max_restart_attempts")]
+    pub max_restart_attempts: u32,
+    /// How many seconds to wait for a context server to finish starting
+    /// before marking it as errored. Set to `0` to wait indefinitely.
+    ///
+    /// Default: `30`
+    #[serde(default = "default_startup_timeout_secs")]
+    pub startup_timeout_secs: u64,
+    /// Whether to log each MCP tool call the agent makes, with its input,
+    /// duration and outcome, and show recent calls in the agent settings.
+    ///
+    /// Default: `false`
+    #[serde(default)]
+    pub log_tool_invocations: bool,
+}
+
+fn default_max_restart_attempts() -> u32 {
+    5
+}
+
+fn default_startup_timeout_secs() -> u64 {
+    30
+}
+
+impl Default for GlobalContextServerSettings {
+    fn default() -> Self {
+        Self {
+            auto_restart: false,
+            max_restart_attempts: default_max_restart_attempts(),
+            startup_timeout_secs: default_startup_timeout_secs(),
+            log_tool_invocations: false,
+        }
+    }
+}
+
 impl ContextServerSettings {
     pub fn default_extension() -> Self {
         Self::Extension {
@@ -134,6 +228,7 @@ impl ContextServerSettings {
         match self {
             ContextServerSettings::Custom { enabled, .. } => *enabled,
             ContextServerSettings::Extension { enabled, .. } => *enabled,
+            ContextServerSettings::Http { enabled, .. } => *enabled,
         }
     }
 
@@ -141,6 +236,7 @@ impl ContextServerSettings {
         match self {
             ContextServerSettings::Custom { enabled: e, .. } => *e = enabled,
             ContextServerSettings::Extension { enabled: e, .. } => *e = enabled,
+            ContextServerSettings::Http { enabled: e, .. } => *e = enabled,
         }
     }
 }
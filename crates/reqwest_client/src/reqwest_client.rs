@@ -17,6 +17,31 @@ const DEFAULT_CAPACITY: usize = 4096;
 static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
 static REDACT_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"key=[^&]+").unwrap());
 
+/// How connections are kept open between requests, for clients that send many
+/// small requests in a row to the same host, such as embedding batches.
+/// Requests reuse an idle connection when there is one, instead of paying for
+/// a new TCP and TLS handshake. Other clients keep reqwest's defaults.
+#[derive(Clone, Copy, Debug)]
+pub struct ConnectionPool {
+    /// How many idle connections are kept open per host.
+    pub max_idle_per_host: usize,
+    /// How long a connection may stay idle before it's closed.
+    pub idle_timeout: Duration,
+    /// How often idle connections are probed, so that proxies and NATs don't
+    /// drop them between requests.
+    pub tcp_keepalive: Duration,
+}
+
+impl Default for ConnectionPool {
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: 16,
+            idle_timeout: Duration::from_secs(90),
+            tcp_keepalive: Duration::from_secs(60),
+        }
+    }
+}
+
 pub struct ReqwestClient {
     client: reqwest::Client,
     proxy: Option<Url>,
@@ -45,9 +70,29 @@ impl ReqwestClient {
     }
 
     pub fn proxy_and_user_agent(proxy: Option<Url>, agent: &str) -> anyhow::Result<Self> {
+        Self::with_proxy(Self::builder(), proxy, agent)
+    }
+
+    pub fn proxy_and_user_agent_with_pool(
+        proxy: Option<Url>,
+        agent: &str,
+        pool: ConnectionPool,
+    ) -> anyhow::Result<Self> {
+        let builder = Self::builder()
+            .pool_max_idle_per_host(pool.max_idle_per_host)
+            .pool_idle_timeout(pool.idle_timeout)
+            .tcp_keepalive(pool.tcp_keepalive);
+        Self::with_proxy(builder, proxy, agent)
+    }
+
+    fn with_proxy(
+        builder: reqwest::ClientBuilder,
+        proxy: Option<Url>,
+        agent: &str,
+    ) -> anyhow::Result<Self> {
         let mut map = HeaderMap::new();
         map.insert(http::header::USER_AGENT, HeaderValue::from_str(agent)?);
-        let mut client = Self::builder().default_headers(map);
+        let mut client = builder.default_headers(map);
         let client_has_proxy;
 
         if let Some(proxy) = proxy.as_ref().and_then(|proxy_url| {
@@ -1,49 +1,106 @@
 use anyhow::Result;
 use client::Client;
+use futures::FutureExt as _;
 use gpui::App;
-use http_client::HttpClientWithUrl;
+use http_client::{HttpClientWithUrl, read_proxy_from_env};
 use language_models::LlmApiToken;
 use project::Project;
-use semantic_index::{CloudEmbeddingProvider, SemanticDb};
-use std::{path::Path, path::PathBuf, sync::Arc};
+use reqwest_client::{ConnectionPool, ReqwestClient};
+use semantic_index::{CLOUD_EMBEDDING_MODEL, CloudEmbeddingProvider, SemanticDb, Status};
+use std::{
+    path::Path,
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+const DB_PATH: &str = "/tmp/cloud-semantic-index-db.mdb";
 
 fn main() {
     env_logger::init();
 
     let args: Vec<String> = std::env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage: {} <project_path>", args[0]);
+    let fresh = args.get(2).is_some_and(|arg| arg == "--fresh");
+    if args.len() != 2 && !(args.len() == 3 && fresh) {
+        eprintln!("Usage: {} <project_path> [--fresh]", args[0]);
         std::process::exit(1);
     }
 
+    // By default, indexing resumes from the previous run: files that were
+    // already indexed are skipped, and unchanged chunks of changed files keep
+    // their embeddings. With --fresh, the previous index is removed first.
+    if fresh {
+        match std::fs::remove_dir_all(DB_PATH) {
+            Ok(()) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => {
+                eprintln!("Failed to remove the previous index at {DB_PATH}: {error}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // The first Ctrl-C stops indexing cleanly, keeping what was embedded so
+    // far. A second one exits right away.
+    let (interrupt_tx, interrupt_rx) = smol::channel::bounded(1);
+    ctrlc::set_handler(move || {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+        eprintln!("\nStopping indexing, press Ctrl-C again to exit immediately");
+        interrupt_tx.try_send(()).ok();
+    })
+    .expect("failed to set the Ctrl-C handler");
+
     App::production(Arc::default()).run(async move |cx| {
-        // Initialize HTTP client with base URL
-        let http = Arc::new(HttpClientWithUrl::new(
-            reqwest_client::ReqwestClient::new(),
+        // Reqwest only goes through the proxy it's built with, so pick it up
+        // from the standard environment variables like the app does.
+        let proxy = read_proxy_from_env();
+        // Set CLOUD_INDEX_NO_KEEP_ALIVE to open a new connection for every
+        // embedding request, to compare throughput against reusing them.
+        let pool = if std::env::var_os("CLOUD_INDEX_NO_KEEP_ALIVE").is_some() {
+            ConnectionPool {
+                max_idle_per_host: 0,
+                ..ConnectionPool::default()
+            }
+        } else {
+            ConnectionPool::default()
+        };
+        let http = Arc::new(HttpClientWithUrl::new_url(
+            Arc::new(
+                ReqwestClient::proxy_and_user_agent_with_pool(
+                    proxy.clone(),
+                    "Oppla cloud index example",
+                    pool,
+                )
+                .unwrap(),
+            ),
             "https://app.oppla.ai/home", // This will be mapped to the LLM endpoint
-            None,                        // No proxy
+            proxy,
         ));
 
         // Get client and token
         let client = Client::global(cx);
         let llm_api_token = LlmApiToken::default();
 
-        // Create the cloud embedding provider
-        // Using Together AI's cheapest embedding tier
+        // Create the cloud embedding provider with the model the app uses
         let embedding_provider = Arc::new(CloudEmbeddingProvider::new(
             http.clone(),
-            "together-ai-embedding-up-to-150m".to_string(), // Together AI cheapest tier
+            CLOUD_EMBEDDING_MODEL.to_string(),
             llm_api_token,
             client.clone(),
+            cx.background_executor().clone(),
         ));
 
         cx.spawn(async move |cx| {
             // Initialize semantic index with cloud provider
-            let semantic_index = SemanticDb::new(
-                PathBuf::from("/tmp/cloud-semantic-index-db.mdb"),
-                embedding_provider,
-                cx,
-            );
+            let semantic_index =
+                SemanticDb::new(PathBuf::from(DB_PATH), embedding_provider, cx);
 
             let mut semantic_index = semantic_index.await.unwrap();
 
@@ -58,23 +115,101 @@ fn main() {
             .unwrap();
 
             let project_index = cx
-                .update(|cx| semantic_index.project_index(project.clone(), cx))
+                .update(|cx| semantic_index.create_project_index(project.clone(), cx))
+                .unwrap();
+
+            let (status_tx, status_rx) = smol::channel::unbounded();
+            let subscription = cx
+                .update(|cx| {
+                    cx.subscribe(&project_index, move |_, status: &Status, _| {
+                        status_tx.try_send(*status).ok();
+                    })
+                })
+                .unwrap();
+
+            let index_start = Instant::now();
+            let interrupted = loop {
+                futures::select_biased! {
+                    _ = interrupt_rx.recv().fuse() => break true,
+                    status = status_rx.recv().fuse() => match status {
+                        Ok(Status::Idle) | Err(_) => break false,
+                        Ok(_) => {}
+                    },
+                }
+            };
+            drop(subscription);
+
+            let freshness = cx
+                .update(|cx| project_index.read(cx).freshness(cx))
                 .unwrap()
                 .await
                 .unwrap();
 
-            cx.update(|cx| {
-                let project_index = project_index.read(cx);
-                let query = "function to handle user authentication";
-                println!("Searching for: {}", query);
-                project_index.search(vec![query.into()], 10, cx)
-            })
-            .await
-            .unwrap()
-            .await
-            .unwrap();
+            let counts = semantic_index.embedding_batch_counts();
+            let resumed_file_count = freshness
+                .indexed_file_count
+                .saturating_sub(counts.embedded_files);
+            let progress = format!(
+                "{} chunks embedded ({:.1}/s), {} unchanged chunks reused",
+                counts.embedded_chunks,
+                counts.embedded_chunks as f64 / index_start.elapsed().as_secs_f64(),
+                counts.reused_chunks
+            );
+
+            if interrupted {
+                // Dropping the last handle to the index cancels its embedding
+                // requests. Files embedded before then were already committed.
+                semantic_index.remove_project_index(&project);
+                cx.update(|_| drop(project_index)).unwrap();
+                cx.update(|cx| semantic_index.flush(cx))
+                    .unwrap()
+                    .await
+                    .unwrap();
+
+                println!(
+                    "Stopped after {:?}: {} files indexed, {} left to index, {} embedding requests completed",
+                    index_start.elapsed(),
+                    freshness.indexed_file_count,
+                    freshness.stale_file_count,
+                    counts.completed
+                );
+                println!("{progress}");
+                println!("Run again to pick up where indexing stopped.");
+                cx.update(|cx| cx.quit()).ok();
+                return;
+            }
+
+            println!(
+                "Indexed {} files in {:?}",
+                freshness.indexed_file_count,
+                index_start.elapsed()
+            );
+            if resumed_file_count > 0 {
+                println!(
+                    "{resumed_file_count} files resumed from the previous run, {} embedded now",
+                    counts.embedded_files
+                );
+            }
+            println!("{progress}");
+            if freshness.is_reindex_recommended() {
+                println!(
+                    "Warning: index is {} files behind ({} indexed), results may be stale",
+                    freshness.stale_file_count, freshness.indexed_file_count
+                );
+            }
+
+            let results = cx
+                .update(|cx| {
+                    let project_index = project_index.read(cx);
+                    let query = "function to handle user authentication";
+                    println!("Searching for: {}", query);
+                    project_index.search(vec![query.into()], 10, cx)
+                })
+                .unwrap()
+                .await
+                .unwrap();
 
-            println!("Search completed successfully!");
+            println!("Search completed with {} results", results.len());
         })
         .await
         .unwrap();
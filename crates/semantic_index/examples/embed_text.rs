@@ -0,0 +1,91 @@
+use client::Client;
+use gpui::App;
+use http_client::{HttpClientWithUrl, read_proxy_from_env};
+use language_models::LlmApiToken;
+use semantic_index::{
+    CLOUD_EMBEDDING_MODEL, CloudEmbeddingProvider, EmbeddingProvider as _, TextToEmbed,
+};
+use std::sync::Arc;
+
+/// Embeds the given texts with the cloud embedding provider and prints each
+/// embedding's dimensions, for checking the `/embeddings` integration without
+/// indexing a whole project. With more than one text, each is also compared to
+/// the first.
+fn main() {
+    zlog::init();
+
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let full = take_flag(&mut args, "--full");
+    let model =
+        take_option(&mut args, "--model").unwrap_or_else(|| CLOUD_EMBEDDING_MODEL.to_string());
+    if args.is_empty() {
+        eprintln!("Usage: embed_text [--model <model>] [--full] <text>...");
+        std::process::exit(1);
+    }
+
+    App::production(Arc::default()).run(async move |cx| {
+        let proxy = read_proxy_from_env();
+        let http = Arc::new(HttpClientWithUrl::new_url(
+            Arc::new(
+                reqwest_client::ReqwestClient::proxy_and_user_agent(
+                    proxy.clone(),
+                    "Oppla embed text example",
+                )
+                .unwrap(),
+            ),
+            "https://app.oppla.ai/home", // This will be mapped to the LLM endpoint
+            proxy,
+        ));
+
+        let embedding_provider = CloudEmbeddingProvider::new(
+            http,
+            model.clone(),
+            LlmApiToken::default(),
+            Client::global(cx),
+            cx.background_executor().clone(),
+        );
+
+        cx.spawn(async move |cx| {
+            let texts = args
+                .iter()
+                .map(|text| TextToEmbed::new(text))
+                .collect::<Vec<_>>();
+            let embeddings = match embedding_provider.embed(&texts).await {
+                Ok(embeddings) => embeddings,
+                Err(error) => {
+                    eprintln!("Failed to embed with {model}: {error:#}");
+                    std::process::exit(1);
+                }
+            };
+
+            println!("Model: {model}");
+            for (text, embedding) in args.iter().zip(&embeddings) {
+                println!("{text:?}: {embedding}");
+                if full {
+                    println!("{:?}", embedding.values());
+                }
+            }
+            if let Some((first, rest)) = embeddings.split_first() {
+                for (text, embedding) in args.iter().skip(1).zip(rest) {
+                    let (similarity, _) = first.similarity(std::slice::from_ref(embedding));
+                    println!("Similarity of {text:?} to {:?}: {similarity:.4}", args[0]);
+                }
+            }
+
+            cx.update(|cx| cx.quit()).ok();
+        })
+        .detach();
+    });
+}
+
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let len = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != len
+}
+
+fn take_option(args: &mut Vec<String>, option: &str) -> Option<String> {
+    let index = args.iter().position(|arg| arg == option)?;
+    args.remove(index);
+    (index < args.len()).then(|| args.remove(index))
+}
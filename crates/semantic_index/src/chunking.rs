@@ -28,6 +28,10 @@ pub struct Chunk {
     pub digest: [u8; 32],
 }
 
+/// Splits a file into chunks to embed. When the language's grammar has an
+/// outline query, chunks end on the boundaries of its outline items, such as
+/// functions and types, so they aren't cut off midway. Other files are split
+/// on lines into chunks of roughly the same size.
 pub fn chunk_text(text: &str, language: Option<&Arc<Language>>, path: &Path) -> Vec<Chunk> {
     chunk_text_with_size_range(text, language, path, CHUNK_SIZE_RANGE)
 }
@@ -1,9 +1,11 @@
 mod cloud;
+mod fallback;
 mod lmstudio;
 mod ollama;
 mod open_ai;
 
 pub use cloud::*;
+pub use fallback::*;
 pub use lmstudio::*;
 pub use ollama::*;
 pub use open_ai::*;
@@ -18,6 +20,49 @@ use std::{fmt, future};
 pub trait EmbeddingProvider: Sync + Send {
     fn embed<'a>(&'a self, texts: &'a [TextToEmbed<'a>]) -> BoxFuture<'a, Result<Vec<Embedding>>>;
     fn batch_size(&self) -> usize;
+
+    /// The model embeddings are produced with, recorded in the index so that
+    /// switching models doesn't mix incompatible embeddings.
+    fn model_name(&self) -> Option<String> {
+        None
+    }
+
+    /// Like [`Self::embed`], but leaves out the texts the provider can't embed
+    /// instead of failing the whole batch, and reports the batch's usage.
+    fn embed_allowing_failures<'a>(
+        &'a self,
+        texts: &'a [TextToEmbed<'a>],
+    ) -> BoxFuture<'a, Result<BatchEmbeddings>> {
+        self.embed(texts)
+            .map(|embeddings| {
+                Ok(BatchEmbeddings {
+                    embeddings: embeddings?.into_iter().map(Some).collect(),
+                    token_usage: None,
+                })
+            })
+            .boxed()
+    }
+
+    /// The price in US dollars per million embedded tokens, for providers that
+    /// bill by usage.
+    fn price_per_million_tokens(&self) -> Option<f64> {
+        None
+    }
+
+    /// The quota the provider's backend last reported, for providers whose
+    /// backend sends one.
+    fn rate_limit(&self) -> Option<RateLimitStatus> {
+        None
+    }
+}
+
+/// The embeddings of one batch, in the order of its texts, with `None` for
+/// the texts that couldn't be embedded.
+#[derive(Debug, Default)]
+pub struct BatchEmbeddings {
+    pub embeddings: Vec<Option<Embedding>>,
+    /// The tokens the batch used, for providers that track usage.
+    pub token_usage: Option<u64>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -44,6 +89,11 @@ impl Embedding {
         self.0.len()
     }
 
+    /// The normalized values of each dimension.
+    pub fn values(&self) -> &[f32] {
+        &self.0
+    }
+
     pub fn similarity(&self, others: &[Embedding]) -> (f32, usize) {
         debug_assert!(others.iter().all(|other| self.0.len() == other.0.len()));
         others
@@ -101,6 +151,13 @@ impl<'a> TextToEmbed<'a> {
     }
 }
 
+/// A rough token count for text sent to an embedding model, for when the
+/// provider doesn't report one. Most tokenizers average about four bytes per
+/// token on code and prose.
+pub fn estimate_token_count(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
 pub struct FakeEmbeddingProvider;
 
 impl EmbeddingProvider for FakeEmbeddingProvider {
@@ -1,17 +1,69 @@
-use crate::{Embedding, EmbeddingProvider, TextToEmbed};
+use crate::{
+    BatchEmbeddings, Embedding, EmbeddingProvider, SecretRedactor, TextToEmbed,
+    estimate_token_count,
+};
 use anyhow::{Context as _, Result, anyhow};
 use client::Client;
-use futures::{AsyncReadExt as _, FutureExt, future::BoxFuture};
-use http_client::{AsyncBody, HttpClient, HttpClientWithUrl, Method, Request};
-use language_model::LlmApiToken;
+use futures::{FutureExt, future::BoxFuture};
+use gpui::BackgroundExecutor;
+use http_client::{HttpClientWithUrl, http::HeaderMap};
+use language_model::{LlmApiError, LlmApiToken, LlmHttp, LlmPostOptions};
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{
+    borrow::Cow,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// The model the cloud endpoint embeds project files with.
+pub const CLOUD_EMBEDDING_MODEL: &str = "togethercomputer/m2-bert-80M-32k-retrieval";
+/// Together AI's name for the pricing tier of [`CLOUD_EMBEDDING_MODEL`], which
+/// the cloud endpoint also accepts as a model.
+const CLOUD_EMBEDDING_MODEL_TIER: &str = "together-ai-embedding-up-to-150m";
+
+/// How many times a batch rejected as too large is halved. Batches hold at
+/// most 100 texts, so this is enough to get down to single texts.
+const MAX_BATCH_SPLIT_DEPTH: usize = 7;
+
+/// `X-RateLimit-Reset` values above this are Unix timestamps rather than
+/// seconds from now.
+const MIN_RATE_LIMIT_RESET_TIMESTAMP: u64 = 1_000_000_000;
+/// Requests are only spread out once fewer than this many are left, so a
+/// generous quota doesn't slow indexing down.
+const RATE_LIMIT_PACING_THRESHOLD: u64 = 20;
+
+/// The request quota the embedding endpoint reported in its last response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// Requests left before the quota resets.
+    pub remaining: u64,
+    pub reset_at: SystemTime,
+}
+
+impl RateLimitStatus {
+    /// When requests can resume, if the quota is used up.
+    pub fn paused_until(&self, now: SystemTime) -> Option<SystemTime> {
+        (self.remaining == 0 && self.reset_at > now).then_some(self.reset_at)
+    }
+
+    /// How long to wait before the next request so that the remaining quota
+    /// lasts until it resets.
+    fn delay_before_next_request(&self, now: SystemTime) -> Duration {
+        let until_reset = self.reset_at.duration_since(now).unwrap_or_default();
+        match self.remaining {
+            0 => until_reset,
+            remaining if remaining < RATE_LIMIT_PACING_THRESHOLD => until_reset / remaining as u32,
+            _ => Duration::ZERO,
+        }
+    }
+}
 
 pub struct CloudEmbeddingProvider {
-    http_client: Arc<HttpClientWithUrl>,
+    http: LlmHttp,
     model: String,
-    llm_api_token: LlmApiToken,
-    client: Arc<Client>,
+    secret_redactor: Option<SecretRedactor>,
+    rate_limit: Mutex<Option<RateLimitStatus>>,
 }
 
 impl CloudEmbeddingProvider {
@@ -20,25 +72,253 @@ impl CloudEmbeddingProvider {
         model: String,
         llm_api_token: LlmApiToken,
         client: Arc<Client>,
+        executor: BackgroundExecutor,
     ) -> Self {
         Self {
-            http_client,
+            http: LlmHttp::new(http_client, client, llm_api_token, executor),
             model,
-            llm_api_token,
-            client,
+            secret_redactor: None,
+            rate_limit: Mutex::new(None),
+        }
+    }
+
+    /// Redacts likely secrets from every text before it's sent.
+    pub fn with_secret_redactor(mut self, secret_redactor: SecretRedactor) -> Self {
+        self.secret_redactor = Some(secret_redactor);
+        self
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// The quota the embedding endpoint reported in its last response.
+    pub fn rate_limit(&self) -> Option<RateLimitStatus> {
+        *self.rate_limit.lock()
+    }
+
+    /// Spreads requests out so that the reported quota isn't used up before
+    /// it resets, and waits for the reset when it already is.
+    async fn pace_requests(&self) {
+        let Some(rate_limit) = self.rate_limit() else {
+            return;
+        };
+        let delay = rate_limit.delay_before_next_request(SystemTime::now());
+        if delay.is_zero() {
+            return;
+        }
+        if rate_limit.remaining == 0 {
+            log::warn!("Embedding rate limit reached, pausing for {delay:?} until it resets");
+        }
+        self.http.executor().timer(delay).await;
+    }
+
+    /// Sends a single-token request so that the backend's cold start doesn't
+    /// land on the first real batch. Failures are only logged.
+    pub async fn warm_up(&self) {
+        let started_at = Instant::now();
+        match self.request_embeddings(&[TextToEmbed::new("warmup")]).await {
+            Ok(_) => log::info!(
+                "Warmed up embedding model {} in {:?}",
+                self.model,
+                started_at.elapsed()
+            ),
+            Err(error) => log::warn!("Failed to warm up embedding model {}: {error}", self.model),
+        }
+    }
+
+    /// Embeds a batch, splitting it in half whenever the backend rejects it as
+    /// too large. Texts that are too large on their own are left out.
+    fn embed_splitting_oversized<'a>(
+        &'a self,
+        texts: &'a [TextToEmbed<'a>],
+        depth: usize,
+    ) -> BoxFuture<'a, Result<BatchEmbeddings>> {
+        async move {
+            let error = match self.request_embeddings(texts).await {
+                Ok((embeddings, tokens)) => {
+                    return Ok(BatchEmbeddings {
+                        embeddings: embeddings.into_iter().map(Some).collect(),
+                        token_usage: Some(tokens),
+                    });
+                }
+                Err(error @ LlmApiError::PayloadTooLarge { .. }) => error,
+                Err(error) => return Err(error.into()),
+            };
+
+            if let [text] = texts {
+                log::error!(
+                    "Skipping chunk {} ({} bytes) that is too large to embed: {error}",
+                    hex_prefix(&text.digest),
+                    text.text.len()
+                );
+                return Ok(BatchEmbeddings {
+                    embeddings: vec![None],
+                    token_usage: Some(0),
+                });
+            }
+            if depth >= MAX_BATCH_SPLIT_DEPTH {
+                return Err(error).context(format!(
+                    "batch of {} texts is still too large after splitting {depth} times",
+                    texts.len()
+                ));
+            }
+
+            log::warn!(
+                "Embedding batch of {} texts was too large, retrying in halves",
+                texts.len()
+            );
+            let (left, right) = texts.split_at(texts.len() / 2);
+            let mut batch = self.embed_splitting_oversized(left, depth + 1).await?;
+            let right = self.embed_splitting_oversized(right, depth + 1).await?;
+            batch.embeddings.extend(right.embeddings);
+            batch.token_usage =
+                Some(batch.token_usage.unwrap_or(0) + right.token_usage.unwrap_or(0));
+            Ok(batch)
+        }
+        .boxed()
+    }
+
+    /// Embeds a batch of texts, returning the embeddings with the tokens they
+    /// used, or an error that can be inspected to decide whether to retry the
+    /// batch.
+    pub async fn request_embeddings(
+        &self,
+        texts: &[TextToEmbed<'_>],
+    ) -> Result<(Vec<Embedding>, u64), LlmApiError> {
+        let mut redaction_count = 0;
+        let input = texts
+            .iter()
+            .map(|text| match &self.secret_redactor {
+                Some(secret_redactor) => {
+                    let (redacted, count) = secret_redactor.redact(text.text);
+                    redaction_count += count;
+                    redacted
+                }
+                None => Cow::Borrowed(text.text),
+            })
+            .collect();
+        if redaction_count > 0 {
+            log::info!(
+                "Redacted {redaction_count} secrets from a batch of {} texts before embedding",
+                texts.len()
+            );
         }
+
+        let request = CloudEmbeddingRequest {
+            model: self.model.clone(),
+            input,
+        };
+
+        self.pace_requests().await;
+        let track_rate_limit = |headers: &HeaderMap| {
+            if let Some(rate_limit) = parse_rate_limit(headers, SystemTime::now()) {
+                *self.rate_limit.lock() = Some(rate_limit);
+            }
+        };
+        let options = LlmPostOptions {
+            inspect_headers: Some(&track_rate_limit),
+            ..Default::default()
+        };
+        let response = self.http.post("/embeddings", &request, options).await?;
+        let response = parse_embedding_response(&response.body, texts.len())?;
+
+        let batch_tokens = match response.usage {
+            Some(usage) => usage.total_tokens,
+            None => texts
+                .iter()
+                .map(|text| estimate_token_count(text.text) as u64)
+                .sum(),
+        };
+        let embeddings = response
+            .data
+            .into_iter()
+            .map(|data| Embedding::new(data.embedding))
+            .collect();
+        Ok((embeddings, batch_tokens))
+    }
+}
+
+/// Parses a successful response, rejecting bodies that don't hold an embedding
+/// for every text. Some proxies in front of the backend answer failures with a
+/// 200 and an error object, which would otherwise look like no embeddings.
+fn parse_embedding_response(
+    body: &str,
+    text_count: usize,
+) -> Result<CloudEmbeddingResponse, LlmApiError> {
+    let value: serde_json::Value = serde_json::from_str(body).map_err(LlmApiError::Decode)?;
+    if value.get("data").is_none()
+        && let Some(error) = value.get("error")
+    {
+        let message = error
+            .as_str()
+            .or_else(|| error.get("message")?.as_str())
+            .map_or_else(|| error.to_string(), ToString::to_string);
+        return Err(anyhow!("embedding service returned an error: {message}").into());
+    }
+
+    let response: CloudEmbeddingResponse =
+        serde_json::from_value(value).map_err(LlmApiError::Decode)?;
+    if response.data.len() != text_count {
+        return Err(anyhow!(
+            "embedding service returned {} embeddings for {text_count} texts",
+            response.data.len()
+        )
+        .into());
+    }
+    Ok(response)
+}
+
+fn parse_rate_limit(headers: &HeaderMap, now: SystemTime) -> Option<RateLimitStatus> {
+    let header = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<u64>().ok();
+    let remaining = header("x-ratelimit-remaining")?;
+    let reset = header("x-ratelimit-reset")?;
+    let reset_at = if reset >= MIN_RATE_LIMIT_RESET_TIMESTAMP {
+        UNIX_EPOCH + Duration::from_secs(reset)
+    } else {
+        now + Duration::from_secs(reset)
+    };
+    Some(RateLimitStatus {
+        remaining,
+        reset_at,
+    })
+}
+
+fn hex_prefix(digest: &[u8; 32]) -> String {
+    digest[..6]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Published prices, in US dollars per million tokens, of the embedding models
+/// the cloud endpoint serves.
+fn price_per_million_tokens(model: &str) -> Option<f64> {
+    match model {
+        CLOUD_EMBEDDING_MODEL | CLOUD_EMBEDDING_MODEL_TIER => Some(0.008),
+        "text-embedding-3-small" => Some(0.02),
+        "text-embedding-3-large" => Some(0.13),
+        "text-embedding-ada-002" => Some(0.1),
+        _ => None,
     }
 }
 
 #[derive(Serialize)]
 struct CloudEmbeddingRequest<'a> {
     model: String,
-    input: Vec<&'a str>,
+    input: Vec<Cow<'a, str>>,
 }
 
 #[derive(Deserialize)]
 struct CloudEmbeddingResponse {
     data: Vec<CloudEmbedding>,
+    #[serde(default)]
+    usage: Option<CloudEmbeddingUsage>,
+}
+
+#[derive(Deserialize)]
+struct CloudEmbeddingUsage {
+    total_tokens: u64,
 }
 
 #[derive(Deserialize)]
@@ -48,83 +328,110 @@ struct CloudEmbedding {
 
 impl EmbeddingProvider for CloudEmbeddingProvider {
     fn embed<'a>(&'a self, texts: &'a [TextToEmbed<'a>]) -> BoxFuture<'a, Result<Vec<Embedding>>> {
-        let model = self.model.clone();
-        let http_client = self.http_client.clone();
-        let llm_api_token = self.llm_api_token.clone();
-        let client = self.client.clone();
-
         async move {
-            // Acquire the JWT token
-            let token = llm_api_token
-                .acquire(&client)
-                .await
-                .context("Failed to acquire LLM API token")?;
-
-            // Build the URL using build_zed_llm_url
-            let url = http_client
-                .build_zed_llm_url("/embeddings", &[])
-                .context("Failed to build embedding URL")?;
-
-            // Prepare the request
-            let request = CloudEmbeddingRequest {
-                model,
-                input: texts.iter().map(|t| t.text).collect(),
-            };
-
-            let body =
-                serde_json::to_string(&request).context("Failed to serialize embedding request")?;
-
-            // Build HTTP request with authentication
-            let http_request = Request::builder()
-                .method(Method::POST)
-                .uri(url.as_str())
-                .header("Content-Type", "application/json")
-                .header("Authorization", format!("Bearer {}", token))
-                .body(AsyncBody::from(body))
-                .context("Failed to build HTTP request")?;
-
-            // Send the request
-            let mut response = http_client
-                .send(http_request)
-                .await
-                .context("Failed to send embedding request")?;
-
-            // Check status
-            if !response.status().is_success() {
-                let mut body = String::new();
-                response.body_mut().read_to_string(&mut body).await?;
-                return Err(anyhow!(
-                    "Embedding request failed with status {}: {}",
-                    response.status(),
-                    body
-                ));
-            }
-
-            // Parse response
-            let mut body = String::new();
-            response
-                .body_mut()
-                .read_to_string(&mut body)
-                .await
-                .context("Failed to read response body")?;
-
-            let response: CloudEmbeddingResponse =
-                serde_json::from_str(&body).context("Failed to parse embedding response")?;
-
-            // Convert to Embedding type
-            let embeddings = response
-                .data
+            self.embed_splitting_oversized(texts, 0)
+                .await?
+                .embeddings
                 .into_iter()
-                .map(|data| Embedding::new(data.embedding))
-                .collect();
-
-            Ok(embeddings)
+                .collect::<Option<Vec<_>>>()
+                .context("text is too large to embed")
         }
         .boxed()
     }
 
+    fn embed_allowing_failures<'a>(
+        &'a self,
+        texts: &'a [TextToEmbed<'a>],
+    ) -> BoxFuture<'a, Result<BatchEmbeddings>> {
+        self.embed_splitting_oversized(texts, 0)
+    }
+
     fn batch_size(&self) -> usize {
         // Conservative batch size for cloud API
         100
     }
+
+    fn model_name(&self) -> Option<String> {
+        Some(self.model.clone())
+    }
+
+    fn price_per_million_tokens(&self) -> Option<f64> {
+        price_per_million_tokens(&self.model)
+    }
+
+    fn rate_limit(&self) -> Option<RateLimitStatus> {
+        self.rate_limit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate_limit() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset", "30".parse().unwrap());
+        let rate_limit = parse_rate_limit(&headers, now).unwrap();
+        assert_eq!(rate_limit.reset_at, now + Duration::from_secs(30));
+        assert_eq!(rate_limit.paused_until(now), Some(rate_limit.reset_at));
+        assert_eq!(
+            rate_limit.delay_before_next_request(now),
+            Duration::from_secs(30)
+        );
+
+        headers.insert("x-ratelimit-remaining", "10".parse().unwrap());
+        headers.insert("x-ratelimit-reset", "1700000060".parse().unwrap());
+        let rate_limit = parse_rate_limit(&headers, now).unwrap();
+        assert_eq!(rate_limit.reset_at, now + Duration::from_secs(60));
+        assert_eq!(rate_limit.paused_until(now), None);
+        assert_eq!(
+            rate_limit.delay_before_next_request(now),
+            Duration::from_secs(6)
+        );
+
+        headers.insert("x-ratelimit-remaining", "500".parse().unwrap());
+        let rate_limit = parse_rate_limit(&headers, now).unwrap();
+        assert_eq!(rate_limit.delay_before_next_request(now), Duration::ZERO);
+
+        assert_eq!(parse_rate_limit(&HeaderMap::new(), now), None);
+    }
+
+    #[test]
+    fn test_parse_embedding_response() {
+        let response = parse_embedding_response(
+            r#"{"data":[{"embedding":[0.5,1.0]}],"usage":{"total_tokens":3}}"#,
+            1,
+        )
+        .unwrap();
+        assert_eq!(response.data[0].embedding, [0.5, 1.0]);
+
+        let error = parse_embedding_response(
+            r#"{"error":{"message":"model is overloaded","type":"server_error"}}"#,
+            1,
+        )
+        .unwrap_err();
+        assert!(matches!(error, LlmApiError::Other(_)));
+        assert_eq!(
+            error.to_string(),
+            "embedding service returned an error: model is overloaded"
+        );
+
+        let error = parse_embedding_response(r#"{"error":"unknown model"}"#, 1).unwrap_err();
+        assert!(error.to_string().ends_with("unknown model"));
+
+        let error = parse_embedding_response(r#"{"data":[]}"#, 2).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "embedding service returned 0 embeddings for 2 texts"
+        );
+
+        assert!(matches!(
+            parse_embedding_response("<html>Bad Gateway</html>", 1),
+            Err(LlmApiError::Decode(_))
+        ));
+    }
 }
@@ -0,0 +1,250 @@
+use anyhow::{Context as _, Result, bail};
+use futures::{FutureExt, future::BoxFuture};
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{BatchEmbeddings, Embedding, EmbeddingProvider, RateLimitStatus, TextToEmbed};
+
+/// Embeds with a primary provider, falling back to a secondary provider when
+/// the primary fails (e.g. when the cloud endpoint is unreachable).
+///
+/// Both providers write into the same index, which records the model its
+/// embeddings come from, so both must embed with the same model. The number
+/// of dimensions is also fixed by the first successful batch, and any later
+/// batch that disagrees is rejected rather than stored.
+pub struct FallbackEmbeddingProvider {
+    primary: Arc<dyn EmbeddingProvider>,
+    fallback: Arc<dyn EmbeddingProvider>,
+    /// Zero until the first batch has been embedded.
+    dimensions: AtomicUsize,
+}
+
+impl FallbackEmbeddingProvider {
+    pub fn new(
+        primary: Arc<dyn EmbeddingProvider>,
+        fallback: Arc<dyn EmbeddingProvider>,
+    ) -> Result<Self> {
+        let primary_model = primary.model_name();
+        let fallback_model = fallback.model_name();
+        if primary_model != fallback_model {
+            bail!(
+                "fallback embedding model {:?} differs from the primary model {:?}",
+                fallback_model,
+                primary_model
+            );
+        }
+        Ok(Self {
+            primary,
+            fallback,
+            dimensions: AtomicUsize::new(0),
+        })
+    }
+
+    fn check_dimensions<'a>(
+        &self,
+        embeddings: impl IntoIterator<Item = &'a Embedding>,
+    ) -> Result<()> {
+        for embedding in embeddings {
+            let expected = match self.dimensions.compare_exchange(
+                0,
+                embedding.len(),
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => embedding.len(),
+                Err(expected) => expected,
+            };
+            if embedding.len() != expected {
+                bail!(
+                    "embedding has {} dimensions, but the index uses {}",
+                    embedding.len(),
+                    expected
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl EmbeddingProvider for FallbackEmbeddingProvider {
+    fn embed<'a>(&'a self, texts: &'a [TextToEmbed<'a>]) -> BoxFuture<'a, Result<Vec<Embedding>>> {
+        async move {
+            match self.primary.embed(texts).await {
+                Ok(embeddings) => {
+                    self.check_dimensions(&embeddings)
+                        .context("primary embedding provider is incompatible with the index")?;
+                    log::debug!("embedded {} texts with primary provider", texts.len());
+                    Ok(embeddings)
+                }
+                Err(primary_error) => {
+                    log::warn!(
+                        "primary embedding provider failed, using fallback: {:?}",
+                        primary_error
+                    );
+                    let embeddings = self
+                        .fallback
+                        .embed(texts)
+                        .await
+                        .context("fallback embedding provider failed")?;
+                    self.check_dimensions(&embeddings)
+                        .context("fallback embedding provider is incompatible with the index")?;
+                    log::info!("embedded {} texts with fallback provider", texts.len());
+                    Ok(embeddings)
+                }
+            }
+        }
+        .boxed()
+    }
+
+    fn embed_allowing_failures<'a>(
+        &'a self,
+        texts: &'a [TextToEmbed<'a>],
+    ) -> BoxFuture<'a, Result<BatchEmbeddings>> {
+        async move {
+            match self.primary.embed_allowing_failures(texts).await {
+                Ok(batch) => {
+                    self.check_dimensions(batch.embeddings.iter().flatten())
+                        .context("primary embedding provider is incompatible with the index")?;
+                    log::debug!("embedded {} texts with primary provider", texts.len());
+                    Ok(batch)
+                }
+                Err(primary_error) => {
+                    log::warn!(
+                        "primary embedding provider failed, using fallback: {:?}",
+                        primary_error
+                    );
+                    let batch = self
+                        .fallback
+                        .embed_allowing_failures(texts)
+                        .await
+                        .context("fallback embedding provider failed")?;
+                    self.check_dimensions(batch.embeddings.iter().flatten())
+                        .context("fallback embedding provider is incompatible with the index")?;
+                    log::info!("embedded {} texts with fallback provider", texts.len());
+                    Ok(batch)
+                }
+            }
+        }
+        .boxed()
+    }
+
+    fn batch_size(&self) -> usize {
+        // Any batch may end up being sent to either provider.
+        self.primary.batch_size().min(self.fallback.batch_size())
+    }
+
+    fn model_name(&self) -> Option<String> {
+        // Both providers embed with this model, so it's accurate whichever
+        // of them produced a batch.
+        self.primary.model_name()
+    }
+
+    fn price_per_million_tokens(&self) -> Option<f64> {
+        self.primary.price_per_million_tokens()
+    }
+
+    fn rate_limit(&self) -> Option<RateLimitStatus> {
+        self.primary.rate_limit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::future;
+
+    struct TestProvider {
+        model: &'static str,
+        /// Zero while the provider is unavailable.
+        dimensions: AtomicUsize,
+        batch_size: usize,
+    }
+
+    impl EmbeddingProvider for TestProvider {
+        fn embed<'a>(
+            &'a self,
+            texts: &'a [TextToEmbed<'a>],
+        ) -> BoxFuture<'a, Result<Vec<Embedding>>> {
+            let result = match self.dimensions.load(Ordering::SeqCst) {
+                0 => Err(anyhow!("provider is unavailable")),
+                dimensions => Ok(texts
+                    .iter()
+                    .map(|_| Embedding::new(vec![1.0; dimensions]))
+                    .collect()),
+            };
+            future::ready(result).boxed()
+        }
+
+        fn batch_size(&self) -> usize {
+            self.batch_size
+        }
+
+        fn model_name(&self) -> Option<String> {
+            Some(self.model.to_string())
+        }
+    }
+
+    fn test_provider(
+        model: &'static str,
+        dimensions: usize,
+        batch_size: usize,
+    ) -> Arc<TestProvider> {
+        Arc::new(TestProvider {
+            model,
+            dimensions: AtomicUsize::new(dimensions),
+            batch_size,
+        })
+    }
+
+    #[gpui::test]
+    async fn test_fallback_embedding_provider() {
+        let texts = [TextToEmbed::new("one"), TextToEmbed::new("two")];
+
+        let provider = FallbackEmbeddingProvider::new(
+            test_provider("model", 0, 100),
+            test_provider("model", 3, 16),
+        )
+        .unwrap();
+        assert_eq!(provider.batch_size(), 16);
+        assert_eq!(provider.model_name().as_deref(), Some("model"));
+        let embeddings = provider.embed(&texts).await.unwrap();
+        assert_eq!(embeddings.len(), 2);
+        assert!(embeddings.iter().all(|embedding| embedding.len() == 3));
+        let batch = provider.embed_allowing_failures(&texts).await.unwrap();
+        assert_eq!(batch.token_usage, None);
+        assert!(
+            batch
+                .embeddings
+                .iter()
+                .all(|embedding| embedding.as_ref().unwrap().len() == 3)
+        );
+
+        // Once the primary has fixed the dimensions, a fallback that
+        // disagrees is rejected.
+        let primary = test_provider("model", 3, 16);
+        let provider =
+            FallbackEmbeddingProvider::new(primary.clone(), test_provider("model", 4, 16)).unwrap();
+        assert!(provider.embed(&texts).await.is_ok());
+        primary.dimensions.store(0, Ordering::SeqCst);
+        assert!(provider.embed(&texts).await.is_err());
+        assert!(provider.embed_allowing_failures(&texts).await.is_err());
+
+        let provider = FallbackEmbeddingProvider::new(
+            test_provider("model", 0, 16),
+            test_provider("model", 0, 16),
+        )
+        .unwrap();
+        assert!(provider.embed(&texts).await.is_err());
+
+        assert!(
+            FallbackEmbeddingProvider::new(
+                test_provider("model", 3, 16),
+                test_provider("other-model", 3, 16),
+            )
+            .is_err()
+        );
+    }
+}
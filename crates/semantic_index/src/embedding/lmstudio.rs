@@ -26,6 +26,14 @@ struct LmStudioEmbeddingResponse {
     embedding: Vec<f32>,
 }
 
+impl LmStudioEmbeddingModel {
+    fn name(&self) -> &'static str {
+        match self {
+            LmStudioEmbeddingModel::NomicEmbedText => "nomic-embed-text",
+        }
+    }
+}
+
 impl LmStudioEmbeddingProvider {
     pub fn new(client: Arc<dyn HttpClient>, model: LmStudioEmbeddingModel) -> Self {
         Self { client, model }
@@ -34,9 +42,7 @@ impl LmStudioEmbeddingProvider {
 
 impl EmbeddingProvider for LmStudioEmbeddingProvider {
     fn embed<'a>(&'a self, texts: &'a [TextToEmbed<'a>]) -> BoxFuture<'a, Result<Vec<Embedding>>> {
-        let model = match self.model {
-            LmStudioEmbeddingModel::NomicEmbedText => "nomic-embed-text",
-        };
+        let model = self.model.name();
 
         futures::future::try_join_all(texts.iter().map(|to_embed| {
             let request = LmStudioEmbeddingRequest {
@@ -67,4 +73,8 @@ impl EmbeddingProvider for LmStudioEmbeddingProvider {
     fn batch_size(&self) -> usize {
         256
     }
+
+    fn model_name(&self) -> Option<String> {
+        Some(self.model.name().to_string())
+    }
 }
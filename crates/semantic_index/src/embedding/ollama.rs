@@ -27,6 +27,15 @@ struct OllamaEmbeddingResponse {
     embedding: Vec<f32>,
 }
 
+impl OllamaEmbeddingModel {
+    fn name(&self) -> &'static str {
+        match self {
+            OllamaEmbeddingModel::NomicEmbedText => "nomic-embed-text",
+            OllamaEmbeddingModel::MxbaiEmbedLarge => "mxbai-embed-large",
+        }
+    }
+}
+
 impl OllamaEmbeddingProvider {
     pub fn new(client: Arc<dyn HttpClient>, model: OllamaEmbeddingModel) -> Self {
         Self { client, model }
@@ -35,11 +44,7 @@ impl OllamaEmbeddingProvider {
 
 impl EmbeddingProvider for OllamaEmbeddingProvider {
     fn embed<'a>(&'a self, texts: &'a [TextToEmbed<'a>]) -> BoxFuture<'a, Result<Vec<Embedding>>> {
-        //
-        let model = match self.model {
-            OllamaEmbeddingModel::NomicEmbedText => "nomic-embed-text",
-            OllamaEmbeddingModel::MxbaiEmbedLarge => "mxbai-embed-large",
-        };
+        let model = self.model.name();
 
         futures::future::try_join_all(texts.iter().map(|to_embed| {
             let request = OllamaEmbeddingRequest {
@@ -71,4 +76,8 @@ impl EmbeddingProvider for OllamaEmbeddingProvider {
         // TODO: Figure out decent value
         10
     }
+
+    fn model_name(&self) -> Option<String> {
+        Some(self.model.name().to_string())
+    }
 }
@@ -52,4 +52,12 @@ impl EmbeddingProvider for OpenAiEmbeddingProvider {
         // From https://platform.openai.com/docs/api-reference/embeddings/create
         2048
     }
+
+    fn model_name(&self) -> Option<String> {
+        let name = match self.model {
+            OpenAiEmbeddingModel::TextEmbedding3Small => "text-embedding-3-small",
+            OpenAiEmbeddingModel::TextEmbedding3Large => "text-embedding-3-large",
+        };
+        Some(name.to_string())
+    }
 }
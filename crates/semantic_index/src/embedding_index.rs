@@ -1,10 +1,12 @@
 use crate::{
+    IndexFreshness, IndexingEstimate,
     chunking::{self, Chunk},
-    embedding::{Embedding, EmbeddingProvider, TextToEmbed},
+    embedding::{Embedding, EmbeddingProvider, TextToEmbed, estimate_token_count},
+    index_metadata::{self, IndexMetadata, IndexMetadataDb},
     indexing::{IndexingEntryHandle, IndexingEntrySet},
 };
 use anyhow::{Context as _, Result};
-use collections::Bound;
+use collections::{Bound, HashMap, HashSet};
 use fs::Fs;
 use fs::MTime;
 use futures::{FutureExt as _, stream::StreamExt};
@@ -13,10 +15,21 @@ use gpui::{App, AppContext as _, Entity, Task};
 use heed::types::{SerdeBincode, Str};
 use language::LanguageRegistry;
 use log;
+use parking_lot::Mutex;
 use project::{Entry, UpdatedEntriesSet, Worktree};
 use serde::{Deserialize, Serialize};
 use smol::channel;
-use std::{cmp::Ordering, future::Future, iter, path::Path, pin::pin, sync::Arc, time::Duration};
+use std::{
+    cmp::Ordering,
+    future::Future,
+    path::Path,
+    pin::pin,
+    sync::{
+        Arc,
+        atomic::{self, AtomicUsize},
+    },
+    time::Duration,
+};
 use util::ResultExt;
 use worktree::Snapshot;
 
@@ -24,20 +37,129 @@ pub struct EmbeddingIndex {
     worktree: Entity<Worktree>,
     db_connection: heed::Env,
     db: heed::Database<Str, SerdeBincode<EmbeddedFile>>,
+    metadata_db: IndexMetadataDb,
+    /// This worktree's key in `metadata_db`.
+    metadata_key: String,
+    /// Why the stored embeddings can't be used with the current embedding
+    /// provider, until the index is cleared.
+    incompatibility: Arc<Mutex<Option<String>>>,
     fs: Arc<dyn Fs>,
     language_registry: Arc<LanguageRegistry>,
     embedding_provider: Arc<dyn EmbeddingProvider>,
+    embedding_batches: Arc<EmbeddingBatchCounter>,
     entry_ids_being_indexed: Arc<IndexingEntrySet>,
 }
 
+/// How many embedding requests are waiting to be sent, being sent, and done,
+/// across every worktree indexed by a [`crate::SemanticDb`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct EmbeddingBatchCounts {
+    pub pending: usize,
+    pub in_flight: usize,
+    /// Batches that finished since startup, whether or not they succeeded.
+    pub completed: usize,
+    /// Files whose every chunk was embedded since startup, ready to be stored.
+    pub embedded_files: usize,
+    /// Chunks sent to the provider and embedded since startup.
+    pub embedded_chunks: usize,
+    /// Chunks of changed files whose text hadn't changed since they were
+    /// last embedded, so their stored embeddings were kept.
+    pub reused_chunks: usize,
+}
+
+impl EmbeddingBatchCounts {
+    pub fn remaining(&self) -> usize {
+        self.pending + self.in_flight
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct EmbeddingBatchCounter {
+    pending: AtomicUsize,
+    in_flight: AtomicUsize,
+    completed: AtomicUsize,
+    embedded_files: AtomicUsize,
+    embedded_chunks: AtomicUsize,
+    reused_chunks: AtomicUsize,
+}
+
+impl EmbeddingBatchCounter {
+    pub fn counts(&self) -> EmbeddingBatchCounts {
+        EmbeddingBatchCounts {
+            pending: self.pending.load(atomic::Ordering::Relaxed),
+            in_flight: self.in_flight.load(atomic::Ordering::Relaxed),
+            completed: self.completed.load(atomic::Ordering::Relaxed),
+            embedded_files: self.embedded_files.load(atomic::Ordering::Relaxed),
+            embedded_chunks: self.embedded_chunks.load(atomic::Ordering::Relaxed),
+            reused_chunks: self.reused_chunks.load(atomic::Ordering::Relaxed),
+        }
+    }
+
+    fn enqueue(self: &Arc<Self>, batch_count: usize) -> QueuedBatches {
+        self.pending
+            .fetch_add(batch_count, atomic::Ordering::Relaxed);
+        QueuedBatches {
+            counter: self.clone(),
+            pending: batch_count,
+            in_flight: false,
+        }
+    }
+}
+
+/// Batches of one group of files, which are taken out of the counts when
+/// dropped so that cancelled indexing doesn't leave them pending forever.
+struct QueuedBatches {
+    counter: Arc<EmbeddingBatchCounter>,
+    pending: usize,
+    in_flight: bool,
+}
+
+impl QueuedBatches {
+    fn start(&mut self) {
+        self.pending -= 1;
+        self.in_flight = true;
+        self.counter.pending.fetch_sub(1, atomic::Ordering::Relaxed);
+        self.counter
+            .in_flight
+            .fetch_add(1, atomic::Ordering::Relaxed);
+    }
+
+    fn finish(&mut self) {
+        self.in_flight = false;
+        self.counter
+            .in_flight
+            .fetch_sub(1, atomic::Ordering::Relaxed);
+        self.counter
+            .completed
+            .fetch_add(1, atomic::Ordering::Relaxed);
+    }
+}
+
+impl Drop for QueuedBatches {
+    fn drop(&mut self) {
+        self.counter
+            .pending
+            .fetch_sub(self.pending, atomic::Ordering::Relaxed);
+        if self.in_flight {
+            self.counter
+                .in_flight
+                .fetch_sub(1, atomic::Ordering::Relaxed);
+        }
+    }
+}
+
 impl EmbeddingIndex {
     pub fn new(
         worktree: Entity<Worktree>,
         fs: Arc<dyn Fs>,
         db_connection: heed::Env,
         embedding_db: heed::Database<Str, SerdeBincode<EmbeddedFile>>,
+        metadata_db: IndexMetadataDb,
+        metadata_key: String,
+        incompatibility: Option<String>,
         language_registry: Arc<LanguageRegistry>,
         embedding_provider: Arc<dyn EmbeddingProvider>,
+        embedding_batches: Arc<EmbeddingBatchCounter>,
         entry_ids_being_indexed: Arc<IndexingEntrySet>,
     ) -> Self {
         Self {
@@ -45,8 +167,12 @@ impl EmbeddingIndex {
             fs,
             db_connection,
             db: embedding_db,
+            metadata_db,
+            metadata_key,
+            incompatibility: Arc::new(Mutex::new(incompatibility)),
             language_registry,
             embedding_provider,
+            embedding_batches,
             entry_ids_being_indexed,
         }
     }
@@ -55,19 +181,38 @@ impl EmbeddingIndex {
         &self.db
     }
 
+    /// Why the stored embeddings can't be searched with the current embedding
+    /// provider, if they can't. Cleared by [`Self::clear`].
+    pub fn incompatibility(&self) -> Option<String> {
+        self.incompatibility.lock().clone()
+    }
+
+    /// Indexes the files that changed since they were last indexed, resolving
+    /// to the tokens it used, for providers that track usage.
     pub fn index_entries_changed_on_disk(
         &self,
         cx: &App,
-    ) -> impl Future<Output = Result<()>> + use<> {
+    ) -> impl Future<Output = Result<Option<u64>>> + use<> {
+        // New embeddings would be mixed with incompatible ones, so nothing is
+        // indexed until the index is rebuilt.
+        if self.incompatibility().is_some() {
+            return futures::future::ready(Ok(None)).boxed();
+        }
         let worktree = self.worktree.read(cx).snapshot();
         let worktree_abs_path = worktree.abs_path().clone();
         let scan = self.scan_entries(worktree, cx);
         let chunk = self.chunk_files(worktree_abs_path, scan.updated_entries, cx);
-        let embed = Self::embed_files(self.embedding_provider.clone(), chunk.files, cx);
+        let embed = Self::embed_files(
+            self.embedding_provider.clone(),
+            self.embedding_batches.clone(),
+            chunk.files,
+            cx,
+        );
         let persist = self.persist_embeddings(scan.deleted_entry_ranges, embed.files, cx);
         async move {
-            futures::try_join!(scan.task, chunk.task, embed.task, persist)?;
-            Ok(())
+            let (_, _, token_usage, _) =
+                futures::try_join!(scan.task, chunk.task, embed.task, persist)?;
+            Ok(token_usage)
         }
         .boxed()
     }
@@ -77,11 +222,19 @@ impl EmbeddingIndex {
         updated_entries: UpdatedEntriesSet,
         cx: &App,
     ) -> impl Future<Output = Result<()>> + use<> {
+        if self.incompatibility().is_some() {
+            return futures::future::ready(Ok(())).boxed();
+        }
         let worktree = self.worktree.read(cx).snapshot();
         let worktree_abs_path = worktree.abs_path().clone();
         let scan = self.scan_updated_entries(worktree, updated_entries.clone(), cx);
         let chunk = self.chunk_files(worktree_abs_path, scan.updated_entries, cx);
-        let embed = Self::embed_files(self.embedding_provider.clone(), chunk.files, cx);
+        let embed = Self::embed_files(
+            self.embedding_provider.clone(),
+            self.embedding_batches.clone(),
+            chunk.files,
+            cx,
+        );
         let persist = self.persist_embeddings(scan.deleted_entry_ranges, embed.files, cx);
         async move {
             futures::try_join!(scan.task, chunk.task, embed.task, persist)?;
@@ -90,6 +243,91 @@ impl EmbeddingIndex {
         .boxed()
     }
 
+    /// Drops every stored embedding for this worktree, so that it can be
+    /// indexed again with the current embedding provider.
+    pub fn clear(&self, cx: &App) -> Task<Result<()>> {
+        let db_connection = self.db_connection.clone();
+        let db = self.db;
+        let metadata_db = self.metadata_db;
+        let metadata_key = self.metadata_key.clone();
+        let metadata = IndexMetadata::current(self.embedding_provider.as_ref());
+        let incompatibility = self.incompatibility.clone();
+        cx.background_spawn(async move {
+            let mut txn = db_connection
+                .write_txn()
+                .context("failed to create write transaction")?;
+            db.clear(&mut txn).context("failed to clear embeddings")?;
+            metadata_db
+                .put(&mut txn, &metadata_key, &metadata)
+                .context("failed to write index metadata")?;
+            txn.commit()
+                .context("failed to commit cleared embeddings")?;
+            *incompatibility.lock() = None;
+            Ok(())
+        })
+    }
+
+    /// Compares the worktree against the database to find how many files were
+    /// added, modified or deleted since they were last indexed.
+    pub fn freshness(&self, cx: &App) -> Task<Result<IndexFreshness>> {
+        let worktree = self.worktree.read(cx).snapshot();
+        let db_connection = self.db_connection.clone();
+        let db = self.db;
+        cx.background_spawn(async move {
+            let txn = db_connection
+                .read_txn()
+                .context("failed to create read transaction")?;
+            let mut indexed_mtimes = HashMap::default();
+            for db_entry in db.iter(&txn).context("failed to iterate database")? {
+                let (db_path, db_embedded_file) = db_entry?;
+                indexed_mtimes.insert(db_path.to_string(), db_embedded_file.mtime);
+            }
+
+            let indexed_file_count = indexed_mtimes.len();
+            let mut stale_file_count = 0;
+            for entry in worktree.files(false, 0) {
+                match indexed_mtimes.remove(&db_key_for_path(&entry.path)) {
+                    Some(mtime) if mtime == entry.mtime => {}
+                    _ => stale_file_count += 1,
+                }
+            }
+            // Anything left in the index no longer exists in the worktree.
+            stale_file_count += indexed_mtimes.len();
+
+            Ok(IndexFreshness {
+                indexed_file_count,
+                stale_file_count,
+            })
+        })
+    }
+
+    /// Chunks every file in the worktree, as a full reindex would, without
+    /// embedding anything.
+    pub fn estimate_reindex(&self, cx: &App) -> Task<Result<IndexingEstimate>> {
+        let worktree = self.worktree.read(cx).snapshot();
+        let worktree_abs_path = worktree.abs_path().clone();
+        let fs = self.fs.clone();
+        let language_registry = self.language_registry.clone();
+        cx.background_spawn(async move {
+            let mut estimate = IndexingEstimate::default();
+            for entry in worktree.files(false, 0) {
+                let Some(text) = fs.load(&worktree_abs_path.join(&entry.path)).await.ok() else {
+                    continue;
+                };
+                let language = language_registry
+                    .language_for_file_path(&entry.path)
+                    .await
+                    .ok();
+                estimate.file_count += 1;
+                for chunk in chunking::chunk_text(&text, language.as_ref(), &entry.path) {
+                    estimate.chunk_count += 1;
+                    estimate.token_count += estimate_token_count(&text[chunk.range]);
+                }
+            }
+            Ok(estimate)
+        })
+    }
+
     fn scan_entries(&self, worktree: Snapshot, cx: &App) -> ScanEntries {
         let (updated_entries_tx, updated_entries_rx) = channel::bounded(512);
         let (deleted_entry_ranges_tx, deleted_entry_ranges_rx) = channel::bounded(128);
@@ -222,6 +460,8 @@ impl EmbeddingIndex {
     ) -> ChunkFiles {
         let language_registry = self.language_registry.clone();
         let fs = self.fs.clone();
+        let db_connection = self.db_connection.clone();
+        let db = self.db;
         let (chunked_files_tx, chunked_files_rx) = channel::bounded(2048);
         let task = cx.spawn(async move |cx| {
             cx.background_executor()
@@ -235,12 +475,17 @@ impl EmbeddingIndex {
                                         .language_for_file_path(&entry.path)
                                         .await
                                         .ok();
+                                    let chunks =
+                                        chunking::chunk_text(&text, language.as_ref(), &entry.path);
+                                    let previous_embeddings = previous_embeddings(
+                                        &db_connection,
+                                        db,
+                                        &entry.path,
+                                        &chunks,
+                                    );
                                     let chunked_file = ChunkedFile {
-                                        chunks: chunking::chunk_text(
-                                            &text,
-                                            language.as_ref(),
-                                            &entry.path,
-                                        ),
+                                        chunks,
+                                        previous_embeddings,
                                         handle,
                                         path: entry.path,
                                         mtime: entry.mtime,
@@ -267,12 +512,14 @@ impl EmbeddingIndex {
 
     pub fn embed_files(
         embedding_provider: Arc<dyn EmbeddingProvider>,
+        embedding_batches: Arc<EmbeddingBatchCounter>,
         chunked_files: channel::Receiver<ChunkedFile>,
         cx: &App,
     ) -> EmbedFiles {
         let embedding_provider = embedding_provider.clone();
         let (embedded_files_tx, embedded_files_rx) = channel::bounded(512);
         let task = cx.background_spawn(async move {
+            let mut token_usage = None;
             let mut chunked_file_batches =
                 pin!(chunked_files.chunks_timeout(512, Duration::from_secs(2)));
             while let Some(chunked_files) = chunked_file_batches.next().await {
@@ -281,32 +528,67 @@ impl EmbeddingIndex {
                 // Once those are done, reassemble them back into the files in which they belong
                 // If any embeddings fail for a file, the entire file is discarded
 
-                let chunks: Vec<TextToEmbed> = chunked_files
-                    .iter()
-                    .flat_map(|file| {
-                        file.chunks.iter().map(|chunk| TextToEmbed {
-                            text: &file.text[chunk.range.clone()],
-                            digest: chunk.digest,
-                        })
-                    })
-                    .collect::<Vec<_>>();
-
+                // Chunks whose text hasn't changed since the file was last
+                // embedded keep their embeddings, so that indexing that was
+                // interrupted, or a small edit to a large file, only embeds
+                // what's new.
                 let mut embeddings: Vec<Option<Embedding>> = Vec::new();
-                for embedding_batch in chunks.chunks(embedding_provider.batch_size()) {
-                    if let Some(batch_embeddings) =
-                        embedding_provider.embed(embedding_batch).await.log_err()
-                    {
-                        if batch_embeddings.len() == embedding_batch.len() {
-                            embeddings.extend(batch_embeddings.into_iter().map(Some));
-                            continue;
+                let mut chunks = Vec::new();
+                let mut chunk_indices = Vec::new();
+                for file in &chunked_files {
+                    for chunk in &file.chunks {
+                        match file.previous_embeddings.get(&chunk.digest) {
+                            Some(embedding) => embeddings.push(Some(embedding.clone())),
+                            None => {
+                                chunk_indices.push(embeddings.len());
+                                embeddings.push(None);
+                                chunks.push(TextToEmbed {
+                                    text: &file.text[chunk.range.clone()],
+                                    digest: chunk.digest,
+                                });
+                            }
                         }
+                    }
+                }
+                embedding_batches
+                    .reused_chunks
+                    .fetch_add(embeddings.len() - chunks.len(), atomic::Ordering::Relaxed);
+
+                let mut chunk_indices = chunk_indices.into_iter();
+                let embedding_batch_chunks = chunks.chunks(embedding_provider.batch_size());
+                let mut queued_batches = embedding_batches.enqueue(embedding_batch_chunks.len());
+                for embedding_batch in embedding_batch_chunks {
+                    queued_batches.start();
+                    let batch = embedding_provider
+                        .embed_allowing_failures(embedding_batch)
+                        .await
+                        .log_err();
+                    queued_batches.finish();
+                    let batch_indices = chunk_indices
+                        .by_ref()
+                        .take(embedding_batch.len())
+                        .collect::<Vec<_>>();
+                    let Some(batch) = batch else {
+                        continue;
+                    };
+                    if let Some(tokens) = batch.token_usage {
+                        *token_usage.get_or_insert(0) += tokens;
+                    }
+                    let batch_embeddings = batch.embeddings;
+                    if batch_embeddings.len() != embedding_batch.len() {
                         log::error!(
                             "embedding provider returned unexpected embedding count {}, expected {}",
                             batch_embeddings.len(), embedding_batch.len()
                         );
+                        continue;
+                    }
+                    embedding_batches.embedded_chunks.fetch_add(
+                        batch_embeddings.iter().flatten().count(),
+                        atomic::Ordering::Relaxed,
+                    );
+                    for (index, embedding) in batch_indices.zip(batch_embeddings) {
+                        embeddings[index] = embedding;
                     }
-
-                    embeddings.extend(iter::repeat(None).take(embedding_batch.len()));
                 }
 
                 let mut embeddings = embeddings.into_iter();
@@ -331,13 +613,16 @@ impl EmbeddingIndex {
                     }
 
                     if embedded_all_chunks {
+                        embedding_batches
+                            .embedded_files
+                            .fetch_add(1, atomic::Ordering::Relaxed);
                         embedded_files_tx
                             .send((embedded_file, chunked_file.handle))
                             .await?;
                     }
                 }
             }
-            Ok(())
+            Ok(token_usage)
         });
 
         EmbedFiles {
@@ -354,10 +639,14 @@ impl EmbeddingIndex {
     ) -> Task<Result<()>> {
         let db_connection = self.db_connection.clone();
         let db = self.db;
+        let metadata_db = self.metadata_db;
+        let metadata_key = self.metadata_key.clone();
+        let incompatibility = self.incompatibility.clone();
 
         cx.background_spawn(async move {
             let mut deleted_entry_ranges = pin!(deleted_entry_ranges);
             let mut embedded_files = pin!(embedded_files);
+            let mut dimensions_checked = false;
             loop {
                 // Interleave deletions and persists of embedded files
                 futures::select_biased! {
@@ -373,7 +662,26 @@ impl EmbeddingIndex {
                     },
                     file = embedded_files.next() => {
                         if let Some((file, _)) = file {
+                            // Embeddings of a different size can't be mixed
+                            // with the stored ones, so they're dropped until
+                            // the index is rebuilt.
+                            if incompatibility.lock().is_some() {
+                                continue;
+                            }
                             let mut txn = db_connection.write_txn()?;
+                            if let Some(chunk) = file.chunks.first().filter(|_| !dimensions_checked) {
+                                dimensions_checked = true;
+                                if let Some(mismatch) = index_metadata::record_dimensions(
+                                    metadata_db,
+                                    &mut txn,
+                                    &metadata_key,
+                                    chunk.embedding.len(),
+                                )? {
+                                    log::warn!("{metadata_key}: {mismatch}");
+                                    *incompatibility.lock() = Some(mismatch);
+                                    continue;
+                                }
+                            }
                             log::debug!("saving embedding for file {:?}", file.path);
                             let key = db_key_for_path(&file.path);
                             db.put(&mut txn, &key, &file)?;
@@ -437,11 +745,15 @@ pub struct ChunkedFile {
     pub handle: IndexingEntryHandle,
     pub text: String,
     pub chunks: Vec<Chunk>,
+    /// The embeddings stored for the file when it was last indexed that can be
+    /// reused for its current chunks, by the digest of the chunk they embed.
+    pub previous_embeddings: HashMap<[u8; 32], Embedding>,
 }
 
 pub struct EmbedFiles {
     pub files: channel::Receiver<(EmbeddedFile, IndexingEntryHandle)>,
-    pub task: Task<Result<()>>,
+    /// Resolves to the tokens the files used, for providers that track usage.
+    pub task: Task<Result<Option<u64>>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -457,6 +769,35 @@ pub struct EmbeddedChunk {
     pub embedding: Embedding,
 }
 
+fn previous_embeddings(
+    db_connection: &heed::Env,
+    db: heed::Database<Str, SerdeBincode<EmbeddedFile>>,
+    path: &Arc<Path>,
+    chunks: &[Chunk],
+) -> HashMap<[u8; 32], Embedding> {
+    if chunks.is_empty() {
+        return HashMap::default();
+    }
+    let digests = chunks
+        .iter()
+        .map(|chunk| chunk.digest)
+        .collect::<HashSet<_>>();
+    let Some(txn) = db_connection.read_txn().log_err() else {
+        return HashMap::default();
+    };
+    db.get(&txn, &db_key_for_path(path))
+        .log_err()
+        .flatten()
+        .map(|file| {
+            file.chunks
+                .into_iter()
+                .filter(|chunk| digests.contains(&chunk.chunk.digest))
+                .map(|chunk| (chunk.chunk.digest, chunk.embedding))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 fn db_key_for_path(path: &Arc<Path>) -> String {
     path.to_string_lossy().replace('/', "\0")
 }
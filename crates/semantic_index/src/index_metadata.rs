@@ -0,0 +1,236 @@
+use crate::embedding::EmbeddingProvider;
+use anyhow::{Context as _, Result};
+use heed::types::{SerdeBincode, Str};
+use serde::{Deserialize, Serialize};
+
+/// Bump this whenever chunking changes in a way that makes existing chunks,
+/// and so their embeddings, differ from what a fresh index would contain.
+pub const CHUNKER_VERSION: u32 = 1;
+
+const METADATA_DB_NAME: &str = "index-metadata";
+
+/// What a worktree's embeddings were produced with. Embeddings from different
+/// models, or of chunks cut differently, can't be compared with each other, so
+/// an index whose metadata no longer matches has to be rebuilt before use.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndexMetadata {
+    pub model: Option<String>,
+    /// Unknown until the first embedding is stored.
+    pub dimensions: Option<usize>,
+    pub chunker_version: u32,
+}
+
+impl IndexMetadata {
+    pub fn current(embedding_provider: &dyn EmbeddingProvider) -> Self {
+        Self {
+            model: embedding_provider.model_name(),
+            dimensions: None,
+            chunker_version: CHUNKER_VERSION,
+        }
+    }
+
+    /// Why embeddings described by `self` can't be searched alongside ones
+    /// described by `current`, if they can't.
+    pub fn incompatibility(&self, current: &Self) -> Option<String> {
+        if let (Some(stored), Some(current)) = (&self.model, &current.model)
+            && stored != current
+        {
+            return Some(format!(
+                "the semantic index was built with the {stored} embedding model, but {current} is configured now"
+            ));
+        }
+        if let (Some(stored), Some(current)) = (self.dimensions, current.dimensions)
+            && stored != current
+        {
+            return Some(format!(
+                "the semantic index has {stored}-dimensional embeddings, but the embedding model produces {current}"
+            ));
+        }
+        if self.chunker_version != current.chunker_version {
+            return Some(format!(
+                "the semantic index was chunked by an older version of Oppla (chunker version {})",
+                self.chunker_version
+            ));
+        }
+        None
+    }
+}
+
+/// The metadata of every worktree's index, keyed by the name of the database
+/// holding the worktree's embeddings.
+pub type IndexMetadataDb = heed::Database<Str, SerdeBincode<IndexMetadata>>;
+
+pub fn open_metadata_db(
+    db_connection: &heed::Env,
+    txn: &mut heed::RwTxn,
+) -> Result<IndexMetadataDb> {
+    db_connection
+        .create_database(txn, Some(METADATA_DB_NAME))
+        .context("failed to open index metadata database")
+}
+
+/// Compares a worktree's stored metadata against `current`, recording
+/// `current` if the worktree is indexed for the first time. Indices built
+/// before metadata was recorded are assumed to match, rather than forcing
+/// everyone to rebuild, and are left without metadata since what they were
+/// built with isn't known.
+pub fn check_metadata(
+    db: IndexMetadataDb,
+    txn: &mut heed::RwTxn,
+    key: &str,
+    current: &IndexMetadata,
+    has_embeddings: bool,
+) -> Result<Option<String>> {
+    match db.get(txn, key).context("failed to read index metadata")? {
+        Some(stored) => Ok(stored.incompatibility(current)),
+        None => {
+            if !has_embeddings {
+                db.put(txn, key, current)
+                    .context("failed to write index metadata")?;
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Records the dimensions of a worktree's embeddings the first time one is
+/// stored. Returns why the index can't be used when the embedding's size
+/// differs from the recorded one.
+pub fn record_dimensions(
+    db: IndexMetadataDb,
+    txn: &mut heed::RwTxn,
+    key: &str,
+    dimensions: usize,
+) -> Result<Option<String>> {
+    let Some(mut metadata) = db.get(txn, key).context("failed to read index metadata")? else {
+        return Ok(None);
+    };
+    match metadata.dimensions {
+        Some(stored) if stored != dimensions => Ok(Some(format!(
+            "the semantic index has {stored}-dimensional embeddings, but the embedding model produces {dimensions}"
+        ))),
+        Some(_) => Ok(None),
+        None => {
+            metadata.dimensions = Some(dimensions);
+            db.put(txn, key, &metadata)
+                .context("failed to write index metadata")?;
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_env() -> (tempfile::TempDir, heed::Env) {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe {
+            heed::EnvOpenOptions::new()
+                .map_size(1024 * 1024)
+                .max_dbs(4)
+                .open(dir.path())
+                .unwrap()
+        };
+        (dir, env)
+    }
+
+    #[test]
+    fn test_check_metadata() {
+        let (_dir, env) = open_env();
+        let mut txn = env.write_txn().unwrap();
+        let db = open_metadata_db(&env, &mut txn).unwrap();
+        let current = IndexMetadata {
+            model: Some("a".into()),
+            dimensions: None,
+            chunker_version: CHUNKER_VERSION,
+        };
+
+        // A new index is stamped with the current metadata.
+        assert_eq!(
+            check_metadata(db, &mut txn, "new", &current, false).unwrap(),
+            None
+        );
+        assert_eq!(db.get(&txn, "new").unwrap(), Some(current.clone()));
+
+        // An index built before metadata was recorded isn't.
+        assert_eq!(
+            check_metadata(db, &mut txn, "legacy", &current, true).unwrap(),
+            None
+        );
+        assert_eq!(db.get(&txn, "legacy").unwrap(), None);
+
+        let other_model = IndexMetadata {
+            model: Some("b".into()),
+            ..current.clone()
+        };
+        assert!(
+            check_metadata(db, &mut txn, "new", &other_model, true)
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_record_dimensions() {
+        let (_dir, env) = open_env();
+        let mut txn = env.write_txn().unwrap();
+        let db = open_metadata_db(&env, &mut txn).unwrap();
+        let current = IndexMetadata {
+            model: Some("a".into()),
+            dimensions: None,
+            chunker_version: CHUNKER_VERSION,
+        };
+        check_metadata(db, &mut txn, "index", &current, false).unwrap();
+
+        assert_eq!(record_dimensions(db, &mut txn, "index", 3).unwrap(), None);
+        assert_eq!(db.get(&txn, "index").unwrap().unwrap().dimensions, Some(3));
+        assert_eq!(record_dimensions(db, &mut txn, "index", 3).unwrap(), None);
+        assert!(
+            record_dimensions(db, &mut txn, "index", 4)
+                .unwrap()
+                .unwrap()
+                .contains("3-dimensional")
+        );
+        assert_eq!(db.get(&txn, "index").unwrap().unwrap().dimensions, Some(3));
+
+        // Legacy indexes have no metadata to record dimensions in.
+        assert_eq!(record_dimensions(db, &mut txn, "legacy", 4).unwrap(), None);
+        assert_eq!(db.get(&txn, "legacy").unwrap(), None);
+    }
+
+    #[test]
+    fn test_index_metadata_incompatibility() {
+        let metadata = |model: Option<&str>, dimensions, chunker_version| IndexMetadata {
+            model: model.map(Into::into),
+            dimensions,
+            chunker_version,
+        };
+        let current = metadata(Some("a"), None, CHUNKER_VERSION);
+
+        assert_eq!(
+            metadata(Some("a"), Some(3), CHUNKER_VERSION).incompatibility(&current),
+            None
+        );
+        assert_eq!(
+            metadata(None, Some(3), CHUNKER_VERSION).incompatibility(&current),
+            None
+        );
+        assert!(
+            metadata(Some("b"), None, CHUNKER_VERSION)
+                .incompatibility(&current)
+                .unwrap()
+                .contains("b embedding model")
+        );
+        assert!(
+            metadata(Some("a"), Some(3), CHUNKER_VERSION)
+                .incompatibility(&metadata(Some("a"), Some(4), CHUNKER_VERSION))
+                .is_some()
+        );
+        assert!(
+            metadata(Some("a"), None, CHUNKER_VERSION + 1)
+                .incompatibility(&current)
+                .is_some()
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     embedding::{EmbeddingProvider, TextToEmbed},
+    embedding_index::EmbeddingBatchCounter,
     summary_index::FileSummary,
     worktree_index::{WorktreeIndex, WorktreeIndexHandle},
 };
@@ -22,6 +23,7 @@ use std::{
     ops::{Range, RangeInclusive},
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 use util::ResultExt;
 
@@ -51,6 +53,65 @@ pub struct WorktreeSearchResult {
     pub score: f32,
 }
 
+/// Whether a search checks chunks for the query text locally, which is much
+/// cheaper than comparing embeddings when the query is an exact lookup.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum KeywordFilter {
+    /// Compare the query embeddings against every chunk.
+    #[default]
+    Off,
+    /// Only compare the query embeddings against chunks that contain one of
+    /// the queries, ignoring case.
+    Narrow,
+    /// Return the chunks that contain one of the queries without embedding
+    /// the queries at all.
+    Only,
+}
+
+/// Restricts a search to files of certain languages or extensions. An empty
+/// filter matches every file.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FileTypeFilter {
+    /// Language names, such as `Rust`, compared ignoring case.
+    pub languages: Vec<String>,
+    /// File extensions without the leading dot, such as `rs`, compared ignoring case.
+    pub extensions: Vec<String>,
+}
+
+impl FileTypeFilter {
+    pub fn is_empty(&self) -> bool {
+        self.languages.is_empty() && self.extensions.is_empty()
+    }
+
+    /// The language is resolved from the path at query time rather than
+    /// stored with each chunk, so existing indices don't have to be rebuilt.
+    async fn matches(&self, path: &Path, language_registry: &Arc<LanguageRegistry>) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        if extension.is_some_and(|extension| {
+            self.extensions
+                .iter()
+                .any(|candidate| candidate.eq_ignore_ascii_case(extension))
+        }) {
+            return true;
+        }
+
+        if self.languages.is_empty() {
+            return false;
+        }
+        let Ok(language) = language_registry.language_for_file_path(path).await else {
+            return false;
+        };
+        let language_name = language.name();
+        self.languages
+            .iter()
+            .any(|candidate| candidate.eq_ignore_ascii_case(language_name.0.as_ref()))
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Status {
     Idle,
@@ -58,6 +119,58 @@ pub enum Status {
     Scanning { remaining_count: NonZeroUsize },
 }
 
+/// How far the index has drifted from the files on disk.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexFreshness {
+    /// The number of files stored in the index.
+    pub indexed_file_count: usize,
+    /// The number of files added, modified or deleted since they were last indexed.
+    pub stale_file_count: usize,
+}
+
+impl IndexFreshness {
+    pub fn is_stale(&self) -> bool {
+        self.stale_file_count > 0
+    }
+
+    /// Whether more than a tenth of the index is out of date, at which point
+    /// search results are likely to be misleading.
+    pub fn is_reindex_recommended(&self) -> bool {
+        self.is_stale() && self.stale_file_count * 10 > self.indexed_file_count
+    }
+}
+
+impl std::ops::AddAssign for IndexFreshness {
+    fn add_assign(&mut self, other: Self) {
+        self.indexed_file_count += other.indexed_file_count;
+        self.stale_file_count += other.stale_file_count;
+    }
+}
+
+/// What embedding every file in the project again would send to the
+/// embedding provider.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexingEstimate {
+    pub file_count: usize,
+    pub chunk_count: usize,
+    /// Approximate, see [`crate::estimate_token_count`].
+    pub token_count: usize,
+}
+
+impl IndexingEstimate {
+    pub fn cost(&self, price_per_million_tokens: f64) -> f64 {
+        self.token_count as f64 / 1_000_000. * price_per_million_tokens
+    }
+}
+
+impl std::ops::AddAssign for IndexingEstimate {
+    fn add_assign(&mut self, other: Self) {
+        self.file_count += other.file_count;
+        self.chunk_count += other.chunk_count;
+        self.token_count += other.token_count;
+    }
+}
+
 pub struct ProjectIndex {
     db_connection: heed::Env,
     project: WeakEntity<Project>,
@@ -67,6 +180,8 @@ pub struct ProjectIndex {
     last_status: Status,
     status_tx: channel::Sender<()>,
     embedding_provider: Arc<dyn EmbeddingProvider>,
+    embedding_batches: Arc<EmbeddingBatchCounter>,
+    reindex_debounce: Option<Duration>,
     _maintain_status: Task<()>,
     _subscription: Subscription,
 }
@@ -76,6 +191,8 @@ impl ProjectIndex {
         project: Entity<Project>,
         db_connection: heed::Env,
         embedding_provider: Arc<dyn EmbeddingProvider>,
+        embedding_batches: Arc<EmbeddingBatchCounter>,
+        reindex_debounce: Option<Duration>,
         cx: &mut Context<Self>,
     ) -> Self {
         let language_registry = project.read(cx).languages().clone();
@@ -90,6 +207,8 @@ impl ProjectIndex {
             status_tx,
             last_status: Status::Idle,
             embedding_provider,
+            embedding_batches,
+            reindex_debounce,
             _subscription: cx.subscribe(&project, Self::handle_project_event),
             _maintain_status: cx.spawn(async move |this, cx| {
                 while status_rx.recv().await.is_ok() {
@@ -107,6 +226,20 @@ impl ProjectIndex {
         self.last_status
     }
 
+    /// Why some of the project's stored embeddings can't be searched with the
+    /// current embedding provider, if they can't. The index has to be rebuilt
+    /// with [`Self::clear_and_reindex`] before it can be searched again.
+    pub fn incompatibility(&self, cx: &App) -> Option<String> {
+        self.worktree_indices
+            .values()
+            .find_map(|worktree_index| match worktree_index {
+                WorktreeIndexHandle::Loaded { index } => {
+                    index.read(cx).embedding_index().incompatibility()
+                }
+                WorktreeIndexHandle::Loading { .. } => None,
+            })
+    }
+
     pub fn project(&self) -> WeakEntity<Project> {
         self.project.clone()
     }
@@ -157,6 +290,8 @@ impl ProjectIndex {
                     self.fs.clone(),
                     self.status_tx.clone(),
                     self.embedding_provider.clone(),
+                    self.embedding_batches.clone(),
+                    self.reindex_debounce,
                     cx,
                 );
 
@@ -231,11 +366,48 @@ impl ProjectIndex {
         limit: usize,
         cx: &App,
     ) -> Task<Result<Vec<SearchResult>>> {
+        self.search_with_filters(
+            queries,
+            limit,
+            KeywordFilter::Off,
+            FileTypeFilter::default(),
+            cx,
+        )
+    }
+
+    pub fn search_with_filters(
+        &self,
+        queries: Vec<String>,
+        limit: usize,
+        keyword_filter: KeywordFilter,
+        file_type_filter: FileTypeFilter,
+        cx: &App,
+    ) -> Task<Result<Vec<SearchResult>>> {
+        let needs_embeddings = keyword_filter != KeywordFilter::Only;
+        if needs_embeddings && let Some(incompatibility) = self.incompatibility(cx) {
+            return Task::ready(Err(anyhow!(
+                "{incompatibility}. Rebuild the project index to search it."
+            )));
+        }
+
+        let file_type_filter = Arc::new(file_type_filter);
+        let keywords: Arc<[String]> = match keyword_filter {
+            KeywordFilter::Off => Arc::default(),
+            KeywordFilter::Narrow | KeywordFilter::Only => queries
+                .iter()
+                .map(|query| query.trim().to_lowercase())
+                .collect(),
+        };
+
         let (chunks_tx, chunks_rx) = channel::bounded(1024);
         let mut worktree_scan_tasks = Vec::new();
         for worktree_index in self.worktree_indices.values() {
             let worktree_index = worktree_index.clone();
             let chunks_tx = chunks_tx.clone();
+            let keywords = keywords.clone();
+            let file_type_filter = file_type_filter.clone();
+            let language_registry = self.language_registry.clone();
+            let fs = self.fs.clone();
             worktree_scan_tasks.push(cx.spawn(async move |cx| {
                 let index = match worktree_index {
                     WorktreeIndexHandle::Loading { index } => {
@@ -246,7 +418,14 @@ impl ProjectIndex {
 
                 index
                     .read_with(cx, |index, cx| {
-                        let worktree_id = index.worktree().read(cx).id();
+                        if needs_embeddings
+                            && let Some(incompatibility) = index.embedding_index().incompatibility()
+                        {
+                            return Task::ready(Err(anyhow!(incompatibility)));
+                        }
+                        let worktree = index.worktree().read(cx);
+                        let worktree_id = worktree.id();
+                        let worktree_abs_path = worktree.abs_path();
                         let db_connection = index.db_connection().clone();
                         let db = *index.embedding_index().db();
                         cx.background_spawn(async move {
@@ -256,9 +435,45 @@ impl ProjectIndex {
                             let db_entries = db.iter(&txn).context("failed to iterate database")?;
                             for db_entry in db_entries {
                                 let (_key, db_embedded_file) = db_entry?;
+                                if !file_type_filter
+                                    .matches(&db_embedded_file.path, &language_registry)
+                                    .await
+                                {
+                                    continue;
+                                }
+
+                                let content = if keywords.is_empty() {
+                                    None
+                                } else {
+                                    let abs_path = worktree_abs_path.join(&db_embedded_file.path);
+                                    match fs.load(&abs_path).await {
+                                        Ok(content) => Some(content),
+                                        Err(_) => continue,
+                                    }
+                                };
+
                                 for chunk in db_embedded_file.chunks {
+                                    let keyword_match = match &content {
+                                        Some(content) => {
+                                            // Files changed since indexing may no longer
+                                            // contain the chunk's range.
+                                            let query_index = content
+                                                .get(chunk.chunk.range.clone())
+                                                .and_then(|text| matching_keyword(text, &keywords));
+                                            if query_index.is_none() {
+                                                continue;
+                                            }
+                                            query_index
+                                        }
+                                        None => None,
+                                    };
                                     chunks_tx
-                                        .send((worktree_id, db_embedded_file.path.clone(), chunk))
+                                        .send((
+                                            worktree_id,
+                                            db_embedded_file.path.clone(),
+                                            chunk,
+                                            keyword_match,
+                                        ))
                                         .await?;
                                 }
                             }
@@ -275,17 +490,24 @@ impl ProjectIndex {
         cx.spawn(async move |cx| {
             #[cfg(debug_assertions)]
             let embedding_query_start = std::time::Instant::now();
-            log::info!("Searching for {queries:?}");
-            let queries: Vec<TextToEmbed> = queries
-                .iter()
-                .map(|s| TextToEmbed::new(s.as_str()))
-                .collect();
-
-            let query_embeddings = embedding_provider.embed(&queries[..]).await?;
-            anyhow::ensure!(
-                query_embeddings.len() == queries.len(),
-                "The number of query embeddings does not match the number of queries"
+            log::info!(
+                "Searching for {queries:?} (keyword filter: {keyword_filter:?}, file types: {file_type_filter:?})"
             );
+            let query_embeddings = if keyword_filter == KeywordFilter::Only {
+                Vec::new()
+            } else {
+                let queries: Vec<TextToEmbed> = queries
+                    .iter()
+                    .map(|s| TextToEmbed::new(s.as_str()))
+                    .collect();
+
+                let query_embeddings = embedding_provider.embed(&queries[..]).await?;
+                anyhow::ensure!(
+                    query_embeddings.len() == queries.len(),
+                    "The number of query embeddings does not match the number of queries"
+                );
+                query_embeddings
+            };
 
             let mut results_by_worker = Vec::new();
             for _ in 0..cx.background_executor().num_cpus() {
@@ -298,9 +520,13 @@ impl ProjectIndex {
                 .scoped(|cx| {
                     for results in results_by_worker.iter_mut() {
                         cx.spawn(async {
-                            while let Ok((worktree_id, path, chunk)) = chunks_rx.recv().await {
-                                let (score, query_index) =
-                                    chunk.embedding.similarity(&query_embeddings);
+                            while let Ok((worktree_id, path, chunk, keyword_match)) =
+                                chunks_rx.recv().await
+                            {
+                                let (score, query_index) = match (keyword_filter, keyword_match) {
+                                    (KeywordFilter::Only, Some(query_index)) => (1., query_index),
+                                    _ => chunk.embedding.similarity(&query_embeddings),
+                                };
 
                                 let ix = match results.binary_search_by(|probe| {
                                     score.partial_cmp(&probe.score).unwrap_or(Ordering::Equal)
@@ -367,6 +593,109 @@ impl ProjectIndex {
         })
     }
 
+    /// Drops the stored embeddings for every worktree and indexes them again from
+    /// scratch, resolving to the tokens it used, for providers that track usage.
+    pub fn clear_and_reindex(&self, cx: &App) -> Task<Result<Option<u64>>> {
+        let worktree_tasks = self
+            .worktree_indices
+            .values()
+            .map(|worktree_index| {
+                let worktree_index = worktree_index.clone();
+                cx.spawn(async move |cx| {
+                    let index = match worktree_index {
+                        WorktreeIndexHandle::Loading { index } => {
+                            index.clone().await.map_err(|error| anyhow!(error))?
+                        }
+                        WorktreeIndexHandle::Loaded { index } => index.clone(),
+                    };
+
+                    index
+                        .read_with(cx, |index, cx| index.embedding_index().clear(cx))?
+                        .await?;
+                    index
+                        .read_with(cx, |index, cx| {
+                            index.embedding_index().index_entries_changed_on_disk(cx)
+                        })?
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        cx.background_spawn(async move {
+            let mut token_usage = None;
+            for result in futures::future::join_all(worktree_tasks).await {
+                if let Some(tokens) = result? {
+                    *token_usage.get_or_insert(0) += tokens;
+                }
+            }
+            Ok(token_usage)
+        })
+    }
+
+    /// Chunks every file without embedding anything, to estimate the usage of
+    /// [`Self::clear_and_reindex`].
+    pub fn estimate_reindex(&self, cx: &App) -> Task<Result<IndexingEstimate>> {
+        let worktree_estimate_tasks = self
+            .worktree_indices
+            .values()
+            .map(|worktree_index| {
+                let worktree_index = worktree_index.clone();
+                cx.spawn(async move |cx| {
+                    let index = match worktree_index {
+                        WorktreeIndexHandle::Loading { index } => {
+                            index.clone().await.map_err(|error| anyhow!(error))?
+                        }
+                        WorktreeIndexHandle::Loaded { index } => index.clone(),
+                    };
+
+                    index
+                        .read_with(cx, |index, cx| index.embedding_index().estimate_reindex(cx))?
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        cx.background_spawn(async move {
+            let mut estimate = IndexingEstimate::default();
+            for worktree_estimate in futures::future::join_all(worktree_estimate_tasks).await {
+                estimate += worktree_estimate?;
+            }
+            Ok(estimate)
+        })
+    }
+
+    /// Reports how many files changed on disk since they were last indexed, so
+    /// callers can warn about stale results before searching.
+    pub fn freshness(&self, cx: &App) -> Task<Result<IndexFreshness>> {
+        let worktree_freshness_tasks = self
+            .worktree_indices
+            .values()
+            .map(|worktree_index| {
+                let worktree_index = worktree_index.clone();
+                cx.spawn(async move |cx| {
+                    let index = match worktree_index {
+                        WorktreeIndexHandle::Loading { index } => {
+                            index.clone().await.map_err(|error| anyhow!(error))?
+                        }
+                        WorktreeIndexHandle::Loaded { index } => index.clone(),
+                    };
+
+                    index
+                        .read_with(cx, |index, cx| index.embedding_index().freshness(cx))?
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        cx.background_spawn(async move {
+            let mut freshness = IndexFreshness::default();
+            for worktree_freshness in futures::future::join_all(worktree_freshness_tasks).await {
+                freshness += worktree_freshness?;
+            }
+            Ok(freshness)
+        })
+    }
+
     #[cfg(test)]
     pub fn path_count(&self, cx: &App) -> Result<u64> {
         let mut result = 0;
@@ -545,4 +874,12 @@ impl ProjectIndex {
     }
 }
 
+/// The index of the first keyword that occurs in the text, ignoring case.
+fn matching_keyword(text: &str, keywords: &[String]) -> Option<usize> {
+    let text = text.to_lowercase();
+    keywords
+        .iter()
+        .position(|keyword| !keyword.is_empty() && text.contains(keyword.as_str()))
+}
+
 impl EventEmitter<Status> for ProjectIndex {}
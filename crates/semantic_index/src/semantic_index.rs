@@ -1,34 +1,56 @@
 mod chunking;
 mod embedding;
 mod embedding_index;
+mod index_metadata;
 mod indexing;
 mod project_index;
 mod project_index_debug_view;
+mod secret_redaction;
 mod summary_backlog;
 mod summary_index;
 mod worktree_index;
 
-use anyhow::{Context as _, Result};
+use anyhow::{Context as _, Result, anyhow};
 use collections::HashMap;
 use fs::Fs;
-use gpui::{App, AppContext as _, AsyncApp, BorrowAppContext, Context, Entity, Global, WeakEntity};
+use gpui::{
+    App, AppContext as _, AsyncApp, BorrowAppContext, Context, Entity, Global, PromptLevel, Task,
+    WeakEntity, Window, actions,
+};
 use language::LineEnding;
 use project::{Project, Worktree};
 use std::{
     cmp::Ordering,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 use util::ResultExt as _;
 use workspace::Workspace;
 
 pub use embedding::*;
-pub use project_index::{LoadedSearchResult, ProjectIndex, SearchResult, Status};
+pub use embedding_index::{EmbeddingBatchCounter, EmbeddingBatchCounts};
+pub use index_metadata::{CHUNKER_VERSION, IndexMetadata};
+pub use project_index::{
+    FileTypeFilter, IndexFreshness, IndexingEstimate, KeywordFilter, LoadedSearchResult,
+    ProjectIndex, SearchResult, Status,
+};
 pub use project_index_debug_view::ProjectIndexDebugView;
+pub use secret_redaction::{SECRET_PLACEHOLDER, SecretRedactor};
 pub use summary_index::FileSummary;
 
+actions!(
+    semantic_index,
+    [
+        /// Drops the project's semantic index and embeds every file again.
+        RebuildProjectIndex
+    ]
+);
+
 pub struct SemanticDb {
     embedding_provider: Arc<dyn EmbeddingProvider>,
+    embedding_batches: Arc<EmbeddingBatchCounter>,
+    reindex_debounce: Option<Duration>,
     db_connection: Option<heed::Env>,
     project_indices: HashMap<WeakEntity<Project>, Entity<ProjectIndex>>,
 }
@@ -56,13 +78,17 @@ impl SemanticDb {
 
         cx.update(|cx| {
             cx.observe_new(
-                |workspace: &mut Workspace, _window, cx: &mut Context<Workspace>| {
+                |workspace: &mut Workspace, window, cx: &mut Context<Workspace>| {
+                    workspace.register_action(Self::rebuild_project_index);
                     let project = workspace.project().clone();
 
                     if cx.has_global::<SemanticDb>() {
-                        cx.update_global::<SemanticDb, _>(|this, cx| {
-                            this.create_project_index(project, cx);
-                        })
+                        let project_index = cx.update_global::<SemanticDb, _>(|this, cx| {
+                            this.create_project_index(project, cx)
+                        });
+                        if let Some(window) = window {
+                            Self::prompt_to_rebuild_incompatible_index(project_index, window, cx);
+                        }
                     } else {
                         log::info!("No SemanticDb, skipping project index")
                     }
@@ -75,10 +101,27 @@ impl SemanticDb {
         Ok(SemanticDb {
             db_connection: Some(db_connection),
             embedding_provider,
+            embedding_batches: Arc::default(),
+            reindex_debounce: None,
             project_indices: HashMap::default(),
         })
     }
 
+    /// The quota the embedding backend last reported, so the UI can explain
+    /// why indexing is paused.
+    pub fn embedding_rate_limit(&self) -> Option<RateLimitStatus> {
+        self.embedding_provider.rate_limit()
+    }
+
+    /// Waits until files have gone unchanged for `debounce` before embedding
+    /// them again, so a file saved several times in quick succession is only
+    /// embedded once. Files that keep changing are embedded after five times
+    /// `debounce` at most. Applies to project indices created afterwards.
+    pub fn with_reindex_debounce(mut self, debounce: Duration) -> Self {
+        self.reindex_debounce = Some(debounce);
+        self
+    }
+
     pub async fn load_results(
         mut results: Vec<SearchResult>,
         fs: &Arc<dyn Fs>,
@@ -212,6 +255,12 @@ impl SemanticDb {
         self.project_indices.get(&project.downgrade()).cloned()
     }
 
+    /// The embedding requests queued, in flight and completed by every
+    /// project index.
+    pub fn embedding_batch_counts(&self) -> EmbeddingBatchCounts {
+        self.embedding_batches.counts()
+    }
+
     pub fn remaining_summaries(
         &self,
         project: &WeakEntity<Project>,
@@ -234,6 +283,8 @@ impl SemanticDb {
                 project.clone(),
                 self.db_connection.clone().unwrap(),
                 self.embedding_provider.clone(),
+                self.embedding_batches.clone(),
+                self.reindex_debounce,
                 cx,
             )
         });
@@ -253,6 +304,169 @@ impl SemanticDb {
 
         project_index
     }
+
+    /// Stops indexing a project once the returned index, and any other handle
+    /// to it, is dropped, cancelling its embedding requests in flight. Files
+    /// that were already embedded stay in the database, so indexing the
+    /// project again picks up where it stopped.
+    pub fn remove_project_index(
+        &mut self,
+        project: &Entity<Project>,
+    ) -> Option<Entity<ProjectIndex>> {
+        self.project_indices.remove(&project.downgrade())
+    }
+
+    /// Writes every committed embedding to disk.
+    pub fn flush(&self, cx: &App) -> Task<Result<()>> {
+        let Some(db_connection) = self.db_connection.clone() else {
+            return Task::ready(Ok(()));
+        };
+        cx.background_spawn(async move {
+            db_connection
+                .force_sync()
+                .context("failed to flush the semantic index to disk")
+        })
+    }
+
+    /// Drops the stored embeddings for a project and indexes it again from
+    /// scratch, resolving to the tokens it used, for providers that track
+    /// usage.
+    pub fn clear_project_index(
+        &self,
+        project: &Entity<Project>,
+        cx: &App,
+    ) -> Task<Result<Option<u64>>> {
+        match self.project_indices.get(&project.downgrade()) {
+            Some(project_index) => project_index.read(cx).clear_and_reindex(cx),
+            None => Task::ready(Err(anyhow!("project has no semantic index"))),
+        }
+    }
+
+    /// Estimates what rebuilding a project's index would send to the embedding
+    /// provider, and what that would cost if the provider's pricing is known.
+    pub fn estimate_rebuild(
+        &self,
+        project: &Entity<Project>,
+        cx: &App,
+    ) -> Task<Result<(IndexingEstimate, Option<f64>)>> {
+        let price = self.embedding_provider.price_per_million_tokens();
+        match self.project_indices.get(&project.downgrade()) {
+            Some(project_index) => {
+                let estimate = project_index.read(cx).estimate_reindex(cx);
+                cx.background_spawn(async move {
+                    let estimate = estimate.await?;
+                    Ok((estimate, price.map(|price| estimate.cost(price))))
+                })
+            }
+            None => Task::ready(Err(anyhow!("project has no semantic index"))),
+        }
+    }
+
+    /// Asks to rebuild the project's index once it turns out to have been
+    /// built with a different embedding model or chunker.
+    fn prompt_to_rebuild_incompatible_index(
+        project_index: Entity<ProjectIndex>,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) {
+        let mut prompted = false;
+        cx.subscribe_in(
+            &project_index,
+            window,
+            move |_, project_index, _: &Status, window, cx| {
+                if prompted {
+                    return;
+                }
+                let Some(incompatibility) = project_index.read(cx).incompatibility(cx) else {
+                    return;
+                };
+                prompted = true;
+
+                let detail = format!(
+                    "Oppla found that {incompatibility}. Until the index is rebuilt, it won't be searched or updated."
+                );
+                let answer = window.prompt(
+                    PromptLevel::Warning,
+                    "Rebuild the semantic index for this project?",
+                    Some(&detail),
+                    &["Rebuild", "Not Now"],
+                    cx,
+                );
+                let project_index = project_index.clone();
+                cx.spawn(async move |_, cx| {
+                    if answer.await? == 0 {
+                        project_index
+                            .read_with(cx, |project_index, cx| project_index.clear_and_reindex(cx))?
+                            .await?;
+                    }
+                    anyhow::Ok(())
+                })
+                .detach_and_log_err(cx);
+            },
+        )
+        .detach();
+    }
+
+    fn rebuild_project_index(
+        workspace: &mut Workspace,
+        _: &RebuildProjectIndex,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) {
+        let project = workspace.project().clone();
+        cx.spawn_in(window, async move |_, cx| {
+            let (estimate_task, embedding_provider) = cx.update(|_, cx| {
+                let Some(semantic_db) = cx.try_global::<SemanticDb>() else {
+                    return Err(anyhow!("semantic index is not enabled"));
+                };
+                Ok((
+                    semantic_db.estimate_rebuild(&project, cx),
+                    semantic_db.embedding_provider.clone(),
+                ))
+            })??;
+            let (estimate, estimated_cost) = estimate_task.await?;
+
+            let mut detail = format!(
+                "{} files will be embedded again as about {} chunks (~{} tokens)",
+                estimate.file_count, estimate.chunk_count, estimate.token_count
+            );
+            match estimated_cost {
+                Some(cost) => detail.push_str(&format!(", at an estimated cost of ${cost:.4}.")),
+                None => detail.push_str(", which counts against your embedding usage."),
+            }
+            let answer = cx.prompt(
+                PromptLevel::Warning,
+                "Rebuild the semantic index for this project?",
+                Some(&detail),
+                &["Rebuild", "Cancel"],
+            );
+            if answer.await? != 0 {
+                return Ok(());
+            }
+
+            let token_usage = cx
+                .update(|_, cx| {
+                    cx.try_global::<SemanticDb>()
+                        .context("semantic index is not enabled")
+                        .map(|semantic_db| semantic_db.clear_project_index(&project, cx))
+                })??
+                .await?;
+            if let Some(tokens) = token_usage {
+                let cost = embedding_provider
+                    .price_per_million_tokens()
+                    .map(|price| tokens as f64 / 1_000_000. * price);
+                log::info!(
+                    "rebuilt semantic index with {} tokens (estimated {}), cost: {:?}, estimated: {:?}",
+                    tokens,
+                    estimate.token_count,
+                    cost,
+                    estimated_cost
+                );
+            }
+            Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
 }
 
 impl Drop for SemanticDb {
@@ -323,6 +537,21 @@ mod tests {
             future::ready(embeddings).boxed()
         }
 
+        /// Reports a token for every text, so that usage can be checked.
+        fn embed_allowing_failures<'a>(
+            &'a self,
+            texts: &'a [TextToEmbed<'a>],
+        ) -> BoxFuture<'a, Result<BatchEmbeddings>> {
+            self.embed(texts)
+                .map(|embeddings| {
+                    Ok(BatchEmbeddings {
+                        embeddings: embeddings?.into_iter().map(Some).collect(),
+                        token_usage: Some(texts.len() as u64),
+                    })
+                })
+                .boxed()
+        }
+
         fn batch_size(&self) -> usize {
             self.batch_size
         }
@@ -397,6 +626,40 @@ mod tests {
             cx.run_until_parked();
         }
 
+        let freshness = cx
+            .update(|cx| project_index.read(cx).freshness(cx))
+            .await
+            .unwrap();
+        assert_eq!(
+            freshness,
+            IndexFreshness {
+                indexed_file_count: 2,
+                stale_file_count: 0,
+            }
+        );
+        assert!(!freshness.is_reindex_recommended());
+
+        let (estimate, estimated_cost) = cx
+            .update(|cx| semantic_index.estimate_rebuild(&project, cx))
+            .await
+            .unwrap();
+        assert_eq!(estimate.file_count, 2);
+        assert!(estimate.chunk_count >= 2);
+        assert!(estimate.token_count > 0);
+        assert_eq!(estimated_cost, None);
+
+        let token_usage = cx
+            .update(|cx| semantic_index.clear_project_index(&project, cx))
+            .await
+            .unwrap();
+        assert_eq!(token_usage, Some(estimate.chunk_count as u64));
+        let freshness = cx
+            .update(|cx| project_index.read(cx).freshness(cx))
+            .await
+            .unwrap();
+        assert_eq!(freshness.stale_file_count, 0);
+        assert_eq!(freshness.indexed_file_count, 2);
+
         let results = cx
             .update(|cx| {
                 let project_index = project_index.read(cx);
@@ -438,6 +701,65 @@ mod tests {
         let content = content[range.clone()].to_owned();
 
         assert!(content.contains("garbage in, garbage out"));
+
+        for keyword_filter in [KeywordFilter::Narrow, KeywordFilter::Only] {
+            let results = cx
+                .update(|cx| {
+                    project_index.read(cx).search_with_filters(
+                        vec!["GARBAGE IN, garbage out".into()],
+                        4,
+                        keyword_filter,
+                        FileTypeFilter::default(),
+                        cx,
+                    )
+                })
+                .await
+                .unwrap();
+            assert!(!results.is_empty(), "{keyword_filter:?} found no results");
+            assert!(
+                results
+                    .iter()
+                    .all(|result| result.path.to_string_lossy() == path!("fixture/needle.md")),
+                "{keyword_filter:?} returned chunks without the query: {results:?}"
+            );
+        }
+
+        for (file_type_filter, expected_path) in [
+            (
+                FileTypeFilter {
+                    languages: vec!["rust".into()],
+                    ..Default::default()
+                },
+                path!("fixture/main.rs"),
+            ),
+            (
+                FileTypeFilter {
+                    extensions: vec!["MD".into()],
+                    ..Default::default()
+                },
+                path!("fixture/needle.md"),
+            ),
+        ] {
+            let results = cx
+                .update(|cx| {
+                    project_index.read(cx).search_with_filters(
+                        vec!["garbage in, garbage out".into()],
+                        4,
+                        KeywordFilter::Off,
+                        file_type_filter.clone(),
+                        cx,
+                    )
+                })
+                .await
+                .unwrap();
+            assert!(!results.is_empty(), "{file_type_filter:?} found no results");
+            assert!(
+                results
+                    .iter()
+                    .all(|result| result.path.to_string_lossy() == expected_path),
+                "{file_type_filter:?} returned other files: {results:?}"
+            );
+        }
     }
 
     #[gpui::test]
@@ -473,6 +795,7 @@ mod tests {
                         digest: Default::default(),
                     })
                     .collect(),
+                previous_embeddings: HashMap::default(),
             })
             .unwrap();
         chunked_files_tx
@@ -488,13 +811,33 @@ mod tests {
                         digest: Default::default(),
                     })
                     .collect(),
+                previous_embeddings: HashMap::default(),
             })
             .unwrap();
         chunked_files_tx.close();
 
-        let embed_files_task =
-            cx.update(|cx| EmbeddingIndex::embed_files(provider.clone(), chunked_files_rx, cx));
-        embed_files_task.task.await.unwrap();
+        let embedding_batches = Arc::new(EmbeddingBatchCounter::default());
+        let embed_files_task = cx.update(|cx| {
+            EmbeddingIndex::embed_files(
+                provider.clone(),
+                embedding_batches.clone(),
+                chunked_files_rx,
+                cx,
+            )
+        });
+        // Batches that failed to embed don't report usage.
+        assert_eq!(embed_files_task.task.await.unwrap(), Some(4));
+        assert_eq!(
+            embedding_batches.counts(),
+            EmbeddingBatchCounts {
+                pending: 0,
+                in_flight: 0,
+                completed: 3,
+                embedded_files: 1,
+                embedded_chunks: 4,
+                reused_chunks: 0,
+            }
+        );
 
         let embedded_files_rx = embed_files_task.files;
         let mut embedded_files = Vec::new();
@@ -518,6 +861,70 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_embed_files_reuses_unchanged_chunks(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+
+        let provider = Arc::new(TestEmbeddingProvider::new(3, |text| {
+            anyhow::ensure!(
+                !text.contains('g'),
+                "cannot embed text containing a 'g' character"
+            );
+            Ok(Embedding::new(vec![text.len() as f32]))
+        }));
+
+        let (indexing_progress_tx, _) = channel::unbounded();
+        let indexing_entries = Arc::new(IndexingEntrySet::new(indexing_progress_tx));
+
+        // Only the unchanged chunk can't be embedded, so the file is only
+        // embedded if its previous embedding is kept.
+        let previous_embedding = Embedding::new(vec![42.]);
+        let (chunked_files_tx, chunked_files_rx) = channel::unbounded::<ChunkedFile>();
+        chunked_files_tx
+            .send_blocking(ChunkedFile {
+                path: Path::new("test.md").into(),
+                mtime: None,
+                handle: indexing_entries.insert(ProjectEntryId::from_proto(0)),
+                text: "abcdefgh".to_string(),
+                chunks: vec![
+                    Chunk {
+                        range: 0..4,
+                        digest: [1; 32],
+                    },
+                    Chunk {
+                        range: 4..8,
+                        digest: [2; 32],
+                    },
+                ],
+                previous_embeddings: HashMap::from_iter([([2; 32], previous_embedding.clone())]),
+            })
+            .unwrap();
+        chunked_files_tx.close();
+
+        let embedding_batches = Arc::new(EmbeddingBatchCounter::default());
+        let embed_files_task = cx.update(|cx| {
+            EmbeddingIndex::embed_files(
+                provider.clone(),
+                embedding_batches.clone(),
+                chunked_files_rx,
+                cx,
+            )
+        });
+        assert_eq!(embed_files_task.task.await.unwrap(), Some(1));
+        let counts = embedding_batches.counts();
+        assert_eq!((counts.embedded_chunks, counts.reused_chunks), (1, 1));
+
+        let (embedded_file, _) = embed_files_task.files.recv().await.unwrap();
+        assert_eq!(
+            embedded_file
+                .chunks
+                .iter()
+                .map(|embedded_chunk| embedded_chunk.embedding.clone())
+                .collect::<Vec<_>>(),
+            vec![Embedding::new(vec![4.]), previous_embedding]
+        );
+    }
+
     #[gpui::test]
     async fn test_load_search_results(cx: &mut TestAppContext) {
         init_test(cx);
@@ -1,16 +1,21 @@
 use crate::embedding::EmbeddingProvider;
-use crate::embedding_index::EmbeddingIndex;
+use crate::embedding_index::{EmbeddingBatchCounter, EmbeddingIndex};
+use crate::index_metadata::{self, IndexMetadata};
 use crate::indexing::IndexingEntrySet;
 use crate::summary_index::SummaryIndex;
 use anyhow::Result;
+use collections::HashMap;
 use fs::Fs;
-use futures::future::Shared;
-use gpui::{App, AppContext as _, AsyncApp, Context, Entity, Subscription, Task, WeakEntity};
+use futures::{FutureExt as _, future::Shared};
+use gpui::{
+    App, AppContext as _, AsyncApp, BackgroundExecutor, Context, Entity, Subscription, Task,
+    WeakEntity,
+};
 use language::LanguageRegistry;
 use log;
 use project::{UpdatedEntriesSet, Worktree};
 use smol::channel;
-use std::sync::Arc;
+use std::{path::Path, sync::Arc, time::Duration};
 use util::ResultExt;
 
 #[derive(Clone)]
@@ -41,6 +46,8 @@ impl WorktreeIndex {
         fs: Arc<dyn Fs>,
         status_tx: channel::Sender<()>,
         embedding_provider: Arc<dyn EmbeddingProvider>,
+        embedding_batches: Arc<EmbeddingBatchCounter>,
+        reindex_debounce: Option<Duration>,
         cx: &mut App,
     ) -> Task<Result<Entity<Self>>> {
         let worktree_for_index = worktree.clone();
@@ -59,14 +66,31 @@ impl WorktreeIndex {
                         let embedding_index = {
                             let db_name = worktree_abs_path.to_string_lossy();
                             let db = db_connection.create_database(&mut txn, Some(&db_name))?;
+                            let metadata_db =
+                                index_metadata::open_metadata_db(&db_connection, &mut txn)?;
+                            let has_embeddings = !db.is_empty(&txn)?;
+                            let incompatibility = index_metadata::check_metadata(
+                                metadata_db,
+                                &mut txn,
+                                &db_name,
+                                &IndexMetadata::current(embedding_provider.as_ref()),
+                                has_embeddings,
+                            )?;
+                            if let Some(incompatibility) = &incompatibility {
+                                log::warn!("{worktree_abs_path:?}: {incompatibility}");
+                            }
 
                             EmbeddingIndex::new(
                                 worktree_for_index,
                                 embedding_fs,
                                 db_connection.clone(),
                                 db,
+                                metadata_db,
+                                db_name.to_string(),
+                                incompatibility,
                                 language_registry,
                                 embedding_provider,
+                                embedding_batches,
                                 Arc::clone(&entries_being_indexed),
                             )
                         };
@@ -109,6 +133,7 @@ impl WorktreeIndex {
                     embedding_index,
                     summary_index,
                     entries_being_indexed,
+                    reindex_debounce,
                     cx,
                 )
             })
@@ -121,6 +146,7 @@ impl WorktreeIndex {
         embedding_index: EmbeddingIndex,
         summary_index: SummaryIndex,
         entry_ids_being_indexed: Arc<IndexingEntrySet>,
+        reindex_debounce: Option<Duration>,
         cx: &mut Context<Self>,
     ) -> Self {
         let (updated_entries_tx, updated_entries_rx) = channel::unbounded();
@@ -138,7 +164,7 @@ impl WorktreeIndex {
             worktree,
             entry_ids_being_indexed,
             _index_entries: cx.spawn(async move |this, cx| {
-                Self::index_entries(this, updated_entries_rx, cx).await
+                Self::index_entries(this, updated_entries_rx, reindex_debounce, cx).await
             }),
             _subscription,
         }
@@ -167,6 +193,7 @@ impl WorktreeIndex {
     async fn index_entries(
         this: WeakEntity<Self>,
         updated_entries: channel::Receiver<UpdatedEntriesSet>,
+        reindex_debounce: Option<Duration>,
         cx: &mut AsyncApp,
     ) -> Result<()> {
         let index = this.update(cx, |this, cx| {
@@ -177,13 +204,23 @@ impl WorktreeIndex {
         })?;
         index.await.log_err();
 
-        while let Ok(updated_entries) = updated_entries.recv().await {
+        while let Ok(mut updated_entries_set) = updated_entries.recv().await {
+            if let Some(debounce) = reindex_debounce {
+                updated_entries_set = debounce_updated_entries(
+                    updated_entries_set,
+                    &updated_entries,
+                    debounce,
+                    cx.background_executor(),
+                )
+                .await;
+            }
+
             let index = this.update(cx, |this, cx| {
                 futures::future::try_join(
                     this.embedding_index
-                        .index_updated_entries(updated_entries.clone(), cx),
+                        .index_updated_entries(updated_entries_set.clone(), cx),
                     this.summary_index
-                        .index_updated_entries(updated_entries, false, cx),
+                        .index_updated_entries(updated_entries_set, false, cx),
                 )
             })?;
             index.await.log_err();
@@ -203,3 +240,115 @@ impl WorktreeIndex {
         Ok(self.embedding_index().db().len(&txn)?)
     }
 }
+
+/// How many debounce intervals to wait at most, so files that keep changing
+/// are still indexed.
+const MAX_DEBOUNCE_INTERVALS: u32 = 5;
+
+/// Collects updates until none arrived for `debounce`, or until
+/// [`MAX_DEBOUNCE_INTERVALS`] intervals passed since the first one.
+async fn debounce_updated_entries(
+    first: UpdatedEntriesSet,
+    updated_entries: &channel::Receiver<UpdatedEntriesSet>,
+    debounce: Duration,
+    executor: &BackgroundExecutor,
+) -> UpdatedEntriesSet {
+    let deadline = executor.now() + debounce * MAX_DEBOUNCE_INTERVALS;
+    let mut pending = vec![first];
+    loop {
+        let now = executor.now();
+        if now >= deadline {
+            break;
+        }
+        let mut timer = executor.timer(debounce.min(deadline - now)).fuse();
+        futures::select_biased! {
+            update = updated_entries.recv().fuse() => match update {
+                Ok(update) => pending.push(update),
+                Err(_) => break,
+            },
+            _ = timer => break,
+        }
+    }
+    coalesce_updated_entries(pending)
+}
+
+/// Merges the updates that arrived during a debounce window, keeping only the
+/// latest change to each path.
+fn coalesce_updated_entries(updates: Vec<UpdatedEntriesSet>) -> UpdatedEntriesSet {
+    let mut indices_by_path = HashMap::<Arc<Path>, usize>::default();
+    let mut coalesced = Vec::new();
+    for (path, entry_id, change) in updates.iter().flat_map(|update| update.iter()) {
+        let change = (path.clone(), *entry_id, *change);
+        match indices_by_path.get(path) {
+            Some(ix) => coalesced[*ix] = change,
+            None => {
+                indices_by_path.insert(path.clone(), coalesced.len());
+                coalesced.push(change);
+            }
+        }
+    }
+    coalesced.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::{PathChange, ProjectEntryId};
+
+    #[test]
+    fn test_coalesce_updated_entries() {
+        let a: Arc<Path> = Path::new("a.rs").into();
+        let b: Arc<Path> = Path::new("b.rs").into();
+        let a_id = ProjectEntryId::from_proto(1);
+        let b_id = ProjectEntryId::from_proto(2);
+
+        let coalesced = coalesce_updated_entries(vec![
+            Arc::from([(a.clone(), a_id, PathChange::Added)]),
+            Arc::from([
+                (a.clone(), a_id, PathChange::Updated),
+                (b.clone(), b_id, PathChange::Updated),
+            ]),
+            Arc::from([(a.clone(), a_id, PathChange::Updated)]),
+            Arc::from([(b.clone(), b_id, PathChange::Removed)]),
+        ]);
+        assert_eq!(
+            coalesced.as_ref(),
+            [
+                (a, a_id, PathChange::Updated),
+                (b, b_id, PathChange::Removed)
+            ]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_debounce_stops_waiting_for_constant_updates(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let debounce = Duration::from_millis(100);
+        let update = |ix: u64| -> UpdatedEntriesSet {
+            let path: Arc<Path> = Path::new(&format!("{ix}.rs")).into();
+            Arc::from([(path, ProjectEntryId::from_proto(ix), PathChange::Updated)])
+        };
+
+        let (updates_tx, updates_rx) = channel::unbounded();
+        let debounced = executor.spawn({
+            let executor = executor.clone();
+            let first = update(0);
+            async move { debounce_updated_entries(first, &updates_rx, debounce, &executor).await }
+        });
+
+        // Keep changing files twice per debounce interval for much longer than
+        // the maximum wait.
+        for ix in 1..=20 {
+            executor.advance_clock(debounce / 2);
+            updates_tx.try_send(update(ix)).unwrap();
+            executor.run_until_parked();
+        }
+
+        // The updates are still coming in, so the debounce must have given up
+        // waiting on its own.
+        let coalesced = debounced.await;
+        assert!(coalesced.len() > 1);
+        assert!(coalesced.len() <= 2 * MAX_DEBOUNCE_INTERVALS as usize + 1);
+    }
+}